@@ -1,40 +1,25 @@
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::Path;
 use std::time::Instant;
 
 use apollo_compiler::ExecutableDocument;
 use apollo_federation::error::FederationError;
+use apollo_federation::error::SingleFederationError;
 use apollo_federation::query_plan::query_planner::QueryPlanner;
 use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
 use apollo_federation::Supergraph;
 
 pub(crate) fn run_bench(
     supergraph: Supergraph,
-    queries_dir: &PathBuf,
+    queries_dir: &Path,
     config: QueryPlannerConfig,
 ) -> Result<Vec<BenchOutput>, FederationError> {
     let planner = QueryPlanner::new(&supergraph, config.clone()).expect("Invalid planner");
 
-    let mut entries = std::fs::read_dir(queries_dir)
-        .unwrap()
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, std::io::Error>>()
-        .unwrap();
-
-    entries.sort();
-
-    let mut results = Vec::with_capacity(entries.len());
-
-    for query_path in entries.into_iter() {
-        let query_string = std::fs::read_to_string(query_path.clone()).unwrap();
-
-        let file_name = query_path
-            .file_name()
-            .to_owned()
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
+    let operations = read_operations(queries_dir)?;
+    let mut results = Vec::with_capacity(operations.len());
 
+    for (file_name, query_string) in operations {
         let document = match ExecutableDocument::parse_and_validate(
             supergraph.schema.schema(),
             query_string,
@@ -78,6 +63,43 @@ pub(crate) fn run_bench(
     Ok(results)
 }
 
+/// Reads the operation files of a directory, returning their file names and contents sorted by
+/// file name. Subdirectories are skipped.
+pub(crate) fn read_operations(
+    operations_dir: &Path,
+) -> Result<Vec<(String, String)>, FederationError> {
+    let read_error = |path: &Path, error: std::io::Error| SingleFederationError::Internal {
+        message: format!("Error: failed to read {}: {error}", path.display()),
+    };
+
+    let mut entries = std::fs::read_dir(operations_dir)
+        .and_then(|entries| {
+            entries
+                .map(|res| res.map(|e| e.path()))
+                .collect::<Result<Vec<_>, std::io::Error>>()
+        })
+        .map_err(|error| read_error(operations_dir, error))?;
+    entries.retain(|path| !path.is_dir());
+
+    entries.sort();
+
+    entries
+        .into_iter()
+        .map(|operation_path| {
+            let operation_string = std::fs::read_to_string(&operation_path)
+                .map_err(|error| read_error(&operation_path, error))?;
+
+            let file_name = operation_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            Ok((file_name, operation_string))
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(serde::Serialize))]
 pub(crate) struct BenchOutput {
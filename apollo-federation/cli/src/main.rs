@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::num::NonZeroU32;
@@ -9,13 +10,20 @@ use apollo_compiler::ExecutableDocument;
 use apollo_federation::error::FederationError;
 use apollo_federation::error::SingleFederationError;
 use apollo_federation::query_graph;
+use apollo_federation::query_plan::output;
 use apollo_federation::query_plan::query_planner::QueryPlanner;
 use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
+use apollo_federation::schema_diff::diff_schemas;
+use apollo_federation::schema_diff::ChangeLevel;
+use apollo_federation::schema_diff::SchemaChange;
 use apollo_federation::subgraph;
 use bench::BenchOutput;
 use clap::Parser;
+use report::ComposeReport;
 
 mod bench;
+mod report;
+use bench::read_operations;
 use bench::run_bench;
 
 #[derive(Parser)]
@@ -44,6 +52,27 @@ struct QueryPlannerArgs {
     single_subgraph_passthrough: bool,
 }
 
+/// Output format of the `plan` command
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum PlanFormat {
+    /// The text format used in query plan snapshots
+    Text,
+    /// A Graphviz DOT graph
+    Dot,
+    /// A Mermaid flowchart
+    Mermaid,
+}
+
+/// Output format of the `compose` command
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ComposeFormat {
+    /// The supergraph SDL, with the composition hints on stderr
+    Sdl,
+    /// A JSON report of the errors, hints and supergraph, with the locations of their causes in
+    /// the subgraph files
+    Json,
+}
+
 /// CLI arguments. See <https://docs.rs/clap/latest/clap/_derive/index.html>
 #[derive(Parser)]
 struct Args {
@@ -78,16 +107,38 @@ enum Command {
         schemas: Vec<PathBuf>,
         #[command(flatten)]
         planner: QueryPlannerArgs,
+        /// Output format of the query plan.
+        #[arg(long, value_enum, default_value_t = PlanFormat::Text)]
+        format: PlanFormat,
     },
     /// Validate one supergraph schema file or multiple subgraph schemas
     Validate {
         /// Path(s) to one supergraph schema file, `-` for stdin or multiple subgraph schemas.
         schemas: Vec<PathBuf>,
     },
+    /// Validate a directory of operations against the API schema of a supergraph
+    ValidateOperations {
+        /// The path to the supergraph schema file, or `-` for stdin
+        #[arg(long)]
+        schema: PathBuf,
+        /// The path to the directory that contains the operations
+        operations_dir: PathBuf,
+        /// Plan every valid operation, reporting planning failures
+        #[arg(long, default_value_t = false)]
+        plan: bool,
+        /// Report the query plans with more fetches than this (implies `--plan`)
+        #[arg(long)]
+        max_fetches: Option<usize>,
+        #[command(flatten)]
+        planner: QueryPlannerArgs,
+    },
     /// Compose a supergraph schema from multiple subgraph schemas
     Compose {
         /// Path(s) to subgraph schemas.
         schemas: Vec<PathBuf>,
+        /// Output format of the composition result.
+        #[arg(long, value_enum, default_value_t = ComposeFormat::Sdl)]
+        format: ComposeFormat,
     },
     /// Extract subgraph schemas from a supergraph schema to stdout (or in a directory if specified)
     Extract {
@@ -96,6 +147,17 @@ enum Command {
         /// The output directory for the extracted subgraph schemas
         destination_dir: Option<PathBuf>,
     },
+    /// Classify the changes between the API schemas of two supergraphs as breaking, dangerous or
+    /// safe
+    Diff {
+        /// The path to the old supergraph schema file
+        old_schema: PathBuf,
+        /// The path to the new supergraph schema file
+        new_schema: PathBuf,
+        /// The path to a directory of operations to check against the new schema
+        #[arg(long)]
+        operations: Option<PathBuf>,
+    },
     Bench {
         /// The path to the supergraph schema file
         supergraph_schema: PathBuf,
@@ -142,13 +204,39 @@ fn main() -> ExitCode {
             query,
             schemas,
             planner,
-        } => cmd_plan(&query, &schemas, planner),
+            format,
+        } => cmd_plan(&query, &schemas, planner, format),
         Command::Validate { schemas } => cmd_validate(&schemas),
-        Command::Compose { schemas } => cmd_compose(&schemas),
+        Command::ValidateOperations {
+            schema,
+            operations_dir,
+            plan,
+            max_fetches,
+            planner,
+        } => match cmd_validate_operations(
+            &schema,
+            &operations_dir,
+            plan || max_fetches.is_some(),
+            max_fetches,
+            planner.into(),
+        ) {
+            Ok(false) => return ExitCode::FAILURE,
+            result => result.map(|_| ()),
+        },
+        Command::Compose { schemas, format } => cmd_compose(&schemas, format),
         Command::Extract {
             supergraph_schema,
             destination_dir,
         } => cmd_extract(&supergraph_schema, destination_dir.as_ref()),
+        Command::Diff {
+            old_schema,
+            new_schema,
+            operations,
+        } => match cmd_diff(&old_schema, &new_schema, operations.as_deref()) {
+            // breaking changes fail the command, to be used as a CI check
+            Ok(true) => return ExitCode::FAILURE,
+            result => result.map(|_| ()),
+        },
         Command::Bench {
             supergraph_schema,
             operations_dir,
@@ -183,9 +271,9 @@ fn cmd_api_schema(file_paths: &[PathBuf], enable_defer: bool) -> Result<(), Fede
     Ok(())
 }
 
-/// Compose a supergraph from multiple subgraph files.
-fn compose_files(file_paths: &[PathBuf]) -> Result<apollo_federation::Supergraph, FederationError> {
-    let schemas: Vec<_> = file_paths
+/// Parse and expand the subgraph files, named after their file stem.
+fn load_subgraphs(file_paths: &[PathBuf]) -> Vec<subgraph::ValidSubgraph> {
+    file_paths
         .iter()
         .map(|pathname| {
             let doc_str = std::fs::read_to_string(pathname).unwrap();
@@ -193,8 +281,25 @@ fn compose_files(file_paths: &[PathBuf]) -> Result<apollo_federation::Supergraph
             let basename = pathname.file_stem().unwrap().to_str().unwrap();
             subgraph::Subgraph::parse_and_expand(basename, &url, &doc_str).unwrap()
         })
-        .collect();
-    let supergraph = apollo_federation::Supergraph::compose(schemas.iter().collect()).unwrap();
+        .collect()
+}
+
+/// Maps the subgraph names to the files they were loaded from.
+fn subgraph_files(
+    schemas: &[subgraph::ValidSubgraph],
+    file_paths: &[PathBuf],
+) -> HashMap<String, PathBuf> {
+    schemas
+        .iter()
+        .zip(file_paths)
+        .map(|(schema, path)| (schema.name.clone(), path.clone()))
+        .collect()
+}
+
+/// Compose a supergraph from multiple subgraph files.
+fn compose_files(file_paths: &[PathBuf]) -> Result<apollo_federation::Supergraph, FederationError> {
+    let schemas = load_subgraphs(file_paths);
+    let supergraph = apollo_federation::Supergraph::compose(schemas.iter().collect())?;
     Ok(supergraph)
 }
 
@@ -245,6 +350,7 @@ fn cmd_plan(
     query_path: &Path,
     schema_paths: &[PathBuf],
     planner: QueryPlannerArgs,
+    format: PlanFormat,
 ) -> Result<(), FederationError> {
     let query = read_input(query_path);
     let supergraph = load_supergraph(schema_paths)?;
@@ -254,7 +360,12 @@ fn cmd_plan(
 
     let query_doc =
         ExecutableDocument::parse_and_validate(planner.api_schema().schema(), query, query_path)?;
-    print!("{}", planner.build_query_plan(&query_doc, None)?);
+    let plan = planner.build_query_plan(&query_doc, None)?;
+    match format {
+        PlanFormat::Text => print!("{plan}"),
+        PlanFormat::Dot => print!("{}", output::to_dot(&plan)),
+        PlanFormat::Mermaid => print!("{}", output::to_mermaid(&plan)),
+    }
     Ok(())
 }
 
@@ -264,10 +375,107 @@ fn cmd_validate(file_paths: &[PathBuf]) -> Result<(), FederationError> {
     Ok(())
 }
 
-fn cmd_compose(file_paths: &[PathBuf]) -> Result<(), FederationError> {
-    let supergraph = compose_files(file_paths)?;
-    println!("{}", supergraph.schema.schema());
-    Ok(())
+/// Validates the operations of a directory, optionally planning them, and returns the problems
+/// found in each operation.
+fn _cmd_validate_operations(
+    schema_path: &Path,
+    operations_dir: &Path,
+    plan: bool,
+    max_fetches: Option<usize>,
+    config: QueryPlannerConfig,
+) -> Result<Vec<String>, FederationError> {
+    let supergraph = load_supergraph_file(schema_path)?;
+    let planner = QueryPlanner::new(&supergraph, config)?;
+
+    let mut problems = vec![];
+    for (file_name, operation) in read_operations(operations_dir)? {
+        let document = match ExecutableDocument::parse_and_validate(
+            planner.api_schema().schema(),
+            operation,
+            &file_name,
+        ) {
+            Ok(document) => document,
+            Err(document) => {
+                for diagnostic in document.errors.iter() {
+                    match diagnostic.line_column_range() {
+                        Some(range) => problems.push(format!(
+                            "{file_name}:{}:{}: {}",
+                            range.start.line, range.start.column, diagnostic.error
+                        )),
+                        None => problems.push(format!("{file_name}: {}", diagnostic.error)),
+                    }
+                }
+                continue;
+            }
+        };
+        if !plan {
+            continue;
+        }
+        // A document may contain several named operations, which are planned one by one.
+        for operation in document.operations.iter() {
+            let operation_name = operation.name.clone();
+            let label = match &operation_name {
+                Some(name) => format!("{file_name} ({name})"),
+                None => file_name.clone(),
+            };
+            match planner.build_query_plan(&document, operation_name) {
+                Ok(plan) => {
+                    let fetch_count = plan.fetch_count();
+                    if let Some(max_fetches) = max_fetches.filter(|max| fetch_count > *max) {
+                        problems.push(format!(
+                            "{label}: the query plan has {fetch_count} fetches, more than the maximum of {max_fetches}"
+                        ));
+                    }
+                }
+                Err(error) => problems.push(format!("{label}: query planning failed: {error}")),
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Prints the problems found in the operations of a directory, returning whether there are none.
+fn cmd_validate_operations(
+    schema_path: &Path,
+    operations_dir: &Path,
+    plan: bool,
+    max_fetches: Option<usize>,
+    config: QueryPlannerConfig,
+) -> Result<bool, FederationError> {
+    let problems =
+        _cmd_validate_operations(schema_path, operations_dir, plan, max_fetches, config)?;
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if problems.is_empty() {
+        println!("[SUCCESS]");
+    }
+    Ok(problems.is_empty())
+}
+
+fn cmd_compose(file_paths: &[PathBuf], format: ComposeFormat) -> Result<(), FederationError> {
+    let schemas = load_subgraphs(file_paths);
+    let result = apollo_federation::merge::merge_subgraphs(schemas.iter().collect());
+    if let ComposeFormat::Json = format {
+        let report = ComposeReport::new(&result, &subgraph_files(&schemas, file_paths));
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return result.map(|_| ()).map_err(Into::into);
+    }
+    match result {
+        Ok(success) => {
+            for hint in &success.composition_hints {
+                eprintln!("{hint}");
+            }
+            println!("{}", success.schema.serialize());
+            Ok(())
+        }
+        Err(failure) => {
+            for hint in &failure.composition_hints {
+                eprintln!("{hint}");
+            }
+            Err(failure.into())
+        }
+    }
 }
 
 fn cmd_extract(file_path: &Path, dest: Option<&PathBuf>) -> Result<(), FederationError> {
@@ -295,9 +503,77 @@ fn cmd_extract(file_path: &Path, dest: Option<&PathBuf>) -> Result<(), Federatio
     Ok(())
 }
 
+/// The changes between two supergraphs, and the operations they break
+struct DiffOutput {
+    changes: Vec<SchemaChange>,
+    /// The file names of the operations valid against the old schema but not the new one, with
+    /// their validation errors
+    broken_operations: Vec<(String, Vec<String>)>,
+}
+
+fn _cmd_diff(
+    old_path: &Path,
+    new_path: &Path,
+    operations_dir: Option<&Path>,
+) -> Result<DiffOutput, FederationError> {
+    let old = load_supergraph_file(old_path)?.to_api_schema(Default::default())?;
+    let new = load_supergraph_file(new_path)?.to_api_schema(Default::default())?;
+    let changes = diff_schemas(old.schema(), new.schema());
+
+    let mut broken_operations = vec![];
+    for (file_name, operation) in operations_dir
+        .map(read_operations)
+        .transpose()?
+        .unwrap_or_default()
+    {
+        if ExecutableDocument::parse_and_validate(old.schema(), &operation, &file_name).is_err() {
+            // already invalid
+            continue;
+        }
+        if let Err(document) =
+            ExecutableDocument::parse_and_validate(new.schema(), &operation, &file_name)
+        {
+            let errors = document
+                .errors
+                .iter()
+                .map(|diagnostic| diagnostic.error.to_string())
+                .collect();
+            broken_operations.push((file_name, errors));
+        }
+    }
+    Ok(DiffOutput {
+        changes,
+        broken_operations,
+    })
+}
+
+/// Prints the changes between two supergraphs, returning whether there are breaking changes.
+fn cmd_diff(
+    old_path: &Path,
+    new_path: &Path,
+    operations_dir: Option<&Path>,
+) -> Result<bool, FederationError> {
+    let output = _cmd_diff(old_path, new_path, operations_dir)?;
+    for change in &output.changes {
+        println!("{change}");
+    }
+    if !output.broken_operations.is_empty() {
+        println!();
+        println!("Operations broken by the changes:");
+        for (file_name, errors) in &output.broken_operations {
+            println!("- {file_name}: {}", errors.join("; "));
+        }
+    }
+    Ok(output
+        .changes
+        .iter()
+        .any(|change| change.level == ChangeLevel::Breaking)
+        || !output.broken_operations.is_empty())
+}
+
 fn _cmd_bench(
     file_path: &Path,
-    operations_dir: &PathBuf,
+    operations_dir: &Path,
     config: QueryPlannerConfig,
 ) -> Result<Vec<BenchOutput>, FederationError> {
     let supergraph = load_supergraph_file(file_path)?;
@@ -306,7 +582,7 @@ fn _cmd_bench(
 
 fn cmd_bench(
     file_path: &Path,
-    operations_dir: &PathBuf,
+    operations_dir: &Path,
     planner: QueryPlannerArgs,
 ) -> Result<(), FederationError> {
     let results = _cmd_bench(file_path, operations_dir, planner.into())?;
@@ -329,3 +605,69 @@ fn test_bench() {
         { "[].timing" => 1.234 },
     );
 }
+
+#[test]
+fn test_compose_report() {
+    let file_paths = [
+        PathBuf::from("./fixtures/compose/products.graphql"),
+        PathBuf::from("./fixtures/compose/reviews.graphql"),
+    ];
+    let schemas = load_subgraphs(&file_paths);
+    let result = apollo_federation::merge::merge_subgraphs(schemas.iter().collect());
+    insta::assert_json_snapshot!(ComposeReport::new(
+        &result,
+        &subgraph_files(&schemas, &file_paths)
+    ));
+}
+
+#[test]
+fn test_diff() {
+    let output = _cmd_diff(
+        Path::new("./fixtures/starstuff.graphql"),
+        Path::new("./fixtures/starstuff_changed.graphql"),
+        Some(Path::new("./fixtures/queries")),
+    )
+    .unwrap();
+    let changes = output
+        .changes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    insta::assert_snapshot!(changes.join("\n"), @r###"
+    [BREAKING] Field "Review.author" was removed
+    [DANGEROUS] Default value of argument "Query.topProducts(first:)" changed from 5 to 10
+    [SAFE] Field "Product.description" was added
+    "###);
+    insta::assert_debug_snapshot!(output.broken_operations, @r###"
+    [
+        (
+            "topproducts.graphql",
+            [
+                "type `Review` does not have a field `author`",
+            ],
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_validate_operations() {
+    let problems = _cmd_validate_operations(
+        Path::new("./fixtures/starstuff.graphql"),
+        Path::new("./fixtures/operations"),
+        true,
+        Some(2),
+        Default::default(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(problems.join("\n"), @r###"
+    named_operations.graphql (MyReviews): the query plan has 3 fetches, more than the maximum of 2
+    reviews.graphql (Reviews): the query plan has 3 fetches, more than the maximum of 2
+    unknown_field.graphql:4:5: type `User` does not have a field `email`
+    "###);
+}
+
+#[test]
+fn test_read_operations_missing_dir() {
+    assert!(read_operations(Path::new("./fixtures/missing")).is_err());
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use apollo_federation::merge::CompositionHint;
+use apollo_federation::merge::MergeFailure;
+use apollo_federation::merge::MergeSuccess;
+use apollo_federation::merge::SubgraphLocation;
+use serde::Serialize;
+
+/// The result of a composition, in a format meant for tools annotating the subgraph files.
+#[derive(Serialize)]
+pub(crate) struct ComposeReport {
+    /// The composed supergraph, if there are no errors
+    supergraph: Option<String>,
+    errors: Vec<ReportEntry>,
+    hints: Vec<ReportEntry>,
+}
+
+#[derive(Serialize)]
+struct ReportEntry {
+    code: String,
+    message: String,
+    locations: Vec<ReportLocation>,
+}
+
+#[derive(Serialize)]
+struct ReportLocation {
+    /// The subgraph file, as given on the command line
+    file: Option<PathBuf>,
+    #[serde(flatten)]
+    location: SubgraphLocation,
+}
+
+impl ComposeReport {
+    /// Creates the report of a composition, with `files` mapping the subgraph names to their
+    /// files.
+    pub(crate) fn new(
+        result: &Result<MergeSuccess, MergeFailure>,
+        files: &HashMap<String, PathBuf>,
+    ) -> Self {
+        let locations = |locations: &[SubgraphLocation]| {
+            locations
+                .iter()
+                .map(|location| ReportLocation {
+                    file: files.get(&location.subgraph).cloned(),
+                    location: location.clone(),
+                })
+                .collect()
+        };
+        let hints = |hints: &[CompositionHint]| {
+            hints
+                .iter()
+                .map(|hint| ReportEntry {
+                    code: hint.code.to_string(),
+                    message: hint.message.clone(),
+                    locations: locations(&hint.locations),
+                })
+                .collect()
+        };
+        match result {
+            Ok(success) => ComposeReport {
+                supergraph: Some(success.schema.serialize().to_string()),
+                errors: vec![],
+                hints: hints(&success.composition_hints),
+            },
+            Err(failure) => ComposeReport {
+                supergraph: None,
+                errors: failure
+                    .errors
+                    .iter()
+                    .zip(&failure.error_locations)
+                    .map(|(error, error_locations)| ReportEntry {
+                        code: error.code().definition().code().to_string(),
+                        message: error.to_string(),
+                        locations: locations(error_locations),
+                    })
+                    .collect(),
+                hints: hints(&failure.composition_hints),
+            },
+        }
+    }
+}
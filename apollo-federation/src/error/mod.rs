@@ -218,6 +218,10 @@ pub enum SingleFederationError {
     InterfaceKeyMissingImplementationType { message: String },
     #[error("@defer is not supported on subscriptions")]
     DeferredSubscriptionUnsupported,
+    #[error("{message}")]
+    QueryPlanningTimeout { message: String },
+    #[error("{message}")]
+    MaxEvaluatedCostExceeded { message: String },
 }
 
 impl SingleFederationError {
@@ -400,6 +404,10 @@ impl SingleFederationError {
                 ErrorCode::InterfaceKeyMissingImplementationType
             }
             SingleFederationError::DeferredSubscriptionUnsupported => ErrorCode::Internal,
+            SingleFederationError::QueryPlanningTimeout { .. } => ErrorCode::QueryPlanningTimeout,
+            SingleFederationError::MaxEvaluatedCostExceeded { .. } => {
+                ErrorCode::MaxEvaluatedCostExceeded
+            }
         }
     }
 }
@@ -1218,6 +1226,18 @@ lazy_static! {
         None,
 
     );
+
+    static ref QUERY_PLANNING_TIMEOUT: ErrorCodeDefinition = ErrorCodeDefinition::new(
+        "QUERY_PLANNING_TIMEOUT".to_owned(),
+        "Query planning took longer than the configured timeout.".to_owned(),
+        None,
+    );
+
+    static ref MAX_EVALUATED_COST_EXCEEDED: ErrorCodeDefinition = ErrorCodeDefinition::new(
+        "MAX_EVALUATED_COST_EXCEEDED".to_owned(),
+        "The best query plan found has a higher cost than the configured maximum.".to_owned(),
+        None,
+    );
 }
 
 #[derive(Debug, strum_macros::EnumIter)]
@@ -1301,6 +1321,8 @@ pub enum ErrorCode {
     InterfaceKeyMissingImplementationType,
     UnsupportedFederationVersion,
     UnsupportedFederationDirective,
+    QueryPlanningTimeout,
+    MaxEvaluatedCostExceeded,
 }
 
 impl ErrorCode {
@@ -1400,6 +1422,8 @@ impl ErrorCode {
             }
             ErrorCode::UnsupportedFederationVersion => &UNSUPPORTED_FEDERATION_VERSION,
             ErrorCode::UnsupportedFederationDirective => &UNSUPPORTED_FEDERATION_DIRECTIVE,
+            ErrorCode::QueryPlanningTimeout => &QUERY_PLANNING_TIMEOUT,
+            ErrorCode::MaxEvaluatedCostExceeded => &MAX_EVALUATED_COST_EXCEEDED,
         }
     }
 }
@@ -28,6 +28,7 @@ pub(crate) mod operation;
 pub mod query_graph;
 pub mod query_plan;
 pub mod schema;
+pub mod schema_diff;
 pub mod subgraph;
 pub(crate) mod supergraph;
 pub(crate) mod utils;
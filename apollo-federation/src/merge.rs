@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::fmt::Display;
 use std::fmt::Formatter;
 use std::iter;
 use std::sync::Arc;
@@ -11,10 +12,15 @@ use apollo_compiler::ast::DirectiveLocation;
 use apollo_compiler::ast::EnumValueDefinition;
 use apollo_compiler::ast::FieldDefinition;
 use apollo_compiler::ast::NamedType;
+use apollo_compiler::ast::Type;
 use apollo_compiler::ast::Value;
 use apollo_compiler::collections::IndexMap;
 use apollo_compiler::collections::IndexSet;
 use apollo_compiler::name;
+use apollo_compiler::parser::FileId;
+use apollo_compiler::parser::LineColumn;
+use apollo_compiler::parser::SourceMap;
+use apollo_compiler::parser::SourceSpan;
 use apollo_compiler::schema::Component;
 use apollo_compiler::schema::EnumType;
 use apollo_compiler::schema::ExtendedType;
@@ -33,8 +39,11 @@ use indexmap::map::Entry::Occupied;
 use indexmap::map::Entry::Vacant;
 use indexmap::map::Iter;
 use itertools::Itertools;
+use serde::Serialize;
 
 use crate::error::FederationError;
+use crate::error::MultipleFederationErrors;
+use crate::error::SingleFederationError;
 use crate::link::federation_spec_definition::FEDERATION_EXTERNAL_DIRECTIVE_NAME_IN_SPEC;
 use crate::link::federation_spec_definition::FEDERATION_FIELDS_ARGUMENT_NAME;
 use crate::link::federation_spec_definition::FEDERATION_FROM_ARGUMENT_NAME;
@@ -44,50 +53,115 @@ use crate::link::federation_spec_definition::FEDERATION_OVERRIDE_DIRECTIVE_NAME_
 use crate::link::federation_spec_definition::FEDERATION_OVERRIDE_LABEL_ARGUMENT_NAME;
 use crate::link::federation_spec_definition::FEDERATION_PROVIDES_DIRECTIVE_NAME_IN_SPEC;
 use crate::link::federation_spec_definition::FEDERATION_REQUIRES_DIRECTIVE_NAME_IN_SPEC;
+use crate::link::federation_spec_definition::FEDERATION_SHAREABLE_DIRECTIVE_NAME_IN_SPEC;
+use crate::link::federation_spec_definition::FEDERATION_VERSIONS;
 use crate::link::inaccessible_spec_definition::InaccessibleSpecDefinition;
 use crate::link::inaccessible_spec_definition::INACCESSIBLE_DIRECTIVE_NAME_IN_SPEC;
 use crate::link::join_spec_definition::JOIN_OVERRIDE_LABEL_ARGUMENT_NAME;
 use crate::link::spec::Identity;
+use crate::link::spec::Url;
 use crate::link::spec::Version;
 use crate::link::spec_definition::SpecDefinition;
+use crate::link::Import;
 use crate::link::LinksMetadata;
+use crate::schema::field_set::collect_target_fields_from_field_set;
 use crate::schema::ValidFederationSchema;
+use crate::subgraph::spec::COMPOSE_DIRECTIVE_NAME;
 use crate::subgraph::ValidSubgraph;
 use crate::ValidFederationSubgraph;
 use crate::ValidFederationSubgraphs;
 
-type MergeWarning = String;
-type MergeError = String;
+type MergeError = SingleFederationError;
 
 struct Merger {
     errors: Vec<MergeError>,
-    composition_hints: Vec<MergeWarning>,
+    /// The subgraph locations of the errors, by index in `errors`
+    error_locations: IndexMap<usize, Vec<SubgraphLocation>>,
+    composition_hints: Vec<CompositionHint>,
+    /// The source files of all the subgraphs
+    sources: SourceMap,
+    /// The subgraph names, by source file
+    source_subgraphs: IndexMap<FileId, String>,
     needs_inaccessible: bool,
+    /// The subgraph names, by their `join__Graph` enum value
+    subgraph_names: IndexMap<Name, String>,
+    /// The directives kept in the supergraph because of `@composeDirective`
+    composed_directives: IndexMap<Name, ComposedDirective>,
+    /// The executable directive definitions of every subgraph
+    executable_directives: IndexMap<Name, Vec<(Name, Node<DirectiveDefinition>)>>,
+    /// The subgraphs defining each input object type, and each of its fields
+    input_object_sources: IndexMap<Name, FieldSources>,
+    /// The subgraphs defining each interface, as an interface or with `@interfaceObject`, and
+    /// each of its fields
+    interface_sources: IndexMap<Name, FieldSources>,
+    /// The types defined as an interface in at least one subgraph
+    interface_definitions: IndexSet<Name>,
+    /// The subgraphs declaring each type with `@interfaceObject`
+    interface_objects: IndexMap<Name, Vec<Name>>,
+    /// The subgraphs resolving each object field, by type and field name
+    shared_fields: IndexMap<Name, IndexMap<Name, Vec<SharedField>>>,
+}
+
+/// An object field resolved by a subgraph, i.e. not `@external` in that subgraph
+struct SharedField {
+    graph: Name,
+    /// Whether the field is `@shareable` in that subgraph, or part of a `@key`
+    is_shareable: bool,
+    is_overridden: bool,
+    location: Option<SourceSpan>,
+}
+
+#[derive(Default)]
+struct FieldSources {
+    type_sources: Vec<Name>,
+    field_sources: IndexMap<Name, Vec<Name>>,
+    /// The number of subgraphs giving a default value to each input field
+    default_values: IndexMap<Name, usize>,
+}
+
+/// A directive definition linked to the supergraph by `@composeDirective`
+struct ComposedDirective {
+    /// The url of the feature defining the directive, with the highest version of the subgraphs
+    url: Url,
+    /// The `@link` import of the directive, if it is not referenced by its namespaced name
+    import: Option<Arc<Import>>,
+    definition: Node<DirectiveDefinition>,
 }
 
 pub struct MergeSuccess {
     pub schema: Valid<Schema>,
-    pub composition_hints: Vec<MergeWarning>,
+    pub composition_hints: Vec<CompositionHint>,
 }
 
 impl From<FederationError> for MergeFailure {
     fn from(err: FederationError) -> Self {
         // TODO: Consider an easier transition / interop between MergeFailure and FederationError
-        // TODO: This is most certainly not the right error kind. MergeFailure's
-        // errors need to be in an enum that could be matched on rather than a
-        // str.
+        let mut errors = MultipleFederationErrors { errors: vec![] };
+        errors.push(err);
         MergeFailure {
             schema: None,
-            errors: vec![err.to_string()],
+            error_locations: vec![vec![]; errors.errors.len()],
+            errors: errors.errors,
             composition_hints: vec![],
         }
     }
 }
 
+impl From<MergeFailure> for FederationError {
+    fn from(failure: MergeFailure) -> Self {
+        MultipleFederationErrors {
+            errors: failure.errors,
+        }
+        .into()
+    }
+}
+
 pub struct MergeFailure {
     pub schema: Option<Schema>,
     pub errors: Vec<MergeError>,
-    pub composition_hints: Vec<MergeWarning>,
+    /// The subgraph locations of each error, in the same order as `errors`
+    pub error_locations: Vec<Vec<SubgraphLocation>>,
+    pub composition_hints: Vec<CompositionHint>,
 }
 
 impl Debug for MergeFailure {
@@ -99,6 +173,49 @@ impl Debug for MergeFailure {
     }
 }
 
+/// A composition issue that does not prevent the subgraphs from being merged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompositionHint {
+    pub code: HintCode,
+    pub message: String,
+    /// The elements of the subgraphs causing the hint
+    pub locations: Vec<SubgraphLocation>,
+}
+
+/// The position of a schema element in the SDL of a subgraph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct SubgraphLocation {
+    pub subgraph: String,
+    pub start: LineColumn,
+    pub end: LineColumn,
+}
+
+impl Display for CompositionHint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+/// The kinds of composition hints, displayed like the hint codes of the JS composition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display, Serialize)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HintCode {
+    InconsistentDescription,
+    InconsistentButCompatibleFieldType,
+    InconsistentButCompatibleArgumentType,
+    InconsistentDefaultValuePresence,
+    InconsistentInputObjectField,
+    InconsistentArgumentPresence,
+    InconsistentExecutableDirectivePresence,
+    NoExecutableDirectiveLocationsIntersection,
+    InconsistentExecutableDirectiveRepeatable,
+    InconsistentExecutableDirectiveLocations,
+    InconsistentNonRepeatableDirectiveArguments,
+    UnusedExternal,
+    MissingShareable,
+}
+
 pub fn merge_subgraphs(subgraphs: Vec<&ValidSubgraph>) -> Result<MergeSuccess, MergeFailure> {
     let mut merger = Merger::new();
     let mut federation_subgraphs = ValidFederationSubgraphs::new();
@@ -124,7 +241,18 @@ impl Merger {
         Merger {
             composition_hints: Vec::new(),
             errors: Vec::new(),
+            error_locations: IndexMap::default(),
+            sources: Default::default(),
+            source_subgraphs: IndexMap::default(),
             needs_inaccessible: false,
+            subgraph_names: IndexMap::default(),
+            composed_directives: IndexMap::default(),
+            executable_directives: IndexMap::default(),
+            input_object_sources: IndexMap::default(),
+            interface_sources: IndexMap::default(),
+            interface_definitions: IndexSet::default(),
+            interface_objects: IndexMap::default(),
+            shared_fields: IndexMap::default(),
         }
     }
 
@@ -139,11 +267,17 @@ impl Merger {
             // TODO: Implement JS codebase's name transform (which always generates a valid GraphQL
             // name and avoids collisions).
             if let Ok(subgraph_name) = Name::new(&subgraph.name.to_uppercase()) {
+                self.subgraph_names
+                    .insert(subgraph_name.clone(), subgraph.name.clone());
                 subgraphs_and_enum_values.push((subgraph, subgraph_name));
             } else {
-                self.errors.push(String::from(
-                    "Subgraph name couldn't be transformed into valid GraphQL name",
-                ));
+                self.errors
+                    .push(SingleFederationError::InvalidSubgraphName {
+                        message: format!(
+                            "Subgraph name \"{}\" couldn't be transformed into valid GraphQL name",
+                            subgraph.name
+                        ),
+                    });
             }
         }
         if !self.errors.is_empty() {
@@ -151,29 +285,46 @@ impl Merger {
                 schema: None,
                 composition_hints: self.composition_hints.to_owned(),
                 errors: self.errors.to_owned(),
+                error_locations: self.error_locations(),
             });
         }
 
         let mut supergraph = Schema::new();
-        // TODO handle @compose
+        for subgraph in &subgraphs {
+            let sources = Arc::make_mut(&mut self.sources);
+            for (key, source) in subgraph.schema.schema().sources.iter() {
+                sources.entry(*key).or_insert_with(|| source.clone());
+                self.source_subgraphs
+                    .entry(*key)
+                    .or_insert_with(|| subgraph.name.clone());
+            }
+        }
+        self.verify_federation_versions(&subgraphs);
+        let composed_directive_names = subgraphs
+            .iter()
+            .map(|subgraph| self.collect_composed_directives(subgraph))
+            .collect_vec();
 
         // add core features
-        // TODO verify federation versions across subgraphs
         add_core_feature_link(&mut supergraph);
         add_core_feature_join(&mut supergraph, &subgraphs_and_enum_values);
+        self.add_composed_directives(&mut supergraph);
 
         // create stubs
-        for (subgraph, subgraph_name) in &subgraphs_and_enum_values {
+        for ((subgraph, subgraph_name), composed) in subgraphs_and_enum_values
+            .iter()
+            .zip(composed_directive_names)
+        {
             let sources = Arc::make_mut(&mut supergraph.sources);
             for (key, source) in subgraph.schema.schema().sources.iter() {
                 sources.entry(*key).or_insert_with(|| source.clone());
             }
 
             self.merge_schema(&mut supergraph, subgraph);
-            // TODO merge directives
 
             let metadata = subgraph.schema.metadata();
-            let relevant_directives = DirectiveNames::for_metadata(&metadata);
+            let relevant_directives = DirectiveNames::for_metadata(&metadata, composed);
+            self.check_object_fields(subgraph, subgraph_name, &relevant_directives);
 
             for (type_name, ty) in &subgraph.schema.schema().types {
                 if ty.is_built_in() || !is_mergeable_type(type_name) {
@@ -231,14 +382,24 @@ impl Merger {
                 }
             }
 
-            // merge executable directives
-            for (_, directive) in subgraph.schema.schema().directive_definitions.iter() {
-                if is_executable_directive(directive) {
-                    merge_directive(&mut supergraph.directive_definitions, directive);
+            // collect executable directives, merged once all subgraphs are known
+            for (name, directive) in subgraph.schema.schema().directive_definitions.iter() {
+                if is_executable_directive(directive)
+                    && !self.composed_directives.contains_key(name)
+                {
+                    self.executable_directives
+                        .entry(name.clone())
+                        .or_default()
+                        .push((subgraph_name.clone(), directive.clone()));
                 }
             }
         }
 
+        self.merge_executable_directives(&mut supergraph);
+        self.merge_input_object_fields(&mut supergraph);
+        self.merge_interface_fields(&mut supergraph);
+        self.hint_missing_shareable();
+
         if self.needs_inaccessible {
             add_core_feature_inaccessible(&mut supergraph);
         }
@@ -255,28 +416,806 @@ impl Merger {
                 schema: Some(supergraph),
                 composition_hints: self.composition_hints.to_owned(),
                 errors: self.errors.to_owned(),
+                error_locations: self.error_locations(),
             })
         }
     }
 
-    fn merge_descriptions<T: Eq + Clone>(&mut self, merged: &mut Option<T>, new: &Option<T>) {
+    fn merge_descriptions(
+        &mut self,
+        merged: &mut Option<Node<str>>,
+        new: &Option<Node<str>>,
+        coordinate: impl Display,
+    ) {
         match (&mut *merged, new) {
             (_, None) => {}
             (None, Some(_)) => merged.clone_from(new),
             (Some(a), Some(b)) => {
                 if a != b {
-                    // TODO add info about type and from/to subgraph
-                    self.composition_hints
-                        .push(String::from("conflicting descriptions"));
+                    let locations = [a.location(), b.location()];
+                    self.hint_at(
+                        HintCode::InconsistentDescription,
+                        format!(
+                            "Element \"{coordinate}\" has inconsistent descriptions across subgraphs: the supergraph keeps the first description"
+                        ),
+                        locations,
+                    );
                 }
             }
         }
     }
 
+    fn hint(&mut self, code: HintCode, message: String) {
+        self.hint_at(code, message, [])
+    }
+
+    /// Records a hint at the given subgraph elements, once even if several subgraphs trigger it
+    fn hint_at(
+        &mut self,
+        code: HintCode,
+        message: String,
+        locations: impl IntoIterator<Item = Option<SourceSpan>>,
+    ) {
+        let locations = self.subgraph_locations(locations);
+        if let Some(hint) = self
+            .composition_hints
+            .iter_mut()
+            .find(|hint| hint.code == code && hint.message == message)
+        {
+            for location in locations {
+                if !hint.locations.contains(&location) {
+                    hint.locations.push(location);
+                }
+            }
+        } else {
+            self.composition_hints.push(CompositionHint {
+                code,
+                message,
+                locations,
+            });
+        }
+    }
+
+    /// Records an error at the given subgraph elements
+    fn error_at(
+        &mut self,
+        error: MergeError,
+        locations: impl IntoIterator<Item = Option<SourceSpan>>,
+    ) {
+        let locations = self.subgraph_locations(locations);
+        self.error_locations.insert(self.errors.len(), locations);
+        self.errors.push(error);
+    }
+
+    /// The subgraph locations of each error, in the same order as the errors
+    fn error_locations(&self) -> Vec<Vec<SubgraphLocation>> {
+        (0..self.errors.len())
+            .map(|index| {
+                self.error_locations
+                    .get(&index)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Converts the locations of subgraph elements to line and column numbers, ignoring the
+    /// elements without location like the ones generated by the subgraph expansion
+    fn subgraph_locations(
+        &self,
+        locations: impl IntoIterator<Item = Option<SourceSpan>>,
+    ) -> Vec<SubgraphLocation> {
+        locations
+            .into_iter()
+            .flatten()
+            .filter_map(|location| {
+                let subgraph = self.source_subgraphs.get(&location.file_id())?;
+                let range = location.line_column_range(&self.sources)?;
+                Some(SubgraphLocation {
+                    subgraph: subgraph.clone(),
+                    start: range.start,
+                    end: range.end,
+                })
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Displays the subgraphs of the given `join__Graph` enum values
+    fn subgraph_list<'a>(&self, graphs: impl IntoIterator<Item = &'a Name>) -> String {
+        graphs
+            .into_iter()
+            .map(|graph| format!("\"{}\"", self.subgraph_names[graph]))
+            .join(", ")
+    }
+
+    fn type_kind_mismatch(
+        &mut self,
+        existing_type: &ExtendedType,
+        type_name: &Name,
+        subgraph_name: &Name,
+        kind: &str,
+        location: Option<SourceSpan>,
+    ) {
+        self.error_at(
+            SingleFederationError::TypeKindMismatch {
+                message: format!(
+                    "Type \"{type_name}\" has mismatched kind: it is defined as {kind} in subgraph {} but as {} in other subgraphs",
+                    self.subgraph_list([subgraph_name]),
+                    type_kind(existing_type),
+                ),
+            },
+            [existing_type.location(), location],
+        );
+    }
+
+    fn verify_federation_versions(&mut self, subgraphs: &[ValidFederationSubgraph]) {
+        for subgraph in subgraphs {
+            // Federation 1 subgraphs don't link the federation spec
+            let Some(link) = subgraph
+                .schema
+                .metadata()
+                .and_then(|metadata| metadata.for_identity(&Identity::federation_identity()))
+            else {
+                continue;
+            };
+            let version = &link.url.version;
+            if FEDERATION_VERSIONS.find(version).is_none() {
+                self.errors
+                    .push(SingleFederationError::UnknownFederationLinkVersion {
+                        message: format!(
+                            "Subgraph \"{}\" links unknown federation version v{version}",
+                            subgraph.name
+                        ),
+                    });
+                continue;
+            }
+
+            let schema = subgraph.schema.schema();
+            let compose_directive = link.directive_name_in_schema(&COMPOSE_DIRECTIVE_NAME);
+            let interface_object =
+                link.directive_name_in_schema(&FEDERATION_INTERFACEOBJECT_DIRECTIVE_NAME_IN_SPEC);
+            let usages = [
+                (
+                    COMPOSE_DIRECTIVE_NAME,
+                    Version { major: 2, minor: 1 },
+                    schema.schema_definition.directives.has(&compose_directive),
+                ),
+                (
+                    FEDERATION_INTERFACEOBJECT_DIRECTIVE_NAME_IN_SPEC,
+                    Version { major: 2, minor: 3 },
+                    schema
+                        .types
+                        .values()
+                        .any(|ty| ty.directives().has(&interface_object)),
+                ),
+            ];
+            for (directive, required_version, is_used) in usages {
+                if is_used && !version.satisfies(&required_version) {
+                    self.errors
+                        .push(SingleFederationError::UnsupportedFederationDirective {
+                            message: format!(
+                                "Directive \"@{directive}\" used in subgraph \"{}\" requires federation v{required_version}, but the subgraph links federation v{version}",
+                                subgraph.name
+                            ),
+                        });
+                }
+            }
+        }
+    }
+
+    /// Validates the `@composeDirective` applications of a subgraph, returning the names of the
+    /// directives it composes.
+    fn collect_composed_directives(
+        &mut self,
+        subgraph: &ValidFederationSubgraph,
+    ) -> IndexSet<Name> {
+        let mut composed = IndexSet::default();
+        let Some(metadata) = subgraph.schema.metadata() else {
+            return composed;
+        };
+        let Some(federation_link) = metadata.for_identity(&Identity::federation_identity()) else {
+            return composed;
+        };
+        let schema = subgraph.schema.schema();
+        let compose_directive = federation_link.directive_name_in_schema(&COMPOSE_DIRECTIVE_NAME);
+        for application in schema
+            .schema_definition
+            .directives
+            .get_all(&compose_directive)
+        {
+            let argument = directive_string_arg_value(application, &name!("name")).unwrap_or("");
+            let Some(name) = argument
+                .strip_prefix('@')
+                .and_then(|name| Name::new(name).ok())
+            else {
+                self.error_at(
+                    SingleFederationError::DirectiveCompositionError {
+                        message: format!(
+                            "Argument to @composeDirective \"{argument}\" in subgraph \"{}\" must be a directive name with a leading \"@\"",
+                            subgraph.name
+                        ),
+                    },
+                    [application.location()],
+                );
+                continue;
+            };
+            let Some(definition) = schema.directive_definitions.get(&name) else {
+                self.error_at(
+                    SingleFederationError::DirectiveCompositionError {
+                        message: format!(
+                            "Could not find matching directive definition for argument to @composeDirective \"@{name}\" in subgraph \"{}\"",
+                            subgraph.name
+                        ),
+                    },
+                    [application.location()],
+                );
+                continue;
+            };
+            let Some(linked) = metadata.source_link_of_directive(&name) else {
+                self.error_at(
+                    SingleFederationError::DirectiveCompositionError {
+                        message: format!(
+                            "Directive \"@{name}\" in subgraph \"{}\" cannot be composed because it is not a member of a core feature",
+                            subgraph.name
+                        ),
+                    },
+                    [application.location()],
+                );
+                continue;
+            };
+            let identity = &linked.link.url.identity;
+            if *identity == Identity::federation_identity()
+                || *identity == Identity::link_identity()
+            {
+                self.error_at(
+                    SingleFederationError::DirectiveCompositionError {
+                        message: format!(
+                            "Composing directive \"@{name}\" of the {identity} specification in subgraph \"{}\" is not supported",
+                            subgraph.name
+                        ),
+                    },
+                    [application.location()],
+                );
+                continue;
+            }
+
+            let conflict = match self.composed_directives.entry(name.clone()) {
+                Vacant(entry) => {
+                    entry.insert(ComposedDirective {
+                        url: linked.link.url.clone(),
+                        import: linked.import,
+                        definition: definition.clone(),
+                    });
+                    None
+                }
+                Occupied(mut entry) => {
+                    let existing = entry.get_mut();
+                    let version = &linked.link.url.version;
+                    if existing.url.identity != *identity {
+                        Some(format!(
+                            "Composed directive \"@{name}\" is not linked by the same core feature in every subgraph: it comes from {} in some subgraphs but from {identity} in subgraph \"{}\"",
+                            existing.url.identity, subgraph.name
+                        ))
+                    } else if existing.url.version.major != version.major {
+                        Some(format!(
+                            "Core feature \"{identity}\" requested to be merged has major version mismatch across subgraphs: v{} and v{version}",
+                            existing.url.version
+                        ))
+                    } else {
+                        // The supergraph uses the definition of the highest version
+                        if version.minor > existing.url.version.minor {
+                            existing.url = linked.link.url.clone();
+                            existing.definition = definition.clone();
+                        }
+                        None
+                    }
+                }
+            };
+            if let Some(message) = conflict {
+                self.error_at(
+                    SingleFederationError::DirectiveCompositionError { message },
+                    [application.location()],
+                );
+                continue;
+            }
+            composed.insert(name);
+        }
+        composed
+    }
+
+    /// Links the features of the composed directives, and adds their definitions
+    fn add_composed_directives(&mut self, supergraph: &mut Schema) {
+        let mut imports_by_url: IndexMap<String, Vec<Node<Value>>> = IndexMap::default();
+        for directive in self.composed_directives.values() {
+            let imports = imports_by_url.entry(directive.url.to_string()).or_default();
+            if let Some(import) = &directive.import {
+                imports.push(Node::new(import_value(import)));
+            }
+            supergraph.directive_definitions.insert(
+                directive.definition.name.clone(),
+                directive.definition.clone(),
+            );
+        }
+
+        for (url, imports) in imports_by_url {
+            let mut arguments = vec![Node::new(Argument {
+                name: name!("url"),
+                value: url.into(),
+            })];
+            if !imports.is_empty() {
+                arguments.push(Node::new(Argument {
+                    name: name!("import"),
+                    value: Node::new(Value::List(imports)),
+                }));
+            }
+            supergraph
+                .schema_definition
+                .make_mut()
+                .directives
+                .push(Component::new(Directive {
+                    name: name!("link"),
+                    arguments,
+                }));
+        }
+    }
+
+    /// Merges the executable directive definitions: the supergraph only keeps the directives,
+    /// locations and arguments every subgraph can handle.
+    fn merge_executable_directives(&mut self, supergraph: &mut Schema) {
+        for (name, sources) in std::mem::take(&mut self.executable_directives) {
+            if supergraph.directive_definitions.contains_key(&name) {
+                // built-in directives
+                continue;
+            }
+            if sources.len() < self.subgraph_names.len() {
+                let missing = self
+                    .subgraph_names
+                    .keys()
+                    .filter(|graph| sources.iter().all(|(source, _)| source != *graph))
+                    .collect_vec();
+                self.hint(
+                    HintCode::InconsistentExecutableDirectivePresence,
+                    format!(
+                        "Executable directive \"@{name}\" will not be part of the supergraph as it does not appear in all subgraphs: it is defined in subgraphs {} but not in subgraphs {}",
+                        self.subgraph_list(sources.iter().map(|(graph, _)| graph)),
+                        self.subgraph_list(missing),
+                    ),
+                );
+                continue;
+            }
+
+            let mut merged = DirectiveDefinition::clone(&sources[0].1);
+            merged
+                .locations
+                .retain(|location| EXECUTABLE_DIRECTIVE_LOCATIONS.contains(location));
+            let mut inconsistent_locations = false;
+            for (_, definition) in &sources[1..] {
+                let locations_before = merged.locations.len();
+                merged
+                    .locations
+                    .retain(|location| definition.locations.contains(location));
+                let executable_locations = definition
+                    .locations
+                    .iter()
+                    .filter(|location| EXECUTABLE_DIRECTIVE_LOCATIONS.contains(location))
+                    .count();
+                inconsistent_locations |= merged.locations.len() != locations_before
+                    || merged.locations.len() != executable_locations;
+                self.merge_descriptions(
+                    &mut merged.description,
+                    &definition.description,
+                    format_args!("@{name}"),
+                );
+            }
+            if merged.locations.is_empty() {
+                self.hint(
+                    HintCode::NoExecutableDirectiveLocationsIntersection,
+                    format!(
+                        "Executable directive \"@{name}\" has no location that is common to all subgraphs, and will not be part of the supergraph"
+                    ),
+                );
+                continue;
+            }
+            if inconsistent_locations {
+                self.hint(
+                    HintCode::InconsistentExecutableDirectiveLocations,
+                    format!(
+                        "Executable directive \"@{name}\" has inconsistent locations across subgraphs, and will use {} in the supergraph, the locations common to all subgraphs",
+                        merged.locations.iter().join(" | ")
+                    ),
+                );
+            }
+            if merged.repeatable && sources.iter().any(|(_, definition)| !definition.repeatable) {
+                merged.repeatable = false;
+                self.hint(
+                    HintCode::InconsistentExecutableDirectiveRepeatable,
+                    format!(
+                        "Executable directive \"@{name}\" is not repeatable in all subgraphs, and will not be repeatable in the supergraph"
+                    ),
+                );
+            }
+
+            merged.arguments = self.merge_directive_arguments(&name, &sources);
+            supergraph
+                .directive_definitions
+                .insert(name, Node::new(merged));
+        }
+    }
+
+    fn merge_directive_arguments(
+        &mut self,
+        directive_name: &Name,
+        sources: &[(Name, Node<DirectiveDefinition>)],
+    ) -> Vec<Node<InputValueDefinition>> {
+        let argument_names: IndexSet<&Name> = sources
+            .iter()
+            .flat_map(|(_, definition)| definition.arguments.iter().map(|arg| &arg.name))
+            .collect();
+        let mut arguments = vec![];
+        for argument_name in argument_names {
+            let coordinate = format!("@{directive_name}({argument_name}:)");
+            let definitions = sources
+                .iter()
+                .filter_map(|(graph, definition)| {
+                    definition
+                        .arguments
+                        .iter()
+                        .find(|arg| arg.name == *argument_name)
+                        .map(|arg| (graph, arg))
+                })
+                .collect_vec();
+            if definitions.len() < sources.len() {
+                let graphs = definitions.iter().map(|(graph, _)| *graph).collect_vec();
+                if definitions
+                    .iter()
+                    .any(|(_, arg)| arg.ty.is_non_null() && arg.default_value.is_none())
+                {
+                    self.errors
+                        .push(SingleFederationError::RequiredArgumentMissingInSomeSubgraph {
+                            message: format!(
+                                "Argument \"{coordinate}\" is required in some subgraphs but does not appear in all subgraphs: it is defined in subgraphs {}",
+                                self.subgraph_list(graphs),
+                            ),
+                        });
+                } else {
+                    self.hint(
+                        HintCode::InconsistentArgumentPresence,
+                        format!(
+                            "Optional argument \"{coordinate}\" will not be included in the supergraph as it does not appear in all subgraphs: it is defined in subgraphs {}",
+                            self.subgraph_list(graphs),
+                        ),
+                    );
+                }
+                continue;
+            }
+
+            let mut merged = definitions[0].1.clone();
+            for (_, definition) in &definitions[1..] {
+                self.merge_input_value(
+                    &mut merged,
+                    definition,
+                    &coordinate,
+                    InputPosition::DirectiveArgument,
+                );
+            }
+            arguments.push(merged);
+        }
+        arguments
+    }
+
+    /// Merges the type, default value and description of an input field or argument with its
+    /// definition in another subgraph
+    fn merge_input_value(
+        &mut self,
+        merged: &mut Node<InputValueDefinition>,
+        definition: &Node<InputValueDefinition>,
+        coordinate: &str,
+        position: InputPosition,
+    ) {
+        let locations = [merged.location(), definition.location()];
+        let merged = merged.make_mut();
+        self.merge_descriptions(&mut merged.description, &definition.description, coordinate);
+
+        if merged.ty != definition.ty {
+            if let Some(ty) = merge_input_types(&merged.ty, &definition.ty) {
+                self.hint_at(
+                    position.compatible_type_hint(),
+                    format!(
+                        "Type of {position} \"{coordinate}\" is inconsistent but compatible across subgraphs: the supergraph uses the stricter type \"{ty}\""
+                    ),
+                    locations,
+                );
+                merged.ty = ty.into();
+            } else {
+                self.error_at(
+                    position.type_mismatch(format!(
+                        "Type of {position} \"{coordinate}\" is incompatible across subgraphs: it has type \"{}\" in some subgraphs but type \"{}\" in others",
+                        merged.ty, definition.ty
+                    )),
+                    locations,
+                );
+            }
+        }
+
+        match (&merged.default_value, &definition.default_value) {
+            (Some(a), Some(b)) if a != b => {
+                self.error_at(
+                    position.default_mismatch(format!(
+                        "Default value of {position} \"{coordinate}\" is incompatible across subgraphs: it has default value {a} in some subgraphs but {b} in others",
+                    )),
+                    locations,
+                );
+            }
+            (None, Some(_)) => merged.default_value.clone_from(&definition.default_value),
+            _ => {}
+        }
+    }
+
+    /// Removes the input fields that are not in all the subgraphs defining their type, as values
+    /// for them couldn't be sent to every subgraph.
+    fn merge_input_object_fields(&mut self, supergraph: &mut Schema) {
+        for (type_name, sources) in std::mem::take(&mut self.input_object_sources) {
+            let Some(ExtendedType::InputObject(input_object)) =
+                supergraph.types.get_mut(&type_name)
+            else {
+                continue;
+            };
+            let input_object = input_object.make_mut();
+            for (field_name, graphs) in &sources.field_sources {
+                let coordinate = format!("{type_name}.{field_name}");
+                if graphs.len() < sources.type_sources.len() {
+                    let is_required = input_object
+                        .fields
+                        .get(field_name)
+                        .is_some_and(|field| field.is_required());
+                    let missing = sources
+                        .type_sources
+                        .iter()
+                        .filter(|graph| !graphs.contains(graph))
+                        .collect_vec();
+                    if is_required {
+                        self.errors
+                            .push(SingleFederationError::RequiredInputFieldMissingInSomeSubgraph {
+                                message: format!(
+                                    "Input object field \"{coordinate}\" is required in some subgraphs but does not appear in all subgraphs: it is defined in subgraphs {} but not in subgraphs {}",
+                                    self.subgraph_list(graphs),
+                                    self.subgraph_list(missing),
+                                ),
+                            });
+                    } else {
+                        self.hint(
+                            HintCode::InconsistentInputObjectField,
+                            format!(
+                                "Input object field \"{coordinate}\" will not be added to the supergraph as it does not appear in all subgraphs: it is defined in subgraphs {} but not in subgraphs {}",
+                                self.subgraph_list(graphs),
+                                self.subgraph_list(missing),
+                            ),
+                        );
+                    }
+                    input_object.fields.shift_remove(field_name);
+                    continue;
+                }
+
+                let default_values = sources.default_values.get(field_name).copied().unwrap_or(0);
+                if default_values > 0 && default_values < graphs.len() {
+                    self.hint(
+                        HintCode::InconsistentDefaultValuePresence,
+                        format!(
+                            "Input object field \"{coordinate}\" has a default value in only some subgraphs: the supergraph uses this default value"
+                        ),
+                    );
+                }
+            }
+            if input_object.fields.is_empty() {
+                self.errors
+                    .push(SingleFederationError::EmptyMergedInputType {
+                        message: format!(
+                            "None of the fields of input object type \"{type_name}\" are consistently defined in all the subgraphs defining that type. As only fields common to all subgraphs are merged, this would result in an empty type."
+                        ),
+                    });
+            }
+        }
+    }
+
+    /// Adds `@join__field` to the interface fields that are not in all the subgraphs defining the
+    /// interface, and adds the fields of `@interfaceObject` types to the interface implementations.
+    fn merge_interface_fields(&mut self, supergraph: &mut Schema) {
+        let interface_objects = std::mem::take(&mut self.interface_objects);
+        for (type_name, graphs) in &interface_objects {
+            if !self.interface_definitions.contains(type_name) {
+                self.errors
+                    .push(SingleFederationError::InterfaceObjectUsageError {
+                        message: format!(
+                            "Type \"{type_name}\" is declared with @interfaceObject in all the subgraphs in which it is defined (it is defined in subgraphs {} but should be defined as an interface in at least one subgraph)",
+                            self.subgraph_list(graphs),
+                        ),
+                    });
+            }
+        }
+
+        // The fields provided by an @interfaceObject, for each interface
+        let mut interface_object_fields: IndexMap<Name, Vec<Component<FieldDefinition>>> =
+            IndexMap::default();
+        for (type_name, sources) in std::mem::take(&mut self.interface_sources) {
+            let Some(ExtendedType::Interface(interface)) = supergraph.types.get_mut(&type_name)
+            else {
+                continue;
+            };
+            let interface = interface.make_mut();
+            let interface_object_graphs = interface_objects.get(&type_name);
+            for (field_name, graphs) in sources.field_sources {
+                let Some(field) = interface.fields.get_mut(&field_name) else {
+                    continue;
+                };
+                if interface_object_graphs
+                    .is_some_and(|object_graphs| graphs.iter().any(|g| object_graphs.contains(g)))
+                {
+                    interface_object_fields
+                        .entry(type_name.clone())
+                        .or_default()
+                        .push(Component::new(FieldDefinition {
+                            directives: Default::default(),
+                            ..FieldDefinition::clone(field)
+                        }));
+                }
+                if graphs.len() == sources.type_sources.len() {
+                    continue;
+                }
+                for graph in graphs {
+                    field
+                        .make_mut()
+                        .directives
+                        .push(Node::new(join_field_applied_directive(
+                            graph, None, None, false, None,
+                        )));
+                }
+            }
+        }
+
+        // The implementations get the fields resolved through an @interfaceObject, with a
+        // `@join__field` without graph as no subgraph defines them on the implementation
+        for ty in supergraph.types.values_mut() {
+            let ExtendedType::Object(object) = ty else {
+                continue;
+            };
+            let missing_fields = object
+                .implements_interfaces
+                .iter()
+                .filter_map(|interface| interface_object_fields.get(&interface.name))
+                .flatten()
+                .filter(|field| !object.fields.contains_key(&field.name))
+                .cloned()
+                .collect_vec();
+            for mut field in missing_fields {
+                field.make_mut().directives.push(Node::new(Directive {
+                    name: name!("join__field"),
+                    arguments: vec![],
+                }));
+                object.make_mut().fields.insert(field.name.clone(), field);
+            }
+        }
+    }
+
+    /// Hints at the `@external` fields that no `@key`, `@requires` or `@provides` of the subgraph
+    /// uses, and records the object fields the subgraph resolves.
+    fn check_object_fields(
+        &mut self,
+        subgraph: &ValidFederationSubgraph,
+        subgraph_name: &Name,
+        directive_names: &DirectiveNames,
+    ) {
+        let schema = subgraph.schema.schema();
+        let mut key_fields = IndexSet::default();
+        let mut used_fields = IndexSet::default();
+        for ty in schema.types.values() {
+            let (type_name, fields, directives) = match ty {
+                ExtendedType::Object(object) => (&object.name, &object.fields, &object.directives),
+                ExtendedType::Interface(intf) => (&intf.name, &intf.fields, &intf.directives),
+                _ => continue,
+            };
+            for key in directives.get_all(&directive_names.key) {
+                key_fields.extend(field_set_targets(schema, type_name, key));
+            }
+            for field in fields.values() {
+                for requires in field.directives.get_all(&directive_names.requires) {
+                    used_fields.extend(field_set_targets(schema, type_name, requires));
+                }
+                for provides in field.directives.get_all(&directive_names.provides) {
+                    used_fields.extend(field_set_targets(
+                        schema,
+                        field.ty.inner_named_type(),
+                        provides,
+                    ));
+                }
+            }
+        }
+
+        for (type_name, ty) in &schema.types {
+            if ty.is_built_in() || !is_mergeable_type(type_name) {
+                continue;
+            }
+            let ExtendedType::Object(object) = ty else {
+                continue;
+            };
+            let is_shareable_type = object.directives.has(&directive_names.shareable);
+            for (field_name, field) in &object.fields {
+                if field_name == "_service" || field_name == "_entities" {
+                    continue;
+                }
+                let coordinate = (object.name.clone(), field_name.clone());
+                let is_key_field = key_fields.contains(&coordinate);
+                if field.directives.has(&directive_names.external) {
+                    // external fields can also be used to satisfy an interface
+                    let implements_field = object.implements_interfaces.iter().any(|intf| {
+                        schema
+                            .get_interface(intf)
+                            .is_some_and(|intf| intf.fields.contains_key(field_name))
+                    });
+                    if !is_key_field && !used_fields.contains(&coordinate) && !implements_field {
+                        self.hint_at(
+                            HintCode::UnusedExternal,
+                            format!(
+                                "Field \"{}.{field_name}\" is marked @external in subgraph \"{}\" but is not used by any @key, @requires or @provides",
+                                object.name, subgraph.name,
+                            ),
+                            [field.location()],
+                        );
+                    }
+                    continue;
+                }
+                self.shared_fields
+                    .entry(object.name.clone())
+                    .or_default()
+                    .entry(field_name.clone())
+                    .or_default()
+                    .push(SharedField {
+                        graph: subgraph_name.clone(),
+                        is_shareable: is_shareable_type
+                            || is_key_field
+                            || field.directives.has(&directive_names.shareable),
+                        is_overridden: field.directives.has(&directive_names.r#override),
+                        location: field.location(),
+                    });
+            }
+        }
+    }
+
+    /// Hints at the fields resolved by several subgraphs that are not `@shareable` in all of them.
+    fn hint_missing_shareable(&mut self) {
+        for (type_name, fields) in std::mem::take(&mut self.shared_fields) {
+            for (field_name, sources) in fields {
+                if sources.len() < 2 || sources.iter().any(|source| source.is_overridden) {
+                    continue;
+                }
+                let unmarked = sources
+                    .iter()
+                    .filter(|source| !source.is_shareable)
+                    .collect_vec();
+                if unmarked.is_empty() {
+                    continue;
+                }
+                self.hint_at(
+                    HintCode::MissingShareable,
+                    format!(
+                        "Field \"{type_name}.{field_name}\" is resolved by subgraphs {} but is not marked @shareable in subgraphs {}: if these subgraphs resolve it the same way, it could be marked @shareable in all of them",
+                        self.subgraph_list(sources.iter().map(|source| &source.graph)),
+                        self.subgraph_list(unmarked.iter().map(|source| &source.graph)),
+                    ),
+                    unmarked.iter().map(|source| source.location),
+                );
+            }
+        }
+    }
+
     fn merge_schema(&mut self, supergraph_schema: &mut Schema, subgraph: &ValidFederationSubgraph) {
         let supergraph_def = &mut supergraph_schema.schema_definition.make_mut();
         let subgraph_def = &subgraph.schema.schema().schema_definition;
-        self.merge_descriptions(&mut supergraph_def.description, &subgraph_def.description);
+        self.merge_descriptions(
+            &mut supergraph_def.description,
+            &subgraph_def.description,
+            "schema",
+        );
 
         if subgraph_def.query.is_some() {
             supergraph_def.query.clone_from(&subgraph_def.query);
@@ -304,20 +1243,24 @@ impl Merger {
     ) {
         let existing_type = types
             .entry(enum_name.clone())
-            .or_insert(copy_enum_type(enum_name, enum_type));
+            .or_insert(copy_enum_type(enum_name.clone(), enum_type));
 
         if let ExtendedType::Enum(e) = existing_type {
             let join_type_directives =
                 join_type_applied_directive(subgraph_name.clone(), iter::empty(), false);
             e.make_mut().directives.extend(join_type_directives);
 
-            self.add_inaccessible(
+            self.merge_applied_directives(
                 metadata,
                 &mut e.make_mut().directives,
                 &enum_type.directives,
             );
 
-            self.merge_descriptions(&mut e.make_mut().description, &enum_type.description);
+            self.merge_descriptions(
+                &mut e.make_mut().description,
+                &enum_type.description,
+                &enum_type.name,
+            );
 
             // TODO we need to merge those fields LAST so we know whether enum is used as input/output/both as different merge rules will apply
             // below logic only works for output enums
@@ -331,9 +1274,13 @@ impl Merger {
                         description: None,
                         directives: Default::default(),
                     }));
-                self.merge_descriptions(&mut ev.make_mut().description, &enum_value.description);
+                self.merge_descriptions(
+                    &mut ev.make_mut().description,
+                    &enum_value.description,
+                    format_args!("{}.{enum_value_name}", enum_type.name),
+                );
 
-                self.add_inaccessible(
+                self.merge_applied_directives(
                     metadata,
                     &mut ev.make_mut().directives,
                     &enum_value.directives,
@@ -350,7 +1297,13 @@ impl Merger {
                 }));
             }
         } else {
-            // TODO - conflict
+            self.type_kind_mismatch(
+                existing_type,
+                &enum_name,
+                &subgraph_name,
+                "Enum Type",
+                enum_type.location(),
+            );
         }
     }
 
@@ -362,45 +1315,90 @@ impl Merger {
         input_object_name: NamedType,
         input_object: &Node<InputObjectType>,
     ) {
-        let existing_type = types
-            .entry(input_object_name.clone())
-            .or_insert(copy_input_object_type(input_object_name, input_object));
+        let existing_type =
+            types
+                .entry(input_object_name.clone())
+                .or_insert(copy_input_object_type(
+                    input_object_name.clone(),
+                    input_object,
+                ));
 
         if let ExtendedType::InputObject(obj) = existing_type {
             let join_type_directives =
-                join_type_applied_directive(subgraph_name, iter::empty(), false);
+                join_type_applied_directive(subgraph_name.clone(), iter::empty(), false);
             let mutable_object = obj.make_mut();
             mutable_object.directives.extend(join_type_directives);
 
-            self.add_inaccessible(
+            self.merge_applied_directives(
                 directive_names,
                 &mut mutable_object.directives,
                 &input_object.directives,
             );
+            self.merge_descriptions(
+                &mut mutable_object.description,
+                &input_object.description,
+                &input_object_name,
+            );
 
+            let sources = self
+                .input_object_sources
+                .entry(input_object_name.clone())
+                .or_default();
+            sources.type_sources.push(subgraph_name.clone());
             for (field_name, field) in input_object.fields.iter() {
-                let existing_field = mutable_object.fields.entry(field_name.clone());
+                sources
+                    .field_sources
+                    .entry(field_name.clone())
+                    .or_default()
+                    .push(subgraph_name.clone());
+                if field.default_value.is_some() {
+                    *sources
+                        .default_values
+                        .entry(field_name.clone())
+                        .or_default() += 1;
+                }
+            }
 
-                match existing_field {
-                    Vacant(_i) => {
-                        // TODO warning - mismatch on input fields
-                    }
-                    Occupied(mut i) => {
-                        self.add_inaccessible(
-                            directive_names,
-                            &mut i.get_mut().make_mut().directives,
-                            &field.directives,
+            // Fields missing from some subgraphs are removed once all subgraphs are merged
+            for (field_name, field) in input_object.fields.iter() {
+                let existing_field = mutable_object.fields.entry(field_name.clone());
+                let supergraph_field = match existing_field {
+                    // keep the location of the field to report conflicts with other subgraphs
+                    Vacant(i) => i.insert(Component {
+                        origin: field.origin.clone(),
+                        node: field.same_location(InputValueDefinition {
+                            name: field.name.clone(),
+                            description: field.description.clone(),
+                            directives: Default::default(),
+                            ty: field.ty.clone(),
+                            default_value: field.default_value.clone(),
+                        }),
+                    }),
+                    Occupied(i) => {
+                        let supergraph_field = i.into_mut();
+                        self.merge_input_value(
+                            &mut supergraph_field.node,
+                            field,
+                            &format!("{input_object_name}.{field_name}"),
+                            InputPosition::InputField,
                         );
-                        // merge_options(&i.get_mut().description, &field.description);
-                        // TODO check description
-                        // TODO check type
-                        // TODO check default value
-                        // TODO process directives
+                        supergraph_field
                     }
-                }
+                };
+                self.merge_applied_directives(
+                    directive_names,
+                    &mut supergraph_field.make_mut().directives,
+                    &field.directives,
+                );
             }
         } else {
-            // TODO conflict on type
+            self.type_kind_mismatch(
+                existing_type,
+                &input_object_name,
+                &subgraph_name,
+                "Input Object Type",
+                input_object.location(),
+            );
         }
     }
 
@@ -414,50 +1412,84 @@ impl Merger {
     ) {
         let existing_type = types
             .entry(interface_name.clone())
-            .or_insert(copy_interface_type(interface_name, interface));
+            .or_insert(copy_interface_type(interface_name.clone(), interface));
+        self.interface_definitions.insert(interface_name.clone());
 
         if let ExtendedType::Interface(intf) = existing_type {
             let key_directives = interface.directives.get_all(&directive_names.key);
             let join_type_directives =
-                join_type_applied_directive(subgraph_name, key_directives, false);
+                join_type_applied_directive(subgraph_name.clone(), key_directives, false);
             let mutable_intf = intf.make_mut();
             mutable_intf.directives.extend(join_type_directives);
 
-            self.add_inaccessible(
+            self.merge_applied_directives(
                 directive_names,
                 &mut mutable_intf.directives,
                 &interface.directives,
             );
+            self.merge_descriptions(
+                &mut mutable_intf.description,
+                &interface.description,
+                &interface_name,
+            );
 
             for (field_name, field) in interface.fields.iter() {
                 let existing_field = mutable_intf.fields.entry(field_name.clone());
-                match existing_field {
-                    Vacant(i) => {
-                        // TODO warning mismatch missing fields
-                        let f = i.insert(Component::new(FieldDefinition {
-                            name: field.name.clone(),
-                            description: field.description.clone(),
-                            arguments: vec![],
-                            ty: field.ty.clone(),
-                            directives: Default::default(),
-                        }));
-
-                        self.add_inaccessible(
-                            directive_names,
-                            &mut f.make_mut().directives,
-                            &field.directives,
-                        );
-                    }
-                    Occupied(_i) => {
-                        // TODO check description
-                        // TODO check type
-                        // TODO check default value
-                        // TODO process directives
-                    }
-                }
+                let supergraph_field = match existing_field {
+                    Vacant(i) => i.insert(Component::new(FieldDefinition {
+                        name: field.name.clone(),
+                        description: field.description.clone(),
+                        arguments: vec![],
+                        ty: field.ty.clone(),
+                        directives: Default::default(),
+                    })),
+                    Occupied(i) => i.into_mut(),
+                };
+                self.merge_descriptions(
+                    &mut supergraph_field.make_mut().description,
+                    &field.description,
+                    format_args!("{interface_name}.{field_name}"),
+                );
+                self.merge_applied_directives(
+                    directive_names,
+                    &mut supergraph_field.make_mut().directives,
+                    &field.directives,
+                );
             }
+            self.record_interface_fields(&interface_name, &subgraph_name, interface.fields.keys());
         } else {
-            // TODO conflict on type
+            self.type_kind_mismatch(
+                existing_type,
+                &interface_name,
+                &subgraph_name,
+                "Interface Type",
+                interface.location(),
+            );
+        }
+    }
+
+    /// Records the subgraph defining an interface and its fields, to find the fields that need
+    /// `@join__field`
+    fn record_interface_fields<'a>(
+        &mut self,
+        interface_name: &Name,
+        subgraph_name: &Name,
+        fields: impl Iterator<Item = &'a Name>,
+    ) {
+        let sources = self
+            .interface_sources
+            .entry(interface_name.clone())
+            .or_default();
+        sources.type_sources.push(subgraph_name.clone());
+        for field_name in fields {
+            if field_name == "_service" || field_name == "_entities" {
+                continue;
+            }
+            sources
+                .field_sources
+                .entry(field_name.clone())
+                .or_default()
+                .push(subgraph_name.clone());
         }
     }
 
@@ -484,8 +1516,12 @@ impl Merger {
                 join_type_applied_directive(subgraph_name.clone(), key_directives, false);
             let mutable_object = obj.make_mut();
             mutable_object.directives.extend(join_type_directives);
-            self.merge_descriptions(&mut mutable_object.description, &object.description);
-            self.add_inaccessible(
+            self.merge_descriptions(
+                &mut mutable_object.description,
+                &object.description,
+                &object_name,
+            );
+            self.merge_applied_directives(
                 directive_names,
                 &mut mutable_object.directives,
                 &object.directives,
@@ -525,9 +1561,10 @@ impl Merger {
                 self.merge_descriptions(
                     &mut supergraph_field.make_mut().description,
                     &field.description,
+                    format_args!("{object_name}.{field_name}"),
                 );
 
-                self.add_inaccessible(
+                self.merge_applied_directives(
                     directive_names,
                     &mut supergraph_field.make_mut().directives,
                     &field.directives,
@@ -540,7 +1577,7 @@ impl Merger {
                         .find_map(|a| (a.name == arg.name).then(|| a.make_mut()));
 
                     if let Some(argument) = argument_to_merge {
-                        self.add_inaccessible(
+                        self.merge_applied_directives(
                             directive_names,
                             &mut argument.directives,
                             &arg.directives,
@@ -554,7 +1591,7 @@ impl Merger {
                             default_value: arg.default_value.clone(),
                         };
 
-                        self.add_inaccessible(
+                        self.merge_applied_directives(
                             directive_names,
                             &mut argument.directives,
                             &arg.directives,
@@ -609,14 +1646,67 @@ impl Merger {
                 // TODO: implement needsJoinField to avoid adding join__field when unnecessary
                 // https://github.com/apollographql/federation/blob/0d8a88585d901dff6844fdce1146a4539dec48df/composition-js/src/merging/merge.ts#L1648
             }
-        } else if let ExtendedType::Interface(intf) = existing_type {
-            // TODO support interface object
+        } else if let (ExtendedType::Interface(intf), true) =
+            (&mut *existing_type, is_interface_object)
+        {
             let key_directives = object.directives.get_all(&directive_names.key);
             let join_type_directives =
-                join_type_applied_directive(subgraph_name, key_directives, true);
-            intf.make_mut().directives.extend(join_type_directives);
-        };
-        // TODO merge fields
+                join_type_applied_directive(subgraph_name.clone(), key_directives, true);
+            let mutable_intf = intf.make_mut();
+            mutable_intf.directives.extend(join_type_directives);
+            self.merge_applied_directives(
+                directive_names,
+                &mut mutable_intf.directives,
+                &object.directives,
+            );
+            self.merge_descriptions(
+                &mut mutable_intf.description,
+                &object.description,
+                &object_name,
+            );
+
+            // The fields of an @interfaceObject are the fields it adds to the interface
+            for (field_name, field) in object.fields.iter() {
+                if field_name == "_service" || field_name == "_entities" {
+                    continue;
+                }
+                let supergraph_field = mutable_intf
+                    .fields
+                    .entry(field_name.clone())
+                    .or_insert_with(|| {
+                        Component::new(FieldDefinition {
+                            name: field.name.clone(),
+                            description: field.description.clone(),
+                            arguments: field.arguments.clone(),
+                            ty: field.ty.clone(),
+                            directives: Default::default(),
+                        })
+                    });
+                self.merge_descriptions(
+                    &mut supergraph_field.make_mut().description,
+                    &field.description,
+                    format_args!("{object_name}.{field_name}"),
+                );
+                self.merge_applied_directives(
+                    directive_names,
+                    &mut supergraph_field.make_mut().directives,
+                    &field.directives,
+                );
+            }
+            self.interface_objects
+                .entry(object_name.clone())
+                .or_default()
+                .push(subgraph_name.clone());
+            self.record_interface_fields(&object_name, &subgraph_name, object.fields.keys());
+        } else {
+            self.type_kind_mismatch(
+                existing_type,
+                &object_name,
+                &subgraph_name,
+                "Object Type",
+                object.location(),
+            );
+        }
     }
 
     fn merge_union_type(
@@ -636,7 +1726,7 @@ impl Merger {
             let join_type_directives =
                 join_type_applied_directive(subgraph_name.clone(), iter::empty(), false);
             u.make_mut().directives.extend(join_type_directives);
-            self.add_inaccessible(
+            self.merge_applied_directives(
                 directive_names,
                 &mut u.make_mut().directives,
                 &union.directives,
@@ -659,6 +1749,14 @@ impl Merger {
                     ],
                 }));
             }
+        } else {
+            self.type_kind_mismatch(
+                existing_type,
+                &union_name,
+                &subgraph_name,
+                "Union Type",
+                union.location(),
+            );
         }
     }
 
@@ -672,19 +1770,66 @@ impl Merger {
     ) {
         let existing_type = types
             .entry(scalar_name.clone())
-            .or_insert(copy_scalar_type(scalar_name, ty));
+            .or_insert(copy_scalar_type(scalar_name.clone(), ty));
 
         if let ExtendedType::Scalar(s) = existing_type {
             let join_type_directives =
                 join_type_applied_directive(subgraph_name.clone(), iter::empty(), false);
             s.make_mut().directives.extend(join_type_directives);
-            self.add_inaccessible(
+            self.merge_applied_directives(
                 directive_names,
                 &mut s.make_mut().directives,
                 &ty.directives,
             );
         } else {
-            // conflict?
+            self.type_kind_mismatch(
+                existing_type,
+                &scalar_name,
+                &subgraph_name,
+                "Scalar Type",
+                ty.location(),
+            );
+        }
+    }
+
+    /// Adds `@inaccessible` and the composed directives applied in the subgraph
+    // generic so it handles ast::DirectiveList and schema::DirectiveList
+    fn merge_applied_directives<I>(
+        &mut self,
+        directive_names: &DirectiveNames,
+        new_directives: &mut Vec<I>,
+        original_directives: &[I],
+    ) where
+        I: AsRef<Directive> + From<Directive> + Clone,
+    {
+        self.add_inaccessible(directive_names, new_directives, original_directives);
+
+        for directive in original_directives {
+            let directive = directive.as_ref();
+            if !directive_names.composed.contains(&directive.name)
+                || new_directives.iter().any(|d| d.as_ref() == directive)
+            {
+                continue;
+            }
+            let is_repeatable = self
+                .composed_directives
+                .get(&directive.name)
+                .is_some_and(|composed| composed.definition.repeatable);
+            if !is_repeatable
+                && new_directives
+                    .iter()
+                    .any(|d| d.as_ref().name == directive.name)
+            {
+                self.hint(
+                    HintCode::InconsistentNonRepeatableDirectiveArguments,
+                    format!(
+                        "Non-repeatable directive \"@{}\" is applied with different arguments across subgraphs: the supergraph keeps the first application",
+                        directive.name
+                    ),
+                );
+                continue;
+            }
+            new_directives.push(directive.clone().into());
         }
     }
 
@@ -725,10 +1870,13 @@ struct DirectiveNames {
     interface_object: Name,
     r#override: Name,
     inaccessible: Name,
+    shareable: Name,
+    /// The directives composed by the subgraph with `@composeDirective`
+    composed: IndexSet<Name>,
 }
 
 impl DirectiveNames {
-    fn for_metadata(metadata: &Option<&LinksMetadata>) -> Self {
+    fn for_metadata(metadata: &Option<&LinksMetadata>, composed: IndexSet<Name>) -> Self {
         let federation_identity =
             metadata.and_then(|m| m.by_identity.get(&Identity::federation_identity()));
 
@@ -762,6 +1910,10 @@ impl DirectiveNames {
             .map(|link| link.directive_name_in_schema(&INACCESSIBLE_DIRECTIVE_NAME_IN_SPEC))
             .unwrap_or(INACCESSIBLE_DIRECTIVE_NAME_IN_SPEC);
 
+        let shareable = federation_identity
+            .map(|link| link.directive_name_in_schema(&FEDERATION_SHAREABLE_DIRECTIVE_NAME_IN_SPEC))
+            .unwrap_or(FEDERATION_SHAREABLE_DIRECTIVE_NAME_IN_SPEC);
+
         Self {
             key,
             requires,
@@ -770,10 +1922,109 @@ impl DirectiveNames {
             interface_object,
             r#override,
             inaccessible,
+            shareable,
+            composed,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum InputPosition {
+    InputField,
+    DirectiveArgument,
+}
+
+impl InputPosition {
+    fn compatible_type_hint(self) -> HintCode {
+        match self {
+            Self::InputField => HintCode::InconsistentButCompatibleFieldType,
+            Self::DirectiveArgument => HintCode::InconsistentButCompatibleArgumentType,
+        }
+    }
+
+    fn type_mismatch(self, message: String) -> SingleFederationError {
+        match self {
+            Self::InputField => SingleFederationError::FieldTypeMismatch { message },
+            Self::DirectiveArgument => SingleFederationError::FieldArgumentTypeMismatch { message },
+        }
+    }
+
+    fn default_mismatch(self, message: String) -> SingleFederationError {
+        match self {
+            Self::InputField => SingleFederationError::InputFieldDefaultMismatch { message },
+            Self::DirectiveArgument => {
+                SingleFederationError::FieldArgumentDefaultMismatch { message }
+            }
         }
     }
 }
 
+impl Display for InputPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InputField => f.write_str("input field"),
+            Self::DirectiveArgument => f.write_str("argument"),
+        }
+    }
+}
+
+/// Merges the types of an input position in two subgraphs. Types only differing by their
+/// nullability are compatible, and merge to the stricter type, which all subgraphs accept.
+fn merge_input_types(a: &Type, b: &Type) -> Option<Type> {
+    let merged = match (a.is_list(), b.is_list()) {
+        (false, false) if a.inner_named_type() == b.inner_named_type() => {
+            Type::Named(a.inner_named_type().clone())
+        }
+        (true, true) => merge_input_types(a.item_type(), b.item_type())?.list(),
+        _ => return None,
+    };
+    if a.is_non_null() || b.is_non_null() {
+        Some(merged.non_null())
+    } else {
+        Some(merged)
+    }
+}
+
+/// The `@link(import:)` value of an imported directive
+fn import_value(import: &Import) -> Value {
+    let name = format!("@{}", import.element);
+    match &import.alias {
+        Some(alias) => Value::Object(vec![
+            (name!("name"), Node::new(name.into())),
+            (name!("as"), Node::new(format!("@{alias}").into())),
+        ]),
+        None => name.into(),
+    }
+}
+
+/// The type and name of the fields selected by a `@key`, `@requires` or `@provides`, ignoring
+/// invalid field sets
+fn field_set_targets(
+    schema: &Valid<Schema>,
+    parent_type_name: &Name,
+    directive: &Directive,
+) -> Vec<(Name, Name)> {
+    directive_string_arg_value(directive, &FEDERATION_FIELDS_ARGUMENT_NAME)
+        .and_then(|fields| {
+            collect_target_fields_from_field_set(schema, parent_type_name.clone(), fields).ok()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|field| (field.type_name().clone(), field.field_name().clone()))
+        .collect()
+}
+
+fn type_kind(ty: &ExtendedType) -> &'static str {
+    match ty {
+        ExtendedType::Scalar(_) => "Scalar Type",
+        ExtendedType::Object(_) => "Object Type",
+        ExtendedType::Interface(_) => "Interface Type",
+        ExtendedType::Union(_) => "Union Type",
+        ExtendedType::Enum(_) => "Enum Type",
+        ExtendedType::InputObject(_) => "Input Object Type",
+    }
+}
+
 const EXECUTABLE_DIRECTIVE_LOCATIONS: [DirectiveLocation; 8] = [
     DirectiveLocation::Query,
     DirectiveLocation::Mutation,
@@ -802,7 +2053,7 @@ fn is_mergeable_type(type_name: &str) -> bool {
 }
 
 fn copy_scalar_type(scalar_name: Name, scalar_type: &Node<ScalarType>) -> ExtendedType {
-    ExtendedType::Scalar(Node::new(ScalarType {
+    ExtendedType::Scalar(scalar_type.same_location(ScalarType {
         description: scalar_type.description.clone(),
         name: scalar_name,
         directives: Default::default(),
@@ -810,7 +2061,7 @@ fn copy_scalar_type(scalar_name: Name, scalar_type: &Node<ScalarType>) -> Extend
 }
 
 fn copy_enum_type(enum_name: Name, enum_type: &Node<EnumType>) -> ExtendedType {
-    ExtendedType::Enum(Node::new(EnumType {
+    ExtendedType::Enum(enum_type.same_location(EnumType {
         description: enum_type.description.clone(),
         name: enum_name,
         directives: Default::default(),
@@ -832,17 +2083,20 @@ fn copy_input_object_type(
     for (field_name, input_field) in input_object.fields.iter() {
         new_input_object.fields.insert(
             field_name.clone(),
-            Component::new(InputValueDefinition {
-                name: input_field.name.clone(),
-                description: input_field.description.clone(),
-                directives: Default::default(),
-                ty: input_field.ty.clone(),
-                default_value: input_field.default_value.clone(),
-            }),
+            Component {
+                origin: input_field.origin.clone(),
+                node: input_field.same_location(InputValueDefinition {
+                    name: input_field.name.clone(),
+                    description: input_field.description.clone(),
+                    directives: Default::default(),
+                    ty: input_field.ty.clone(),
+                    default_value: input_field.default_value.clone(),
+                }),
+            },
         );
     }
 
-    ExtendedType::InputObject(Node::new(new_input_object))
+    ExtendedType::InputObject(input_object.same_location(new_input_object))
 }
 
 fn copy_interface_type(interface_name: Name, interface: &Node<InterfaceType>) -> ExtendedType {
@@ -853,7 +2107,7 @@ fn copy_interface_type(interface_name: Name, interface: &Node<InterfaceType>) ->
         fields: copy_fields(interface.fields.iter()),
         implements_interfaces: interface.implements_interfaces.clone(),
     };
-    ExtendedType::Interface(Node::new(new_interface))
+    ExtendedType::Interface(interface.same_location(new_interface))
 }
 
 fn copy_object_type_stub(
@@ -869,7 +2123,7 @@ fn copy_object_type_stub(
             fields: copy_fields(object.fields.iter()),
             implements_interfaces: object.implements_interfaces.clone(),
         };
-        ExtendedType::Interface(Node::new(new_interface))
+        ExtendedType::Interface(object.same_location(new_interface))
     } else {
         let new_object = ObjectType {
             description: object.description.clone(),
@@ -878,7 +2132,7 @@ fn copy_object_type_stub(
             fields: copy_fields(object.fields.iter()),
             implements_interfaces: object.implements_interfaces.clone(),
         };
-        ExtendedType::Object(Node::new(new_object))
+        ExtendedType::Object(object.same_location(new_object))
     }
 }
 
@@ -1592,22 +2846,17 @@ fn parse_keys<'a>(
     )
 }
 
-fn merge_directive(
-    supergraph_directives: &mut IndexMap<Name, Node<DirectiveDefinition>>,
-    directive: &Node<DirectiveDefinition>,
-) {
-    if !supergraph_directives.contains_key(&directive.name.clone()) {
-        supergraph_directives.insert(directive.name.clone(), directive.clone());
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use apollo_compiler::Schema;
     use insta::assert_snapshot;
 
     use crate::merge::merge_federation_subgraphs;
+    use crate::merge::merge_subgraphs;
+    use crate::merge::MergeFailure;
     use crate::schema::ValidFederationSchema;
+    use crate::subgraph::Subgraph;
+    use crate::subgraph::ValidSubgraph;
     use crate::ValidFederationSubgraph;
     use crate::ValidFederationSubgraphs;
 
@@ -1743,4 +2992,516 @@ mod tests {
 
         assert_snapshot!(schema.serialize());
     }
+
+    fn subgraph(name: &str, sdl: &str) -> ValidSubgraph {
+        Subgraph::parse_and_expand(name, &format!("https://{name}"), sdl).unwrap()
+    }
+
+    fn merge_errors(subgraphs: &[ValidSubgraph]) -> MergeFailure {
+        match merge_subgraphs(subgraphs.iter().collect()) {
+            Ok(_) => panic!("expected merge errors"),
+            Err(failure) => failure,
+        }
+    }
+
+    fn display_errors(failure: &MergeFailure) -> String {
+        failure
+            .errors
+            .iter()
+            .map(|error| format!("[{}] {error}", error.code().definition().code()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn display_hints<'a>(hints: impl IntoIterator<Item = &'a super::CompositionHint>) -> String {
+        hints
+            .into_iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_input_object_field_conflicts() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema @link(url: "https://specs.apollo.dev/federation/v2.5")
+            type Query { a(input: In): Int }
+            input In {
+              "from a"
+              x: Int! = 1
+              y: String
+              optional: Int
+              required: ID!
+            }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema @link(url: "https://specs.apollo.dev/federation/v2.5")
+            type Query { b(input: In): Int }
+            input In {
+              "from b"
+              x: Int = 2
+              y: [String]
+            }
+            "#,
+        );
+
+        let failure = merge_errors(&[a, b]);
+        assert_snapshot!(display_errors(&failure), @r###"
+        [INPUT_FIELD_DEFAULT_MISMATCH] Default value of input field "In.x" is incompatible across subgraphs: it has default value 1 in some subgraphs but 2 in others
+        [FIELD_TYPE_MISMATCH] Type of input field "In.y" is incompatible across subgraphs: it has type "String" in some subgraphs but type "[String]" in others
+        [REQUIRED_INPUT_FIELD_MISSING_IN_SOME_SUBGRAPH] Input object field "In.required" is required in some subgraphs but does not appear in all subgraphs: it is defined in subgraphs "a" but not in subgraphs "b"
+        "###);
+        assert_snapshot!(display_hints(&failure.composition_hints), @r###"
+        [INCONSISTENT_DESCRIPTION] Element "In.x" has inconsistent descriptions across subgraphs: the supergraph keeps the first description
+        [INCONSISTENT_BUT_COMPATIBLE_FIELD_TYPE] Type of input field "In.x" is inconsistent but compatible across subgraphs: the supergraph uses the stricter type "Int!"
+        [INCONSISTENT_INPUT_OBJECT_FIELD] Input object field "In.optional" will not be added to the supergraph as it does not appear in all subgraphs: it is defined in subgraphs "a" but not in subgraphs "b"
+        "###);
+    }
+
+    #[test]
+    fn test_compatible_input_object_fields() {
+        let a = subgraph(
+            "a",
+            r#"
+            type Query { a(input: In): Int }
+            input In { x: Int!, y: [String] = ["a"], z: Int }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            type Query { b(input: In): Int }
+            input In { x: Int, y: [String!] }
+            "#,
+        );
+
+        let result = merge_subgraphs(vec![&a, &b]).unwrap();
+        assert_snapshot!(display_hints(&result.composition_hints), @r###"
+        [INCONSISTENT_BUT_COMPATIBLE_FIELD_TYPE] Type of input field "In.x" is inconsistent but compatible across subgraphs: the supergraph uses the stricter type "Int!"
+        [INCONSISTENT_BUT_COMPATIBLE_FIELD_TYPE] Type of input field "In.y" is inconsistent but compatible across subgraphs: the supergraph uses the stricter type "[String!]"
+        [INCONSISTENT_DEFAULT_VALUE_PRESENCE] Input object field "In.y" has a default value in only some subgraphs: the supergraph uses this default value
+        [INCONSISTENT_INPUT_OBJECT_FIELD] Input object field "In.z" will not be added to the supergraph as it does not appear in all subgraphs: it is defined in subgraphs "a" but not in subgraphs "b"
+        "###);
+        let schema = result.schema.into_inner();
+        assert_snapshot!(schema.types["In"].serialize(), @r###"
+        input In @join__type(graph: A) @join__type(graph: B) {
+          x: Int!
+          y: [String!] = [
+            "a",
+          ]
+        }
+
+        "###);
+    }
+
+    #[test]
+    fn test_executable_directives() {
+        let a = subgraph(
+            "a",
+            r#"
+            directive @both(x: Int, y: Int) repeatable on FIELD | QUERY | OBJECT
+            directive @onlyA on FIELD
+            directive @noCommonLocation on QUERY
+            type Query { a: Int }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            directive @both(x: Int!) on FIELD | FRAGMENT_SPREAD
+            directive @noCommonLocation on MUTATION
+            type Query { b: Int }
+            "#,
+        );
+
+        let result = merge_subgraphs(vec![&a, &b]).unwrap();
+        assert_snapshot!(display_hints(&result.composition_hints), @r###"
+        [INCONSISTENT_EXECUTABLE_DIRECTIVE_LOCATIONS] Executable directive "@both" has inconsistent locations across subgraphs, and will use FIELD in the supergraph, the locations common to all subgraphs
+        [INCONSISTENT_EXECUTABLE_DIRECTIVE_REPEATABLE] Executable directive "@both" is not repeatable in all subgraphs, and will not be repeatable in the supergraph
+        [INCONSISTENT_BUT_COMPATIBLE_ARGUMENT_TYPE] Type of argument "@both(x:)" is inconsistent but compatible across subgraphs: the supergraph uses the stricter type "Int!"
+        [INCONSISTENT_ARGUMENT_PRESENCE] Optional argument "@both(y:)" will not be included in the supergraph as it does not appear in all subgraphs: it is defined in subgraphs "a"
+        [INCONSISTENT_EXECUTABLE_DIRECTIVE_PRESENCE] Executable directive "@onlyA" will not be part of the supergraph as it does not appear in all subgraphs: it is defined in subgraphs "a" but not in subgraphs "b"
+        [NO_EXECUTABLE_DIRECTIVE_LOCATIONS_INTERSECTION] Executable directive "@noCommonLocation" has no location that is common to all subgraphs, and will not be part of the supergraph
+        "###);
+        let schema = result.schema.into_inner();
+        assert!(!schema.directive_definitions.contains_key("onlyA"));
+        assert!(!schema
+            .directive_definitions
+            .contains_key("noCommonLocation"));
+        assert_snapshot!(schema.directive_definitions["both"].serialize(), @r###"
+        directive @both(x: Int!) on FIELD
+        "###);
+    }
+
+    #[test]
+    fn test_compose_directive() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@composeDirective"])
+              @link(url: "https://custom.dev/custom/v1.0", import: ["@custom"])
+              @composeDirective(name: "@custom")
+            directive @custom(name: String) on OBJECT | FIELD_DEFINITION
+            type Query { t: T }
+            type T @key(fields: "id") @custom(name: "t") {
+              id: ID!
+              a: Int @custom(name: "a")
+            }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@composeDirective"])
+              @link(url: "https://custom.dev/custom/v1.1", import: ["@custom"])
+              @composeDirective(name: "@custom")
+            directive @custom(name: String, other: Int) on OBJECT | FIELD_DEFINITION
+            type T @key(fields: "id") @custom(name: "t") {
+              id: ID!
+              b: Int @custom(name: "b")
+            }
+            "#,
+        );
+
+        let result = merge_subgraphs(vec![&a, &b]).unwrap();
+        let schema = result.schema.into_inner();
+        let validation = schema.clone().validate();
+        assert!(validation.is_ok(), "{:?}", validation);
+        assert!(schema
+            .schema_definition
+            .directives
+            .get_all("link")
+            .any(|link| link.to_string()
+                == r#"@link(url: "https://custom.dev/custom/v1.1", import: ["@custom"])"#));
+        assert_snapshot!(schema.directive_definitions["custom"].serialize(), @r###"
+        directive @custom(name: String, other: Int) on OBJECT | FIELD_DEFINITION
+        "###);
+        assert_snapshot!(schema.types["T"].serialize(), @r###"
+        type T @join__type(graph: A, key: "id") @custom(name: "t") @join__type(graph: B, key: "id") {
+          id: ID! @join__field(graph: A) @join__field(graph: B)
+          a: Int @custom(name: "a") @join__field(graph: A)
+          b: Int @custom(name: "b") @join__field(graph: B)
+        }
+
+        "###);
+    }
+
+    #[test]
+    fn test_compose_directive_errors() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@composeDirective"])
+              @link(url: "https://custom.dev/custom/v1.0", import: ["@custom"])
+              @composeDirective(name: "@key")
+              @composeDirective(name: "@unknown")
+              @composeDirective(name: "@unlinked")
+              @composeDirective(name: "custom")
+            directive @custom on OBJECT
+            directive @unlinked on OBJECT
+            type Query { a: Int }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@composeDirective"])
+              @link(url: "https://custom.dev/custom/v2.0", import: ["@custom"])
+              @composeDirective(name: "@custom")
+            directive @custom on OBJECT
+            type Query { b: Int }
+            "#,
+        );
+        let c = subgraph(
+            "c",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@composeDirective"])
+              @link(url: "https://other.dev/custom/v1.0", import: ["@custom"])
+              @composeDirective(name: "@custom")
+            directive @custom on OBJECT
+            type Query { c: Int }
+            "#,
+        );
+        let d = subgraph(
+            "d",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@composeDirective"])
+              @link(url: "https://custom.dev/custom/v1.0", import: ["@custom"])
+              @composeDirective(name: "@custom")
+            directive @custom on OBJECT
+            type Query { d: Int }
+            "#,
+        );
+
+        let failure = merge_errors(&[a, b, c, d]);
+        assert_snapshot!(display_errors(&failure), @r###"
+        [DIRECTIVE_COMPOSITION_ERROR] Composing directive "@key" of the https://specs.apollo.dev/federation specification in subgraph "a" is not supported
+        [DIRECTIVE_COMPOSITION_ERROR] Could not find matching directive definition for argument to @composeDirective "@unknown" in subgraph "a"
+        [DIRECTIVE_COMPOSITION_ERROR] Directive "@unlinked" in subgraph "a" cannot be composed because it is not a member of a core feature
+        [DIRECTIVE_COMPOSITION_ERROR] Argument to @composeDirective "custom" in subgraph "a" must be a directive name with a leading "@"
+        [DIRECTIVE_COMPOSITION_ERROR] Composed directive "@custom" is not linked by the same core feature in every subgraph: it comes from https://custom.dev/custom in some subgraphs but from https://other.dev/custom in subgraph "c"
+        [DIRECTIVE_COMPOSITION_ERROR] Core feature "https://custom.dev/custom" requested to be merged has major version mismatch across subgraphs: v2.0 and v1.0
+        "###);
+    }
+
+    #[test]
+    fn test_interface_object() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key"])
+            type Query { i: I }
+            interface I { id: ID! }
+            type T implements I @key(fields: "id") { id: ID! }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@interfaceObject"])
+            type I @key(fields: "id") @interfaceObject {
+              id: ID!
+              extra: Int
+            }
+            "#,
+        );
+
+        let result = merge_subgraphs(vec![&a, &b]).unwrap();
+        let schema = result.schema.into_inner();
+        let validation = schema.clone().validate();
+        assert!(validation.is_ok(), "{:?}", validation);
+        assert_snapshot!(schema.types["I"].serialize(), @r###"
+        interface I @join__type(graph: A) @join__type(graph: B, isInterfaceObject: true, key: "id") {
+          id: ID!
+          extra: Int @join__field(graph: B)
+        }
+
+        "###);
+        assert_snapshot!(schema.types["T"].serialize(), @r###"
+        type T implements I @join__type(graph: A, key: "id") @join__implements(graph: A, interface: "I") {
+          id: ID! @join__field(graph: A)
+          extra: Int @join__field
+        }
+
+        "###);
+    }
+
+    #[test]
+    fn test_interface_object_without_interface() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@interfaceObject"])
+            type Query { i: I }
+            type I @key(fields: "id") @interfaceObject { id: ID! }
+            "#,
+        );
+
+        let failure = merge_errors(&[a]);
+        assert_snapshot!(display_errors(&failure), @r###"
+        [INTERFACE_OBJECT_USAGE_ERROR] Type "I" is declared with @interfaceObject in all the subgraphs in which it is defined (it is defined in subgraphs "a" but should be defined as an interface in at least one subgraph)
+        "###);
+    }
+
+    #[test]
+    fn test_federation_version_of_directives() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.2", import: ["@key", "@interfaceObject"])
+            type Query { i: I }
+            type I @key(fields: "id") @interfaceObject { id: ID! }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema @link(url: "https://specs.apollo.dev/federation/v2.2", import: ["@key"])
+            interface I { id: ID! }
+            type T implements I @key(fields: "id") { id: ID! }
+            "#,
+        );
+
+        let failure = merge_errors(&[a, b]);
+        assert_snapshot!(display_errors(&failure), @r###"
+        [UNSUPPORTED_FEDERATION_DIRECTIVE] Directive "@interfaceObject" used in subgraph "a" requires federation v2.3, but the subgraph links federation v2.2
+        "###);
+    }
+
+    #[test]
+    fn test_type_kind_mismatch() {
+        let a = subgraph("a", "type Query { a: T } type T { a: Int }");
+        let b = subgraph("b", "type Query { b: Int } input T { b: Int }");
+
+        let failure = merge_errors(&[a, b]);
+        assert_snapshot!(display_errors(&failure), @r###"
+        [TYPE_KIND_MISMATCH] Type "T" has mismatched kind: it is defined as Input Object Type in subgraph "b" but as Object Type in other subgraphs
+        "###);
+    }
+    #[test]
+    fn test_hints_with_locations() {
+        let a = subgraph(
+            "a",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@external", "@shareable"])
+            type Query { t: T }
+            type T @key(fields: "id") {
+              id: ID!
+              "the name of T"
+              name: String @shareable
+              price: Int
+              weight: Int @external
+            }
+            "#,
+        );
+        let b = subgraph(
+            "b",
+            r#"
+            extend schema
+              @link(url: "https://specs.apollo.dev/federation/v2.5", import: ["@key", "@shareable"])
+            type T @key(fields: "id") {
+              id: ID!
+              "the name"
+              name: String @shareable
+              price: Int
+            }
+            "#,
+        );
+
+        let result = merge_subgraphs(vec![&a, &b]).unwrap();
+        insta::assert_json_snapshot!(result.composition_hints, @r###"
+        [
+          {
+            "code": "UNUSED_EXTERNAL",
+            "message": "Field \"T.weight\" is marked @external in subgraph \"a\" but is not used by any @key, @requires or @provides",
+            "locations": [
+              {
+                "subgraph": "a",
+                "start": {
+                  "line": 10,
+                  "column": 15
+                },
+                "end": {
+                  "line": 10,
+                  "column": 36
+                }
+              }
+            ]
+          },
+          {
+            "code": "INCONSISTENT_DESCRIPTION",
+            "message": "Element \"T.name\" has inconsistent descriptions across subgraphs: the supergraph keeps the first description",
+            "locations": [
+              {
+                "subgraph": "a",
+                "start": {
+                  "line": 7,
+                  "column": 15
+                },
+                "end": {
+                  "line": 7,
+                  "column": 30
+                }
+              },
+              {
+                "subgraph": "b",
+                "start": {
+                  "line": 6,
+                  "column": 15
+                },
+                "end": {
+                  "line": 6,
+                  "column": 25
+                }
+              }
+            ]
+          },
+          {
+            "code": "MISSING_SHAREABLE",
+            "message": "Field \"T.price\" is resolved by subgraphs \"a\", \"b\" but is not marked @shareable in subgraphs \"a\", \"b\": if these subgraphs resolve it the same way, it could be marked @shareable in all of them",
+            "locations": [
+              {
+                "subgraph": "a",
+                "start": {
+                  "line": 9,
+                  "column": 15
+                },
+                "end": {
+                  "line": 9,
+                  "column": 25
+                }
+              },
+              {
+                "subgraph": "b",
+                "start": {
+                  "line": 8,
+                  "column": 15
+                },
+                "end": {
+                  "line": 8,
+                  "column": 25
+                }
+              }
+            ]
+          }
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_error_locations() {
+        let a = subgraph(
+            "a",
+            "type Query { a(input: In): Int }\ninput In { x: Int = 1 }",
+        );
+        let b = subgraph(
+            "b",
+            "type Query { b(input: In): Int }\ninput In { x: Int = 2 }",
+        );
+
+        let failure = merge_errors(&[a, b]);
+        insta::assert_json_snapshot!(failure.error_locations, @r###"
+        [
+          [
+            {
+              "subgraph": "a",
+              "start": {
+                "line": 2,
+                "column": 12
+              },
+              "end": {
+                "line": 2,
+                "column": 22
+              }
+            },
+            {
+              "subgraph": "b",
+              "start": {
+                "line": 2,
+                "column": 12
+              },
+              "end": {
+                "line": 2,
+                "column": 22
+              }
+            }
+          ]
+        ]
+        "###);
+    }
 }
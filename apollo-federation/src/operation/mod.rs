@@ -33,6 +33,8 @@ use crate::compat::coerce_executable_values;
 use crate::error::FederationError;
 use crate::error::SingleFederationError;
 use crate::error::SingleFederationError::Internal;
+use crate::link::graphql_definition::BooleanOrVariable;
+use crate::link::graphql_definition::DeferDirectiveArguments;
 use crate::query_graph::graph_path::OpPathElement;
 use crate::query_plan::conditions::Conditions;
 use crate::query_plan::FetchDataKeyRenamer;
@@ -201,6 +203,58 @@ pub(crate) struct NormalizedDefer {
     pub defer_conditions: IndexMap<String, IndexSet<String>>,
 }
 
+/// Generates the missing `@defer` labels of an operation, and tracks the conditional `@defer`
+/// applications.
+// PORT_NOTE: In the JS codebase, this is `DeferNormalizer`.
+#[derive(Default)]
+struct DeferNormalizer {
+    used_labels: IndexSet<String>,
+    assigned_labels: IndexSet<String>,
+    /// Maps condition variable names to the labels of the `@defer` applications using them.
+    conditions: IndexMap<String, IndexSet<String>>,
+    next_index: usize,
+}
+
+impl DeferNormalizer {
+    fn new(selection_set: &SelectionSet) -> Result<Self, FederationError> {
+        let mut normalizer = Self::default();
+        let mut stack = selection_set.iter().collect::<Vec<_>>();
+        while let Some(selection) = stack.pop() {
+            if let Selection::InlineFragment(inline) = selection {
+                if let Some(label) = inline
+                    .inline_fragment
+                    .defer_directive_arguments()?
+                    .and_then(|args| args.label)
+                {
+                    normalizer.used_labels.insert(label);
+                }
+            }
+            stack.extend(selection.selection_set().into_iter().flat_map(|s| s.iter()));
+        }
+        Ok(normalizer)
+    }
+
+    fn new_label(&mut self) -> String {
+        loop {
+            // It's unlikely that an auto-generated label conflicts with an existing one, but we
+            // don't take any chances.
+            let label = format!("qp__{}", self.next_index);
+            self.next_index += 1;
+            if !self.used_labels.contains(&label) {
+                self.assigned_labels.insert(label.clone());
+                return label;
+            }
+        }
+    }
+
+    fn register_condition(&mut self, label: String, condition: Name) {
+        self.conditions
+            .entry(condition.to_string())
+            .or_default()
+            .insert(label);
+    }
+}
+
 impl Operation {
     /// Parse an operation from a source string.
     #[cfg(any(test, doc))]
@@ -240,29 +294,37 @@ impl Operation {
         })
     }
 
-    // PORT_NOTE(@goto-bus-stop): It might make sense for the returned data structure to *be* the
-    // `DeferNormalizer` from the JS side
-    pub(crate) fn with_normalized_defer(self) -> NormalizedDefer {
-        NormalizedDefer {
-            operation: self,
-            has_defers: false,
-            assigned_defer_labels: IndexSet::default(),
-            defer_conditions: IndexMap::default(),
-        }
-        // TODO(@TylerBloom): Once defer is implement, the above statement needs to be replaced
-        // with the commented-out one below. This is part of FED-95
-        /*
-        if self.has_defer() {
-            todo!("@defer not implemented");
-        } else {
-            NormalizedDefer {
+    /// Assigns a label to every `@defer` application of the operation, and removes the ones
+    /// disabled with `if: false`. The returned `NormalizedDefer` also records the labels that were
+    /// generated, and the labels of the applications conditioned on each variable.
+    pub(crate) fn with_normalized_defer(mut self) -> Result<NormalizedDefer, FederationError> {
+        if !self.selection_set.has_defer() {
+            return Ok(NormalizedDefer {
                 operation: self,
                 has_defers: false,
                 assigned_defer_labels: IndexSet::default(),
                 defer_conditions: IndexMap::default(),
-            }
+            });
         }
-        */
+        let mut normalizer = DeferNormalizer::new(&self.selection_set)?;
+        self.selection_set = self.selection_set.with_normalized_defer(&mut normalizer)?;
+        Ok(NormalizedDefer {
+            has_defers: self.selection_set.has_defer(),
+            operation: self,
+            assigned_defer_labels: normalizer.assigned_labels,
+            defer_conditions: normalizer.conditions,
+        })
+    }
+
+    /// Removes the `@defer` applications with the given labels, keeping their selections.
+    pub(crate) fn without_defer_labels(
+        &self,
+        labels: &IndexSet<String>,
+    ) -> Result<Self, FederationError> {
+        Ok(Self {
+            selection_set: self.selection_set.without_defer_labels(labels)?,
+            ..self.clone()
+        })
     }
 
     fn has_defer(&self) -> bool {
@@ -2679,6 +2741,111 @@ impl SelectionSet {
         Ok(())
     }
 
+    /// Replaces the deferred inline fragments of this selection set, recursively, with the result
+    /// of `mapper`.
+    // NOTE: `lazy_map` compares selections by key, and the key of a deferred fragment does not
+    // depend on the arguments of `@defer`, so the selection set is always rebuilt here.
+    fn map_deferred_fragments(
+        &self,
+        mapper: &mut impl FnMut(
+            &InlineFragmentSelection,
+            DeferDirectiveArguments,
+        ) -> Result<Selection, FederationError>,
+    ) -> Result<SelectionSet, FederationError> {
+        let mut updated_selections = MultiIndexMap::new();
+        for selection in self.selections.values() {
+            let updated = selection
+                .map_selection_set(|ss| ss.map_deferred_fragments(&mut *mapper).map(Some))?;
+            let updated = match &updated {
+                Selection::InlineFragment(inline) => {
+                    match inline.inline_fragment.defer_directive_arguments()? {
+                        Some(defer_args) => mapper(inline, defer_args)?,
+                        None => updated,
+                    }
+                }
+                _ => updated,
+            };
+            updated_selections.insert(updated.key(), updated);
+        }
+        Self::make_selection_set(
+            &self.schema,
+            &self.type_position,
+            updated_selections.values().map(|v| v.iter()),
+            /*named_fragments*/ &Default::default(),
+        )
+    }
+
+    /// Assigns a label to the `@defer` applications of this selection set, and removes the ones
+    /// with `if: false`.
+    fn with_normalized_defer(
+        &self,
+        normalizer: &mut DeferNormalizer,
+    ) -> Result<SelectionSet, FederationError> {
+        self.map_deferred_fragments(&mut |inline, defer_args| {
+            let other_directives = inline
+                .inline_fragment
+                .directives
+                .iter()
+                .filter(|directive| directive.name != "defer")
+                .cloned();
+            let condition = match defer_args.if_ {
+                Some(BooleanOrVariable::Boolean(false)) => {
+                    return Ok(inline
+                        .with_updated_directives(other_directives.collect::<DirectiveList>())
+                        .into());
+                }
+                Some(BooleanOrVariable::Boolean(true)) | None => None,
+                Some(BooleanOrVariable::Variable(variable)) => Some(variable),
+            };
+            let label = match defer_args.label {
+                Some(label) => label,
+                None => normalizer.new_label(),
+            };
+            let mut arguments = vec![Node::new(executable::Argument {
+                name: name!("label"),
+                value: Node::new(executable::Value::String(label.clone())),
+            })];
+            if let Some(condition) = condition {
+                normalizer.register_condition(label, condition.clone());
+                arguments.push(Node::new(executable::Argument {
+                    name: name!("if"),
+                    value: Node::new(executable::Value::Variable(condition)),
+                }));
+            }
+            let defer = Node::new(executable::Directive {
+                name: name!("defer"),
+                arguments,
+            });
+            Ok(inline
+                .with_updated_directives(
+                    other_directives
+                        .chain(std::iter::once(defer))
+                        .collect::<DirectiveList>(),
+                )
+                .into())
+        })
+    }
+
+    /// Removes the `@defer` applications with the given labels, keeping their selections.
+    fn without_defer_labels(&self, labels: &IndexSet<String>) -> Result<Self, FederationError> {
+        self.map_deferred_fragments(&mut |inline, defer_args| {
+            if !defer_args
+                .label
+                .is_some_and(|label| labels.contains(&label))
+            {
+                return Ok(inline.clone().into());
+            }
+            let directives = inline
+                .inline_fragment
+                .directives
+                .iter()
+                .filter(|directive| directive.name != "defer")
+                .cloned()
+                .collect::<DirectiveList>();
+            Ok(inline.with_updated_directives(directives).into())
+        })
+    }
+
     /// Removes the @defer directive from all selections without removing that selection.
     fn without_defer(&mut self) {
         for (_key, mut selection) in Arc::make_mut(&mut self.selections).iter_mut() {
@@ -169,9 +169,7 @@ impl ConditionNode {
                 state.indent()?;
                 if_clause.write_indented(state)?;
                 state.dedent()?;
-                state.write("},")?;
-
-                state.write("Else {")?;
+                state.write("} Else {")?;
                 state.indent()?;
                 else_clause.write_indented(state)?;
                 state.dedent()?;
@@ -238,11 +236,7 @@ impl PrimaryDeferBlock {
             state.indent()?;
 
             if let Some(sub_selection) = sub_selection {
-                state.write(
-                    sub_selection
-                        .serialize()
-                        .initial_indent_level(state.indent_level()),
-                )?;
+                write_selections(state, &sub_selection.selections)?;
                 if node.is_some() {
                     state.write(":")?;
                     state.new_line()?;
@@ -254,7 +248,7 @@ impl PrimaryDeferBlock {
 
             state.dedent()?;
         }
-        state.write("},")
+        state.write("}")
     }
 }
 
@@ -295,6 +289,10 @@ impl DeferredDeferBlock {
 
             if let Some(sub_selection) = sub_selection {
                 write_selections(state, &sub_selection.selections)?;
+                if node.is_some() {
+                    state.write(":")?;
+                    state.new_line()?;
+                }
             }
             if let Some(node) = node {
                 node.write_indented(state)?;
@@ -1548,11 +1548,12 @@ impl FetchDependencyGraph {
             if node.defer_ref == child.defer_ref {
                 children.push(child_index);
             } else {
-                let parent_defer_ref = node.defer_ref.as_ref().unwrap();
                 let Some(child_defer_ref) = &child.defer_ref else {
-                    panic!("{} has defer_ref `{parent_defer_ref}`, so its child {} cannot have a top-level defer_ref.",
-                           node.display(node_index),
-                           child.display(child_index),
+                    panic!(
+                        "{} has defer_ref `{}`, so its child {} cannot have a top-level defer_ref.",
+                        node.display(node_index),
+                        node.defer_ref.as_deref().unwrap_or_default(),
+                        child.display(child_index),
                     );
                 };
 
@@ -3258,7 +3259,7 @@ fn compute_nodes_for_key_resolution<'a>(
         conditions,
         stack_item.node_id,
         stack_item.node_path.clone(),
-        stack_item.defer_context.clone(),
+        defer_context_for_conditions(&stack_item.defer_context),
         &Default::default(),
     )?;
     created_nodes.extend(conditions_nodes.iter().copied());
@@ -13,6 +13,7 @@ pub(crate) mod display;
 pub(crate) mod fetch_dependency_graph;
 pub(crate) mod fetch_dependency_graph_processor;
 pub mod generate;
+pub mod output;
 pub mod query_planner;
 pub(crate) mod query_planning_traversal;
 
@@ -257,4 +258,64 @@ impl QueryPlan {
             statistics,
         }
     }
+
+    /// The number of fetches in the plan, counting the fetches of both branches of conditions
+    /// and of all deferred blocks.
+    pub fn fetch_count(&self) -> usize {
+        match &self.node {
+            None => 0,
+            Some(TopLevelPlanNode::Subscription(node)) => {
+                1 + node.rest.as_ref().map_or(0, |rest| rest.fetch_count())
+            }
+            Some(TopLevelPlanNode::Fetch(_)) => 1,
+            Some(TopLevelPlanNode::Sequence(node)) => {
+                node.nodes.iter().map(PlanNode::fetch_count).sum()
+            }
+            Some(TopLevelPlanNode::Parallel(node)) => {
+                node.nodes.iter().map(PlanNode::fetch_count).sum()
+            }
+            Some(TopLevelPlanNode::Flatten(node)) => node.node.fetch_count(),
+            Some(TopLevelPlanNode::Defer(node)) => node.fetch_count(),
+            Some(TopLevelPlanNode::Condition(node)) => node.fetch_count(),
+        }
+    }
+}
+
+impl PlanNode {
+    fn fetch_count(&self) -> usize {
+        match self {
+            PlanNode::Fetch(_) => 1,
+            PlanNode::Sequence(node) => node.nodes.iter().map(PlanNode::fetch_count).sum(),
+            PlanNode::Parallel(node) => node.nodes.iter().map(PlanNode::fetch_count).sum(),
+            PlanNode::Flatten(node) => node.node.fetch_count(),
+            PlanNode::Defer(node) => node.fetch_count(),
+            PlanNode::Condition(node) => node.fetch_count(),
+        }
+    }
+}
+
+impl DeferNode {
+    fn fetch_count(&self) -> usize {
+        let deferred = self
+            .deferred
+            .iter()
+            .filter_map(|block| block.node.as_ref())
+            .map(|node| node.fetch_count());
+        self.primary
+            .node
+            .iter()
+            .map(|node| node.fetch_count())
+            .chain(deferred)
+            .sum()
+    }
+}
+
+impl ConditionNode {
+    fn fetch_count(&self) -> usize {
+        self.if_clause
+            .iter()
+            .chain(&self.else_clause)
+            .map(|node| node.fetch_count())
+            .sum()
+    }
 }
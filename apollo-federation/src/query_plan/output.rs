@@ -0,0 +1,320 @@
+// Output module for query plans
+// - Renders query plans as Graphviz DOT graphs or Mermaid flowcharts, next to the text format of
+//   the `display` module.
+
+use std::fmt::Display;
+use std::fmt::Write;
+
+use petgraph::dot::Config;
+use petgraph::dot::Dot;
+use petgraph::graph::DiGraph;
+use petgraph::graph::NodeIndex;
+
+use super::ConditionNode;
+use super::DeferNode;
+use super::FetchNode;
+use super::FlattenNode;
+use super::PlanNode;
+use super::QueryPlan;
+use super::SubscriptionNode;
+use super::TopLevelPlanNode;
+
+/// A query plan as a graph of labelled nodes.
+///
+/// Plans are converted to this graph before being rendered, so that the plans executed by the
+/// router are rendered like the ones generated by this crate.
+#[derive(Debug, Default)]
+pub struct PlanDiagram {
+    /// Edges without label have an empty weight
+    graph: DiGraph<String, String>,
+}
+
+/// The parent of a node: its index, and the label of the edge to the node
+pub type Parent = (usize, Option<String>);
+
+impl PlanDiagram {
+    /// Adds the `QueryPlan` root node, returning the parent of the plan nodes
+    pub fn add_root(&mut self) -> Parent {
+        (self.graph.add_node("QueryPlan".to_string()).index(), None)
+    }
+
+    /// Adds a `Sequence` node, returning the parents of its `len` nodes. Their edges are numbered
+    /// in execution order
+    pub fn add_sequence(&mut self, parent: Parent, len: usize) -> Vec<Parent> {
+        let index = self.add_child(parent, "Sequence");
+        (1..=len)
+            .map(|position| (index, Some(position.to_string())))
+            .collect()
+    }
+
+    /// Adds a `Parallel` node, returning the parents of its `len` nodes
+    pub fn add_parallel(&mut self, parent: Parent, len: usize) -> Vec<Parent> {
+        let index = self.add_child(parent, "Parallel");
+        vec![(index, None); len]
+    }
+
+    pub fn add_fetch(&mut self, parent: Parent, service: &str, id: Option<impl Display>) {
+        let label = match id {
+            Some(id) => format!("Fetch(service: \"{service}\", id: {id})"),
+            None => format!("Fetch(service: \"{service}\")"),
+        };
+        self.add_child(parent, label);
+    }
+
+    /// Adds a `Flatten` node, returning the parent of its node
+    pub fn add_flatten(
+        &mut self,
+        parent: Parent,
+        path: impl IntoIterator<Item = impl Display>,
+    ) -> Parent {
+        let label = format!("Flatten(path: \"{}\")", join(path, "."));
+        (self.add_child(parent, label), None)
+    }
+
+    /// Adds a `Subscription` node, returning the parents of its primary and rest nodes
+    pub fn add_subscription(&mut self, parent: Parent) -> (Parent, Parent) {
+        let index = self.add_child(parent, "Subscription");
+        (
+            (index, Some("primary".to_string())),
+            (index, Some("rest".to_string())),
+        )
+    }
+
+    /// Adds a `Condition` node, or an `Include` or `Skip` node if it only has one clause.
+    /// Returns the parents of its if and else clauses
+    pub fn add_condition(
+        &mut self,
+        parent: Parent,
+        variable: &str,
+        has_if_clause: bool,
+        has_else_clause: bool,
+    ) -> (Parent, Parent) {
+        let (label, if_label, else_label) = match (has_if_clause, has_else_clause) {
+            (true, true) => (
+                format!("Condition(if: ${variable})"),
+                Some("then".to_string()),
+                Some("else".to_string()),
+            ),
+            (true, false) => (format!("Include(if: ${variable})"), None, None),
+            _ => (format!("Skip(if: ${variable})"), None, None),
+        };
+        let index = self.add_child(parent, label);
+        ((index, if_label), (index, else_label))
+    }
+
+    /// Adds a `Defer` node, returning its index for `add_deferred` and the parent of its primary
+    /// node
+    pub fn add_defer(&mut self, parent: Parent) -> (usize, Parent) {
+        let index = self.add_child(parent, "Defer");
+        (index, (index, Some("primary".to_string())))
+    }
+
+    /// Adds a `Deferred` node to a `Defer` node, returning the parent of its node
+    pub fn add_deferred(
+        &mut self,
+        defer: usize,
+        depends: impl IntoIterator<Item = impl Display>,
+        path: impl IntoIterator<Item = impl Display>,
+        label: Option<&str>,
+    ) -> Parent {
+        let depends = join(depends, ", ");
+        let path = join(path, "/");
+        let label = match label {
+            Some(label) => {
+                format!("Deferred(depends: [{depends}], path: \"{path}\", label: \"{label}\")")
+            }
+            None => format!("Deferred(depends: [{depends}], path: \"{path}\")"),
+        };
+        let index = self.add_child((defer, Some("deferred".to_string())), label);
+        (index, None)
+    }
+
+    fn add_child(&mut self, (parent, edge_label): Parent, label: impl Into<String>) -> usize {
+        let index = self.graph.add_node(label.into());
+        self.graph.add_edge(
+            NodeIndex::new(parent),
+            index,
+            edge_label.unwrap_or_default(),
+        );
+        index.index()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let config = [Config::NodeNoLabel, Config::EdgeNoLabel];
+        Dot::with_attr_getters(
+            &self.graph,
+            &config,
+            &(|_, edge| {
+                if edge.weight().is_empty() {
+                    String::new()
+                } else {
+                    format!("label=\"{}\"", escape_dot(edge.weight()))
+                }
+            }),
+            &(|_, (_, label)| format!("label=\"{}\"", escape_dot(label))),
+        )
+        .to_string()
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut output = String::from("flowchart TD\n");
+        for index in self.graph.node_indices() {
+            // Writing to a `String` cannot fail
+            let _ = writeln!(
+                output,
+                "    n{}[\"{}\"]",
+                index.index(),
+                escape_mermaid(&self.graph[index])
+            );
+        }
+        for edge in self.graph.raw_edges() {
+            let _ = if edge.weight.is_empty() {
+                writeln!(
+                    output,
+                    "    n{} --> n{}",
+                    edge.source().index(),
+                    edge.target().index()
+                )
+            } else {
+                writeln!(
+                    output,
+                    "    n{} -->|\"{}\"| n{}",
+                    edge.source().index(),
+                    escape_mermaid(&edge.weight),
+                    edge.target().index()
+                )
+            };
+        }
+        output
+    }
+}
+
+fn join(elements: impl IntoIterator<Item = impl Display>, separator: &str) -> String {
+    elements
+        .into_iter()
+        .map(|element| element.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(label: &str) -> String {
+    label.replace('"', "#quot;")
+}
+
+/// Renders the query plan as a Graphviz DOT graph
+pub fn to_dot(plan: &QueryPlan) -> String {
+    diagram(plan).to_dot()
+}
+
+/// Renders the query plan as a Mermaid flowchart
+pub fn to_mermaid(plan: &QueryPlan) -> String {
+    diagram(plan).to_mermaid()
+}
+
+fn diagram(plan: &QueryPlan) -> PlanDiagram {
+    let mut diagram = PlanDiagram::default();
+    let parent = diagram.add_root();
+    if let Some(node) = &plan.node {
+        match node {
+            TopLevelPlanNode::Subscription(node) => node.add_to(&mut diagram, parent),
+            TopLevelPlanNode::Fetch(node) => node.add_to(&mut diagram, parent),
+            TopLevelPlanNode::Sequence(node) => add_sequence(&mut diagram, parent, &node.nodes),
+            TopLevelPlanNode::Parallel(node) => add_parallel(&mut diagram, parent, &node.nodes),
+            TopLevelPlanNode::Flatten(node) => node.add_to(&mut diagram, parent),
+            TopLevelPlanNode::Defer(node) => node.add_to(&mut diagram, parent),
+            TopLevelPlanNode::Condition(node) => node.add_to(&mut diagram, parent),
+        }
+    }
+    diagram
+}
+
+impl PlanNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        match self {
+            Self::Fetch(node) => node.add_to(diagram, parent),
+            Self::Sequence(node) => add_sequence(diagram, parent, &node.nodes),
+            Self::Parallel(node) => add_parallel(diagram, parent, &node.nodes),
+            Self::Flatten(node) => node.add_to(diagram, parent),
+            Self::Defer(node) => node.add_to(diagram, parent),
+            Self::Condition(node) => node.add_to(diagram, parent),
+        }
+    }
+}
+
+fn add_sequence(diagram: &mut PlanDiagram, parent: Parent, nodes: &[PlanNode]) {
+    let parents = diagram.add_sequence(parent, nodes.len());
+    for (node, parent) in nodes.iter().zip(parents) {
+        node.add_to(diagram, parent);
+    }
+}
+
+fn add_parallel(diagram: &mut PlanDiagram, parent: Parent, nodes: &[PlanNode]) {
+    let parents = diagram.add_parallel(parent, nodes.len());
+    for (node, parent) in nodes.iter().zip(parents) {
+        node.add_to(diagram, parent);
+    }
+}
+
+impl SubscriptionNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        let (primary, rest) = diagram.add_subscription(parent);
+        self.primary.add_to(diagram, primary);
+        if let Some(node) = &self.rest {
+            node.add_to(diagram, rest);
+        }
+    }
+}
+
+impl FetchNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        diagram.add_fetch(parent, &self.subgraph_name, self.id);
+    }
+}
+
+impl FlattenNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        let parent = diagram.add_flatten(parent, &self.path);
+        self.node.add_to(diagram, parent);
+    }
+}
+
+impl ConditionNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        let (if_parent, else_parent) = diagram.add_condition(
+            parent,
+            &self.condition_variable,
+            self.if_clause.is_some(),
+            self.else_clause.is_some(),
+        );
+        if let Some(if_clause) = &self.if_clause {
+            if_clause.add_to(diagram, if_parent);
+        }
+        if let Some(else_clause) = &self.else_clause {
+            else_clause.add_to(diagram, else_parent);
+        }
+    }
+}
+
+impl DeferNode {
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        let (index, primary) = diagram.add_defer(parent);
+        if let Some(node) = &self.primary.node {
+            node.add_to(diagram, primary);
+        }
+        for deferred in &self.deferred {
+            let parent = diagram.add_deferred(
+                index,
+                deferred.depends.iter().map(|dependency| &dependency.id),
+                &deferred.query_path,
+                deferred.label.as_deref(),
+            );
+            if let Some(node) = &deferred.node {
+                node.add_to(diagram, parent);
+            }
+        }
+    }
+}
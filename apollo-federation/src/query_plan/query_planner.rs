@@ -1,6 +1,8 @@
 use std::cell::Cell;
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use apollo_compiler::collections::IndexMap;
 use apollo_compiler::collections::IndexSet;
@@ -16,6 +18,7 @@ use crate::error::SingleFederationError;
 use crate::link::federation_spec_definition::FederationSpecDefinition;
 use crate::operation::normalize_operation;
 use crate::operation::NamedFragments;
+use crate::operation::NormalizedDefer;
 use crate::operation::Operation;
 use crate::operation::SelectionSet;
 use crate::query_graph::build_federated_query_graph;
@@ -30,6 +33,7 @@ use crate::query_plan::fetch_dependency_graph_processor::FetchDependencyGraphToQ
 use crate::query_plan::query_planning_traversal::BestQueryPlanInfo;
 use crate::query_plan::query_planning_traversal::QueryPlanningParameters;
 use crate::query_plan::query_planning_traversal::QueryPlanningTraversal;
+use crate::query_plan::ConditionNode;
 use crate::query_plan::FetchNode;
 use crate::query_plan::PlanNode;
 use crate::query_plan::QueryPlan;
@@ -143,9 +147,21 @@ pub struct QueryPlannerDebugConfig {
     /// debug a specific issue (with unexpectedly slow query planning for instance). Remember that
     /// setting this value too low can negatively affect query runtime (due to the use of
     /// sub-optimal query plans).
-    // TODO: should there additionally be a max_evaluated_cost?
     pub max_evaluated_plans: NonZeroU32,
 
+    /// The maximum cost of the plan returned by the query planner. The cost of the best plan
+    /// evaluated for a query can only be known once planning completes, and planning fails with a
+    /// `MAX_EVALUATED_COST_EXCEEDED` error if it is higher than this value.
+    ///
+    /// The default value is None, which specifies no limit.
+    pub max_evaluated_cost: Option<u64>,
+
+    /// The maximum wall-clock duration of planning a query. Query planning aborts with a
+    /// `QUERY_PLANNING_TIMEOUT` error once it is exceeded.
+    ///
+    /// The default value is None, which specifies no limit.
+    pub planning_timeout: Option<Duration>,
+
     /// Before creating query plans, for each path of fields in the query we compute all the
     /// possible options to traverse that path via the subgraphs. Multiple options can arise because
     /// fields in the path can be provided by multiple subgraphs, and abstract types (i.e. unions
@@ -166,6 +182,8 @@ impl Default for QueryPlannerDebugConfig {
         Self {
             bypass_planner_for_single_subgraph: false,
             max_evaluated_plans: NonZeroU32::new(10_000).unwrap(),
+            max_evaluated_cost: None,
+            planning_timeout: None,
             paths_limit: None,
         }
     }
@@ -345,6 +363,11 @@ impl QueryPlanner {
         let is_subscription = operation.is_subscription();
 
         let statistics = QueryPlanningStatistics::default();
+        let deadline = self
+            .config
+            .debug
+            .planning_timeout
+            .map(|timeout| Instant::now() + timeout);
 
         if self.config.debug.bypass_planner_for_single_subgraph {
             let mut subgraphs = self.federated_query_graph.subgraphs();
@@ -379,37 +402,35 @@ impl QueryPlanner {
             &self.interface_types_with_interface_objects,
         )?;
 
-        let (normalized_operation, assigned_defer_labels, defer_conditions, has_defers) = (
-            normalized_operation.without_defer(),
-            None,
-            None::<IndexMap<String, IndexSet<String>>>,
-            false,
-        );
-        /* TODO(TylerBloom): After defer is impl-ed and after the private preview, the call
-         * above needs to be replaced with this if-else expression.
-        if self.config.incremental_delivery.enable_defer {
-            let NormalizedDefer {
-                operation,
-                assigned_defer_labels,
-                defer_conditions,
-                has_defers,
-            } = normalized_operation.with_normalized_defer();
-            if has_defers && is_subscription {
-                return Err(SingleFederationError::DeferredSubscriptionUnsupported.into());
-            }
-            (
-                operation,
-                Some(assigned_defer_labels),
-                Some(defer_conditions),
-                has_defers,
-            )
-        } else {
-            // If defer is not enabled, we remove all @defer from the query. This feels cleaner do this once here than
-            // having to guard all the code dealing with defer later, and is probably less error prone too (less likely
-            // to end up passing through a @defer to a subgraph by mistake).
-            (normalized_operation.without_defer(), None, None, false)
-        };
-        */
+        let (normalized_operation, assigned_defer_labels, defer_conditions, has_defers) =
+            if self.config.incremental_delivery.enable_defer {
+                let NormalizedDefer {
+                    operation,
+                    assigned_defer_labels,
+                    defer_conditions,
+                    has_defers,
+                } = normalized_operation.with_normalized_defer()?;
+                if is_subscription && !defer_conditions.is_empty() {
+                    // The primary fetch of a subscription cannot depend on a condition.
+                    return Err(SingleFederationError::UnsupportedFeature {
+                        message: "@defer with a condition is not supported on subscriptions"
+                            .to_owned(),
+                        kind: crate::error::UnsupportedFeatureKind::Defer,
+                    }
+                    .into());
+                }
+                (
+                    operation,
+                    Some(assigned_defer_labels),
+                    Some(defer_conditions),
+                    has_defers,
+                )
+            } else {
+                // If defer is not enabled, we remove all @defer from the query. This feels cleaner do this once here than
+                // having to guard all the code dealing with defer later, and is probably less error prone too (less likely
+                // to end up passing through a @defer to a subgraph by mistake).
+                (normalized_operation.without_defer(), None, None, false)
+            };
 
         if normalized_operation.selection_set.is_empty() {
             return Ok(QueryPlan::default());
@@ -472,12 +493,17 @@ impl QueryPlanner {
                 .clone()
                 .into(),
             config: self.config.clone(),
+            deadline,
             // PORT_NOTE: JS provides `override_conditions` here: see port note in `QueryPlanner::new`.
         };
 
         let root_node = match defer_conditions {
             Some(defer_conditions) if !defer_conditions.is_empty() => {
-                compute_plan_for_defer_conditionals(&mut parameters, defer_conditions)?
+                compute_plan_for_defer_conditionals(
+                    &mut parameters,
+                    &mut processor,
+                    defer_conditions,
+                )?
             }
             _ => compute_plan_internal(&mut parameters, &mut processor, has_defers)?,
         };
@@ -485,35 +511,15 @@ impl QueryPlanner {
         let root_node = match root_node {
             // If this is a subscription, we want to make sure that we return a SubscriptionNode rather than a PlanNode
             // We potentially will need to separate "primary" from "rest"
-            // Note that if it is a subscription, we are guaranteed that nothing is deferred.
-            Some(PlanNode::Fetch(root_node)) if is_subscription => Some(
-                TopLevelPlanNode::Subscription(crate::query_plan::SubscriptionNode {
-                    primary: root_node,
-                    rest: None,
-                }),
-            ),
-            Some(PlanNode::Sequence(root_node)) if is_subscription => {
-                let Some((primary, rest)) = root_node.nodes.split_first() else {
-                    unreachable!("Sequence must have at least one node");
-                };
-                let PlanNode::Fetch(primary) = primary.clone() else {
-                    unreachable!("Primary node of a subscription is not a Fetch");
-                };
-                let rest = PlanNode::Sequence(SequenceNode {
-                    nodes: rest.to_vec(),
-                });
+            Some(node) if is_subscription => {
+                let (primary, rest) = split_subscription_plan(node)?;
                 Some(TopLevelPlanNode::Subscription(
                     crate::query_plan::SubscriptionNode {
                         primary,
-                        rest: Some(Box::new(rest)),
+                        rest: rest.map(Box::new),
                     },
                 ))
             }
-            Some(node) if is_subscription => {
-                unreachable!(
-                    "Unexpected top level PlanNode: '{node:?}' when processing subscription"
-                )
-            }
             Some(PlanNode::Fetch(inner)) => Some(TopLevelPlanNode::Fetch(inner)),
             Some(PlanNode::Sequence(inner)) => Some(TopLevelPlanNode::Sequence(inner)),
             Some(PlanNode::Parallel(inner)) => Some(TopLevelPlanNode::Parallel(inner)),
@@ -849,16 +855,79 @@ fn compute_plan_internal(
     }
 }
 
-// TODO: FED-95
+/// Splits the plan of a subscription into the fetch of the subscription's root field, and the
+/// plan to run for each event. When the subscription has deferred fragments, the plan of each
+/// event is a `DeferNode` whose primary block fetches the non-deferred part of the event.
+fn split_subscription_plan(
+    node: PlanNode,
+) -> Result<(Box<FetchNode>, Option<PlanNode>), FederationError> {
+    match node {
+        PlanNode::Fetch(primary) => Ok((primary, None)),
+        PlanNode::Sequence(root_node) => {
+            let mut nodes = root_node.nodes.into_iter();
+            let Some(PlanNode::Fetch(primary)) = nodes.next() else {
+                unreachable!("Primary node of a subscription is not a Fetch");
+            };
+            let rest = PlanNode::Sequence(SequenceNode {
+                nodes: nodes.collect(),
+            });
+            Ok((primary, Some(rest)))
+        }
+        PlanNode::Defer(mut defer) => {
+            let Some(main) = defer.primary.node.take() else {
+                return Err(SingleFederationError::UnsupportedFeature {
+                    message: "the subscription field cannot be deferred".to_owned(),
+                    kind: crate::error::UnsupportedFeatureKind::Defer,
+                }
+                .into());
+            };
+            let (primary, main_rest) = split_subscription_plan(*main)?;
+            defer.primary.node = main_rest.map(Box::new);
+            Ok((primary, Some(PlanNode::Defer(defer))))
+        }
+        node => {
+            unreachable!("Unexpected top level PlanNode: '{node:?}' when processing subscription")
+        }
+    }
+}
+
 fn compute_plan_for_defer_conditionals(
-    _parameters: &mut QueryPlanningParameters,
-    _defer_conditions: IndexMap<String, IndexSet<String>>,
+    parameters: &mut QueryPlanningParameters,
+    processor: &mut FetchDependencyGraphToQueryPlanProcessor,
+    defer_conditions: IndexMap<String, IndexSet<String>>,
+) -> Result<Option<PlanNode>, FederationError> {
+    generate_condition_nodes(
+        parameters.operation.clone(),
+        defer_conditions.iter(),
+        &mut |operation| {
+            parameters.operation = operation;
+            compute_plan_internal(parameters, processor, true)
+        },
+    )
+}
+
+/// Plans `operation` for each combination of the `@defer` conditions, the `@defer` applications
+/// of a condition being removed when its variable is false.
+fn generate_condition_nodes<'a>(
+    operation: Arc<Operation>,
+    mut conditions: impl Clone + Iterator<Item = (&'a String, &'a IndexSet<String>)>,
+    on_final_operation: &mut impl FnMut(Arc<Operation>) -> Result<Option<PlanNode>, FederationError>,
 ) -> Result<Option<PlanNode>, FederationError> {
-    Err(SingleFederationError::UnsupportedFeature {
-        message: String::from("@defer is currently not supported"),
-        kind: crate::error::UnsupportedFeatureKind::Defer,
+    match conditions.next() {
+        None => on_final_operation(operation),
+        Some((variable, labels)) => {
+            let else_operation = Arc::new(operation.without_defer_labels(labels)?);
+            let if_clause =
+                generate_condition_nodes(operation, conditions.clone(), on_final_operation)?;
+            let else_clause =
+                generate_condition_nodes(else_operation, conditions, on_final_operation)?;
+            Ok(Some(PlanNode::Condition(Box::new(ConditionNode {
+                condition_variable: Name::new(variable)?,
+                if_clause: if_clause.map(Box::new),
+                else_clause: else_clause.map(Box::new),
+            }))))
+        }
     }
-    .into())
 }
 
 /// Tracks fragments from the original operation, along with versions rebased on other subgraphs.
@@ -1182,6 +1251,59 @@ type User
         "###);
     }
 
+    #[test]
+    fn render_plan_as_dot_and_mermaid() {
+        let supergraph = Supergraph::new(TEST_SUPERGRAPH).unwrap();
+        let planner = QueryPlanner::new(&supergraph, Default::default()).unwrap();
+
+        let document = ExecutableDocument::parse_and_validate(
+            planner.api_schema().schema(),
+            r#"
+            {
+                bestRatedProducts {
+                    vendor { name }
+                }
+            }
+            "#,
+            "operation.graphql",
+        )
+        .unwrap();
+        let plan = planner.build_query_plan(&document, None).unwrap();
+        insta::assert_snapshot!(crate::query_plan::output::to_dot(&plan), @r###"
+        digraph {
+            0 [ label="QueryPlan"]
+            1 [ label="Sequence"]
+            2 [ label="Fetch(service: \"reviews\")"]
+            3 [ label="Flatten(path: \"bestRatedProducts.@\")"]
+            4 [ label="Fetch(service: \"products\")"]
+            5 [ label="Flatten(path: \"bestRatedProducts.@.vendor\")"]
+            6 [ label="Fetch(service: \"accounts\")"]
+            0 -> 1 [ ]
+            1 -> 2 [ label="1"]
+            1 -> 3 [ label="2"]
+            3 -> 4 [ ]
+            1 -> 5 [ label="3"]
+            5 -> 6 [ ]
+        }
+        "###);
+        insta::assert_snapshot!(crate::query_plan::output::to_mermaid(&plan), @r###"
+        flowchart TD
+            n0["QueryPlan"]
+            n1["Sequence"]
+            n2["Fetch(service: #quot;reviews#quot;)"]
+            n3["Flatten(path: #quot;bestRatedProducts.@#quot;)"]
+            n4["Fetch(service: #quot;products#quot;)"]
+            n5["Flatten(path: #quot;bestRatedProducts.@.vendor#quot;)"]
+            n6["Fetch(service: #quot;accounts#quot;)"]
+            n0 --> n1
+            n1 -->|"1"| n2
+            n1 -->|"2"| n3
+            n3 --> n4
+            n1 -->|"3"| n5
+            n5 --> n6
+        "###);
+    }
+
     #[test]
     fn plan_simple_root_field_query_for_multiple_subgraphs() {
         let supergraph = Supergraph::new(TEST_SUPERGRAPH).unwrap();
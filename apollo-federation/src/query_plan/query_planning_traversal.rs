@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use apollo_compiler::collections::IndexSet;
 use petgraph::graph::EdgeIndex;
@@ -7,6 +8,7 @@ use serde::Serialize;
 use tracing::trace;
 
 use crate::error::FederationError;
+use crate::error::SingleFederationError;
 use crate::operation::Operation;
 use crate::operation::Selection;
 use crate::operation::SelectionSet;
@@ -71,6 +73,8 @@ pub(crate) struct QueryPlanningParameters<'a> {
     /// The configuration for the query planner.
     pub(crate) config: QueryPlannerConfig,
     pub(crate) statistics: &'a QueryPlanningStatistics,
+    /// When query planning must abort, if the configuration sets a planning timeout.
+    pub(crate) deadline: Option<Instant>,
 }
 
 pub(crate) struct QueryPlanningTraversal<'a, 'b> {
@@ -265,9 +269,49 @@ impl<'a: 'b, 'b> QueryPlanningTraversal<'a, 'b> {
     )]
     pub fn find_best_plan(mut self) -> Result<Option<BestQueryPlanInfo>, FederationError> {
         self.find_best_plan_inner()?;
+        if self.is_top_level {
+            self.check_max_evaluated_cost()?;
+        }
         Ok(self.best_plan)
     }
 
+    fn check_max_evaluated_cost(&self) -> Result<(), FederationError> {
+        let (Some(max_evaluated_cost), Some(best_plan)) = (
+            self.parameters.config.debug.max_evaluated_cost,
+            &self.best_plan,
+        ) else {
+            return Ok(());
+        };
+        if best_plan.cost > max_evaluated_cost as QueryPlanCost {
+            return Err(SingleFederationError::MaxEvaluatedCostExceeded {
+                message: format!(
+                    "The best query plan found has a cost of {}, higher than the maximum of {max_evaluated_cost}.",
+                    best_plan.cost
+                ),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn check_deadline(&self) -> Result<(), FederationError> {
+        match (
+            self.parameters.deadline,
+            self.parameters.config.debug.planning_timeout,
+        ) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Err(SingleFederationError::QueryPlanningTimeout {
+                    message: format!(
+                        "Query planning took longer than the timeout of {}ms.",
+                        timeout.as_millis()
+                    ),
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
     #[cfg_attr(
         feature = "snapshot_tracing",
         tracing::instrument(
@@ -278,6 +322,7 @@ impl<'a: 'b, 'b> QueryPlanningTraversal<'a, 'b> {
     )]
     fn find_best_plan_inner(&mut self) -> Result<Option<&BestQueryPlanInfo>, FederationError> {
         while let Some(mut current_branch) = self.open_branches.pop() {
+            self.check_deadline()?;
             let Some(current_selection) = current_branch.selections.pop() else {
                 return Err(FederationError::internal(
                     "Sub-stack unexpectedly empty during query plan traversal",
@@ -1039,6 +1084,7 @@ impl<'a: 'b, 'b> QueryPlanningTraversal<'a, 'b> {
                 .clone(),
             config: self.parameters.config.clone(),
             statistics: self.parameters.statistics,
+            deadline: self.parameters.deadline,
         };
         let best_plan_opt = QueryPlanningTraversal::new_inner(
             &parameters,
@@ -1080,6 +1126,8 @@ impl<'a: 'b, 'b> PlanBuilder<PlanInfo, Arc<OpPathTree>> for QueryPlanningTravers
         &mut self,
         plan_info: &mut PlanInfo,
     ) -> Result<QueryPlanCost, FederationError> {
+        // Called for each plan evaluated, as their number can grow large
+        self.check_deadline()?;
         self.cost(&mut plan_info.fetch_dependency_graph)
     }
 
@@ -0,0 +1,453 @@
+//! Classifies the changes between two versions of an API schema, to find the changes that can
+//! break the operations of existing clients.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use apollo_compiler::ast::FieldDefinition;
+use apollo_compiler::ast::Type;
+use apollo_compiler::ast::Value;
+use apollo_compiler::collections::IndexMap;
+use apollo_compiler::collections::IndexSet;
+use apollo_compiler::schema::Component;
+use apollo_compiler::schema::ComponentName;
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::schema::InputValueDefinition;
+use apollo_compiler::Name;
+use apollo_compiler::Node;
+use apollo_compiler::Schema;
+
+/// How a schema change can affect existing operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum_macros::Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChangeLevel {
+    /// The change cannot affect existing operations.
+    Safe,
+    /// Existing operations stay valid, but their results can change, e.g. with a new enum value
+    /// clients don't handle.
+    Dangerous,
+    /// Existing operations can become invalid.
+    Breaking,
+}
+
+/// A change between two versions of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    pub level: ChangeLevel,
+    /// The schema coordinate of the changed element, like `Type.field(argument:)`
+    pub coordinate: String,
+    pub message: String,
+}
+
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.level, self.message)
+    }
+}
+
+/// Lists the changes from the `old` schema to the `new` one, the breaking changes first.
+///
+/// The schemas are meant to be API schemas: the changes of the elements of a supergraph that are
+/// not part of the API are not relevant to clients.
+pub fn diff_schemas(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut diff = SchemaDiff::default();
+    diff.diff_types(old, new);
+    diff.diff_directives(old, new);
+    // stable sort, keeping the schema order for changes of the same level
+    diff.changes.sort_by(|a, b| b.level.cmp(&a.level));
+    diff.changes
+}
+
+#[derive(Default)]
+struct SchemaDiff {
+    changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    fn push(&mut self, level: ChangeLevel, coordinate: impl Display, message: String) {
+        self.changes.push(SchemaChange {
+            level,
+            coordinate: coordinate.to_string(),
+            message,
+        });
+    }
+
+    fn diff_types(&mut self, old: &Schema, new: &Schema) {
+        for (type_name, old_type) in &old.types {
+            if old_type.is_built_in() {
+                continue;
+            }
+            match new.types.get(type_name) {
+                Some(new_type) => self.diff_type(type_name, old_type, new_type),
+                None => self.push(
+                    ChangeLevel::Breaking,
+                    type_name,
+                    format!("Type \"{type_name}\" was removed"),
+                ),
+            }
+        }
+        for (type_name, new_type) in &new.types {
+            if !new_type.is_built_in() && !old.types.contains_key(type_name) {
+                self.push(
+                    ChangeLevel::Safe,
+                    type_name,
+                    format!("Type \"{type_name}\" was added"),
+                );
+            }
+        }
+    }
+
+    fn diff_type(&mut self, type_name: &Name, old_type: &ExtendedType, new_type: &ExtendedType) {
+        match (old_type, new_type) {
+            (ExtendedType::Object(old), ExtendedType::Object(new)) => {
+                self.diff_fields(type_name, &old.fields, &new.fields);
+                self.diff_implementations(
+                    type_name,
+                    &old.implements_interfaces,
+                    &new.implements_interfaces,
+                );
+            }
+            (ExtendedType::Interface(old), ExtendedType::Interface(new)) => {
+                self.diff_fields(type_name, &old.fields, &new.fields);
+                self.diff_implementations(
+                    type_name,
+                    &old.implements_interfaces,
+                    &new.implements_interfaces,
+                );
+            }
+            (ExtendedType::InputObject(old), ExtendedType::InputObject(new)) => {
+                for (field_name, old_field) in &old.fields {
+                    let coordinate = format!("{type_name}.{field_name}");
+                    match new.fields.get(field_name) {
+                        Some(new_field) => {
+                            self.diff_input_value("input field", &coordinate, old_field, new_field)
+                        }
+                        None => self.push(
+                            ChangeLevel::Breaking,
+                            &coordinate,
+                            format!("Input field \"{coordinate}\" was removed"),
+                        ),
+                    }
+                }
+                for (field_name, new_field) in &new.fields {
+                    if !old.fields.contains_key(field_name) {
+                        self.added_input_value(
+                            "input field",
+                            format!("{type_name}.{field_name}"),
+                            new_field,
+                        );
+                    }
+                }
+            }
+            (ExtendedType::Enum(old), ExtendedType::Enum(new)) => {
+                for value in old.values.keys() {
+                    if !new.values.contains_key(value) {
+                        self.push(
+                            ChangeLevel::Breaking,
+                            format_args!("{type_name}.{value}"),
+                            format!("Enum value \"{type_name}.{value}\" was removed"),
+                        );
+                    }
+                }
+                for value in new.values.keys() {
+                    if !old.values.contains_key(value) {
+                        self.push(
+                            ChangeLevel::Dangerous,
+                            format_args!("{type_name}.{value}"),
+                            format!("Enum value \"{type_name}.{value}\" was added"),
+                        );
+                    }
+                }
+            }
+            (ExtendedType::Union(old), ExtendedType::Union(new)) => {
+                for member in &old.members {
+                    if !new.members.contains(member) {
+                        self.push(
+                            ChangeLevel::Breaking,
+                            type_name,
+                            format!(
+                                "Type \"{}\" was removed from union \"{type_name}\"",
+                                member.name
+                            ),
+                        );
+                    }
+                }
+                for member in &new.members {
+                    if !old.members.contains(member) {
+                        self.push(
+                            ChangeLevel::Dangerous,
+                            type_name,
+                            format!(
+                                "Type \"{}\" was added to union \"{type_name}\"",
+                                member.name
+                            ),
+                        );
+                    }
+                }
+            }
+            (ExtendedType::Scalar(_), ExtendedType::Scalar(_)) => {}
+            _ => self.push(
+                ChangeLevel::Breaking,
+                type_name,
+                format!(
+                    "Type \"{type_name}\" changed from {} to {}",
+                    type_kind(old_type),
+                    type_kind(new_type)
+                ),
+            ),
+        }
+    }
+
+    fn diff_fields(
+        &mut self,
+        type_name: &Name,
+        old_fields: &IndexMap<Name, Component<FieldDefinition>>,
+        new_fields: &IndexMap<Name, Component<FieldDefinition>>,
+    ) {
+        for (field_name, old_field) in old_fields {
+            let coordinate = format!("{type_name}.{field_name}");
+            let Some(new_field) = new_fields.get(field_name) else {
+                self.push(
+                    ChangeLevel::Breaking,
+                    &coordinate,
+                    format!("Field \"{coordinate}\" was removed"),
+                );
+                continue;
+            };
+            if old_field.ty != new_field.ty {
+                let level = if is_safe_output_type_change(&old_field.ty, &new_field.ty) {
+                    ChangeLevel::Safe
+                } else {
+                    ChangeLevel::Breaking
+                };
+                self.push(
+                    level,
+                    &coordinate,
+                    format!(
+                        "Type of field \"{coordinate}\" changed from \"{}\" to \"{}\"",
+                        old_field.ty, new_field.ty
+                    ),
+                );
+            }
+            if !old_field.directives.has("deprecated") && new_field.directives.has("deprecated") {
+                self.push(
+                    ChangeLevel::Safe,
+                    &coordinate,
+                    format!("Field \"{coordinate}\" was deprecated"),
+                );
+            }
+            self.diff_arguments(&coordinate, &old_field.arguments, &new_field.arguments);
+        }
+        for field_name in new_fields.keys() {
+            if !old_fields.contains_key(field_name) {
+                self.push(
+                    ChangeLevel::Safe,
+                    format_args!("{type_name}.{field_name}"),
+                    format!("Field \"{type_name}.{field_name}\" was added"),
+                );
+            }
+        }
+    }
+
+    fn diff_implementations(
+        &mut self,
+        type_name: &Name,
+        old: &IndexSet<ComponentName>,
+        new: &IndexSet<ComponentName>,
+    ) {
+        for interface in old {
+            if !new.contains(interface) {
+                self.push(
+                    ChangeLevel::Breaking,
+                    type_name,
+                    format!(
+                        "Type \"{type_name}\" no longer implements interface \"{}\"",
+                        interface.name
+                    ),
+                );
+            }
+        }
+        for interface in new {
+            if !old.contains(interface) {
+                self.push(
+                    ChangeLevel::Dangerous,
+                    type_name,
+                    format!(
+                        "Type \"{type_name}\" now implements interface \"{}\"",
+                        interface.name
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Diffs the arguments of the field or directive at `coordinate`
+    fn diff_arguments(
+        &mut self,
+        coordinate: &str,
+        old_arguments: &[Node<InputValueDefinition>],
+        new_arguments: &[Node<InputValueDefinition>],
+    ) {
+        for old_argument in old_arguments {
+            let argument_coordinate = format!("{coordinate}({}:)", old_argument.name);
+            match new_arguments
+                .iter()
+                .find(|arg| arg.name == old_argument.name)
+            {
+                Some(new_argument) => self.diff_input_value(
+                    "argument",
+                    &argument_coordinate,
+                    old_argument,
+                    new_argument,
+                ),
+                None => self.push(
+                    ChangeLevel::Breaking,
+                    &argument_coordinate,
+                    format!("Argument \"{argument_coordinate}\" was removed"),
+                ),
+            }
+        }
+        for new_argument in new_arguments {
+            if !old_arguments
+                .iter()
+                .any(|arg| arg.name == new_argument.name)
+            {
+                self.added_input_value(
+                    "argument",
+                    format!("{coordinate}({}:)", new_argument.name),
+                    new_argument,
+                );
+            }
+        }
+    }
+
+    /// Diffs the type and default value of an argument or input field
+    fn diff_input_value(
+        &mut self,
+        kind: &str,
+        coordinate: &str,
+        old: &InputValueDefinition,
+        new: &InputValueDefinition,
+    ) {
+        if old.ty != new.ty {
+            let level = if is_safe_input_type_change(&old.ty, &new.ty) {
+                ChangeLevel::Safe
+            } else {
+                ChangeLevel::Breaking
+            };
+            self.push(
+                level,
+                coordinate,
+                format!(
+                    "Type of {kind} \"{coordinate}\" changed from \"{}\" to \"{}\"",
+                    old.ty, new.ty
+                ),
+            );
+        }
+        if old.default_value != new.default_value {
+            let display = |value: &Option<Node<Value>>| match value {
+                Some(value) => value.to_string(),
+                None => "none".to_string(),
+            };
+            self.push(
+                ChangeLevel::Dangerous,
+                coordinate,
+                format!(
+                    "Default value of {kind} \"{coordinate}\" changed from {} to {}",
+                    display(&old.default_value),
+                    display(&new.default_value)
+                ),
+            );
+        }
+    }
+
+    fn added_input_value(&mut self, kind: &str, coordinate: String, new: &InputValueDefinition) {
+        if new.is_required() {
+            self.push(
+                ChangeLevel::Breaking,
+                &coordinate,
+                format!("Required {kind} \"{coordinate}\" was added"),
+            );
+        } else {
+            self.push(
+                ChangeLevel::Dangerous,
+                &coordinate,
+                format!("Optional {kind} \"{coordinate}\" was added"),
+            );
+        }
+    }
+
+    fn diff_directives(&mut self, old: &Schema, new: &Schema) {
+        for (name, old_directive) in &old.directive_definitions {
+            if old_directive.is_built_in() {
+                continue;
+            }
+            let coordinate = format!("@{name}");
+            let Some(new_directive) = new.directive_definitions.get(name) else {
+                self.push(
+                    ChangeLevel::Breaking,
+                    &coordinate,
+                    format!("Directive \"{coordinate}\" was removed"),
+                );
+                continue;
+            };
+            for location in &old_directive.locations {
+                if !new_directive.locations.contains(location) {
+                    self.push(
+                        ChangeLevel::Breaking,
+                        &coordinate,
+                        format!("Location {location} was removed from directive \"{coordinate}\""),
+                    );
+                }
+            }
+            self.diff_arguments(
+                &coordinate,
+                &old_directive.arguments,
+                &new_directive.arguments,
+            );
+        }
+        for (name, new_directive) in &new.directive_definitions {
+            if !new_directive.is_built_in() && !old.directive_definitions.contains_key(name) {
+                self.push(
+                    ChangeLevel::Safe,
+                    format_args!("@{name}"),
+                    format!("Directive \"@{name}\" was added"),
+                );
+            }
+        }
+    }
+}
+
+/// Whether the values of the `new` output type are valid values of the `old` one, which clients
+/// already handle
+fn is_safe_output_type_change(old: &Type, new: &Type) -> bool {
+    match (old, new) {
+        (Type::Named(old), Type::Named(new) | Type::NonNullNamed(new))
+        | (Type::NonNullNamed(old), Type::NonNullNamed(new)) => old == new,
+        (Type::List(old), Type::List(new) | Type::NonNullList(new))
+        | (Type::NonNullList(old), Type::NonNullList(new)) => is_safe_output_type_change(old, new),
+        _ => false,
+    }
+}
+
+/// Whether the values clients send for the `old` input type are valid values of the `new` one
+fn is_safe_input_type_change(old: &Type, new: &Type) -> bool {
+    match (old, new) {
+        (Type::Named(old) | Type::NonNullNamed(old), Type::Named(new))
+        | (Type::NonNullNamed(old), Type::NonNullNamed(new)) => old == new,
+        (Type::List(old) | Type::NonNullList(old), Type::List(new))
+        | (Type::NonNullList(old), Type::NonNullList(new)) => is_safe_input_type_change(old, new),
+        _ => false,
+    }
+}
+
+fn type_kind(ty: &ExtendedType) -> &'static str {
+    match ty {
+        ExtendedType::Scalar(_) => "a scalar type",
+        ExtendedType::Object(_) => "an object type",
+        ExtendedType::Interface(_) => "an interface type",
+        ExtendedType::Union(_) => "a union type",
+        ExtendedType::Enum(_) => "an enum type",
+        ExtendedType::InputObject(_) => "an input object type",
+    }
+}
@@ -2,4 +2,5 @@ mod api_schema;
 mod composition_tests;
 mod extract_subgraphs;
 mod query_plan;
+mod schema_diff;
 mod subgraph;
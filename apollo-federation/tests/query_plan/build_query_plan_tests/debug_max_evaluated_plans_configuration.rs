@@ -1,4 +1,5 @@
 use std::num::NonZeroU32;
+use std::time::Duration;
 
 use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
 use apollo_federation::query_plan::query_planner::QueryPlannerDebugConfig;
@@ -641,3 +642,57 @@ fn multiplication_overflow_in_reduce_options_if_needed() {
     // max_evaluated_plans defaults to 10_000
     assert_eq!(plan.statistics.evaluated_plan_count.get(), 8192);
 }
+
+#[test]
+fn fails_when_the_max_evaluated_cost_is_exceeded() {
+    let (api_schema, planner) = planner!(
+        config = QueryPlannerConfig {
+            debug: QueryPlannerDebugConfig {
+                max_evaluated_cost: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Subgraph1: SUBGRAPH,
+        Subgraph2: SUBGRAPH,
+    );
+    let document = apollo_compiler::ExecutableDocument::parse_and_validate(
+        api_schema.schema(),
+        "{ t { v1 v2 } }",
+        "operation.graphql",
+    )
+    .unwrap();
+    let error = planner.build_query_plan(&document, None).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("The best query plan found has a cost of"),
+        "{error}"
+    );
+}
+
+#[test]
+fn fails_when_the_planning_timeout_is_exceeded() {
+    let (api_schema, planner) = planner!(
+        config = QueryPlannerConfig {
+            debug: QueryPlannerDebugConfig {
+                planning_timeout: Some(Duration::ZERO),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Subgraph1: SUBGRAPH,
+        Subgraph2: SUBGRAPH,
+    );
+    let document = apollo_compiler::ExecutableDocument::parse_and_validate(
+        api_schema.schema(),
+        "{ t { v1 v2 } }",
+        "operation.graphql",
+    )
+    .unwrap();
+    let error = planner.build_query_plan(&document, None).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Query planning took longer than the timeout of 0ms."
+    );
+}
@@ -0,0 +1,197 @@
+use apollo_federation::query_plan::query_planner::QueryPlanIncrementalDeliveryConfig;
+use apollo_federation::query_plan::query_planner::QueryPlannerConfig;
+
+#[test]
+fn defer_on_entity_fields() {
+    let config = QueryPlannerConfig {
+        incremental_delivery: QueryPlanIncrementalDeliveryConfig { enable_defer: true },
+        ..Default::default()
+    };
+    let planner = planner!(
+        config = config,
+        Subgraph1: r#"
+          type Query {
+            t: T
+          }
+
+          type T @key(fields: "id") {
+            id: ID!
+          }
+          "#,
+        Subgraph2: r#"
+          type T @key(fields: "id") {
+            id: ID!
+            data: String
+          }
+          "#,
+    );
+    assert_plan!(
+        &planner,
+        r#"
+          {
+              t {
+                  id
+                  ... @defer {
+                    data
+                  }
+              }
+          }
+        "#,
+        @r###"
+        QueryPlan {
+          Defer {
+            Primary {
+              {
+                t {
+                  id
+                }
+              }:
+              Fetch(service: "Subgraph1", id: 0) {
+                {
+                  t {
+                    __typename
+                    id
+                  }
+                }
+              },
+            }, [
+              Deferred(depends: [0], path: "t") {
+                {
+                  data
+                }:
+                Flatten(path: "t") {
+                  Fetch(service: "Subgraph2") {
+                    {
+                      ... on T {
+                        __typename
+                        id
+                      }
+                    } =>
+                    {
+                      ... on T {
+                        data
+                      }
+                    }
+                  },
+                },
+              },
+            ]
+          },
+        }
+        "###
+    );
+}
+
+#[test]
+fn defer_with_labels_and_conditions() {
+    let config = QueryPlannerConfig {
+        incremental_delivery: QueryPlanIncrementalDeliveryConfig { enable_defer: true },
+        ..Default::default()
+    };
+    let planner = planner!(
+        config = config,
+        Subgraph1: r#"
+          type Query {
+            t: T
+          }
+
+          type T @key(fields: "id") {
+            id: ID!
+          }
+          "#,
+        Subgraph2: r#"
+          type T @key(fields: "id") {
+            id: ID!
+            data: String
+          }
+          "#,
+    );
+    assert_plan!(
+        &planner,
+        r#"
+          query($cond: Boolean!) {
+              t {
+                  id
+                  ... @defer(if: $cond) {
+                    data
+                  }
+                  ... @defer(label: "never", if: false) {
+                    id
+                  }
+              }
+          }
+        "#,
+        @r###"
+        QueryPlan {
+          Condition(if: $cond) {
+            Then {
+              Defer {
+                Primary {
+                  {
+                    t {
+                      id
+                    }
+                  }:
+                  Fetch(service: "Subgraph1", id: 0) {
+                    {
+                      t {
+                        __typename
+                        id
+                      }
+                    }
+                  },
+                }, [
+                  Deferred(depends: [0], path: "t") {
+                    {
+                      data
+                    }:
+                    Flatten(path: "t") {
+                      Fetch(service: "Subgraph2") {
+                        {
+                          ... on T {
+                            __typename
+                            id
+                          }
+                        } =>
+                        {
+                          ... on T {
+                            data
+                          }
+                        }
+                      },
+                    },
+                  },
+                ]
+              },
+            } Else {
+              Sequence {
+                Fetch(service: "Subgraph1") {
+                  {
+                    t {
+                      __typename
+                      id
+                    }
+                  }
+                },
+                Flatten(path: "t") {
+                  Fetch(service: "Subgraph2") {
+                    {
+                      ... on T {
+                        __typename
+                        id
+                      }
+                    } =>
+                    {
+                      ... on T {
+                        data
+                      }
+                    }
+                  },
+                },
+              },
+            },
+          },
+        }
+        "###
+    );
+}
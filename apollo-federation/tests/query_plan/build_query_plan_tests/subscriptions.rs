@@ -135,14 +135,8 @@ fn basic_subscription_with_single_subgraph() {
     );
 }
 
-// TODO(@TylerBloom): Currently, all defer directives are stripped out, so this does not panic
-// quite as expected. Instead, it panics because the snapshots doesn't match. Once this behavior is
-// changed, this should panic with an error along the lines of "@defer can't be used with
-// subscriptions".
 #[test]
-#[should_panic(expected = "snapshot assertion")]
-// TODO: Subscription handling
-fn trying_to_use_defer_with_a_subcription_results_in_an_error() {
+fn defer_in_subscription() {
     let config = QueryPlannerConfig {
         incremental_delivery: QueryPlanIncrementalDeliveryConfig { enable_defer: true },
         ..Default::default()
@@ -186,23 +180,125 @@ fn trying_to_use_defer_with_a_subcription_results_in_an_error() {
           }
         }
         "#,
-        // This is just a placeholder. We expect the planner to return an Err, which is then
-        // unwrapped.
         @r###"
-      QueryPlan {
-        Subscription {
-          Primary: {
-            Fetch(service: "subgraphA") {
-              {
-                onNewUser {
-                  id
-                  name
+        QueryPlan {
+          Subscription {
+            Primary: {
+              Fetch(service: "SubgraphA", id: 0) {
+                {
+                  onNewUser {
+                    __typename
+                    id
+                  }
                 }
-              }
-            }
+              },
+            },
+            Rest: {
+              Defer {
+                Primary {
+                  {
+                    onNewUser {
+                      id
+                      address
+                    }
+                  }:
+                  Sequence {
+                    Flatten(path: "onNewUser") {
+                      Fetch(service: "SubgraphB") {
+                        {
+                          ... on User {
+                            __typename
+                            id
+                          }
+                        } =>
+                        {
+                          ... on User {
+                            address
+                          }
+                        }
+                      },
+                    },
+                  },
+                }, [
+                  Deferred(depends: [0], path: "onNewUser") {
+                    {
+                      name
+                    }:
+                    Flatten(path: "onNewUser") {
+                      Fetch(service: "SubgraphA") {
+                        {
+                          ... on User {
+                            __typename
+                            id
+                          }
+                        } =>
+                        {
+                          ... on User {
+                            name
+                          }
+                        }
+                      },
+                    },
+                  },
+                ]
+              },
+            },
           },
-          }
-        },
-      "###
+        }
+        "###
     );
 }
+
+#[test]
+fn conditional_defer_in_subscription_is_unsupported() {
+    let config = QueryPlannerConfig {
+        incremental_delivery: QueryPlanIncrementalDeliveryConfig { enable_defer: true },
+        ..Default::default()
+    };
+    let planner = planner!(
+        config = config,
+    SubgraphA: r#"
+        type Query {
+          me: User!
+        }
+
+        type Subscription {
+          onNewUser: User!
+        }
+
+        type User @key(fields: "id") {
+          id: ID!
+          name: String!
+        }
+    "#,
+    SubgraphB: r#"
+        type Query {
+          foo: Int
+        }
+
+        type User @key(fields: "id") {
+          id: ID!
+          address: String!
+        }
+    "#);
+    let (api_schema, planner) = planner;
+    let document = apollo_compiler::ExecutableDocument::parse_and_validate(
+        api_schema.schema(),
+        r#"
+        subscription MySubscription($cond: Boolean!) {
+          onNewUser {
+            id
+            ... @defer(if: $cond) {
+              name
+            }
+          }
+        }
+        "#,
+        "operation.graphql",
+    )
+    .unwrap();
+    let error = planner.build_query_plan(&document, None).unwrap_err();
+    insta::assert_snapshot!(error, @r###"
+    @defer with a condition is not supported on subscriptions
+    "###);
+}
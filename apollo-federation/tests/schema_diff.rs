@@ -0,0 +1,84 @@
+use apollo_compiler::Schema;
+use apollo_federation::schema_diff::diff_schemas;
+use insta::assert_snapshot;
+
+fn diff(old: &str, new: &str) -> String {
+    let old = Schema::parse_and_validate(old, "old.graphql").unwrap();
+    let new = Schema::parse_and_validate(new, "new.graphql").unwrap();
+    diff_schemas(&old, &new)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn classifies_schema_changes() {
+    let changes = diff(
+        r#"
+        directive @tag(name: String!) on FIELD | QUERY
+        type Query {
+          me: User
+          users(first: Int = 10, after: String): [User]
+          search(term: String): [Result!]
+          legacy: String
+        }
+        interface Node { id: ID! }
+        type User implements Node {
+          id: ID!
+          name: String
+          role: Role
+        }
+        enum Role { ADMIN USER GUEST }
+        union Result = User
+        input UserFilter { role: Role!, name: String }
+        scalar Cursor
+        "#,
+        r#"
+        directive @tag(name: String!) on FIELD
+        type Query {
+          me: User!
+          users(first: Int = 20, after: String!, filter: UserFilter): [User]
+          search(term: String!): [Result]
+          post: Post
+        }
+        interface Node { id: ID! }
+        type User {
+          id: ID!
+          name: String @deprecated
+          role: Role
+        }
+        type Post { title: String }
+        enum Role { ADMIN USER MODERATOR }
+        union Result = User | Post
+        input UserFilter { role: Role, name: String, limit: Int! }
+        type Cursor { value: String }
+        "#,
+    );
+    assert_snapshot!(changes, @r###"
+    [BREAKING] Type of argument "Query.users(after:)" changed from "String" to "String!"
+    [BREAKING] Type of field "Query.search" changed from "[Result!]" to "[Result]"
+    [BREAKING] Type of argument "Query.search(term:)" changed from "String" to "String!"
+    [BREAKING] Field "Query.legacy" was removed
+    [BREAKING] Type "User" no longer implements interface "Node"
+    [BREAKING] Enum value "Role.GUEST" was removed
+    [BREAKING] Required input field "UserFilter.limit" was added
+    [BREAKING] Type "Cursor" changed from a scalar type to an object type
+    [BREAKING] Location QUERY was removed from directive "@tag"
+    [DANGEROUS] Default value of argument "Query.users(first:)" changed from 10 to 20
+    [DANGEROUS] Optional argument "Query.users(filter:)" was added
+    [DANGEROUS] Enum value "Role.MODERATOR" was added
+    [DANGEROUS] Type "Post" was added to union "Result"
+    [SAFE] Type of field "Query.me" changed from "User" to "User!"
+    [SAFE] Field "Query.post" was added
+    [SAFE] Field "User.name" was deprecated
+    [SAFE] Type of input field "UserFilter.role" changed from "Role!" to "Role"
+    [SAFE] Type "Post" was added
+    "###);
+}
+
+#[test]
+fn no_changes() {
+    let schema = "type Query { a(b: Int): [String!]! }";
+    assert_eq!(diff(schema, schema), "");
+}
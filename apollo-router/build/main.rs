@@ -1,5 +1,7 @@
+use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
 
 mod studio;
 
@@ -32,6 +34,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let fed_version = it.next().expect("invalid router-bridge version format");
 
     println!("cargo:rustc-env=FEDERATION_VERSION={fed_version}");
+    println!("cargo:rustc-env=APOLLO_ROUTER_BUILD_ID={}", build_id()?);
 
     studio::main()
 }
+
+/// Identifies the build, so that plugin libraries compiled against another router build are
+/// rejected: Rust types only have the same layout when compiled the same way.
+fn build_id() -> Result<String, Box<dyn std::error::Error>> {
+    let rustc = Command::new(env::var("RUSTC")?).arg("--version").output()?;
+    let mut features = env::vars()
+        .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        // Plugin libraries use the router's allocator instead of setting their own
+        .filter(|feature| feature != "global_allocator")
+        .collect::<Vec<_>>();
+    features.sort();
+
+    Ok(format!(
+        "apollo-router {} ({}; {}; features: {})",
+        env::var("CARGO_PKG_VERSION")?,
+        String::from_utf8(rustc.stdout)?.trim(),
+        env::var("TARGET")?,
+        features.join(",")
+    ))
+}
@@ -10,6 +10,8 @@ use tracing::Span;
 
 use crate::plugins::telemetry::consts::OTEL_STATUS_CODE;
 use crate::plugins::telemetry::consts::OTEL_STATUS_CODE_ERROR;
+use crate::plugins::telemetry::otel::layer::with_root_sampling;
+use crate::plugins::telemetry::tracing::sampling_rules;
 use crate::plugins::telemetry::SpanMode;
 use crate::uplink::license_enforcement::LicenseState;
 use crate::uplink::license_enforcement::LICENSE_EXPIRED_SHORT_MESSAGE;
@@ -29,27 +31,33 @@ impl<B> MakeSpan<B> for PropagatingMakeSpan {
             propagator.extract(&opentelemetry_http::HeaderExtractor(request.headers()))
         });
         let use_legacy_request_span = matches!(self.span_mode, SpanMode::Deprecated);
+        // Sampling rules are evaluated before the root span is created, as this is where the
+        // sampling decision is made, unless a supergraph rule defers it
+        let sampling = sampling_rules::root_sampling(request);
 
         // If there was no span from the request then it will default to the NOOP span.
         // Attaching the NOOP span has the effect of preventing further tracing.
-        let span = if context.span().span_context().is_valid()
-            || context.span().span_context().trace_id() != opentelemetry::trace::TraceId::INVALID
-        {
-            // We have a valid remote span, attach it to the current thread before creating the root span.
-            let _context_guard = context.attach();
-            if use_legacy_request_span {
-                self.span_mode.create_request(request, self.license)
+        let span = with_root_sampling(sampling, || {
+            if context.span().span_context().is_valid()
+                || context.span().span_context().trace_id()
+                    != opentelemetry::trace::TraceId::INVALID
+            {
+                // We have a valid remote span, attach it to the current thread before creating the root span.
+                let _context_guard = context.attach();
+                if use_legacy_request_span {
+                    self.span_mode.create_request(request, self.license)
+                } else {
+                    self.span_mode.create_router(request)
+                }
             } else {
-                self.span_mode.create_router(request)
+                // No remote span, we can go ahead and create the span without context.
+                if use_legacy_request_span {
+                    self.span_mode.create_request(request, self.license)
+                } else {
+                    self.span_mode.create_router(request)
+                }
             }
-        } else {
-            // No remote span, we can go ahead and create the span without context.
-            if use_legacy_request_span {
-                self.span_mode.create_request(request, self.license)
-            } else {
-                self.span_mode.create_router(request)
-            }
-        };
+        });
         if matches!(
             self.license,
             LicenseState::LicensedWarn | LicenseState::LicensedHalt
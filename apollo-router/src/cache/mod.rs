@@ -195,7 +195,6 @@ where
     }
 
     /// sends the value without storing it into the cache
-    #[allow(unused)]
     pub(crate) async fn send(self, value: V) {
         if let EntryInner::First {
             sender, cache, key, ..
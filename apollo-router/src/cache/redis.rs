@@ -9,6 +9,7 @@ use fred::interfaces::EventInterface;
 use fred::mocks::Mocks;
 use fred::prelude::ClientLike;
 use fred::prelude::KeysInterface;
+use fred::prelude::LuaInterface;
 use fred::prelude::RedisClient;
 use fred::prelude::RedisError;
 use fred::prelude::RedisErrorKind;
@@ -31,6 +32,23 @@ use super::ValueType;
 use crate::configuration::RedisCache;
 use crate::services::generate_tls_client_config;
 
+/// Increments a counter, and sets its time to live (in milliseconds) if it has none.
+///
+/// Returns the new value of the counter and its remaining time to live.
+const INCR_SCRIPT: &str = r#"
+local count = redis.call('INCRBY', KEYS[1], ARGV[1])
+local ttl = tonumber(ARGV[2])
+if ttl > 0 and redis.call('PTTL', KEYS[1]) == -1 then
+  redis.call('PEXPIRE', KEYS[1], ttl)
+end
+return {count, redis.call('PTTL', KEYS[1])}
+"#;
+
+/// Returns the value stored at a key and its remaining time to live, in milliseconds.
+const GET_WITH_TTL_SCRIPT: &str = r#"
+return {redis.call('GET', KEYS[1]), redis.call('PTTL', KEYS[1])}
+"#;
+
 const SUPPORTED_REDIS_SCHEMES: [&str; 6] = [
     "redis",
     "rediss",
@@ -530,6 +548,50 @@ impl RedisCacheStorage {
         tracing::trace!("insert result {:?}", r);
     }
 
+    /// Atomically increments the integer stored at `key`, and returns its new value and its
+    /// remaining time to live.
+    ///
+    /// `ttl` is only applied if the key has no time to live yet.
+    pub(crate) async fn incr<K: KeyType>(
+        &self,
+        key: RedisKey<K>,
+        by: i64,
+        ttl: Option<Duration>,
+    ) -> Result<(i64, Option<Duration>), RedisError> {
+        let key = self.make_key(key);
+        let ttl = ttl.map_or(0, |ttl| ttl.as_millis() as i64);
+        tracing::trace!("incrementing in redis: {:?} by {}", key, by);
+
+        let (count, remaining): (i64, i64) =
+            self.inner.eval(INCR_SCRIPT, key, vec![by, ttl]).await?;
+        // PTTL returns a negative value if the key has no time to live
+        Ok((
+            count,
+            u64::try_from(remaining).ok().map(Duration::from_millis),
+        ))
+    }
+
+    /// Returns the value stored at `key` and its remaining time to live.
+    pub(crate) async fn get_with_ttl<K: KeyType, V: ValueType>(
+        &self,
+        key: RedisKey<K>,
+    ) -> Result<Option<(RedisValue<V>, Option<Duration>)>, RedisError> {
+        let key = self.make_key(key);
+        tracing::trace!("getting from redis with its ttl: {:?}", key);
+
+        let (value, remaining): (Option<RedisValue<V>>, i64) = self
+            .inner
+            .eval(GET_WITH_TTL_SCRIPT, key, Vec::<i64>::new())
+            .await?;
+        // PTTL returns a negative value if the key has no time to live
+        Ok(value.map(|value| {
+            (
+                value,
+                u64::try_from(remaining).ok().map(Duration::from_millis),
+            )
+        }))
+    }
+
     pub(crate) async fn insert_multiple<K: KeyType, V: ValueType>(
         &self,
         data: &[(RedisKey<K>, RedisValue<V>)],
@@ -5,7 +5,9 @@ use std::num::NonZeroUsize;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
+use fred::prelude::RedisError;
 use lru::LruCache;
 use opentelemetry::metrics::MeterProvider;
 use opentelemetry_api::metrics::Meter;
@@ -249,15 +251,77 @@ where
     }
 
     pub(crate) async fn insert(&self, key: K, value: V) {
+        self.insert_with_ttl(key, value, None).await;
+    }
+
+    /// Inserts a value, overriding the Redis TTL configuration if `ttl` is set.
+    pub(crate) async fn insert_with_ttl(&self, key: K, value: V, ttl: Option<Duration>) {
         if let Some(redis) = self.redis.as_ref() {
             redis
-                .insert(RedisKey(key.clone()), RedisValue(value.clone()), None)
+                .insert(RedisKey(key.clone()), RedisValue(value.clone()), ttl)
                 .await;
         }
 
         self.insert_in_memory(key, value).await;
     }
 
+    pub(crate) async fn remove(&self, key: &K) {
+        if let Some(redis) = self.redis.as_ref() {
+            redis.delete(vec![RedisKey(key.clone())]).await;
+        }
+
+        self.remove_in_memory(key).await;
+    }
+
+    pub(crate) async fn remove_in_memory(&self, key: &K) {
+        let (old_value, length) = {
+            let mut in_memory = self.inner.lock().await;
+            (in_memory.pop(key), in_memory.len())
+        };
+        if let Some(old_value) = old_value {
+            let old_value_size = old_value.estimated_size().unwrap_or(0) as i64;
+            self.cache_estimated_storage
+                .fetch_sub(old_value_size, Ordering::SeqCst);
+        }
+        self.cache_size.store(length as i64, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the values are stored in Redis.
+    pub(crate) fn has_redis(&self) -> bool {
+        self.redis.is_some()
+    }
+
+    /// Reads a value from Redis, without going through the in memory cache, and returns it with
+    /// its remaining time to live.
+    ///
+    /// Returns `None` if Redis is not configured.
+    pub(crate) async fn get_from_redis(
+        &self,
+        key: &K,
+    ) -> Option<Result<Option<(V, Option<Duration>)>, RedisError>> {
+        let redis = self.redis.as_ref()?;
+        Some(
+            redis
+                .get_with_ttl(RedisKey(key.clone()))
+                .await
+                .map(|value| value.map(|(value, remaining)| (value.0, remaining))),
+        )
+    }
+
+    /// Atomically increments an integer stored in Redis, and returns its new value and its
+    /// remaining time to live.
+    ///
+    /// Returns `None` if Redis is not configured.
+    pub(crate) async fn incr_in_redis(
+        &self,
+        key: &K,
+        by: i64,
+        ttl: Option<Duration>,
+    ) -> Option<Result<(i64, Option<Duration>), RedisError>> {
+        let redis = self.redis.as_ref()?;
+        Some(redis.incr(RedisKey(key.clone()), by, ttl).await)
+    }
+
     pub(crate) async fn insert_in_memory(&self, key: K, value: V)
     where
         V: ValueType,
@@ -292,6 +356,12 @@ where
     pub(crate) async fn len(&self) -> usize {
         self.inner.lock().await.len()
     }
+
+    #[cfg(test)]
+    pub(crate) fn with_redis(mut self, redis: RedisCacheStorage) -> Self {
+        self.redis = Some(redis);
+        self
+    }
 }
 
 enum CacheStorageName {
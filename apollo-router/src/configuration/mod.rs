@@ -6,6 +6,7 @@ use std::iter;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -41,6 +42,7 @@ use self::expansion::Expansion;
 pub(crate) use self::experimental::Discussed;
 pub(crate) use self::schema::generate_config_schema;
 pub(crate) use self::schema::generate_upgrade;
+pub(crate) use self::schema::validate_user_plugins;
 use self::subgraph::SubgraphConfiguration;
 use crate::cache::DEFAULT_CACHE_CAPACITY;
 use crate::configuration::schema::Mode;
@@ -169,6 +171,10 @@ pub struct Configuration {
     #[serde(default)]
     pub(crate) experimental_query_planner_mode: QueryPlannerMode,
 
+    /// Set the introspection implementation to use.
+    #[serde(default)]
+    pub(crate) experimental_introspection_mode: IntrospectionMode,
+
     /// Plugin configuration
     #[serde(default)]
     pub(crate) plugins: UserPlugins,
@@ -192,6 +198,11 @@ pub struct Configuration {
     /// Type conditioned fetching configuration.
     #[serde(default)]
     pub(crate) experimental_type_conditioned_fetching: bool,
+
+    /// Shared libraries to load native plugins from. The plugins they export can then be
+    /// configured under `plugins`.
+    #[serde(default)]
+    pub(crate) experimental_plugin_libraries: Vec<PathBuf>,
 }
 
 impl PartialEq for Configuration {
@@ -246,6 +257,22 @@ pub(crate) enum QueryPlannerMode {
     BothBestEffort,
 }
 
+/// Introspection modes.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Default, Derivative, Serialize, Deserialize, JsonSchema,
+)]
+#[derivative(Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum IntrospectionMode {
+    /// Use the new Rust-based implementation, executing introspection from the API schema.
+    ///
+    /// Introspection fields can be mixed with other fields in a query.
+    New,
+    /// Use the old JavaScript-based implementation.
+    #[default]
+    Legacy,
+}
+
 impl<'de> serde::Deserialize<'de> for Configuration {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -273,6 +300,8 @@ impl<'de> serde::Deserialize<'de> for Configuration {
             experimental_type_conditioned_fetching: bool,
             experimental_apollo_metrics_generation_mode: ApolloMetricsGenerationMode,
             experimental_query_planner_mode: QueryPlannerMode,
+            experimental_introspection_mode: IntrospectionMode,
+            experimental_plugin_libraries: Vec<PathBuf>,
         }
         let ad_hoc: AdHocConfiguration = serde::Deserialize::deserialize(deserializer)?;
 
@@ -295,6 +324,8 @@ impl<'de> serde::Deserialize<'de> for Configuration {
                 .experimental_apollo_metrics_generation_mode,
             experimental_type_conditioned_fetching: ad_hoc.experimental_type_conditioned_fetching,
             experimental_query_planner_mode: ad_hoc.experimental_query_planner_mode,
+            experimental_introspection_mode: ad_hoc.experimental_introspection_mode,
+            experimental_plugin_libraries: ad_hoc.experimental_plugin_libraries,
             plugins: ad_hoc.plugins,
             apollo_plugins: ad_hoc.apollo_plugins,
             batching: ad_hoc.batching,
@@ -342,6 +373,7 @@ impl Configuration {
         batching: Option<Batching>,
         experimental_apollo_metrics_generation_mode: Option<ApolloMetricsGenerationMode>,
         experimental_query_planner_mode: Option<QueryPlannerMode>,
+        experimental_introspection_mode: Option<IntrospectionMode>,
     ) -> Result<Self, ConfigurationError> {
         let notify = Self::notify(&apollo_plugins)?;
 
@@ -359,6 +391,8 @@ impl Configuration {
             experimental_apollo_metrics_generation_mode:
                 experimental_apollo_metrics_generation_mode.unwrap_or_default(),
             experimental_query_planner_mode: experimental_query_planner_mode.unwrap_or_default(),
+            experimental_introspection_mode: experimental_introspection_mode.unwrap_or_default(),
+            experimental_plugin_libraries: Default::default(),
             plugins: UserPlugins {
                 plugins: Some(plugins),
             },
@@ -462,6 +496,7 @@ impl Configuration {
         experimental_type_conditioned_fetching: Option<bool>,
         experimental_apollo_metrics_generation_mode: Option<ApolloMetricsGenerationMode>,
         experimental_query_planner_mode: Option<QueryPlannerMode>,
+        experimental_introspection_mode: Option<IntrospectionMode>,
     ) -> Result<Self, ConfigurationError> {
         let configuration = Self {
             validated_yaml: Default::default(),
@@ -475,6 +510,8 @@ impl Configuration {
             experimental_apollo_metrics_generation_mode:
                 experimental_apollo_metrics_generation_mode.unwrap_or_default(),
             experimental_query_planner_mode: experimental_query_planner_mode.unwrap_or_default(),
+            experimental_introspection_mode: experimental_introspection_mode.unwrap_or_default(),
+            experimental_plugin_libraries: Default::default(),
             plugins: UserPlugins {
                 plugins: Some(plugins),
             },
@@ -1038,6 +1075,10 @@ pub(crate) struct QueryPlanning {
     /// the in memory cache
     pub(crate) warmed_up_queries: Option<usize>,
 
+    /// Writes the most used queries of the cache to a local file, and plans them
+    /// when the router starts without a previous cache
+    pub(crate) experimental_warm_up_file: Option<QueryPlanWarmUpFile>,
+
     /// Sets a limit to the number of generated query plans.
     /// The planning process generates many different query plans as it
     /// explores the graph, and the list can grow large. By using this
@@ -1061,6 +1102,20 @@ pub(crate) struct QueryPlanning {
     /// The default value is None, which specifies no limit.
     pub(crate) experimental_paths_limit: Option<u32>,
 
+    /// Aborts the planning of an operation once it takes longer than this duration, and returns
+    /// a `QUERY_PLANNING_TIMEOUT` error. Only applies to the Rust query planner.
+    ///
+    /// The default value is None, which specifies no limit.
+    #[serde(deserialize_with = "humantime_serde::deserialize", default)]
+    #[schemars(with = "Option<String>", default)]
+    pub(crate) experimental_planning_timeout: Option<Duration>,
+
+    /// Fails the planning of an operation with a `MAX_EVALUATED_COST_EXCEEDED` error when the
+    /// best query plan found has a higher cost than this value. Only applies to the Rust query planner.
+    ///
+    /// The default value is None, which specifies no limit.
+    pub(crate) experimental_max_evaluated_cost: Option<u64>,
+
     /// If cache warm up is configured, this will allow the router to keep a query plan created with
     /// the old schema, if it determines that the schema update does not affect the corresponding query
     pub(crate) experimental_reuse_query_plans: bool,
@@ -1083,15 +1138,31 @@ impl Default for QueryPlanning {
         Self {
             cache: QueryPlanCache::default(),
             warmed_up_queries: Default::default(),
+            experimental_warm_up_file: Default::default(),
             experimental_plans_limit: Default::default(),
             experimental_parallelism: Default::default(),
             experimental_paths_limit: Default::default(),
+            experimental_planning_timeout: Default::default(),
+            experimental_max_evaluated_cost: Default::default(),
             experimental_reuse_query_plans: Default::default(),
             legacy_introspection_caching: default_legacy_introspection_caching(),
         }
     }
 }
 
+/// Query plan cache warm up file configuration
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct QueryPlanWarmUpFile {
+    /// Path of the file, written when the router shuts down or reloads
+    pub(crate) path: PathBuf,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize", default)]
+    #[schemars(with = "Option<String>", default)]
+    /// Also writes the file at this interval, so that it survives a crash
+    pub(crate) interval: Option<Duration>,
+}
+
 const fn default_legacy_introspection_caching() -> bool {
     true
 }
@@ -25,6 +25,7 @@ use super::plugins;
 use super::yaml;
 use super::Configuration;
 use super::ConfigurationError;
+use super::UserPlugins;
 use super::APOLLO_PLUGIN_PREFIX;
 pub(crate) use crate::configuration::upgrade::generate_upgrade;
 pub(crate) use crate::configuration::upgrade::upgrade_configuration;
@@ -57,19 +58,21 @@ impl Visitor for RefRenameVisitor {
     }
 }
 
-/// Generate a JSON schema for the configuration.
-pub(crate) fn generate_config_schema() -> RootSchema {
-    let settings = SchemaSettings::draft07().with(|s| {
+fn schema_settings() -> SchemaSettings {
+    SchemaSettings::draft07().with(|s| {
         s.option_nullable = true;
         s.option_add_null_type = false;
         s.inline_subschemas = false;
         s.visitors = vec![Box::new(RefRenameVisitor)]
-    });
+    })
+}
 
+/// Generate a JSON schema for the configuration.
+pub(crate) fn generate_config_schema() -> RootSchema {
     // Manually patch up the schema
     // We don't want to allow unknown fields, but serde doesn't work if we put the annotation on Configuration as the struct has a flattened type.
     // It's fine to just add it here.
-    let gen = settings.into_generator();
+    let gen = schema_settings().into_generator();
     let mut schema = gen.into_root_schema_for::<Configuration>();
     let root = schema.schema.object.as_mut().expect("schema not generated");
     root.additional_properties = Some(Box::new(schemars::schema::Schema::Bool(false)));
@@ -135,7 +138,7 @@ pub(crate) fn validate_yaml_configuration(
 
     if migration == Mode::Upgrade {
         let upgraded = upgrade_configuration(&yaml, true)?;
-        let expanded_yaml = expansion.expand(&upgraded)?;
+        let expanded_yaml = without_library_plugins(expansion.expand(&upgraded)?);
         if schema.validate(&expanded_yaml).is_ok() {
             yaml = upgraded;
         } else {
@@ -145,7 +148,7 @@ pub(crate) fn validate_yaml_configuration(
 
     let expanded_yaml = expansion.expand(&yaml)?;
     let parsed_yaml = super::yaml::parse(raw_yaml)?;
-    if let Err(errors_it) = schema.validate(&expanded_yaml) {
+    if let Err(errors_it) = schema.validate(&without_library_plugins(expanded_yaml.clone())) {
         // Validation failed, translate the errors into something nice for the user
         // We have to reparse the yaml to get the line number information for each error.
         let yaml_split_by_lines = raw_yaml.split('\n').collect::<Vec<_>>();
@@ -308,6 +311,71 @@ pub(crate) fn validate_yaml_configuration(
     Ok(config)
 }
 
+/// Validates the `plugins` section once plugin libraries are loaded.
+///
+/// The configuration of the plugins loaded from libraries is checked against their schema, and
+/// plugins that neither the router nor a library registered are rejected.
+pub(crate) fn validate_user_plugins(
+    configuration: &Configuration,
+) -> Result<(), ConfigurationError> {
+    let Some(user_plugins) = &configuration.plugins.plugins else {
+        return Ok(());
+    };
+    let schema = serde_json::to_value(
+        schema_settings()
+            .into_generator()
+            .into_root_schema_for::<UserPlugins>(),
+    )
+    .expect("failed to parse plugins configuration schema");
+    let schema = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .map_err(|e| ConfigurationError::InvalidConfiguration {
+            message: "failed to compile plugins configuration schema",
+            error: e.to_string(),
+        })?;
+
+    let user_plugins = serde_json::Value::Object(user_plugins.clone());
+    if let Err(errors) = schema.validate(&user_plugins) {
+        return Err(ConfigurationError::InvalidConfiguration {
+            message: "plugins configuration had errors",
+            error: errors
+                .map(|e| format!("plugins{}: {e}", e.instance_path))
+                .join("\n"),
+        });
+    }
+    Ok(())
+}
+
+/// Removes the configuration of plugins that may come from a plugin library, when libraries are
+/// listed.
+///
+/// Libraries are only loaded when the router starts its plugins, so validating a configuration
+/// has no side effects. Libraries can't register plugins in the namespaces of the router, so only
+/// the plugins of other namespaces are removed. They are checked by [`validate_user_plugins`]
+/// once the libraries are loaded.
+fn without_library_plugins(mut expanded_yaml: serde_json::Value) -> serde_json::Value {
+    let has_libraries = expanded_yaml
+        .get("experimental_plugin_libraries")
+        .and_then(|libraries| libraries.as_array())
+        .is_some_and(|libraries| !libraries.is_empty());
+    if has_libraries {
+        if let Some(user_plugins) = expanded_yaml
+            .get_mut("plugins")
+            .and_then(|plugins| plugins.as_object_mut())
+        {
+            user_plugins.retain(|name, _| {
+                name.starts_with(APOLLO_PLUGIN_PREFIX)
+                    || name.starts_with("experimental.")
+                    || crate::plugin::PLUGINS
+                        .iter()
+                        .any(|factory| factory.name == *name)
+            });
+        }
+    }
+    expanded_yaml
+}
+
 fn context_lines(
     yaml_split_by_lines: &[&str],
     start_marker: &Marker,
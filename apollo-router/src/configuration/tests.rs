@@ -227,6 +227,71 @@ fn empty_config() {
     .expect("should have been ok with an empty config");
 }
 
+#[test]
+fn plugin_libraries_are_not_loaded_when_validating() {
+    let configuration = validate_yaml_configuration(
+        r#"
+experimental_plugin_libraries:
+  - does/not/exist.so
+plugins:
+  example.from_library:
+    foo: "bar"
+  "#,
+        Expansion::default().unwrap(),
+        Mode::NoUpgrade,
+    )
+    .expect("plugins from libraries are only checked when the router starts");
+    assert!(configuration
+        .plugins
+        .plugins
+        .unwrap_or_default()
+        .contains_key("example.from_library"));
+    assert!(crate::plugin::dynamic::factories().is_empty());
+}
+
+#[test]
+fn plugin_libraries_dont_hide_unknown_router_plugins() {
+    let error = validate_yaml_configuration(
+        r#"
+experimental_plugin_libraries:
+  - does/not/exist.so
+plugins:
+  experimental.expose_query_pla: true
+  "#,
+        Expansion::default().unwrap(),
+        Mode::NoUpgrade,
+    )
+    .expect_err("libraries can't register plugins in the router namespaces");
+    assert!(
+        error.to_string().contains("experimental.expose_query_pla"),
+        "{error}"
+    );
+}
+
+#[test]
+fn user_plugins_are_validated_once_libraries_are_loaded() {
+    let configuration = validate_yaml_configuration(
+        r#"
+experimental_plugin_libraries:
+  - does/not/exist.so
+plugins:
+  experimental.expose_query_plan: true
+  example.from_library:
+    foo: "bar"
+  "#,
+        Expansion::default().unwrap(),
+        Mode::NoUpgrade,
+    )
+    .expect("plugins from libraries are only checked when the router starts");
+
+    // No library registered the plugin
+    let error = validate_user_plugins(&configuration).unwrap_err();
+    assert!(
+        error.to_string().contains("example.from_library"),
+        "{error}"
+    );
+}
+
 #[test]
 fn line_precise_config_errors() {
     let error = validate_yaml_configuration(
@@ -153,6 +153,14 @@ impl Context {
         self.entries.insert(key.into(), value)
     }
 
+    /// Remove a value from the context, returning it if it was present.
+    pub(crate) fn remove_json_value<K>(&self, key: K) -> Option<Value>
+    where
+        K: Into<String>,
+    {
+        self.entries.remove(&key.into()).map(|(_, value)| value)
+    }
+
     /// Get a json value from the context using the provided key.
     pub fn get_json_value<K>(&self, key: K) -> Option<Value>
     where
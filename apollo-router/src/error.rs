@@ -4,6 +4,7 @@ use std::sync::Arc;
 use apollo_compiler::validation::DiagnosticList;
 use apollo_compiler::validation::WithErrors;
 use apollo_federation::error::FederationError;
+use apollo_federation::error::SingleFederationError;
 use displaydoc::Display;
 use lazy_static::__Deref;
 use router_bridge::introspect::IntrospectionError;
@@ -312,6 +313,39 @@ pub(crate) enum QueryPlannerError {
     /// Federation error: {0}
     // TODO: make `FederationError` serializable and store it as-is?
     FederationError(String),
+
+    /// query planning timed out: {0}
+    Timeout(String),
+
+    /// query plan cost limit exceeded: {0}
+    MaxEvaluatedCostExceeded(String),
+}
+
+impl QueryPlannerError {
+    /// Planning limits depend on the load of the planner and on the configuration, their errors
+    /// are not stored in the query plan cache
+    pub(crate) fn is_cacheable(&self) -> bool {
+        !matches!(
+            self,
+            QueryPlannerError::Timeout(_) | QueryPlannerError::MaxEvaluatedCostExceeded(_)
+        )
+    }
+}
+
+impl From<FederationError> for QueryPlannerError {
+    fn from(error: FederationError) -> Self {
+        match &error {
+            FederationError::SingleFederationError {
+                inner: SingleFederationError::QueryPlanningTimeout { message },
+                ..
+            } => QueryPlannerError::Timeout(message.clone()),
+            FederationError::SingleFederationError {
+                inner: SingleFederationError::MaxEvaluatedCostExceeded { message },
+                ..
+            } => QueryPlannerError::MaxEvaluatedCostExceeded(message.clone()),
+            _ => QueryPlannerError::FederationError(error.to_string()),
+        }
+    }
 }
 
 impl IntoGraphQLErrors for Vec<apollo_compiler::execution::GraphQLError> {
@@ -401,6 +435,14 @@ impl IntoGraphQLErrors for QueryPlannerError {
                 );
                 Ok(errors)
             }
+            QueryPlannerError::Timeout(message) => Ok(vec![Error::builder()
+                .message(message)
+                .extension_code("QUERY_PLANNING_TIMEOUT")
+                .build()]),
+            QueryPlannerError::MaxEvaluatedCostExceeded(message) => Ok(vec![Error::builder()
+                .message(message)
+                .extension_code("MAX_EVALUATED_COST_EXCEEDED")
+                .build()]),
             err => Err(err),
         }
     }
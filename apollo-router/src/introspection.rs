@@ -3,13 +3,26 @@ use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 
+use apollo_compiler::executable::Selection;
+use apollo_compiler::execution::coerce_variable_values;
+use apollo_compiler::execution::JsonMap;
+use apollo_compiler::execution::ResponseData;
+use apollo_compiler::execution::SchemaIntrospectionQuery;
+use apollo_compiler::execution::SchemaIntrospectionSplit;
+use apollo_compiler::validation::Valid;
+use apollo_compiler::ExecutableDocument;
 use router_bridge::introspect::IntrospectionError;
 use router_bridge::planner::Planner;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json_bytes::Value;
 use tower::BoxError;
 
 use crate::cache::storage::CacheStorage;
 use crate::graphql::Response;
+use crate::json_ext::Object;
 use crate::query_planner::QueryPlanResult;
+use crate::spec::Schema;
 
 const DEFAULT_INTROSPECTION_CACHE_CAPACITY: NonZeroUsize =
     unsafe { NonZeroUsize::new_unchecked(5) };
@@ -17,30 +30,62 @@ const DEFAULT_INTROSPECTION_CACHE_CAPACITY: NonZeroUsize =
 /// A cache containing our well known introspection queries.
 pub(crate) struct Introspection {
     cache: CacheStorage<String, Response>,
-    planner: Arc<Planner<QueryPlanResult>>,
+    executor: IntrospectionExecutor,
+}
+
+pub(crate) enum IntrospectionExecutor {
+    /// Executes introspection queries with the JavaScript query planner
+    Js(Arc<Planner<QueryPlanResult>>),
+    /// Executes introspection queries from the API schema
+    Rust(Arc<Schema>),
+}
+
+/// The introspection fields of an operation, split from the rest of the operation
+pub(crate) struct IntrospectionSplit {
+    /// The response of the introspection fields
+    pub(crate) response: IntrospectionResponse,
+    /// The rest of the operation to plan, its response is merged with the introspection response
+    pub(crate) document: Option<Valid<ExecutableDocument>>,
+}
+
+/// The response of the introspection fields of an operation
+pub(crate) enum IntrospectionResponse {
+    /// The introspection fields do not use variables, they were executed when planning
+    Executed(Response),
+    /// The introspection fields use variables, they are executed for each request
+    Deferred(VariablesIntrospection),
+}
+
+/// Introspection fields using variables, executed with the variables of each request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct VariablesIntrospection {
+    /// The introspection fields of the operation
+    query: String,
+    /// The root `__typename` fields of the operation, resolved when planning
+    typenames: Object,
 }
 
 impl Introspection {
     pub(crate) async fn with_capacity(
-        planner: Arc<Planner<QueryPlanResult>>,
+        executor: IntrospectionExecutor,
         capacity: NonZeroUsize,
     ) -> Result<Self, BoxError> {
         Ok(Self {
             cache: CacheStorage::new(capacity, None, "introspection").await?,
-            planner,
+            executor,
         })
     }
 
-    pub(crate) async fn new(planner: Arc<Planner<QueryPlanResult>>) -> Result<Self, BoxError> {
-        Self::with_capacity(planner, DEFAULT_INTROSPECTION_CACHE_CAPACITY).await
+    pub(crate) async fn new(executor: IntrospectionExecutor) -> Result<Self, BoxError> {
+        Self::with_capacity(executor, DEFAULT_INTROSPECTION_CACHE_CAPACITY).await
     }
 
     #[cfg(test)]
     pub(crate) async fn from_cache(
-        planner: Arc<Planner<QueryPlanResult>>,
+        executor: IntrospectionExecutor,
         cache: HashMap<String, Response>,
     ) -> Result<Self, BoxError> {
-        let this = Self::with_capacity(planner, cache.len().try_into().unwrap()).await?;
+        let this = Self::with_capacity(executor, cache.len().try_into().unwrap()).await?;
 
         for (query, response) in cache.into_iter() {
             this.cache.insert(query, response).await;
@@ -49,14 +94,98 @@ impl Introspection {
     }
 
     /// Execute an introspection and cache the response.
-    pub(crate) async fn execute(&self, query: String) -> Result<Response, IntrospectionError> {
+    pub(crate) async fn execute(
+        &self,
+        query: String,
+        document: &Valid<ExecutableDocument>,
+        operation_name: Option<&str>,
+    ) -> Result<Response, IntrospectionError> {
+        match &self.executor {
+            IntrospectionExecutor::Js(planner) => self.execute_js(planner, query).await,
+            IntrospectionExecutor::Rust(schema) => {
+                match self.split(document, operation_name).await? {
+                    Some(IntrospectionSplit {
+                        response: IntrospectionResponse::Executed(response),
+                        document: None,
+                    }) => Ok(response),
+                    Some(IntrospectionSplit {
+                        response: IntrospectionResponse::Deferred(introspection),
+                        document: None,
+                    }) => Ok(introspection.execute(schema, &Object::new())),
+                    Some(IntrospectionSplit {
+                        document: Some(_), ..
+                    }) => Err(IntrospectionError {
+                        message: String::from("introspection fields are mixed with other fields")
+                            .into(),
+                    }),
+                    None => Ok(Response::builder().data(Object::new()).build()),
+                }
+            }
+        }
+    }
+
+    /// Executes the introspection fields of an operation, when introspection is executed from the
+    /// API schema. The other fields, if any, are left to plan.
+    ///
+    /// Returns `None` if the operation has no introspection fields, or if introspection is
+    /// executed by the JavaScript query planner, which does not support mixing them with other
+    /// fields.
+    pub(crate) async fn split(
+        &self,
+        document: &Valid<ExecutableDocument>,
+        operation_name: Option<&str>,
+    ) -> Result<Option<IntrospectionSplit>, IntrospectionError> {
+        let IntrospectionExecutor::Rust(schema) = &self.executor else {
+            return Ok(None);
+        };
+        let (introspection_query, document) = match split(schema, document, operation_name)? {
+            SchemaIntrospectionSplit::None => return Ok(None),
+            SchemaIntrospectionSplit::Only(introspection_query) => (introspection_query, None),
+            SchemaIntrospectionSplit::Both {
+                introspection_query,
+                filtered_document,
+            } => (introspection_query, Some(filtered_document)),
+        };
+        // Root `__typename` fields are resolved from the root type, without planning
+        let (typenames, document) = match document.as_ref().and_then(only_root_typenames) {
+            Some(typenames) => (typenames, None),
+            None => (Object::new(), document),
+        };
+        let uses_variables = introspection_query
+            .operations
+            .get(None)
+            .map_or(false, |operation| !operation.variables.is_empty());
+        // The response depends on the variables of each request, so it cannot be executed and
+        // cached here
+        if uses_variables {
+            let response = IntrospectionResponse::Deferred(VariablesIntrospection {
+                query: introspection_query.to_string(),
+                typenames,
+            });
+            return Ok(Some(IntrospectionSplit { response, document }));
+        }
+        let mut response = self.execute_rust(schema, introspection_query).await?;
+        if let Some(Value::Object(data)) = &mut response.data {
+            data.extend(typenames);
+        }
+        Ok(Some(IntrospectionSplit {
+            response: IntrospectionResponse::Executed(response),
+            document,
+        }))
+    }
+
+    async fn execute_js(
+        &self,
+        planner: &Planner<QueryPlanResult>,
+        query: String,
+    ) -> Result<Response, IntrospectionError> {
         if let Some(response) = self.cache.get(&query, |_| Ok(())).await {
             return Ok(response);
         }
 
         // Do the introspection query and cache it
         let response =
-            self.planner
+            planner
                 .introspect(query.clone())
                 .await
                 .map_err(|_e| IntrospectionError {
@@ -80,6 +209,162 @@ impl Introspection {
 
         Ok(response)
     }
+
+    async fn execute_rust(
+        &self,
+        schema: &Schema,
+        introspection_query: SchemaIntrospectionQuery,
+    ) -> Result<Response, IntrospectionError> {
+        // The query only has the introspection fields of a single operation, so it identifies
+        // the response
+        let query = introspection_query.to_string();
+        if let Some(response) = self.cache.get(&query, |_| Ok(())).await {
+            return Ok(response);
+        }
+
+        // The query does not use variables, so only their default values are used
+        let response = execute_introspection_query(schema, &introspection_query, &JsonMap::new())?;
+
+        self.cache.insert(query, response.clone()).await;
+
+        Ok(response)
+    }
+}
+
+impl VariablesIntrospection {
+    /// Executes the introspection fields with the variables of a request
+    pub(crate) fn execute(&self, schema: &Schema, variables: &Object) -> Response {
+        let result =
+            parse_introspection_query(schema, &self.query).and_then(|introspection_query| {
+                execute_introspection_query(schema, &introspection_query, variables)
+            });
+        let mut response = match result {
+            Ok(response) => response,
+            Err(err) => Response::builder()
+                .error(
+                    crate::graphql::Error::builder()
+                        .message(
+                            err.message
+                                .unwrap_or_else(|| "introspection error".to_string()),
+                        )
+                        .extension_code("INTROSPECTION_ERROR")
+                        .build(),
+                )
+                .build(),
+        };
+        if let Some(Value::Object(data)) = &mut response.data {
+            data.extend(
+                self.typenames
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+        }
+        response
+    }
+}
+
+/// Parses introspection fields split from an operation
+fn parse_introspection_query(
+    schema: &Schema,
+    query: &str,
+) -> Result<SchemaIntrospectionQuery, IntrospectionError> {
+    let document =
+        ExecutableDocument::parse_and_validate(schema.api_schema(), query, "introspection.graphql")
+            .map_err(|err| IntrospectionError {
+                message: format!("introspection error : {}", err.errors).into(),
+            })?;
+    match split(schema, &document, None)? {
+        SchemaIntrospectionSplit::Only(introspection_query) => Ok(introspection_query),
+        _ => Err(IntrospectionError {
+            message: String::from("introspection fields are mixed with other fields").into(),
+        }),
+    }
+}
+
+fn execute_introspection_query(
+    schema: &Schema,
+    introspection_query: &SchemaIntrospectionQuery,
+    variables: &Object,
+) -> Result<Response, IntrospectionError> {
+    let api_schema = schema.api_schema();
+    let operation = introspection_query
+        .operations
+        .get(None)
+        .map_err(|_| IntrospectionError {
+            message: String::from("cannot find the introspection operation").into(),
+        })?;
+    let variables = coerce_variable_values(api_schema, operation, variables).map_err(|err| {
+        IntrospectionError {
+            message: format!(
+                "introspection error : {}",
+                err.into_graphql_error(&introspection_query.sources).message
+            )
+            .into(),
+        }
+    })?;
+    let response = introspection_query
+        .execute(api_schema, &variables)
+        .map_err(|err| IntrospectionError {
+            message: format!("introspection error : {}", err.message).into(),
+        })?;
+
+    Ok(Response::builder()
+        .and_data(match response.data {
+            ResponseData::Object(data) => Some(Value::Object(data)),
+            ResponseData::Null => Some(Value::Null),
+            ResponseData::Absent => None,
+        })
+        .errors(
+            response
+                .errors
+                .into_iter()
+                .filter_map(|error| {
+                    serde_json_bytes::to_value(error)
+                        .and_then(serde_json_bytes::from_value)
+                        .ok()
+                })
+                .collect(),
+        )
+        .build())
+}
+
+fn split(
+    schema: &Schema,
+    document: &Valid<ExecutableDocument>,
+    operation_name: Option<&str>,
+) -> Result<SchemaIntrospectionSplit, IntrospectionError> {
+    let operation = document
+        .operations
+        .get(operation_name)
+        .map_err(|_| IntrospectionError {
+            message: String::from("cannot find the operation").into(),
+        })?;
+    SchemaIntrospectionSplit::split(schema.api_schema(), document, operation).map_err(|err| {
+        IntrospectionError {
+            message: format!(
+                "introspection error : {}",
+                err.into_graphql_error(&document.sources).message
+            )
+            .into(),
+        }
+    })
+}
+
+/// The root `__typename` fields of an operation resolved from its root type, if it only has them
+pub(crate) fn only_root_typenames(document: &Valid<ExecutableDocument>) -> Option<Object> {
+    let operation = document.operations.get(None).ok()?;
+    operation
+        .selection_set
+        .selections
+        .iter()
+        .map(|selection| match selection {
+            Selection::Field(field) if field.name == "__typename" => Some((
+                field.response_key().as_str().into(),
+                Value::String(operation.object_type().as_str().into()),
+            )),
+            _ => None,
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -88,8 +373,10 @@ mod introspection_tests {
 
     use router_bridge::planner::IncrementalDeliverySupport;
     use router_bridge::planner::QueryPlannerConfig;
+    use serde_json_bytes::json;
 
     use super::*;
+    use crate::Configuration;
 
     #[tokio::test]
     async fn test_plan_cache() {
@@ -125,14 +412,151 @@ mod introspection_tests {
             .iter()
             .cloned()
             .collect();
-        let introspection = Introspection::from_cache(planner, cache).await.unwrap();
+        let introspection = Introspection::from_cache(IntrospectionExecutor::Js(planner), cache)
+            .await
+            .unwrap();
+        let document = ExecutableDocument::parse_and_validate(
+            &apollo_compiler::Schema::parse_and_validate(schema, "schema.graphql").unwrap(),
+            query_to_test,
+            "query.graphql",
+        )
+        .unwrap();
 
         assert_eq!(
             expected_data,
             introspection
-                .execute(query_to_test.to_string())
+                .execute(query_to_test.to_string(), &document, None)
                 .await
                 .unwrap()
         );
     }
+
+    async fn rust_introspection() -> (Arc<Schema>, Introspection) {
+        let schema = Arc::new(
+            Schema::parse(
+                include_str!("../tests/fixtures/supergraph.graphql"),
+                &Configuration::default(),
+            )
+            .unwrap(),
+        );
+        let introspection = Introspection::new(IntrospectionExecutor::Rust(schema.clone()))
+            .await
+            .unwrap();
+        (schema, introspection)
+    }
+
+    fn parse(schema: &Schema, query: &str) -> Valid<ExecutableDocument> {
+        ExecutableDocument::parse_and_validate(schema.api_schema(), query, "query.graphql").unwrap()
+    }
+
+    fn executed(response: IntrospectionResponse) -> Response {
+        match response {
+            IntrospectionResponse::Executed(response) => response,
+            IntrospectionResponse::Deferred(_) => panic!("the introspection should be executed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rust_introspection_execution() {
+        let (schema, introspection) = rust_introspection().await;
+        let query = r#"query { __typename __type(name: "User") { name kind fields { name } } }"#;
+
+        let response = introspection
+            .execute(query.to_string(), &parse(&schema, query), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.data,
+            Some(json!({
+                "__type": {
+                    "name": "User",
+                    "kind": "OBJECT",
+                    "fields": [{ "name": "id" }, { "name": "name" }, { "name": "username" }]
+                },
+                "__typename": "Query"
+            }))
+        );
+        assert!(response.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rust_introspection_split() {
+        let (schema, introspection) = rust_introspection().await;
+        let query = r#"{ me { name } schema: __schema { queryType { name } } }"#;
+
+        let split = introspection
+            .split(&parse(&schema, query), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            executed(split.response).data,
+            Some(json!({ "schema": { "queryType": { "name": "Query" } } }))
+        );
+        assert_eq!(
+            split.document.unwrap().to_string(),
+            "{\n  me {\n    name\n  }\n}\n"
+        );
+
+        let query = r#"{ __typename schema: __schema { queryType { name } } }"#;
+        let split = introspection
+            .split(&parse(&schema, query), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            executed(split.response).data,
+            Some(json!({ "schema": { "queryType": { "name": "Query" } }, "__typename": "Query" }))
+        );
+        assert!(split.document.is_none());
+
+        let query = r#"{ me { name } }"#;
+        assert!(introspection
+            .split(&parse(&schema, query), None)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rust_introspection_with_variables() {
+        let (schema, introspection) = rust_introspection().await;
+        let query = r#"query($name: String!) { me { name } __type(name: $name) { name } }"#;
+
+        let split = introspection
+            .split(&parse(&schema, query), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let IntrospectionResponse::Deferred(introspection_query) = split.response else {
+            panic!("introspection using variables should be executed for each request");
+        };
+        assert_eq!(
+            split.document.unwrap().to_string(),
+            "query {\n  me {\n    name\n  }\n}\n"
+        );
+
+        for name in ["User", "Query"] {
+            let variables = json!({ "name": name }).as_object().unwrap().clone();
+            let response = introspection_query.execute(&schema, &variables);
+            assert_eq!(response.data, Some(json!({ "__type": { "name": name } })));
+            assert!(response.errors.is_empty());
+        }
+
+        let query = r#"query($name: String!) { __typename __type(name: $name) { name } }"#;
+        let split = introspection
+            .split(&parse(&schema, query), None)
+            .await
+            .unwrap()
+            .unwrap();
+        let IntrospectionResponse::Deferred(introspection_query) = split.response else {
+            panic!("introspection using variables should be executed for each request");
+        };
+        assert!(split.document.is_none());
+        let variables = json!({ "name": "User" }).as_object().unwrap().clone();
+        assert_eq!(
+            introspection_query.execute(&schema, &variables).data,
+            Some(json!({ "__type": { "name": "User" }, "__typename": "Query" }))
+        );
+    }
 }
@@ -111,6 +111,8 @@ pub mod _private {
     pub use router_bridge;
     pub use serde_json;
 
+    pub use crate::plugin::dynamic::LibraryAllocator;
+    pub use crate::plugin::dynamic::PluginLibrary;
     pub use crate::plugin::PluginFactory;
     pub use crate::plugin::PLUGINS;
     // For comparison/fuzzing
@@ -21,6 +21,9 @@ use opentelemetry_api::Context;
 use opentelemetry_api::KeyValue;
 use regex::Regex;
 
+use crate::plugins::telemetry::metrics::exemplars::ExemplarHistogram;
+use crate::plugins::telemetry::metrics::exemplars::Exemplars;
+
 #[derive(Clone)]
 pub(crate) enum MeterProvider {
     Regular(opentelemetry::sdk::metrics::MeterProvider),
@@ -76,16 +79,23 @@ pub(crate) struct FilterMeterProvider {
     delegate: MeterProvider,
     deny: Option<Regex>,
     allow: Option<Regex>,
+    exemplars: Option<Arc<Exemplars>>,
 }
 
 #[buildstructor]
 impl FilterMeterProvider {
     #[builder]
-    fn new<T: Into<MeterProvider>>(delegate: T, deny: Option<Regex>, allow: Option<Regex>) -> Self {
+    fn new<T: Into<MeterProvider>>(
+        delegate: T,
+        deny: Option<Regex>,
+        allow: Option<Regex>,
+        exemplars: Option<Arc<Exemplars>>,
+    ) -> Self {
         FilterMeterProvider {
             delegate: delegate.into(),
             deny,
             allow,
+            exemplars,
         }
     }
 
@@ -111,6 +121,12 @@ impl FilterMeterProvider {
             .build()
     }
 
+    /// Keeps exemplars of the histograms that are not filtered out
+    pub(crate) fn with_exemplars(mut self, exemplars: Option<Arc<Exemplars>>) -> Self {
+        self.exemplars = exemplars;
+        self
+    }
+
     #[cfg(test)]
     pub(crate) fn all<T: Into<MeterProvider>>(delegate: T) -> Self {
         FilterMeterProvider::builder().delegate(delegate).build()
@@ -131,6 +147,18 @@ struct FilteredInstrumentProvider {
     noop: Meter,
     deny: Option<Regex>,
     allow: Option<Regex>,
+    exemplars: Option<Arc<Exemplars>>,
+}
+
+impl FilteredInstrumentProvider {
+    fn is_filtered(&self, name: &str) -> bool {
+        match (&self.deny, &self.allow) {
+            (Some(deny), Some(allow)) => deny.is_match(name) && !allow.is_match(name),
+            (Some(deny), None) => deny.is_match(name),
+            (None, Some(allow)) => !allow.is_match(name),
+            (None, None) => false,
+        }
+    }
 }
 
 macro_rules! filter_instrument_fn {
@@ -141,13 +169,10 @@ macro_rules! filter_instrument_fn {
             description: Option<Cow<'static, str>>,
             unit: Option<Unit>,
         ) -> opentelemetry::metrics::Result<$wrapper<$ty>> {
-            let mut builder = match (&self.deny, &self.allow) {
-                (Some(deny), Some(allow)) if deny.is_match(&name) && !allow.is_match(&name) => {
-                    self.noop.$name(name)
-                }
-                (Some(deny), None) if deny.is_match(&name) => self.noop.$name(name),
-                (None, Some(allow)) if !allow.is_match(&name) => self.noop.$name(name),
-                (_, _) => self.delegate.$name(name),
+            let mut builder = if self.is_filtered(&name) {
+                self.noop.$name(name)
+            } else {
+                self.delegate.$name(name)
             };
             if let Some(description) = &description {
                 builder = builder.with_description(description.clone())
@@ -160,6 +185,37 @@ macro_rules! filter_instrument_fn {
     };
 }
 
+macro_rules! filter_histogram_fn {
+    ($name:ident, $ty:ty) => {
+        fn $name(
+            &self,
+            name: Cow<'static, str>,
+            description: Option<Cow<'static, str>>,
+            unit: Option<Unit>,
+        ) -> opentelemetry::metrics::Result<Histogram<$ty>> {
+            let filtered = self.is_filtered(&name);
+            let mut builder = if filtered {
+                self.noop.$name(name.clone())
+            } else {
+                self.delegate.$name(name.clone())
+            };
+            if let Some(description) = &description {
+                builder = builder.with_description(description.clone())
+            }
+            if let Some(unit) = &unit {
+                builder = builder.with_unit(unit.clone());
+            }
+            let histogram = builder.try_init()?;
+            match &self.exemplars {
+                Some(exemplars) if !filtered => Ok(Histogram::new(Arc::new(
+                    ExemplarHistogram::new(histogram, &name, unit.as_ref(), exemplars),
+                ))),
+                _ => Ok(histogram),
+            }
+        }
+    };
+}
+
 macro_rules! filter_observable_instrument_fn {
     ($name:ident, $ty:ty, $wrapper:ident) => {
         fn $name(
@@ -169,13 +225,10 @@ macro_rules! filter_observable_instrument_fn {
             unit: Option<Unit>,
             callback: Vec<Callback<$ty>>,
         ) -> opentelemetry::metrics::Result<$wrapper<$ty>> {
-            let mut builder = match (&self.deny, &self.allow) {
-                (Some(deny), Some(allow)) if deny.is_match(&name) && !allow.is_match(&name) => {
-                    self.noop.$name(name)
-                }
-                (Some(deny), None) if deny.is_match(&name) => self.noop.$name(name),
-                (None, Some(allow)) if !allow.is_match(&name) => self.noop.$name(name),
-                (_, _) => self.delegate.$name(name),
+            let mut builder = if self.is_filtered(&name) {
+                self.noop.$name(name)
+            } else {
+                self.delegate.$name(name)
             };
             if let Some(description) = &description {
                 builder = builder.with_description(description.clone());
@@ -200,9 +253,9 @@ impl InstrumentProvider for FilteredInstrumentProvider {
     filter_observable_instrument_fn!(f64_observable_counter, f64, ObservableCounter);
     filter_observable_instrument_fn!(u64_observable_counter, u64, ObservableCounter);
 
-    filter_instrument_fn!(u64_histogram, u64, Histogram);
-    filter_instrument_fn!(f64_histogram, f64, Histogram);
-    filter_instrument_fn!(i64_histogram, i64, Histogram);
+    filter_histogram_fn!(u64_histogram, u64);
+    filter_histogram_fn!(f64_histogram, f64);
+    filter_histogram_fn!(i64_histogram, i64);
 
     filter_instrument_fn!(i64_up_down_counter, i64, UpDownCounter);
     filter_instrument_fn!(f64_up_down_counter, f64, UpDownCounter);
@@ -238,6 +291,7 @@ impl opentelemetry::metrics::MeterProvider for FilterMeterProvider {
                 .versioned_meter(name, version, schema_url, attributes),
             deny: self.deny.clone(),
             allow: self.allow.clone(),
+            exemplars: self.exemplars.clone(),
         }))
     }
 }
@@ -495,6 +495,16 @@ pub(crate) fn meter_provider() -> AggregateMeterProvider {
         .clone()
 }
 
+/// Shares the router's meter provider with a plugin library, which has its own copy of this
+/// module.
+#[cfg(not(test))]
+pub(crate) fn set_meter_provider(meter_provider: AggregateMeterProvider) {
+    let _ = AGGREGATE_METER_PROVIDER.set(meter_provider);
+}
+
+#[cfg(test)]
+pub(crate) fn set_meter_provider(_meter_provider: AggregateMeterProvider) {}
+
 #[macro_export]
 /// Get or create a u64 monotonic counter metric and add a value to it
 ///
@@ -0,0 +1,340 @@
+//! Plugins loaded from shared libraries.
+//!
+//! A plugin library is a `cdylib` crate depending on this crate, which registers its plugins
+//! with [`register_plugin!`](crate::register_plugin) and calls
+//! [`export_plugin_library!`](crate::export_plugin_library) once. The library embeds its own
+//! copy of the router, so it is only accepted if it was compiled by the same compiler, for the
+//! same target and with the same router version and features.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use super::PluginFactory;
+use super::PLUGINS;
+use crate::metrics::aggregation::AggregateMeterProvider;
+
+/// Version of the [`PluginLibrary`] layout. Rev this if it changes
+pub(crate) const PLUGIN_LIBRARY_ABI_VERSION: u32 = 1;
+
+/// Identifies this build of the router, NUL-terminated
+const BUILD_ID: &str = concat!(env!("APOLLO_ROUTER_BUILD_ID"), "\0");
+
+/// Name of the symbol exported by plugin libraries
+const PLUGIN_LIBRARY_SYMBOL: &[u8] = b"APOLLO_ROUTER_PLUGIN_LIBRARY\0";
+
+/// Declaration exported by a plugin library.
+///
+/// The first two fields have a stable layout, and are checked before anything else is used.
+#[doc(hidden)]
+#[repr(C)]
+pub struct PluginLibrary {
+    abi_version: u32,
+    build_id: *const c_char,
+    init: fn(&'static HostAllocator, &tracing::Dispatch, &AggregateMeterProvider),
+    factories: fn() -> Vec<PluginFactory>,
+}
+
+// The build id is a pointer to a static string
+unsafe impl Sync for PluginLibrary {}
+
+impl PluginLibrary {
+    /// Declares the plugins of the library being compiled
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            abi_version: PLUGIN_LIBRARY_ABI_VERSION,
+            build_id: BUILD_ID.as_ptr() as *const c_char,
+            init: library_init,
+            factories: library_factories,
+        }
+    }
+}
+
+impl Default for PluginLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs in the library: makes it allocate from the router's allocator, and send its logs and
+/// metrics to the router's subscriber and meter provider
+fn library_init(
+    allocator: &'static HostAllocator,
+    dispatch: &tracing::Dispatch,
+    meter_provider: &AggregateMeterProvider,
+) {
+    LIBRARY_ALLOCATOR.store(allocator as *const _ as *mut _, Ordering::Release);
+    let _ = tracing::dispatcher::set_global_default(dispatch.clone());
+    crate::metrics::set_meter_provider(meter_provider.clone());
+}
+
+/// Runs in the library: returns the plugins it registered
+fn library_factories() -> Vec<PluginFactory> {
+    PLUGINS
+        .iter()
+        .filter(|factory| !factory.is_apollo())
+        .map(|factory| (**factory).clone())
+        .collect()
+}
+
+/// The router's allocation functions.
+///
+/// Plugins allocate values that the router frees, and the other way around, so a library must
+/// use the same allocator as the router. [`export_plugin_library!`](crate::export_plugin_library)
+/// makes this the global allocator of the library.
+pub(crate) struct HostAllocator {
+    alloc: unsafe fn(Layout) -> *mut u8,
+    alloc_zeroed: unsafe fn(Layout) -> *mut u8,
+    dealloc: unsafe fn(*mut u8, Layout),
+    realloc: unsafe fn(*mut u8, Layout, usize) -> *mut u8,
+}
+
+static ROUTER_ALLOCATOR: HostAllocator = HostAllocator {
+    alloc: std::alloc::alloc,
+    alloc_zeroed: std::alloc::alloc_zeroed,
+    dealloc: std::alloc::dealloc,
+    realloc: std::alloc::realloc,
+};
+
+/// In a library, the router's allocator once the library is initialised
+static LIBRARY_ALLOCATOR: AtomicPtr<HostAllocator> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Global allocator of a plugin library
+#[doc(hidden)]
+pub struct LibraryAllocator;
+
+impl LibraryAllocator {
+    fn host() -> Option<&'static HostAllocator> {
+        // Safety: only ever set to a pointer to the router's static allocator
+        unsafe { LIBRARY_ALLOCATOR.load(Ordering::Acquire).as_ref() }
+    }
+}
+
+// Nothing is allocated between loading the library and its initialisation, so all allocations
+// are made by the router's allocator. The system allocator only serves code running before that,
+// such as tests of the library.
+unsafe impl GlobalAlloc for LibraryAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match Self::host() {
+            Some(host) => (host.alloc)(layout),
+            None => System.alloc(layout),
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        match Self::host() {
+            Some(host) => (host.alloc_zeroed)(layout),
+            None => System.alloc_zeroed(layout),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        match Self::host() {
+            Some(host) => (host.dealloc)(ptr, layout),
+            None => System.dealloc(ptr, layout),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        match Self::host() {
+            Some(host) => (host.realloc)(ptr, layout, new_size),
+            None => System.realloc(ptr, layout, new_size),
+        }
+    }
+}
+
+struct LoadedLibraries {
+    paths: Vec<PathBuf>,
+    factories: Vec<&'static PluginFactory>,
+}
+
+static LOADED: Mutex<LoadedLibraries> = Mutex::new(LoadedLibraries {
+    paths: Vec::new(),
+    factories: Vec::new(),
+});
+
+/// Plugin factories loaded from libraries so far.
+pub(crate) fn factories() -> Vec<&'static PluginFactory> {
+    LOADED.lock().expect("lock poisoned").factories.clone()
+}
+
+/// Loads the plugins of each library that was not loaded yet.
+///
+/// Libraries can't be unloaded: a library removed from the configuration stays loaded, but its
+/// plugins are no longer used.
+pub(crate) fn load(paths: &[PathBuf]) -> Result<(), String> {
+    let mut loaded = LOADED.lock().expect("lock poisoned");
+    for path in paths {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        if loaded.paths.contains(&canonical) {
+            continue;
+        }
+
+        // Safety: loading a library runs its initialisers. Only libraries listed in the
+        // configuration are loaded, and they are checked against this build before use.
+        let factories = unsafe { load_library(path)? };
+        for factory in &factories {
+            let registered = PLUGINS
+                .iter()
+                .map(|factory| &**factory)
+                .chain(loaded.factories.iter().copied())
+                .any(|registered| registered.name == factory.name);
+            if registered {
+                return Err(format!(
+                    "{}: plugin {} is already registered",
+                    path.display(),
+                    factory.name
+                ));
+            }
+        }
+
+        tracing::info!(
+            library = %path.display(),
+            plugins = %factories.iter().map(|factory| factory.name.as_str()).collect::<Vec<_>>().join(", "),
+            "loaded plugin library"
+        );
+        // Factories point to code in the library, which stays loaded until the router exits
+        loaded.factories.extend(
+            factories
+                .into_iter()
+                .map(|factory| &*Box::leak(Box::new(factory))),
+        );
+        loaded.paths.push(canonical);
+    }
+    Ok(())
+}
+
+unsafe fn load_library(path: &Path) -> Result<Vec<PluginFactory>, String> {
+    let library = libloading::Library::new(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let declaration = library
+        .get::<*const PluginLibrary>(PLUGIN_LIBRARY_SYMBOL)
+        .map_err(|_| {
+            format!(
+                "{} is not a router plugin library: it must call `apollo_router::export_plugin_library!()`",
+                path.display()
+            )
+        })?;
+    let declaration = &**declaration;
+    check_declaration(path, declaration)?;
+
+    tracing::dispatcher::get_default(|dispatch| {
+        (declaration.init)(
+            &ROUTER_ALLOCATOR,
+            dispatch,
+            &crate::metrics::meter_provider(),
+        )
+    });
+    let factories = (declaration.factories)();
+    std::mem::forget(library);
+    Ok(factories)
+}
+
+/// Checks that a library was built against this router.
+///
+/// Safety: the declaration's `build_id` must be a valid C string.
+unsafe fn check_declaration(path: &Path, declaration: &PluginLibrary) -> Result<(), String> {
+    if declaration.abi_version != PLUGIN_LIBRARY_ABI_VERSION {
+        return Err(format!(
+            "{} uses version {} of the plugin library interface, but this router requires version {PLUGIN_LIBRARY_ABI_VERSION}",
+            path.display(),
+            declaration.abi_version,
+        ));
+    }
+    let build_id = CStr::from_ptr(declaration.build_id).to_string_lossy();
+    let expected = &BUILD_ID[..BUILD_ID.len() - 1];
+    if build_id != expected {
+        return Err(format!(
+            "{} was built against `{build_id}`, but this router is `{expected}`. The library must be rebuilt against this router",
+            path.display(),
+        ));
+    }
+    Ok(())
+}
+
+/// Exports the plugins registered in a library crate, so that the router can load them
+/// from the `experimental_plugin_libraries` configuration option.
+///
+/// The crate must be a `cdylib`, built with the same compiler, target, and version and features
+/// of `apollo-router` as the router loading it. It must disable the `global-allocator` feature:
+/// this macro sets the global allocator of the library, so that it uses the router's.
+///
+/// ```ignore
+/// apollo_router::register_plugin!("acme", "hello", Hello);
+/// apollo_router::export_plugin_library!();
+/// ```
+#[macro_export]
+macro_rules! export_plugin_library {
+    () => {
+        #[no_mangle]
+        pub static APOLLO_ROUTER_PLUGIN_LIBRARY: $crate::_private::PluginLibrary =
+            $crate::_private::PluginLibrary::new();
+
+        #[global_allocator]
+        static APOLLO_ROUTER_LIBRARY_ALLOCATOR: $crate::_private::LibraryAllocator =
+            $crate::_private::LibraryAllocator;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_library_is_rejected() {
+        let error = load(&[PathBuf::from("does/not/exist.so")]).unwrap_err();
+        assert!(error.starts_with("does/not/exist.so: "), "{error}");
+    }
+
+    #[test]
+    fn library_from_another_abi_version_is_rejected() {
+        let declaration = PluginLibrary {
+            abi_version: PLUGIN_LIBRARY_ABI_VERSION + 1,
+            ..PluginLibrary::new()
+        };
+        let error =
+            unsafe { check_declaration(Path::new("plugins.so"), &declaration) }.unwrap_err();
+        assert!(
+            error.starts_with("plugins.so uses version 2 of the plugin library interface"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn library_from_another_build_is_rejected() {
+        let declaration = PluginLibrary {
+            build_id: b"apollo-router 0.0.0\0".as_ptr() as *const c_char,
+            ..PluginLibrary::new()
+        };
+        let error =
+            unsafe { check_declaration(Path::new("plugins.so"), &declaration) }.unwrap_err();
+        assert!(
+            error.starts_with("plugins.so was built against `apollo-router 0.0.0`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn library_from_this_build_is_accepted() {
+        unsafe { check_declaration(Path::new("plugins.so"), &PluginLibrary::new()) }.unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn library_without_declaration_is_rejected() {
+        let error = unsafe { load_library(Path::new("libc.so.6")) }.unwrap_err();
+        assert!(
+            error.starts_with("libc.so.6 is not a router plugin library"),
+            "{error}"
+        );
+    }
+}
@@ -14,6 +14,7 @@
 //! processing. At each stage a [`Service`] is provided which provides an appropriate
 //! mechanism for interacting with the request and response.
 
+pub(crate) mod dynamic;
 pub mod serde;
 #[macro_use]
 pub mod test;
@@ -331,8 +332,11 @@ impl PluginFactory {
 
 // If we wanted to create a custom subset of plugins, this is where we would do it
 /// Get a copy of the registered plugin factories.
-pub(crate) fn plugins() -> impl Iterator<Item = &'static Lazy<PluginFactory>> {
-    PLUGINS.iter()
+pub(crate) fn plugins() -> impl Iterator<Item = &'static PluginFactory> {
+    PLUGINS
+        .iter()
+        .map(|factory| &**factory)
+        .chain(dynamic::factories())
 }
 
 /// All router plugins must implement the Plugin trait.
@@ -205,6 +205,39 @@ impl AuthorizationPlugin {
         }
     }
 
+    /// Updates the authorization requirements stored in the context for a document that
+    /// replaces the one analyzed by [`Self::query_analysis`].
+    ///
+    /// Policies that were already evaluated keep their result if the new document still
+    /// requires them.
+    pub(crate) fn update_query_analysis(
+        doc: &ParsedDocumentInner,
+        operation_name: Option<&str>,
+        schema: &Schema,
+        context: &Context,
+    ) {
+        context.remove_json_value(AUTHENTICATED_KEY);
+        context.remove_json_value(REQUIRED_SCOPES_KEY);
+        let evaluated_policies = context
+            .remove_json_value(REQUIRED_POLICIES_KEY)
+            .and_then(|value| match value {
+                Value::Object(policies) => Some(policies),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        Self::query_analysis(doc, operation_name, schema, context);
+
+        if let Some(Value::Object(mut policies)) = context.get_json_value(REQUIRED_POLICIES_KEY) {
+            for (policy, result) in policies.iter_mut() {
+                if let Some(evaluated) = evaluated_policies.get(policy.as_str()) {
+                    *result = evaluated.clone();
+                }
+            }
+            context.insert_json_value(REQUIRED_POLICIES_KEY, Value::Object(policies));
+        }
+    }
+
     pub(crate) fn generate_cache_metadata(
         document: &ExecutableDocument,
         operation_name: Option<&str>,
@@ -21,7 +21,7 @@ use crate::services::execution;
 /// What information is passed to a router request/response stage
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default, deny_unknown_fields)]
-pub(super) struct ExecutionRequestConf {
+pub(in crate::plugins) struct ExecutionRequestConf {
     /// Send the headers
     pub(super) headers: bool,
     /// Send the context
@@ -39,7 +39,7 @@ pub(super) struct ExecutionRequestConf {
 /// What information is passed to a router request/response stage
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default, deny_unknown_fields)]
-pub(super) struct ExecutionResponseConf {
+pub(in crate::plugins) struct ExecutionResponseConf {
     /// Send the headers
     pub(super) headers: bool,
     /// Send the context
@@ -54,11 +54,11 @@ pub(super) struct ExecutionResponseConf {
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default)]
-pub(super) struct ExecutionStage {
+pub(in crate::plugins) struct ExecutionStage {
     /// The request configuration
-    pub(super) request: ExecutionRequestConf,
+    pub(in crate::plugins) request: ExecutionRequestConf,
     // /// The response configuration
-    pub(super) response: ExecutionResponseConf,
+    pub(in crate::plugins) response: ExecutionResponseConf,
 }
 
 impl ExecutionStage {
@@ -62,6 +62,9 @@ mod test;
 mod execution;
 mod supergraph;
 
+pub(super) use self::execution::ExecutionStage;
+pub(super) use self::supergraph::SupergraphStage;
+
 pub(crate) const EXTERNAL_SPAN_NAME: &str = "external_plugin";
 const POOL_IDLE_TIMEOUT_DURATION: Option<Duration> = Some(Duration::from_secs(5));
 const COPROCESSOR_ERROR_EXTENSION: &str = "ERROR";
@@ -23,7 +23,7 @@ use crate::services::supergraph;
 /// What information is passed to a router request/response stage
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default, deny_unknown_fields)]
-pub(super) struct SupergraphRequestConf {
+pub(in crate::plugins) struct SupergraphRequestConf {
     /// Condition to trigger this stage
     #[serde(skip_serializing)]
     pub(super) condition: Option<Condition<SupergraphSelector>>,
@@ -42,7 +42,7 @@ pub(super) struct SupergraphRequestConf {
 /// What information is passed to a router request/response stage
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default, deny_unknown_fields)]
-pub(super) struct SupergraphResponseConf {
+pub(in crate::plugins) struct SupergraphResponseConf {
     /// Condition to trigger this stage
     #[serde(skip_serializing)]
     pub(super) condition: Option<Condition<SupergraphSelector>>,
@@ -60,11 +60,11 @@ pub(super) struct SupergraphResponseConf {
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, JsonSchema)]
 #[serde(default)]
-pub(super) struct SupergraphStage {
+pub(in crate::plugins) struct SupergraphStage {
     /// The request configuration
-    pub(super) request: SupergraphRequestConf,
+    pub(in crate::plugins) request: SupergraphRequestConf,
     // /// The response configuration
-    pub(super) response: SupergraphResponseConf,
+    pub(in crate::plugins) response: SupergraphResponseConf,
 }
 
 impl SupergraphStage {
@@ -17,10 +17,14 @@ use crate::services::execution;
 use crate::services::supergraph;
 
 const EXPOSE_QUERY_PLAN_HEADER_NAME: &str = "Apollo-Expose-Query-Plan";
+const EXPOSE_QUERY_PLAN_DIAGRAM_HEADER_NAME: &str = "Apollo-Expose-Query-Plan-Diagram";
 const ENABLE_EXPOSE_QUERY_PLAN_ENV: &str = "APOLLO_EXPOSE_QUERY_PLAN";
 const QUERY_PLAN_CONTEXT_KEY: &str = "experimental::expose_query_plan.plan";
 const FORMATTED_QUERY_PLAN_CONTEXT_KEY: &str = "experimental::expose_query_plan.formatted_plan";
+const DOT_QUERY_PLAN_CONTEXT_KEY: &str = "experimental::expose_query_plan.dot_plan";
+const MERMAID_QUERY_PLAN_CONTEXT_KEY: &str = "experimental::expose_query_plan.mermaid_plan";
 const ENABLED_CONTEXT_KEY: &str = "experimental::expose_query_plan.enabled";
+const DIAGRAM_FORMATS_CONTEXT_KEY: &str = "experimental::expose_query_plan.diagram_formats";
 
 #[derive(Debug, Clone)]
 struct ExposeQueryPlan {
@@ -65,6 +69,26 @@ impl Plugin for ExposeQueryPlan {
                             req.query_plan.formatted_query_plan.clone(),
                         )
                         .unwrap();
+                    // Diagrams are only rendered if the client asked for them
+                    let formats = req
+                        .context
+                        .get::<_, Vec<String>>(DIAGRAM_FORMATS_CONTEXT_KEY)
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default();
+                    if !formats.is_empty() {
+                        let diagram = req.query_plan.root.diagram();
+                        if formats.iter().any(|format| format == "dot") {
+                            req.context
+                                .insert(DOT_QUERY_PLAN_CONTEXT_KEY, diagram.to_dot())
+                                .unwrap();
+                        }
+                        if formats.iter().any(|format| format == "mermaid") {
+                            req.context
+                                .insert(MERMAID_QUERY_PLAN_CONTEXT_KEY, diagram.to_mermaid())
+                                .unwrap();
+                        }
+                    }
                 }
 
                 req
@@ -79,6 +103,18 @@ impl Plugin for ExposeQueryPlan {
                 let is_enabled = conf_enabled && req.supergraph_request.headers().get(EXPOSE_QUERY_PLAN_HEADER_NAME) == Some(&HeaderValue::from_static("true"));
                 if is_enabled {
                     req.context.insert(ENABLED_CONTEXT_KEY, true).unwrap();
+                    let formats = req
+                        .supergraph_request
+                        .headers()
+                        .get_all(EXPOSE_QUERY_PLAN_DIAGRAM_HEADER_NAME)
+                        .iter()
+                        .filter_map(|value| value.to_str().ok())
+                        .flat_map(|value| value.split(','))
+                        .map(|format| format.trim().to_ascii_lowercase())
+                        .collect::<Vec<_>>();
+                    if !formats.is_empty() {
+                        req.context.insert(DIAGRAM_FORMATS_CONTEXT_KEY, formats).unwrap();
+                    }
                 }
 
                 is_enabled
@@ -95,9 +131,17 @@ impl Plugin for ExposeQueryPlan {
                                 if let Some(plan) =
                                     res.context.get_json_value(QUERY_PLAN_CONTEXT_KEY)
                                 {
+                                    let mut query_plan = json!({ "object": { "kind": "QueryPlan", "node": plan }, "text": res.context.get_json_value(FORMATTED_QUERY_PLAN_CONTEXT_KEY) });
+                                    if let Some(query_plan) = query_plan.as_object_mut() {
+                                        for (key, context_key) in [("dot", DOT_QUERY_PLAN_CONTEXT_KEY), ("mermaid", MERMAID_QUERY_PLAN_CONTEXT_KEY)] {
+                                            if let Some(diagram) = res.context.get_json_value(context_key) {
+                                                query_plan.insert(key, diagram);
+                                            }
+                                        }
+                                    }
                                     first
                                         .extensions
-                                        .insert("apolloQueryPlan", json!({ "object": { "kind": "QueryPlan", "node": plan }, "text": res.context.get_json_value(FORMATTED_QUERY_PLAN_CONTEXT_KEY) }));
+                                        .insert("apolloQueryPlan", query_plan);
                                 }
                             }
                             res.response = http::Response::from_parts(
@@ -240,6 +284,42 @@ mod tests {
         insta::assert_json_snapshot!(serde_json::to_value(response).unwrap());
     }
 
+    #[tokio::test]
+    async fn it_only_renders_the_requested_diagrams() {
+        let mut supergraph = build_mock_supergraph(serde_json::json! {{
+            "plugins": {
+                "experimental.expose_query_plan": true
+            }
+        }})
+        .await;
+
+        let request = supergraph::Request::fake_builder()
+            .query(VALID_QUERY)
+            .variable("first", 2usize)
+            .header(EXPOSE_QUERY_PLAN_HEADER_NAME, "true")
+            .header(EXPOSE_QUERY_PLAN_DIAGRAM_HEADER_NAME, "mermaid")
+            .build()
+            .unwrap();
+        let response = supergraph
+            .ready()
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap()
+            .next_response()
+            .await
+            .unwrap();
+
+        let query_plan = response.extensions.get("apolloQueryPlan").unwrap();
+        assert!(query_plan
+            .get("mermaid")
+            .and_then(|mermaid| mermaid.as_str())
+            .unwrap()
+            .starts_with("flowchart TD\n    n0[\"QueryPlan\"]\n    n1[\"Sequence\"]"));
+        assert!(query_plan.get("dot").is_none());
+    }
+
     #[tokio::test]
     async fn it_doesnt_expose_query_plan() {
         let supergraph = build_mock_supergraph(serde_json::json! {{
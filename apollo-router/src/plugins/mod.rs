@@ -32,7 +32,9 @@ mod forbid_mutations;
 mod headers;
 mod include_subgraph_errors;
 pub(crate) mod override_url;
+mod profiling;
 pub(crate) mod progressive_override;
+pub(crate) mod query_plan_dry_run;
 mod record_replay;
 pub(crate) mod rhai;
 pub(crate) mod subscription;
@@ -40,3 +42,4 @@ pub(crate) mod telemetry;
 #[cfg(test)]
 pub(crate) mod test;
 pub(crate) mod traffic_shaping;
+mod wasm;
@@ -0,0 +1,355 @@
+//! CPU profiler sampling the stacks of the running threads.
+//!
+//! A `SIGPROF` signal is delivered to the thread using the CPU at the requested frequency, the
+//! signal handler records its stack in a buffer allocated for the profile. The stacks are
+//! symbolized once the profile is complete.
+//!
+//! Unwinding is not async-signal-safe, so the handler walks the frame pointers of the interrupted
+//! thread instead, like pprof-rs does. Code compiled without frame pointers ends the stacks
+//! early, but never makes the handler read an invalid address: every frame is checked by writing
+//! it to a pipe, which fails instead of faulting on unreadable memory.
+use std::ffi::c_int;
+use std::ffi::c_void;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use tower::BoxError;
+
+use super::pprof::Profile;
+use super::pprof::ProfileBuilder;
+use super::pprof::Symbol;
+
+/// Frames recorded for each sample, deeper frames are dropped
+const MAX_DEPTH: usize = 64;
+/// Bounds the memory used by a profile, later samples are dropped
+const MAX_SAMPLES: usize = 100_000;
+
+/// Only one profile can be collected at a time, as there is one `SIGPROF` timer per process
+static PROFILING: AtomicBool = AtomicBool::new(false);
+static SAMPLES: AtomicPtr<Samples> = AtomicPtr::new(std::ptr::null_mut());
+/// Signal handlers currently using `SAMPLES`
+static ACTIVE_HANDLERS: AtomicUsize = AtomicUsize::new(0);
+static INSTALL_HANDLER: Once = Once::new();
+/// The read and write ends of the pipe used to check that frames are readable
+static VALIDATION_PIPE: [AtomicI32; 2] = [AtomicI32::new(-1), AtomicI32::new(-1)];
+
+struct Samples {
+    frames: Box<[AtomicUsize]>,
+    depths: Box<[AtomicUsize]>,
+    next: AtomicUsize,
+}
+
+impl Samples {
+    fn new(capacity: usize) -> Self {
+        Self {
+            frames: (0..capacity * MAX_DEPTH)
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+            depths: (0..capacity).map(|_| AtomicUsize::new(0)).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Called from the signal handler, it must only call async-signal-safe functions: it does not
+    /// allocate, take locks or unwind
+    ///
+    /// # Safety
+    ///
+    /// `context` must be the `ucontext_t` received by the signal handler
+    unsafe fn record(&self, context: *mut c_void) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        if index >= self.depths.len() {
+            return;
+        }
+        let frames = &self.frames[index * MAX_DEPTH..(index + 1) * MAX_DEPTH];
+        let (pc, mut frame_pointer) = registers(context);
+        frames[0].store(pc, Ordering::Relaxed);
+        let mut depth = 1;
+        while depth < MAX_DEPTH {
+            // A frame starts with the caller's frame pointer, followed by the return address
+            let frame = frame_pointer as *const usize;
+            if frame.is_null()
+                || frame_pointer % std::mem::align_of::<usize>() != 0
+                || !is_readable(frame, 2)
+            {
+                break;
+            }
+            let caller_frame_pointer = *frame;
+            let return_address = *frame.add(1);
+            if return_address == 0 {
+                break;
+            }
+            frames[depth].store(return_address, Ordering::Relaxed);
+            depth += 1;
+            // Stacks grow down, anything else is not a frame of this stack
+            if caller_frame_pointer <= frame_pointer {
+                break;
+            }
+            frame_pointer = caller_frame_pointer;
+        }
+        self.depths[index].store(depth, Ordering::Release);
+    }
+
+    fn stacks(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        let count = self.next.load(Ordering::Acquire).min(self.depths.len());
+        (0..count).map(move |index| {
+            let depth = self.depths[index].load(Ordering::Acquire);
+            self.frames[index * MAX_DEPTH..index * MAX_DEPTH + depth]
+                .iter()
+                .map(|frame| frame.load(Ordering::Relaxed))
+                .collect()
+        })
+    }
+}
+
+/// The program counter and frame pointer of the interrupted thread
+///
+/// # Safety
+///
+/// `context` must be the `ucontext_t` received by a signal handler
+unsafe fn registers(context: *mut c_void) -> (usize, usize) {
+    let context = &*(context as *const libc::ucontext_t);
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    let registers = (
+        context.uc_mcontext.gregs[libc::REG_RIP as usize] as usize,
+        context.uc_mcontext.gregs[libc::REG_RBP as usize] as usize,
+    );
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    let registers = (
+        context.uc_mcontext.pc as usize,
+        context.uc_mcontext.regs[29] as usize,
+    );
+    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+    let registers = (
+        (*context.uc_mcontext).__ss.__rip as usize,
+        (*context.uc_mcontext).__ss.__rbp as usize,
+    );
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    let registers = (
+        (*context.uc_mcontext).__ss.__pc as usize,
+        (*context.uc_mcontext).__ss.__fp as usize,
+    );
+    registers
+}
+
+/// Whether `len` words can be read at `address`, without faulting if they can't
+///
+/// # Safety
+///
+/// It must only be called once the validation pipe was created
+unsafe fn is_readable(address: *const usize, len: usize) -> bool {
+    let size = len * std::mem::size_of::<usize>();
+    let [read, write] = &VALIDATION_PIPE;
+    // write() returns EFAULT instead of faulting when the buffer is not readable
+    if libc::write(
+        write.load(Ordering::Relaxed),
+        address as *const c_void,
+        size,
+    ) != size as isize
+    {
+        return false;
+    }
+    // Several handlers can share the pipe, only its capacity matters
+    let mut buffer = [0u8; 16];
+    libc::read(
+        read.load(Ordering::Relaxed),
+        buffer.as_mut_ptr() as *mut c_void,
+        size.min(buffer.len()),
+    );
+    true
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn errno() -> *mut c_int {
+    libc::__errno_location()
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn errno() -> *mut c_int {
+    libc::__error()
+}
+
+extern "C" fn on_sigprof(_signal: c_int, _info: *mut libc::siginfo_t, context: *mut c_void) {
+    // SAFETY: errno is thread local, and restored for the interrupted code
+    let saved_errno = unsafe { *errno() };
+    ACTIVE_HANDLERS.fetch_add(1, Ordering::SeqCst);
+    let samples = SAMPLES.load(Ordering::SeqCst);
+    if !samples.is_null() {
+        // SAFETY: the samples are only freed once no handler uses them, the validation pipe is
+        // created before the handler is installed and the context is the handler's
+        unsafe { (*samples).record(context) };
+    }
+    ACTIVE_HANDLERS.fetch_sub(1, Ordering::SeqCst);
+    // SAFETY: see above
+    unsafe { *errno() = saved_errno };
+}
+
+/// Creates the non-blocking pipe used to check that frames are readable
+fn create_validation_pipe() -> Result<(), BoxError> {
+    let mut fds = [-1; 2];
+    // SAFETY: the file descriptors are written to a valid array of two elements
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        for fd in fds {
+            if libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) != 0
+                || libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) != 0
+            {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+    }
+    for (end, fd) in VALIDATION_PIPE.iter().zip(fds) {
+        end.store(fd, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// The handler stays installed once the first profile starts, a `SIGPROF` signal delivered after
+/// a profile ends must not terminate the process
+fn install_handler() -> Result<(), BoxError> {
+    let mut result = Ok(());
+    INSTALL_HANDLER.call_once(|| {
+        if let Err(error) = create_validation_pipe() {
+            result = Err(error);
+            return;
+        }
+        // SAFETY: the action is fully initialized before it is installed
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sigprof as usize;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGPROF, &action, std::ptr::null_mut()) != 0 {
+                result = Err(std::io::Error::last_os_error().into());
+            }
+        }
+    });
+    result
+}
+
+extern "C" {
+    // Not exposed by the libc crate on every unix platform
+    fn setitimer(
+        which: c_int,
+        new_value: *const libc::itimerval,
+        old_value: *mut libc::itimerval,
+    ) -> c_int;
+}
+
+fn set_timer(interval: Duration) -> Result<(), BoxError> {
+    let interval = libc::timeval {
+        tv_sec: interval.as_secs() as libc::time_t,
+        tv_usec: interval.subsec_micros() as libc::suseconds_t,
+    };
+    let timer = libc::itimerval {
+        it_interval: interval,
+        it_value: interval,
+    };
+    // SAFETY: the timer is a valid itimerval
+    if unsafe { setitimer(libc::ITIMER_PROF, &timer, std::ptr::null_mut()) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// A profile being collected, stopped when dropped so that a cancelled request does not leave
+/// the timer running
+struct Session {
+    samples: *mut Samples,
+}
+
+// SAFETY: the samples are only shared with the signal handler
+unsafe impl Send for Session {}
+
+impl Session {
+    fn start(duration: Duration, frequency: u32) -> Result<Self, BoxError> {
+        if PROFILING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err("a CPU profile is already being collected".into());
+        }
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let capacity = (duration.as_secs_f64() * f64::from(frequency) * threads as f64)
+            .ceil()
+            .min(MAX_SAMPLES as f64) as usize;
+        let session = Self {
+            samples: Box::into_raw(Box::new(Samples::new(capacity))),
+        };
+        install_handler()?;
+        SAMPLES.store(session.samples, Ordering::SeqCst);
+        set_timer(Duration::from_secs(1) / frequency)?;
+        Ok(session)
+    }
+
+    fn stop(&mut self) -> Option<Box<Samples>> {
+        if self.samples.is_null() {
+            return None;
+        }
+        let _ = set_timer(Duration::ZERO);
+        SAMPLES.store(std::ptr::null_mut(), Ordering::SeqCst);
+        while ACTIVE_HANDLERS.load(Ordering::SeqCst) > 0 {
+            std::hint::spin_loop();
+        }
+        // SAFETY: the samples are no longer reachable from the signal handler
+        let samples = unsafe { Box::from_raw(self.samples) };
+        self.samples = std::ptr::null_mut();
+        PROFILING.store(false, Ordering::SeqCst);
+        Some(samples)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Profiles the CPU usage of the process for the given duration
+pub(crate) async fn profile(duration: Duration, frequency: u32) -> Result<Profile, BoxError> {
+    let frequency = frequency.clamp(1, 1000);
+    let start = SystemTime::now();
+    let mut session = Session::start(duration, frequency)?;
+    tokio::time::sleep(duration).await;
+    let samples = session
+        .stop()
+        .ok_or("the CPU profile was already stopped")?;
+    let elapsed = start.elapsed().unwrap_or(duration);
+
+    // Symbolization reads debug information, which is slow
+    tokio::task::spawn_blocking(move || {
+        let mut builder = ProfileBuilder::new(frequency, start, elapsed);
+        for stack in samples.stacks() {
+            builder.add(stack);
+        }
+        builder.build(resolve)
+    })
+    .await
+    .map_err(BoxError::from)
+}
+
+fn resolve(address: usize) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    // Return addresses point after the call instruction
+    backtrace::resolve(address.saturating_sub(1) as *mut c_void, |symbol| {
+        symbols.push(Symbol {
+            name: symbol
+                .name()
+                .map(|name| format!("{name:#}"))
+                .unwrap_or_else(|| format!("{address:#x}")),
+            file: symbol
+                .filename()
+                .map(|file| file.to_string_lossy().into_owned()),
+            line: symbol.lineno(),
+        });
+    });
+    symbols
+}
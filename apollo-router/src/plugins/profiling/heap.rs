@@ -0,0 +1,65 @@
+//! Heap profiles of the jemalloc allocator.
+//!
+//! jemalloc only samples allocations when profiling was enabled at startup, with the
+//! `_RJEM_MALLOC_CONF=prof:true` environment variable. The profiles are dumped by jemalloc to a
+//! temporary file in its own heap profile format, which `pprof` reads.
+use std::ffi::c_char;
+use std::ffi::c_void;
+use std::ffi::CString;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use tower::BoxError;
+
+static DUMPS: AtomicUsize = AtomicUsize::new(0);
+
+/// Dumps the heap profile of the process
+pub(crate) async fn profile() -> Result<Vec<u8>, BoxError> {
+    if !profiling_enabled()? {
+        return Err("jemalloc heap profiling is not enabled, start the router with the _RJEM_MALLOC_CONF=prof:true environment variable".into());
+    }
+    let path = std::env::temp_dir().join(format!(
+        "router-{}-{}.heap",
+        std::process::id(),
+        DUMPS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let file = CString::new(path.to_string_lossy().into_owned())?;
+    let result = {
+        let mut filename = file.as_ptr();
+        // SAFETY: prof.dump takes the path of the file to write, as a C string
+        unsafe {
+            tikv_jemalloc_sys::mallctl(
+                b"prof.dump\0".as_ptr() as *const c_char,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut filename as *mut _ as *mut c_void,
+                std::mem::size_of_val(&filename),
+            )
+        }
+    };
+    if result != 0 {
+        return Err(format!("jemalloc could not dump the heap profile: error {result}").into());
+    }
+    let profile = tokio::fs::read(&path).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    Ok(profile?)
+}
+
+fn profiling_enabled() -> Result<bool, BoxError> {
+    let mut enabled = false;
+    let mut size = std::mem::size_of_val(&enabled);
+    // SAFETY: opt.prof is a bool
+    let result = unsafe {
+        tikv_jemalloc_sys::mallctl(
+            b"opt.prof\0".as_ptr() as *const c_char,
+            &mut enabled as *mut _ as *mut c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(format!("jemalloc was built without profiling support: error {result}").into());
+    }
+    Ok(enabled)
+}
@@ -0,0 +1,293 @@
+//! Profiling endpoints, serving CPU and heap profiles of the router process.
+//!
+//! - `{path}/profile?seconds=30` samples the stacks of the threads using the CPU for the requested
+//!   duration, in the pprof format
+//! - `{path}/heap` dumps the jemalloc heap profile in the jemalloc format, when the router uses
+//!   jemalloc as its global allocator and was started with heap profiling enabled
+//!
+//! They can be read with `go tool pprof http://127.0.0.1:8088/debug/pprof/profile`.
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos"),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod cpu;
+#[cfg(all(
+    feature = "global-allocator",
+    not(feature = "dhat-heap"),
+    target_os = "linux"
+))]
+mod heap;
+mod pprof;
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::task::Poll;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use http::StatusCode;
+use multimap::MultiMap;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tower::BoxError;
+use tower::Service;
+use tower::ServiceExt;
+
+use crate::plugin::Plugin;
+use crate::plugin::PluginInit;
+use crate::register_plugin;
+use crate::router_factory::Endpoint;
+use crate::services::router;
+use crate::services::router::Body;
+use crate::ListenAddr;
+
+/// Profiling endpoints, serving CPU and heap profiles of the router process
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct ProfilingConfig {
+    /// Serve the profiling endpoints
+    pub(crate) enabled: bool,
+    /// The socket address and port to listen on, the same as the health check by default.
+    /// Defaults to 127.0.0.1:8088
+    pub(crate) listen: ListenAddr,
+    /// The path prefix of the endpoints.
+    /// Defaults to /debug/pprof
+    pub(crate) path: String,
+    /// The maximum duration of a CPU profile
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[schemars(with = "String")]
+    pub(crate) max_duration: Duration,
+    /// The number of stack samples taken per second of CPU time
+    pub(crate) frequency: u32,
+}
+
+impl Default for ProfilingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: SocketAddr::from_str("127.0.0.1:8088").unwrap().into(),
+            path: "/debug/pprof".to_string(),
+            max_duration: Duration::from_secs(60),
+            frequency: 99,
+        }
+    }
+}
+
+/// The duration of a CPU profile when the request does not set it, like Go's pprof endpoint
+const DEFAULT_SECONDS: u64 = 30;
+
+#[derive(Debug)]
+struct Profiling {
+    config: ProfilingConfig,
+}
+
+#[async_trait::async_trait]
+impl Plugin for Profiling {
+    type Config = ProfilingConfig;
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        let mut config = init.config;
+        if !config.path.starts_with('/') {
+            config.path = format!("/{}", config.path);
+        }
+        if config.frequency == 0 || config.frequency > 1000 {
+            return Err("profiling frequency must be between 1 and 1000".into());
+        }
+        Ok(Self { config })
+    }
+
+    fn web_endpoints(&self) -> MultiMap<ListenAddr, Endpoint> {
+        let mut map = MultiMap::new();
+        if !self.config.enabled {
+            return map;
+        }
+        let path = self.config.path.trim_end_matches('/');
+        map.insert(
+            self.config.listen.clone(),
+            Endpoint::from_router_service(
+                format!("{path}/profile"),
+                CpuProfileService {
+                    max_duration: self.config.max_duration,
+                    frequency: self.config.frequency,
+                }
+                .boxed(),
+            ),
+        );
+        map.insert(
+            self.config.listen.clone(),
+            Endpoint::from_router_service(format!("{path}/heap"), HeapProfileService.boxed()),
+        );
+        tracing::info!(
+            "Profiling endpoints listening on: {}{}",
+            self.config.listen,
+            path
+        );
+        map
+    }
+}
+
+#[derive(Clone)]
+struct CpuProfileService {
+    max_duration: Duration,
+    frequency: u32,
+}
+
+impl Service<router::Request> for CpuProfileService {
+    type Response = router::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Ok(()).into()
+    }
+
+    fn call(&mut self, req: router::Request) -> Self::Future {
+        let seconds = requested_seconds(req.router_request.uri().query());
+        let max_duration = self.max_duration;
+        let frequency = self.frequency;
+        Box::pin(async move {
+            let duration = match seconds {
+                Ok(seconds) => Duration::from_secs(seconds).min(max_duration),
+                Err(error) => return response(req, StatusCode::BAD_REQUEST, error),
+            };
+            cpu_profile(req, duration, frequency).await
+        })
+    }
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos"),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+async fn cpu_profile(
+    req: router::Request,
+    duration: Duration,
+    frequency: u32,
+) -> Result<router::Response, BoxError> {
+    match cpu::profile(duration, frequency).await {
+        Ok(profile) => profile_response(req, pprof::encode(&profile)?),
+        Err(error) => response(req, StatusCode::CONFLICT, error.to_string()),
+    }
+}
+
+#[cfg(not(all(
+    any(target_os = "linux", target_os = "macos"),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+async fn cpu_profile(
+    req: router::Request,
+    _duration: Duration,
+    _frequency: u32,
+) -> Result<router::Response, BoxError> {
+    response(
+        req,
+        StatusCode::NOT_IMPLEMENTED,
+        "CPU profiles are only available on Linux and macOS, on x86_64 and aarch64".to_string(),
+    )
+}
+
+#[derive(Clone)]
+struct HeapProfileService;
+
+impl Service<router::Request> for HeapProfileService {
+    type Response = router::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Ok(()).into()
+    }
+
+    fn call(&mut self, req: router::Request) -> Self::Future {
+        Box::pin(heap_profile(req))
+    }
+}
+
+#[cfg(all(
+    feature = "global-allocator",
+    not(feature = "dhat-heap"),
+    target_os = "linux"
+))]
+async fn heap_profile(req: router::Request) -> Result<router::Response, BoxError> {
+    match heap::profile().await {
+        Ok(profile) => profile_response(req, profile),
+        Err(error) => response(req, StatusCode::NOT_IMPLEMENTED, error.to_string()),
+    }
+}
+
+#[cfg(not(all(
+    feature = "global-allocator",
+    not(feature = "dhat-heap"),
+    target_os = "linux"
+)))]
+async fn heap_profile(req: router::Request) -> Result<router::Response, BoxError> {
+    response(
+        req,
+        StatusCode::NOT_IMPLEMENTED,
+        "heap profiles are only available when the router uses jemalloc as its global allocator"
+            .to_string(),
+    )
+}
+
+fn requested_seconds(query: Option<&str>) -> Result<u64, String> {
+    let seconds = query
+        .into_iter()
+        .flat_map(|query| url::form_urlencoded::parse(query.as_bytes()))
+        .find(|(name, _)| name == "seconds")
+        .map(|(_, value)| value);
+    match seconds {
+        None => Ok(DEFAULT_SECONDS),
+        Some(seconds) => match seconds.parse() {
+            Ok(0) | Err(_) => Err(format!(
+                "invalid 'seconds' parameter '{seconds}', expected a positive integer"
+            )),
+            Ok(seconds) => Ok(seconds),
+        },
+    }
+}
+
+fn profile_response(req: router::Request, profile: Vec<u8>) -> Result<router::Response, BoxError> {
+    Ok(router::Response {
+        response: http::Response::builder()
+            .status(StatusCode::OK)
+            .header(http::header::CONTENT_TYPE, "application/octet-stream")
+            .header(
+                http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"profile\"",
+            )
+            .body::<Body>(profile.into())
+            .map_err(BoxError::from)?,
+        context: req.context,
+    })
+}
+
+fn response(
+    req: router::Request,
+    status: StatusCode,
+    message: String,
+) -> Result<router::Response, BoxError> {
+    Ok(router::Response {
+        response: http::Response::builder()
+            .status(status)
+            .header(http::header::CONTENT_TYPE, "text/plain")
+            .body::<Body>(message.into())
+            .map_err(BoxError::from)?,
+        context: req.context,
+    })
+}
+
+register_plugin!("apollo", "experimental_profiling", Profiling);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn requested_duration() {
+        assert_eq!(requested_seconds(None), Ok(DEFAULT_SECONDS));
+        assert_eq!(requested_seconds(Some("debug=1")), Ok(DEFAULT_SECONDS));
+        assert_eq!(requested_seconds(Some("seconds=5")), Ok(5));
+        assert!(requested_seconds(Some("seconds=0")).is_err());
+        assert!(requested_seconds(Some("seconds=soon")).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Encoding of CPU profiles in the pprof format.
+//!
+//! See https://github.com/google/pprof/blob/main/proto/profile.proto
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Profile {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) sample_type: Vec<ValueType>,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) sample: Vec<Sample>,
+    #[prost(message, repeated, tag = "4")]
+    pub(crate) location: Vec<Location>,
+    #[prost(message, repeated, tag = "5")]
+    pub(crate) function: Vec<Function>,
+    #[prost(string, repeated, tag = "6")]
+    pub(crate) string_table: Vec<String>,
+    #[prost(int64, tag = "9")]
+    pub(crate) time_nanos: i64,
+    #[prost(int64, tag = "10")]
+    pub(crate) duration_nanos: i64,
+    #[prost(message, optional, tag = "11")]
+    pub(crate) period_type: Option<ValueType>,
+    #[prost(int64, tag = "12")]
+    pub(crate) period: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ValueType {
+    #[prost(int64, tag = "1")]
+    pub(crate) r#type: i64,
+    #[prost(int64, tag = "2")]
+    pub(crate) unit: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Sample {
+    #[prost(uint64, repeated, tag = "1")]
+    pub(crate) location_id: Vec<u64>,
+    #[prost(int64, repeated, tag = "2")]
+    pub(crate) value: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Location {
+    #[prost(uint64, tag = "1")]
+    pub(crate) id: u64,
+    #[prost(uint64, tag = "3")]
+    pub(crate) address: u64,
+    #[prost(message, repeated, tag = "4")]
+    pub(crate) line: Vec<Line>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Line {
+    #[prost(uint64, tag = "1")]
+    pub(crate) function_id: u64,
+    #[prost(int64, tag = "2")]
+    pub(crate) line: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Function {
+    #[prost(uint64, tag = "1")]
+    pub(crate) id: u64,
+    #[prost(int64, tag = "2")]
+    pub(crate) name: i64,
+    #[prost(int64, tag = "3")]
+    pub(crate) system_name: i64,
+    #[prost(int64, tag = "4")]
+    pub(crate) filename: i64,
+}
+
+/// A function at an address, there are several of them for inlined functions
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Symbol {
+    pub(crate) name: String,
+    pub(crate) file: Option<String>,
+    pub(crate) line: Option<u32>,
+}
+
+/// Builds a CPU profile from the sampled stacks, with the leaf frame first
+pub(crate) struct ProfileBuilder {
+    stacks: HashMap<Vec<usize>, i64>,
+    frequency: u32,
+    start: SystemTime,
+    duration: Duration,
+}
+
+impl ProfileBuilder {
+    pub(crate) fn new(frequency: u32, start: SystemTime, duration: Duration) -> Self {
+        Self {
+            stacks: HashMap::new(),
+            frequency,
+            start,
+            duration,
+        }
+    }
+
+    pub(crate) fn add(&mut self, stack: Vec<usize>) {
+        *self.stacks.entry(stack).or_default() += 1;
+    }
+
+    /// Builds the profile, resolving the symbols of each address
+    pub(crate) fn build(self, mut resolve: impl FnMut(usize) -> Vec<Symbol>) -> Profile {
+        let mut strings = StringTable::default();
+        let mut locations: HashMap<usize, u64> = HashMap::new();
+        let mut functions: HashMap<(String, Option<String>), u64> = HashMap::new();
+        let mut profile = Profile {
+            sample_type: vec![
+                ValueType {
+                    r#type: strings.get("samples"),
+                    unit: strings.get("count"),
+                },
+                ValueType {
+                    r#type: strings.get("cpu"),
+                    unit: strings.get("nanoseconds"),
+                },
+            ],
+            period_type: Some(ValueType {
+                r#type: strings.get("cpu"),
+                unit: strings.get("nanoseconds"),
+            }),
+            period: 1_000_000_000 / i64::from(self.frequency.max(1)),
+            time_nanos: self
+                .start
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as i64,
+            duration_nanos: self.duration.as_nanos() as i64,
+            ..Default::default()
+        };
+
+        for (stack, count) in self.stacks {
+            let mut location_ids = Vec::with_capacity(stack.len());
+            for address in stack {
+                let next_id = locations.len() as u64 + 1;
+                let location_id = *locations.entry(address).or_insert_with(|| {
+                    let line = resolve(address)
+                        .into_iter()
+                        .map(|symbol| {
+                            let key = (symbol.name, symbol.file);
+                            let next_id = functions.len() as u64 + 1;
+                            let function_id = *functions.entry(key.clone()).or_insert_with(|| {
+                                let name = strings.get(&key.0);
+                                profile.function.push(Function {
+                                    id: next_id,
+                                    name,
+                                    system_name: name,
+                                    filename: key
+                                        .1
+                                        .as_deref()
+                                        .map(|file| strings.get(file))
+                                        .unwrap_or(0),
+                                });
+                                next_id
+                            });
+                            Line {
+                                function_id,
+                                line: symbol.line.map(i64::from).unwrap_or(0),
+                            }
+                        })
+                        .collect();
+                    profile.location.push(Location {
+                        id: next_id,
+                        address: address as u64,
+                        line,
+                    });
+                    next_id
+                });
+                location_ids.push(location_id);
+            }
+            profile.sample.push(Sample {
+                location_id: location_ids,
+                value: vec![count, count * profile.period],
+            });
+        }
+
+        profile.string_table = strings.strings;
+        profile
+    }
+}
+
+/// Strings are referenced by their index, the first one must be empty
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, i64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indexes: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn get(&mut self, string: &str) -> i64 {
+        if let Some(index) = self.indexes.get(string) {
+            return *index;
+        }
+        let index = self.strings.len() as i64;
+        self.strings.push(string.to_string());
+        self.indexes.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// Profiles are served gzipped, like the Go pprof endpoints
+pub(crate) fn encode(profile: &Profile) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&profile.encode_to_vec())?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn symbol(name: &str, line: u32) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            file: Some("src/lib.rs".to_string()),
+            line: Some(line),
+        }
+    }
+
+    #[test]
+    fn profile_of_sampled_stacks() {
+        let mut builder = ProfileBuilder::new(100, UNIX_EPOCH, Duration::from_secs(1));
+        builder.add(vec![0x10, 0x20]);
+        builder.add(vec![0x10, 0x20]);
+        builder.add(vec![0x30]);
+        let profile = builder.build(|address| match address {
+            // 0x10 is inlined in 0x20
+            0x10 => vec![symbol("parse", 10), symbol("plan", 20)],
+            0x20 => vec![symbol("plan", 21)],
+            _ => Vec::new(),
+        });
+
+        let name = |function_id: u64| {
+            let function = &profile.function[function_id as usize - 1];
+            profile.string_table[function.name as usize].as_str()
+        };
+        let mut samples: Vec<(Vec<Vec<&str>>, Vec<i64>)> = profile
+            .sample
+            .iter()
+            .map(|sample| {
+                let stack = sample
+                    .location_id
+                    .iter()
+                    .map(|id| {
+                        profile.location[*id as usize - 1]
+                            .line
+                            .iter()
+                            .map(|line| name(line.function_id))
+                            .collect()
+                    })
+                    .collect();
+                (stack, sample.value.clone())
+            })
+            .collect();
+        samples.sort();
+        assert_eq!(
+            samples,
+            vec![
+                (vec![vec![]], vec![1, 10_000_000]),
+                (
+                    vec![vec!["parse", "plan"], vec!["plan"]],
+                    vec![2, 20_000_000]
+                ),
+            ]
+        );
+        assert_eq!(profile.function.len(), 2);
+        assert_eq!(profile.string_table[0], "");
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(encode(&profile).unwrap().as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(Profile::decode(decoded.as_slice()).unwrap(), profile);
+    }
+}
@@ -0,0 +1,263 @@
+//! Query plan dry run.
+//!
+//! A request sending the configured secret in the `Apollo-Query-Plan-Dry-Run` header gets the
+//! query plan of its operation and planning diagnostics, without calling the subgraphs.
+use std::sync::Arc;
+
+use futures::future::ready;
+use futures::stream::once;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json_bytes::json;
+use serde_json_bytes::Value;
+use tower::BoxError;
+use tower::ServiceExt as TowerServiceExt;
+
+use crate::layers::ServiceExt;
+use crate::plugin::Plugin;
+use crate::plugin::PluginInit;
+use crate::plugins::demand_control::CostContext;
+use crate::query_planner::QueryPlan;
+use crate::query_planner::QueryPlanningDiagnostics;
+use crate::register_plugin;
+use crate::services::supergraph;
+use crate::Context;
+
+const DRY_RUN_HEADER_NAME: &str = "Apollo-Query-Plan-Dry-Run";
+
+/// Query plan dry run configuration
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct QueryPlanDryRunConfig {
+    /// Enable the query plan dry run
+    enabled: bool,
+    /// Secret that requests send in the `Apollo-Query-Plan-Dry-Run` header to get a dry run
+    secret: String,
+}
+
+#[derive(Debug)]
+struct QueryPlanDryRun {
+    /// `None` if the dry run is disabled
+    secret: Option<Arc<String>>,
+}
+
+/// A dry run request, in the context extensions.
+///
+/// The execution service keeps the query plan here instead of executing it. It is the innermost
+/// execution service, so every plugin processed the execution request before, whatever the order
+/// of the plugins.
+#[derive(Default)]
+pub(crate) struct DryRun {
+    pub(crate) query_plan: Option<Arc<QueryPlan>>,
+}
+
+#[async_trait::async_trait]
+impl Plugin for QueryPlanDryRun {
+    type Config = QueryPlanDryRunConfig;
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        if init.config.enabled && init.config.secret.is_empty() {
+            return Err("the query plan dry run secret must not be empty".into());
+        }
+        Ok(QueryPlanDryRun {
+            secret: init.config.enabled.then(|| Arc::new(init.config.secret)),
+        })
+    }
+
+    fn supergraph_service(&self, service: supergraph::BoxService) -> supergraph::BoxService {
+        let Some(secret) = self.secret.clone() else {
+            return service;
+        };
+        service
+            .map_future_with_request_data(
+                move |req: &supergraph::Request| {
+                    let is_dry_run = req
+                        .supergraph_request
+                        .headers()
+                        .get(DRY_RUN_HEADER_NAME)
+                        .map_or(false, |value| {
+                            is_secret(value.as_bytes(), secret.as_bytes())
+                        });
+                    if is_dry_run {
+                        req.context
+                            .extensions()
+                            .with_lock(|mut lock| lock.insert(DryRun::default()));
+                    }
+                    is_dry_run
+                },
+                |is_dry_run: bool, f| async move {
+                    let mut res: supergraph::Response = f.await?;
+                    if is_dry_run {
+                        let (parts, stream) = res.response.into_parts();
+                        let (first, rest) = stream.into_future().await;
+                        let mut first = first.unwrap_or_default();
+                        if let Some(query_plan) = query_plan_extension(&res.context) {
+                            first.extensions.insert("apolloQueryPlan", query_plan);
+                        }
+                        res.response = http::Response::from_parts(
+                            parts,
+                            once(ready(first)).chain(rest).boxed(),
+                        );
+                    }
+                    Ok(res)
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Compares in constant time, so that the response time does not reveal the secret
+fn is_secret(value: &[u8], secret: &[u8]) -> bool {
+    value.len() == secret.len()
+        && value
+            .iter()
+            .zip(secret)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// The query plan of a dry run and its planning diagnostics. The demand control estimate is set
+/// when the execution request goes through the demand control plugin, before the execution service
+fn query_plan_extension(context: &Context) -> Option<Value> {
+    let (dry_run, planning, estimated_cost) = context.extensions().with_lock(|mut lock| {
+        (
+            lock.remove::<DryRun>(),
+            lock.get::<QueryPlanningDiagnostics>().cloned(),
+            lock.get::<CostContext>().map(|cost| cost.estimated),
+        )
+    });
+    let query_plan = dry_run?.query_plan?;
+    Some(json!({
+        "object": { "kind": "QueryPlan", "node": serde_json_bytes::to_value(&query_plan.root).ok() },
+        "text": query_plan.formatted_query_plan.as_deref(),
+        "diagnostics": {
+            "cacheSource": planning.as_ref().map(|planning| planning.source),
+            "planningTimeMs": planning.map(|planning| planning.duration.as_secs_f64() * 1000.0),
+            "evaluatedPlanCount": query_plan.evaluated_plan_count,
+            "estimatedCost": estimated_cost,
+        }
+    }))
+}
+
+register_plugin!("experimental", "query_plan_dry_run", QueryPlanDryRun);
+
+#[cfg(test)]
+mod tests {
+    use tower::Service;
+
+    use super::*;
+    use crate::graphql::Response;
+    use crate::plugin::test::MockSubgraph;
+    use crate::MockedSubgraphs;
+
+    static QUERY: &str = r#"query TopProducts { topProducts { upc name reviews { id } } }"#;
+
+    async fn build_supergraph(config: serde_json::Value) -> supergraph::BoxCloneService {
+        crate::TestHarness::builder()
+            .schema(include_str!(
+                "../../../apollo-router-benchmarks/benches/fixtures/supergraph.graphql"
+            ))
+            // No query is mocked, the subgraphs return errors if they are called
+            .extra_plugin(MockedSubgraphs(
+                [
+                    ("products", MockSubgraph::new(Default::default())),
+                    ("reviews", MockSubgraph::new(Default::default())),
+                ]
+                .into_iter()
+                .collect(),
+            ))
+            .configuration_json(config)
+            .unwrap()
+            .build_supergraph()
+            .await
+            .unwrap()
+    }
+
+    async fn execute(supergraph: &mut supergraph::BoxCloneService, secret: &str) -> Response {
+        let request = supergraph::Request::fake_builder()
+            .query(QUERY)
+            .header(DRY_RUN_HEADER_NAME, secret)
+            .build()
+            .unwrap();
+        supergraph
+            .ready()
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap()
+            .next_response()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_query_plan_without_execution() {
+        let mut supergraph = build_supergraph(serde_json::json!({
+            "plugins": {
+                "experimental.query_plan_dry_run": { "enabled": true, "secret": "s3cr3t" }
+            },
+            "demand_control": {
+                "enabled": true,
+                "mode": "measure",
+                "strategy": { "static_estimated": { "list_size": 10, "max": 1000.0 } }
+            }
+        }))
+        .await;
+
+        let response = execute(&mut supergraph, "s3cr3t").await;
+        // The subgraphs are not called
+        assert!(response.data.is_none());
+        assert!(response.errors.is_empty());
+        let query_plan = response.extensions.get("apolloQueryPlan").unwrap();
+        assert!(query_plan.get("object").is_some());
+        let diagnostics = query_plan.get("diagnostics").unwrap();
+        assert_eq!(
+            diagnostics.get("cacheSource"),
+            Some(&Value::from("planner"))
+        );
+        assert!(diagnostics.get("planningTimeMs").unwrap().is_number());
+        // The demand control plugin estimated the cost before the execution service
+        assert!(diagnostics.get("estimatedCost").unwrap().is_number());
+
+        let response = execute(&mut supergraph, "s3cr3t").await;
+        let diagnostics = &response.extensions["apolloQueryPlan"]["diagnostics"];
+        assert_eq!(
+            diagnostics.get("cacheSource"),
+            Some(&Value::from("in_memory_cache"))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_requires_the_secret() {
+        let mut supergraph = build_supergraph(serde_json::json!({
+            "plugins": {
+                "experimental.query_plan_dry_run": { "enabled": true, "secret": "s3cr3t" }
+            }
+        }))
+        .await;
+
+        // The operation is executed
+        let response = execute(&mut supergraph, "guess").await;
+        assert!(response.extensions.get("apolloQueryPlan").is_none());
+        assert!(!response.errors.is_empty());
+
+        let mut supergraph = build_supergraph(serde_json::json!({
+            "plugins": {
+                "experimental.query_plan_dry_run": { "enabled": false, "secret": "s3cr3t" }
+            }
+        }))
+        .await;
+        let response = execute(&mut supergraph, "s3cr3t").await;
+        assert!(response.extensions.get("apolloQueryPlan").is_none());
+    }
+
+    #[test]
+    fn secret_comparison() {
+        assert!(is_secret(b"s3cr3t", b"s3cr3t"));
+        assert!(!is_secret(b"s3cr3T", b"s3cr3t"));
+        assert!(!is_secret(b"s3cr3", b"s3cr3t"));
+        assert!(!is_secret(b"", b"s3cr3t"));
+    }
+}
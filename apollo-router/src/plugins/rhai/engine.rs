@@ -35,7 +35,10 @@ use tower::BoxError;
 use uuid::Uuid;
 
 use super::execution;
+use super::http_client::HttpClient;
+use super::operation;
 use super::router;
+use super::store::Store;
 use super::subgraph;
 use super::supergraph;
 use super::Rhai;
@@ -1687,7 +1690,13 @@ impl Rhai {
         Ok(())
     }
 
-    pub(super) fn new_rhai_engine(path: Option<PathBuf>, sdl: String, main: PathBuf) -> Engine {
+    pub(super) fn new_rhai_engine(
+        path: Option<PathBuf>,
+        sdl: String,
+        main: PathBuf,
+        http_client: Arc<HttpClient>,
+        store: Arc<Store>,
+    ) -> Engine {
         let mut engine = Engine::new();
         // If we pass in a path, use it to configure our engine
         // with a FileModuleResolver which allows import to work
@@ -1710,6 +1719,15 @@ impl Rhai {
 
         let expansion_module = exported_module!(router_expansion);
 
+        let http_module = http_client.module();
+        let store_module = store.module();
+
+        // Rewritten operations are only validated against the API schema at planning time, so
+        // the supergraph schema is enough to look up fields and their arguments.
+        let schema = apollo_compiler::Schema::parse(sdl.as_str(), "supergraph.graphql")
+            .unwrap_or_else(|invalid| invalid.partial);
+        let operation_module = operation::module(Arc::new(schema));
+
         // Share main so we can move copies into each closure as required for logging
         let shared_main = Arc::new(main.display().to_string());
 
@@ -1738,6 +1756,12 @@ impl Rhai {
             // Register our expansion module (not global)
             // Hide the fact that it is an expansion module by calling it "env"
             .register_static_module("env", expansion_module.into())
+            // Register our http client module (not global)
+            .register_static_module("http", http_module.into())
+            // Register our shared store module (not global)
+            .register_static_module("store", store_module.into())
+            // Register our operation module (not global)
+            .register_static_module("operation", operation_module.into())
             // Register HeaderMap as an iterator so we can loop over contents
             .register_iterator::<HeaderMap>()
             // Register a series of logging functions
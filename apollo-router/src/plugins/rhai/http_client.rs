@@ -0,0 +1,183 @@
+//! Outbound HTTP calls from Rhai scripts.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::header::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use rhai::Dynamic;
+use rhai::EvalAltResult;
+use rhai::ImmutableString;
+use rhai::Map;
+use rhai::Module;
+use rhai::INT;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tower::BoxError;
+
+use super::block_on;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// Configuration for HTTP requests made from Rhai scripts
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct HttpClientConf {
+    /// Hosts that scripts are allowed to send requests to. A leading `*.` matches any
+    /// subdomain. Requests to any other host are rejected.
+    pub(crate) allowed_hosts: Vec<String>,
+    /// The timeout for each request
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[schemars(with = "String", default = "default_timeout")]
+    pub(crate) timeout: Duration,
+    /// The maximum size of a response body, in bytes
+    pub(crate) max_response_size: usize,
+}
+
+impl Default for HttpClientConf {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        }
+    }
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
+
+/// HTTP client shared by every engine created for a Rhai plugin instance.
+pub(crate) struct HttpClient {
+    client: reqwest::Client,
+    allowed_hosts: Vec<String>,
+    max_response_size: usize,
+}
+
+impl HttpClient {
+    pub(crate) fn new(conf: &HttpClientConf) -> Result<Self, BoxError> {
+        // Redirects are not followed: they could send the request to a host which is not
+        // in the allow list.
+        let client = reqwest::Client::builder()
+            .timeout(conf.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            client,
+            allowed_hosts: conf
+                .allowed_hosts
+                .iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+            max_response_size: conf.max_response_size,
+        })
+    }
+
+    /// Creates the `http` module exposed to scripts.
+    pub(crate) fn module(self: &Arc<Self>) -> Module {
+        let mut module = Module::new();
+
+        let client = self.clone();
+        module.set_native_fn("get", move |url: ImmutableString| {
+            client.call(Method::GET, &url, Map::new())
+        });
+        let client = self.clone();
+        module.set_native_fn("get", move |url: ImmutableString, options: Map| {
+            client.call(Method::GET, &url, options)
+        });
+        let client = self.clone();
+        module.set_native_fn("post", move |url: ImmutableString| {
+            client.call(Method::POST, &url, Map::new())
+        });
+        let client = self.clone();
+        module.set_native_fn("post", move |url: ImmutableString, options: Map| {
+            client.call(Method::POST, &url, options)
+        });
+
+        module
+    }
+
+    fn is_allowed(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .map_or(false, |prefix| prefix.ends_with('.')),
+                None => *allowed == host,
+            })
+    }
+
+    fn call(&self, method: Method, url: &str, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let url = reqwest::Url::parse(url).map_err(|e| format!("invalid url '{url}': {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("unsupported url scheme '{}'", url.scheme()).into());
+        }
+        let host = url.host_str().unwrap_or_default();
+        if !self.is_allowed(host) {
+            return Err(format!("host '{host}' is not in the list of allowed hosts").into());
+        }
+
+        let mut request = self.client.request(method, url);
+        if let Some(headers) = options.get("headers") {
+            let headers = headers
+                .clone()
+                .try_cast::<Map>()
+                .ok_or("'headers' must be a map")?;
+            for (name, value) in headers {
+                request = request.header(
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|e| e.to_string())?,
+                    HeaderValue::from_str(&value.to_string()).map_err(|e| e.to_string())?,
+                );
+            }
+        }
+        if let Some(json) = options.get("json") {
+            request = request.json(json);
+        } else if let Some(body) = options.get("body") {
+            request = request.body(body.to_string());
+        }
+
+        block_on(self.send(request))?.map_err(|e| format!("http request failed: {e}").into())
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Map, BoxError> {
+        let mut response = request.send().await?;
+
+        let mut headers = Map::new();
+        for (name, value) in response.headers() {
+            headers.insert(
+                name.as_str().into(),
+                String::from_utf8_lossy(value.as_bytes())
+                    .into_owned()
+                    .into(),
+            );
+        }
+        let status = response.status().as_u16() as INT;
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_response_size {
+                return Err(format!(
+                    "response body exceeds the maximum size of {} bytes",
+                    self.max_response_size
+                )
+                .into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let mut result = Map::new();
+        result.insert("status".into(), status.into());
+        result.insert("headers".into(), headers.into());
+        result.insert(
+            "body".into(),
+            Dynamic::from(String::from_utf8_lossy(&body).into_owned()),
+        );
+        Ok(result)
+    }
+}
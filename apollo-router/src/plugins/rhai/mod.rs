@@ -1,6 +1,7 @@
 //! Customization via Rhai.
 
 use std::fmt;
+use std::future::Future;
 use std::ops::ControlFlow;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
@@ -33,6 +34,8 @@ use rhai::Shared;
 use rhai::AST;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use tokio::runtime::Handle;
+use tokio::runtime::RuntimeFlavor;
 use tower::util::BoxService;
 use tower::BoxError;
 use tower::ServiceBuilder;
@@ -40,6 +43,10 @@ use tower::ServiceExt;
 
 use self::engine::RhaiService;
 use self::engine::SharedMut;
+use self::http_client::HttpClient;
+use self::http_client::HttpClientConf;
+use self::store::Store;
+use self::store::StoreConf;
 use crate::error::Error;
 use crate::layers::ServiceBuilderExt;
 use crate::plugin::Plugin;
@@ -52,7 +59,10 @@ mod engine;
 pub(crate) const RHAI_SPAN_NAME: &str = "rhai_plugin";
 
 mod execution;
+mod http_client;
+mod operation;
 mod router;
+mod store;
 mod subgraph;
 mod supergraph;
 
@@ -67,11 +77,15 @@ impl EngineBlock {
         scripts: Option<PathBuf>,
         main: PathBuf,
         sdl: Arc<String>,
+        http_client: Arc<HttpClient>,
+        store: Arc<Store>,
     ) -> Result<Self, BoxError> {
         let engine = Arc::new(Rhai::new_rhai_engine(
             scripts,
             sdl.to_string(),
             main.clone(),
+            http_client,
+            store,
         ));
         let ast = engine
             .compile_file(main.clone())
@@ -115,6 +129,12 @@ pub(crate) struct Conf {
     scripts: Option<PathBuf>,
     /// The main entry point for Rhai script evaluation
     main: Option<String>,
+    /// HTTP requests made from Rhai scripts
+    #[serde(default)]
+    http_client: HttpClientConf,
+    /// Key/value store shared by Rhai scripts across requests
+    #[serde(default)]
+    store: StoreConf,
 }
 
 #[async_trait::async_trait]
@@ -134,15 +154,21 @@ impl Plugin for Rhai {
         };
 
         let main = scripts_path.join(main_file);
+        let http_client = Arc::new(HttpClient::new(&init.config.http_client)?);
+        let store = Arc::new(Store::new(&init.config.store).await?);
 
         let watched_path = scripts_path.clone();
         let watched_main = main.clone();
         let watched_sdl = sdl.clone();
+        let watched_http_client = http_client.clone();
+        let watched_store = store.clone();
 
         let block = Arc::new(ArcSwap::from_pointee(EngineBlock::try_new(
             Some(scripts_path),
             main,
             sdl,
+            http_client,
+            store,
         )?));
         let watched_block = block.clone();
 
@@ -182,6 +208,8 @@ impl Plugin for Rhai {
                                         Some(watching_path.clone()),
                                         watched_main.clone(),
                                         watched_sdl.clone(),
+                                        watched_http_client.clone(),
+                                        watched_store.clone(),
                                     ) {
                                         Ok(eb) => {
                                             tracing::info!("updating rhai execution engine");
@@ -822,6 +850,18 @@ fn execute(
     }
 }
 
+/// Waits for a future from within a Rhai function.
+///
+/// Rhai functions are synchronous, so we block the current worker until the future completes.
+/// This is only possible on the multi threaded runtime used by the router.
+fn block_on<F: Future>(future: F) -> Result<F::Output, Box<EvalAltResult>> {
+    let handle = Handle::try_current().map_err(|e| e.to_string())?;
+    if handle.runtime_flavor() == RuntimeFlavor::CurrentThread {
+        return Err("this function is not supported on a current thread runtime".into());
+    }
+    Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+}
+
 register_plugin!("apollo", "rhai", Rhai);
 
 #[cfg(test)]
@@ -0,0 +1,576 @@
+//! Inspection and rewriting of GraphQL operations from Rhai scripts.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use apollo_compiler::ast;
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::Name;
+use apollo_compiler::Node;
+use apollo_compiler::Schema;
+use indexmap::IndexSet;
+use rhai::plugin::*;
+use rhai::serde::from_dynamic;
+use rhai::serde::to_dynamic;
+use rhai::Array;
+use rhai::Map;
+use serde_json_bytes::Value;
+use tower::BoxError;
+
+use crate::spec::query::parse_hir_value;
+use crate::spec::query::transform;
+
+/// A GraphQL document, with the operation selected for execution.
+#[derive(Clone)]
+pub(crate) struct Operation {
+    document: ast::Document,
+    operation_name: Option<String>,
+    schema: Arc<Schema>,
+}
+
+/// A field selected by the operation, directly or through fragments.
+struct SelectedField {
+    /// Response keys from the root of the operation, separated by dots
+    path: String,
+    field: Node<ast::Field>,
+}
+
+/// What the selected operation uses, with fragments expanded.
+#[derive(Default)]
+struct Selections {
+    fields: Vec<SelectedField>,
+    directives: IndexSet<Name>,
+    /// For each named fragment, the paths where it is spread
+    spreads: HashMap<Name, Vec<Vec<String>>>,
+}
+
+impl Operation {
+    fn parse(
+        query: &str,
+        operation_name: Option<String>,
+        schema: Arc<Schema>,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let document = ast::Document::parse(query, "query.graphql")
+            .map_err(|invalid| format!("cannot parse operation: {}", invalid.errors))?;
+        let operation = Self {
+            document,
+            operation_name,
+            schema,
+        };
+        operation.selected()?;
+        Ok(operation)
+    }
+
+    fn selected(&self) -> Result<&Node<ast::OperationDefinition>, Box<EvalAltResult>> {
+        let mut operations =
+            self.document
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    ast::Definition::OperationDefinition(operation) => Some(operation),
+                    _ => None,
+                });
+        match &self.operation_name {
+            Some(name) => operations
+                .find(|operation| operation.name.as_deref() == Some(name.as_str()))
+                .ok_or_else(|| format!("operation '{name}' not found").into()),
+            None => match (operations.next(), operations.next()) {
+                (Some(operation), None) => Ok(operation),
+                (None, _) => Err("the document contains no operation".into()),
+                (Some(_), Some(_)) => Err(
+                    "an operation name is required when the document contains several operations"
+                        .into(),
+                ),
+            },
+        }
+    }
+
+    fn selections(&self) -> Result<Selections, Box<EvalAltResult>> {
+        let operation = self.selected()?;
+        let fragments = transform::collect_fragments(&self.document);
+        let mut selections = Selections::default();
+        selections
+            .directives
+            .extend(operation.directives.iter().map(|d| d.name.clone()));
+        collect(
+            &fragments,
+            &operation.selection_set,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut selections,
+        );
+        Ok(selections)
+    }
+
+    fn rewrite(&mut self, path: &str, change: Change) -> Result<bool, Box<EvalAltResult>> {
+        let selected = self.selected()?.clone();
+        let spreads = self.selections()?.spreads;
+        let mut visitor = RewriteVisitor {
+            schema: &self.schema,
+            selected: &selected,
+            target: path.split('.').map(str::to_string).collect(),
+            change,
+            spreads,
+            prefixes: Vec::new(),
+            current: Vec::new(),
+            removed_fragments: HashSet::new(),
+            matched: false,
+        };
+        let document = transform::document(&mut visitor, &self.document)
+            .map_err(|e| format!("cannot rewrite field '{path}': {e}"))?;
+        if !visitor.matched {
+            return Ok(false);
+        }
+        let selected_remains = document.definitions.iter().any(|definition| {
+            matches!(definition, ast::Definition::OperationDefinition(operation)
+                if operation.name == selected.name)
+        });
+        if !selected_remains {
+            return Err(format!("removing field '{path}' would leave the operation empty").into());
+        }
+        self.document = document;
+        Ok(true)
+    }
+}
+
+fn collect(
+    fragments: &HashMap<&Name, &ast::FragmentDefinition>,
+    selection_set: &[ast::Selection],
+    path: &mut Vec<String>,
+    visiting: &mut Vec<Name>,
+    selections: &mut Selections,
+) {
+    for selection in selection_set {
+        match selection {
+            ast::Selection::Field(field) => {
+                path.push(field.response_name().to_string());
+                let joined = path.join(".");
+                if !selections.fields.iter().any(|f| f.path == joined) {
+                    selections.fields.push(SelectedField {
+                        path: joined,
+                        field: field.clone(),
+                    });
+                }
+                selections
+                    .directives
+                    .extend(field.directives.iter().map(|d| d.name.clone()));
+                collect(fragments, &field.selection_set, path, visiting, selections);
+                path.pop();
+            }
+            ast::Selection::InlineFragment(fragment) => {
+                selections
+                    .directives
+                    .extend(fragment.directives.iter().map(|d| d.name.clone()));
+                collect(
+                    fragments,
+                    &fragment.selection_set,
+                    path,
+                    visiting,
+                    selections,
+                );
+            }
+            ast::Selection::FragmentSpread(spread) => {
+                selections
+                    .directives
+                    .extend(spread.directives.iter().map(|d| d.name.clone()));
+                // Fragment cycles are invalid, but scripts can parse documents that were never validated
+                let Some(fragment) = fragments.get(&spread.fragment_name) else {
+                    continue;
+                };
+                if visiting.contains(&spread.fragment_name) {
+                    continue;
+                }
+                selections
+                    .spreads
+                    .entry(spread.fragment_name.clone())
+                    .or_default()
+                    .push(path.clone());
+                selections
+                    .directives
+                    .extend(fragment.directives.iter().map(|d| d.name.clone()));
+                visiting.push(spread.fragment_name.clone());
+                collect(
+                    fragments,
+                    &fragment.selection_set,
+                    path,
+                    visiting,
+                    selections,
+                );
+                visiting.pop();
+            }
+        }
+    }
+}
+
+enum Change {
+    Remove,
+    AddDirective { name: Name, arguments: Map },
+    SetArgument { name: Name, value: Value },
+}
+
+/// Applies a change to the fields at a path of the selected operation.
+///
+/// Fields selected in named fragments are changed in the fragment definition, so the change
+/// applies everywhere the fragment is spread.
+struct RewriteVisitor<'a> {
+    schema: &'a Schema,
+    selected: &'a Node<ast::OperationDefinition>,
+    target: Vec<String>,
+    change: Change,
+    spreads: HashMap<Name, Vec<Vec<String>>>,
+    /// Paths of the definition being visited, from the root of the operation
+    prefixes: Vec<Vec<String>>,
+    /// Path of the field being visited, within its definition
+    current: Vec<String>,
+    removed_fragments: HashSet<Name>,
+    matched: bool,
+}
+
+impl<'a> RewriteVisitor<'a> {
+    fn is_target(&self) -> bool {
+        self.prefixes.iter().any(|prefix| {
+            prefix.len() + self.current.len() == self.target.len()
+                && prefix
+                    .iter()
+                    .chain(self.current.iter())
+                    .eq(self.target.iter())
+        })
+    }
+
+    fn apply(
+        &self,
+        field_def: &ast::FieldDefinition,
+        field: &mut ast::Field,
+    ) -> Result<(), BoxError> {
+        match &self.change {
+            Change::Remove => {}
+            Change::AddDirective { name, arguments } => {
+                let definition = self
+                    .schema
+                    .directive_definitions
+                    .get(name)
+                    .ok_or_else(|| format!("unknown directive @{name}"))?;
+                if !definition
+                    .locations
+                    .contains(&ast::DirectiveLocation::Field)
+                {
+                    return Err(format!("directive @{name} cannot be used on fields").into());
+                }
+                if !definition.repeatable && field.directives.has(name) {
+                    return Err(format!("directive @{name} is already used on this field").into());
+                }
+                let mut directive = ast::Directive {
+                    name: name.clone(),
+                    arguments: Vec::new(),
+                };
+                for (argument, value) in arguments {
+                    let argument_def = definition
+                        .argument_by_name(argument)
+                        .ok_or_else(|| format!("directive @{name} has no argument '{argument}'"))?;
+                    let value: Value = from_dynamic(value)?;
+                    directive.arguments.push(
+                        ast::Argument {
+                            name: argument_def.name.clone(),
+                            value: to_ast_value(self.schema, &argument_def.ty, &value)?.into(),
+                        }
+                        .into(),
+                    );
+                }
+                field.directives.push(directive.into());
+            }
+            Change::SetArgument { name, value } => {
+                let argument_def = field_def.argument_by_name(name).ok_or_else(|| {
+                    format!("field '{}' has no argument '{name}'", field_def.name)
+                })?;
+                let value = Node::new(to_ast_value(self.schema, &argument_def.ty, value)?);
+                match field
+                    .arguments
+                    .iter_mut()
+                    .find(|argument| argument.name == *name)
+                {
+                    Some(argument) => argument.make_mut().value = value,
+                    None => field.arguments.push(
+                        ast::Argument {
+                            name: name.clone(),
+                            value,
+                        }
+                        .into(),
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> transform::Visitor for RewriteVisitor<'a> {
+    fn schema(&self) -> &Schema {
+        self.schema
+    }
+
+    fn operation(
+        &mut self,
+        root_type: &str,
+        def: &ast::OperationDefinition,
+    ) -> Result<Option<ast::OperationDefinition>, BoxError> {
+        if def.name != self.selected.name {
+            return Ok(Some(def.clone()));
+        }
+        self.prefixes = vec![Vec::new()];
+        transform::operation(self, root_type, def)
+    }
+
+    fn fragment_definition(
+        &mut self,
+        def: &ast::FragmentDefinition,
+    ) -> Result<Option<ast::FragmentDefinition>, BoxError> {
+        let Some(prefixes) = self.spreads.get(&def.name) else {
+            return Ok(Some(def.clone()));
+        };
+        self.prefixes = prefixes.clone();
+        let new = transform::fragment_definition(self, def)?;
+        if new.is_none() {
+            self.removed_fragments.insert(def.name.clone());
+        }
+        Ok(new)
+    }
+
+    fn field(
+        &mut self,
+        _parent_type: &str,
+        field_def: &ast::FieldDefinition,
+        def: &ast::Field,
+    ) -> Result<Option<ast::Field>, BoxError> {
+        self.current.push(def.response_name().to_string());
+        let result = if self.is_target() {
+            self.matched = true;
+            match self.change {
+                Change::Remove => Ok(None),
+                _ => {
+                    let mut new = def.clone();
+                    self.apply(field_def, &mut new).map(|()| Some(new))
+                }
+            }
+        } else {
+            transform::field(self, field_def, def)
+        };
+        self.current.pop();
+        result
+    }
+
+    fn fragment_spread(
+        &mut self,
+        def: &ast::FragmentSpread,
+    ) -> Result<Option<ast::FragmentSpread>, BoxError> {
+        if self.removed_fragments.contains(&def.fragment_name) {
+            return Ok(None);
+        }
+        transform::fragment_spread(self, def)
+    }
+}
+
+/// Converts a JSON value to a GraphQL value of the given input type.
+fn to_ast_value(schema: &Schema, ty: &ast::Type, value: &Value) -> Result<ast::Value, BoxError> {
+    Ok(match value {
+        Value::Null => ast::Value::Null,
+        Value::Array(items) if ty.is_list() => ast::Value::List(
+            items
+                .iter()
+                .map(|item| Ok(to_ast_value(schema, ty.item_type(), item)?.into()))
+                .collect::<Result<_, BoxError>>()?,
+        ),
+        // Input coercion accepts a single item where a list is expected
+        _ if ty.is_list() => {
+            ast::Value::List(vec![to_ast_value(schema, ty.item_type(), value)?.into()])
+        }
+        Value::Array(items) => ast::Value::List(
+            items
+                .iter()
+                .map(|item| Ok(to_ast_value(schema, ty, item)?.into()))
+                .collect::<Result<_, BoxError>>()?,
+        ),
+        Value::Bool(value) => ast::Value::Boolean(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => ast::Value::Int(ast::IntValue::new_parsed(&int.to_string())),
+            None => ast::Value::Float(number.as_f64().unwrap_or_default().into()),
+        },
+        Value::String(string) => match schema.types.get(ty.inner_named_type()) {
+            Some(ExtendedType::Enum(_)) => ast::Value::Enum(Name::new(string.as_str())?),
+            _ => ast::Value::String(string.as_str().to_string()),
+        },
+        Value::Object(object) => {
+            let fields = match schema.types.get(ty.inner_named_type()) {
+                Some(ExtendedType::InputObject(input)) => Some(&input.fields),
+                _ => None,
+            };
+            ast::Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match fields.and_then(|fields| fields.get(key.as_str())) {
+                            Some(field) => to_ast_value(schema, &field.ty, value)?,
+                            None => to_ast_value(
+                                schema,
+                                &ast::Type::Named(ty.inner_named_type().clone()),
+                                value,
+                            )?,
+                        };
+                        Ok((Name::new(key.as_str())?, value.into()))
+                    })
+                    .collect::<Result<_, BoxError>>()?,
+            )
+        }
+    })
+}
+
+/// Creates the `operation` module exposed to scripts.
+pub(crate) fn module(schema: Arc<Schema>) -> Module {
+    let mut module = exported_module!(operation_api);
+
+    let parse_schema = schema.clone();
+    module.set_native_fn("parse", move |query: ImmutableString| {
+        Operation::parse(&query, None, parse_schema.clone())
+    });
+    module.set_native_fn(
+        "parse",
+        move |query: ImmutableString, operation_name: Dynamic| {
+            let operation_name = if operation_name.is_unit() {
+                None
+            } else {
+                Some(operation_name.into_string()?)
+            };
+            Operation::parse(&query, operation_name, schema.clone())
+        },
+    );
+
+    module
+}
+
+// We have to keep the modules that we export using `export_module` inline because
+// error[E0658]: non-inline modules in proc macro input are unstable
+#[export_module]
+#[allow(unreachable_pub)]
+mod operation_api {
+    pub type Operation = super::Operation;
+
+    #[rhai_fn(get = "type", pure, return_raw)]
+    pub(crate) fn operation_type(op: &mut Operation) -> Result<String, Box<EvalAltResult>> {
+        Ok(op.selected()?.operation_type.name().to_string())
+    }
+
+    #[rhai_fn(get = "name", pure, return_raw)]
+    pub(crate) fn operation_name(op: &mut Operation) -> Result<Dynamic, Box<EvalAltResult>> {
+        Ok(op
+            .selected()?
+            .name
+            .as_ref()
+            .map(|name| name.to_string().into())
+            .unwrap_or_default())
+    }
+
+    #[rhai_fn(get = "root_fields", pure, return_raw)]
+    pub(crate) fn root_fields(op: &mut Operation) -> Result<Array, Box<EvalAltResult>> {
+        Ok(op
+            .selections()?
+            .fields
+            .into_iter()
+            .filter(|selected| !selected.path.contains('.'))
+            .map(|selected| selected.field.name.to_string().into())
+            .collect())
+    }
+
+    #[rhai_fn(get = "fields", pure, return_raw)]
+    pub(crate) fn fields(op: &mut Operation) -> Result<Array, Box<EvalAltResult>> {
+        op.selections()?
+            .fields
+            .into_iter()
+            .map(|selected| {
+                let field = &selected.field;
+                let mut arguments = Map::new();
+                for argument in &field.arguments {
+                    // Variables are kept as their GraphQL representation, eg. `$id`
+                    let value = match parse_hir_value(&argument.value) {
+                        Some(value) => to_dynamic(value)?,
+                        None => argument.value.to_string().into(),
+                    };
+                    arguments.insert(argument.name.as_str().into(), value);
+                }
+                let mut result = Map::new();
+                result.insert("path".into(), selected.path.into());
+                result.insert("name".into(), field.name.to_string().into());
+                result.insert(
+                    "alias".into(),
+                    field
+                        .alias
+                        .as_ref()
+                        .map(|alias| alias.to_string().into())
+                        .unwrap_or_default(),
+                );
+                result.insert("arguments".into(), arguments.into());
+                result.insert(
+                    "directives".into(),
+                    field
+                        .directives
+                        .iter()
+                        .map(|directive| Dynamic::from(directive.name.to_string()))
+                        .collect::<Array>()
+                        .into(),
+                );
+                Ok(result.into())
+            })
+            .collect()
+    }
+
+    #[rhai_fn(get = "directives", pure, return_raw)]
+    pub(crate) fn directives(op: &mut Operation) -> Result<Array, Box<EvalAltResult>> {
+        Ok(op
+            .selections()?
+            .directives
+            .into_iter()
+            .map(|name| name.to_string().into())
+            .collect())
+    }
+
+    #[rhai_fn(global, return_raw)]
+    pub(crate) fn remove_field(op: &mut Operation, path: &str) -> Result<bool, Box<EvalAltResult>> {
+        op.rewrite(path, Change::Remove)
+    }
+
+    #[rhai_fn(global, return_raw)]
+    pub(crate) fn add_directive(
+        op: &mut Operation,
+        path: &str,
+        name: &str,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        add_directive_with_arguments(op, path, name, Map::new())
+    }
+
+    #[rhai_fn(global, name = "add_directive", return_raw)]
+    pub(crate) fn add_directive_with_arguments(
+        op: &mut Operation,
+        path: &str,
+        name: &str,
+        arguments: Map,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        let name = Name::new(name).map_err(|e| e.to_string())?;
+        op.rewrite(path, Change::AddDirective { name, arguments })
+    }
+
+    #[rhai_fn(global, return_raw)]
+    pub(crate) fn set_argument(
+        op: &mut Operation,
+        path: &str,
+        name: &str,
+        value: Dynamic,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        let name = Name::new(name).map_err(|e| e.to_string())?;
+        let value: Value = from_dynamic(&value)?;
+        op.rewrite(path, Change::SetArgument { name, value })
+    }
+
+    #[rhai_fn(global, name = "to_string", pure)]
+    pub(crate) fn operation_to_string(op: &mut Operation) -> String {
+        op.document.to_string()
+    }
+}
@@ -0,0 +1,376 @@
+//! Key/value store shared by Rhai scripts across requests.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use rhai::serde::from_dynamic;
+use rhai::serde::to_dynamic;
+use rhai::Dynamic;
+use rhai::EvalAltResult;
+use rhai::ImmutableString;
+use rhai::Module;
+use rhai::INT;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json_bytes::Value;
+use tokio::sync::Mutex;
+use tokio::sync::OwnedMutexGuard;
+use tower::BoxError;
+
+use super::block_on;
+use crate::cache::estimate_size;
+use crate::cache::storage::CacheStorage;
+use crate::cache::storage::ValueType;
+use crate::configuration::InMemoryCache;
+use crate::configuration::RedisCache;
+
+const DEFAULT_MAX_VALUE_SIZE: usize = 64 * 1024;
+
+/// Configuration for the key/value store shared by Rhai scripts
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct StoreConf {
+    /// Configures the in memory store (always active)
+    pub(crate) in_memory: InMemoryCache,
+    /// Configures Redis to share the store between router instances
+    pub(crate) redis: Option<RedisCache>,
+    /// Time to live of entries stored without an explicit one
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[schemars(with = "Option<String>")]
+    pub(crate) ttl: Option<Duration>,
+    /// The maximum size of a stored value, in bytes
+    pub(crate) max_value_size: usize,
+}
+
+impl Default for StoreConf {
+    fn default() -> Self {
+        Self {
+            in_memory: InMemoryCache::default(),
+            redis: None,
+            ttl: None,
+            max_value_size: DEFAULT_MAX_VALUE_SIZE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(from = "StoredValue", into = "StoredValue")]
+pub(crate) struct StoreEntry {
+    value: Value,
+    /// Expiration date, in milliseconds since the Unix epoch
+    expires_at: Option<u64>,
+}
+
+/// Representation of a store entry in Redis.
+///
+/// Integers are stored as is, so that counters can be incremented by Redis, and expire through
+/// the Redis time to live.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum StoredValue {
+    Counter(i64),
+    Entry {
+        value: Value,
+        expires_at: Option<u64>,
+    },
+}
+
+impl From<StoredValue> for StoreEntry {
+    fn from(stored: StoredValue) -> Self {
+        match stored {
+            // The expiration of a counter is its Redis time to live, read along with it
+            StoredValue::Counter(count) => StoreEntry {
+                value: count.into(),
+                expires_at: None,
+            },
+            StoredValue::Entry { value, expires_at } => StoreEntry { value, expires_at },
+        }
+    }
+}
+
+impl From<StoreEntry> for StoredValue {
+    fn from(entry: StoreEntry) -> Self {
+        match entry.value.as_i64() {
+            Some(count) => StoredValue::Counter(count),
+            None => StoredValue::Entry {
+                value: entry.value,
+                expires_at: entry.expires_at,
+            },
+        }
+    }
+}
+
+impl StoreEntry {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= now)
+    }
+}
+
+impl ValueType for StoreEntry {
+    fn estimated_size(&self) -> Option<usize> {
+        Some(estimate_size(&self.value))
+    }
+}
+
+/// Store shared by every engine created for a Rhai plugin instance.
+pub(crate) struct Store {
+    storage: CacheStorage<String, StoreEntry>,
+    ttl: Option<Duration>,
+    max_value_size: usize,
+    /// Serializes the read-modify-write operations on each key of the in memory store
+    key_locks: KeyLocks,
+}
+
+impl Store {
+    pub(crate) async fn new(conf: &StoreConf) -> Result<Self, BoxError> {
+        // Redis expirations are set in seconds
+        if conf.redis.is_some() && conf.ttl.map_or(false, |ttl| ttl.as_secs() == 0) {
+            return Err(
+                "the store ttl must be at least one second when Redis is configured".into(),
+            );
+        }
+        Ok(Self {
+            storage: CacheStorage::new(conf.in_memory.limit, conf.redis.clone(), "rhai").await?,
+            ttl: conf.ttl,
+            max_value_size: conf.max_value_size,
+            key_locks: KeyLocks::default(),
+        })
+    }
+
+    /// Backs the store with the given Redis storage, which can be mocked.
+    #[cfg(test)]
+    pub(crate) fn with_redis(mut self, redis: crate::cache::redis::RedisCacheStorage) -> Self {
+        self.storage = self.storage.with_redis(redis);
+        self
+    }
+
+    /// Creates the `store` module exposed to scripts.
+    pub(crate) fn module(self: &Arc<Self>) -> Module {
+        let mut module = Module::new();
+
+        let store = self.clone();
+        module.set_native_fn("get", move |key: ImmutableString| {
+            match block_on(store.get(&key))? {
+                Some(value) => to_dynamic(value),
+                None => Ok(Dynamic::UNIT),
+            }
+        });
+        let store = self.clone();
+        module.set_native_fn("set", move |key: ImmutableString, value: Dynamic| {
+            let value = store.to_value(&value)?;
+            block_on(store.set(&key, value, None))
+        });
+        let store = self.clone();
+        module.set_native_fn(
+            "set",
+            move |key: ImmutableString, value: Dynamic, ttl: INT| {
+                let value = store.to_value(&value)?;
+                block_on(store.set(&key, value, Some(ttl_from_secs(ttl)?)))
+            },
+        );
+        let store = self.clone();
+        module.set_native_fn("remove", move |key: ImmutableString| {
+            block_on(store.remove(&key))
+        });
+        let store = self.clone();
+        module.set_native_fn("incr", move |key: ImmutableString| {
+            block_on(store.incr(&key, 1, None))?
+        });
+        let store = self.clone();
+        module.set_native_fn("incr", move |key: ImmutableString, by: INT| {
+            block_on(store.incr(&key, by, None))?
+        });
+        let store = self.clone();
+        module.set_native_fn("incr", move |key: ImmutableString, by: INT, ttl: INT| {
+            block_on(store.incr(&key, by, Some(ttl_from_secs(ttl)?)))?
+        });
+
+        module
+    }
+
+    fn to_value(&self, value: &Dynamic) -> Result<Value, Box<EvalAltResult>> {
+        let value: Value = from_dynamic(value)?;
+        let size = estimate_size(&value);
+        if size > self.max_value_size {
+            return Err(format!(
+                "value of {size} bytes exceeds the maximum size of {} bytes",
+                self.max_value_size
+            )
+            .into());
+        }
+        Ok(value)
+    }
+
+    /// Reads a value.
+    ///
+    /// When Redis is configured, values are read from Redis so that the values written by other
+    /// router instances are visible. The in memory copy keeps the remaining Redis time to live, and
+    /// is only read if Redis cannot be reached.
+    async fn get(&self, key: &str) -> Option<Value> {
+        let key = key.to_string();
+        let now = now_ms();
+
+        match self.storage.get_from_redis(&key).await {
+            Some(Ok(Some((mut entry, remaining)))) => {
+                if let Some(remaining) = remaining {
+                    entry.expires_at = Some(now + remaining.as_millis() as u64);
+                }
+                self.storage.insert_in_memory(key, entry.clone()).await;
+                return (!entry.is_expired(now)).then_some(entry.value);
+            }
+            Some(Ok(None)) => {
+                self.storage.remove_in_memory(&key).await;
+                return None;
+            }
+            Some(Err(e)) => {
+                tracing::warn!("could not read '{key}' from Redis, using the in memory store: {e}");
+            }
+            None => {}
+        }
+
+        let entry = self.storage.get(&key, |_| Ok(())).await?;
+        if entry.is_expired(now) {
+            self.storage.remove_in_memory(&key).await;
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    async fn set(&self, key: &str, value: Value, ttl: Option<Duration>) {
+        let _guard = self.lock_in_memory(key).await;
+        let ttl = ttl.or(self.ttl);
+        let entry = StoreEntry {
+            value,
+            expires_at: ttl.map(|ttl| now_ms() + ttl.as_millis() as u64),
+        };
+        self.storage
+            .insert_with_ttl(key.to_string(), entry, ttl)
+            .await;
+    }
+
+    async fn remove(&self, key: &str) {
+        let _guard = self.lock_in_memory(key).await;
+        self.storage.remove(&key.to_string()).await;
+    }
+
+    /// Increments a counter and returns its new value.
+    ///
+    /// Increments are atomic within a router instance, and across router instances when Redis
+    /// is configured. The time to live is only applied when the counter is created, so that a
+    /// counter can be used to implement a quota over a fixed window.
+    async fn incr(
+        &self,
+        key: &str,
+        by: INT,
+        ttl: Option<Duration>,
+    ) -> Result<INT, Box<EvalAltResult>> {
+        let key = key.to_string();
+        let now = now_ms();
+
+        // Redis increments atomically
+        if let Some(result) = self.storage.incr_in_redis(&key, by, ttl.or(self.ttl)).await {
+            let (count, remaining) =
+                result.map_err(|e| format!("could not increment counter '{key}': {e}"))?;
+            let entry = StoreEntry {
+                value: count.into(),
+                expires_at: remaining.map(|remaining| now + remaining.as_millis() as u64),
+            };
+            self.storage.insert_in_memory(key, entry).await;
+            return Ok(count);
+        }
+
+        let _guard = self.key_locks.lock(&key).await;
+        let current = self
+            .storage
+            .get(&key, |_| Ok(()))
+            .await
+            .filter(|entry| !entry.is_expired(now));
+        let (count, expires_at) = match current {
+            Some(entry) => {
+                let count = entry
+                    .value
+                    .as_i64()
+                    .ok_or_else(|| format!("value stored at '{key}' is not an integer"))?;
+                (count, entry.expires_at)
+            }
+            None => (0, ttl.or(self.ttl).map(|ttl| now + ttl.as_millis() as u64)),
+        };
+        let count = count
+            .checked_add(by)
+            .ok_or_else(|| format!("counter '{key}' overflowed"))?;
+
+        self.storage
+            .insert_in_memory(
+                key,
+                StoreEntry {
+                    value: count.into(),
+                    expires_at,
+                },
+            )
+            .await;
+        Ok(count)
+    }
+
+    /// Locks a key of the in memory store. Redis writes are not locked, they are applied in
+    /// order by Redis.
+    async fn lock_in_memory(&self, key: &str) -> Option<KeyGuard<'_>> {
+        if self.storage.has_redis() {
+            None
+        } else {
+            Some(self.key_locks.lock(key).await)
+        }
+    }
+}
+
+/// One lock per key being written. Locks are removed once they are released.
+#[derive(Default)]
+struct KeyLocks(parking_lot::Mutex<HashMap<String, Arc<Mutex<()>>>>);
+
+struct KeyGuard<'a> {
+    locks: &'a KeyLocks,
+    key: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl KeyLocks {
+    async fn lock(&self, key: &str) -> KeyGuard<'_> {
+        let lock = self.0.lock().entry(key.to_string()).or_default().clone();
+        KeyGuard {
+            locks: self,
+            key: key.to_string(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+}
+
+impl Drop for KeyGuard<'_> {
+    fn drop(&mut self) {
+        self.guard.take();
+        let mut locks = self.locks.0.lock();
+        // Locks are only cloned with the map locked, so no other task uses this one
+        if locks
+            .get(&self.key)
+            .map_or(false, |lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+fn ttl_from_secs(ttl: INT) -> Result<Duration, Box<EvalAltResult>> {
+    match u64::try_from(ttl) {
+        Ok(ttl) if ttl > 0 => Ok(Duration::from_secs(ttl)),
+        _ => Err("ttl must be a positive number of seconds".into()),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
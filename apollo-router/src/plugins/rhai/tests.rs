@@ -1,13 +1,20 @@
 //! Rhai module tests.
 
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::SystemTime;
 
+use fred::mocks::MockCommand;
+use fred::mocks::Mocks;
+use fred::prelude::RedisError;
+use fred::prelude::RedisErrorKind;
+use fred::prelude::RedisValue;
 use http::HeaderMap;
 use http::Method;
 use http::StatusCode;
+use rhai::Dynamic;
 use rhai::Engine;
 use rhai::EvalAltResult;
 use serde_json::Value;
@@ -18,10 +25,15 @@ use tower::Service;
 use tower::ServiceExt;
 use uuid::Uuid;
 
+use super::http_client::HttpClient;
+use super::http_client::HttpClientConf;
 use super::process_error;
+use super::store::Store;
+use super::store::StoreConf;
 use super::subgraph;
 use super::PathBuf;
 use super::Rhai;
+use crate::cache::redis::RedisCacheStorage;
 use crate::graphql;
 use crate::graphql::Error;
 use crate::graphql::Request;
@@ -232,7 +244,21 @@ async fn rhai_plugin_execution_service_error() -> Result<(), BoxError> {
 // A Rhai engine suitable for minimal testing. There are no scripts and the SDL is an empty
 // string.
 fn new_rhai_test_engine() -> Engine {
-    Rhai::new_rhai_engine(None, "".to_string(), PathBuf::new())
+    new_rhai_test_engine_with_http_client(&HttpClientConf::default())
+}
+
+fn new_rhai_test_engine_with_http_client(conf: &HttpClientConf) -> Engine {
+    new_rhai_test_engine_with("", conf, &StoreConf::default())
+}
+
+fn new_rhai_test_engine_with(sdl: &str, http_client: &HttpClientConf, store: &StoreConf) -> Engine {
+    Rhai::new_rhai_engine(
+        None,
+        sdl.to_string(),
+        PathBuf::new(),
+        Arc::new(HttpClient::new(http_client).expect("can create http client")),
+        Arc::new(futures::executor::block_on(Store::new(store)).expect("can create store")),
+    )
 }
 
 // Some of these tests rely extensively on internal implementation details of the tracing_test crate.
@@ -545,6 +571,428 @@ fn it_can_sha256_string() {
     assert_eq!(hash_rhai, hex::encode(hash));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn it_can_send_http_requests_to_allowed_hosts() {
+    let mock_server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("POST"))
+        .and(wiremock::matchers::path("/lookup"))
+        .and(wiremock::matchers::header("x-api-key", "secret"))
+        .and(wiremock::matchers::body_json(serde_json::json!({"id": 1})))
+        .respond_with(
+            wiremock::ResponseTemplate::new(200)
+                .insert_header("x-lookup", "hit")
+                .set_body_string(r#"{"name":"Ada"}"#),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let engine = new_rhai_test_engine_with_http_client(&HttpClientConf {
+        allowed_hosts: vec!["127.0.0.1".to_string()],
+        ..Default::default()
+    });
+    let result: String = engine
+        .eval(&format!(
+            r#"
+        let response = http::post("{}/lookup", #{{
+            headers: #{{ "x-api-key": "secret" }},
+            json: #{{ id: 1 }}
+        }});
+        `${{response.status}} ${{response.headers["x-lookup"]}} ${{json::decode(response.body).name}}`
+        "#,
+            mock_server.uri()
+        ))
+        .expect("can send http request");
+    assert_eq!(result, "200 hit Ada");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_rejects_http_requests_to_other_hosts() {
+    let engine = new_rhai_test_engine_with_http_client(&HttpClientConf {
+        allowed_hosts: vec!["*.example.com".to_string()],
+        ..Default::default()
+    });
+    let error = engine
+        .eval::<rhai::Map>(r#"http::get("http://127.0.0.1:1/")"#)
+        .expect_err("host is not allowed");
+    assert!(error
+        .to_string()
+        .contains("host '127.0.0.1' is not in the list of allowed hosts"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_rejects_http_responses_over_the_size_limit() {
+    let mock_server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("GET"))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_string("0123456789"))
+        .mount(&mock_server)
+        .await;
+
+    let engine = new_rhai_test_engine_with_http_client(&HttpClientConf {
+        allowed_hosts: vec!["127.0.0.1".to_string()],
+        max_response_size: 5,
+        ..Default::default()
+    });
+    let error = engine
+        .eval::<rhai::Map>(&format!(r#"http::get("{}")"#, mock_server.uri()))
+        .expect_err("response is too large");
+    assert!(error
+        .to_string()
+        .contains("response body exceeds the maximum size of 5 bytes"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_can_share_values_through_the_store() {
+    let engine = new_rhai_test_engine();
+    engine
+        .run(r#"store::set("user", #{ name: "Ada", roles: ["admin"] });"#)
+        .expect("can set value");
+    let name: String = engine
+        .eval(r#"store::get("user").name"#)
+        .expect("can get value");
+    assert_eq!(name, "Ada");
+
+    let missing: bool = engine
+        .eval(
+            r#"
+        store::remove("user");
+        store::get("user") == ()
+        "#,
+        )
+        .expect("can remove value");
+    assert!(missing);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_can_increment_counters_in_the_store() {
+    let engine = new_rhai_test_engine();
+    let count: i64 = engine
+        .eval(
+            r#"
+        store::incr("requests");
+        store::incr("requests", 5);
+        store::incr("requests", 10, 60)
+        "#,
+        )
+        .expect("can increment counter");
+    assert_eq!(count, 16);
+
+    let error = engine
+        .eval::<i64>(
+            r#"
+        store::set("name", "Ada");
+        store::incr("name")
+        "#,
+        )
+        .expect_err("cannot increment a string");
+    assert!(error
+        .to_string()
+        .contains("value stored at 'name' is not an integer"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_expires_values_in_the_store() {
+    let engine = new_rhai_test_engine_with(
+        "",
+        &HttpClientConf::default(),
+        &StoreConf {
+            ttl: Some(std::time::Duration::from_millis(1)),
+            ..Default::default()
+        },
+    );
+    engine
+        .run(r#"store::set("short", 1); store::set("long", 2, 60);"#)
+        .expect("can set values");
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    let values: rhai::Array = engine
+        .eval(r#"[store::get("short"), store::get("long")]"#)
+        .expect("can get values");
+    assert!(values[0].is_unit());
+    assert_eq!(values[1].as_int().unwrap(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_rejects_store_values_over_the_size_limit() {
+    let engine = new_rhai_test_engine_with(
+        "",
+        &HttpClientConf::default(),
+        &StoreConf {
+            max_value_size: 4,
+            ..Default::default()
+        },
+    );
+    let error = engine
+        .run(r#"store::set("key", "a long value");"#)
+        .expect_err("value is too large");
+    assert!(error
+        .to_string()
+        .contains("exceeds the maximum size of 4 bytes"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_rejects_a_zero_ttl_in_the_store() {
+    let engine = new_rhai_test_engine();
+    let error = engine
+        .run(r#"store::set("key", 1, 0);"#)
+        .expect_err("ttl is zero");
+    assert!(error
+        .to_string()
+        .contains("ttl must be a positive number of seconds"));
+
+    let conf: StoreConf = serde_json::from_value(serde_json::json!({
+        "redis": { "urls": ["redis://localhost:6379"] },
+        "ttl": "500ms"
+    }))
+    .unwrap();
+    assert!(Store::new(&conf).await.is_err());
+}
+
+#[derive(Debug, Default)]
+struct MockCounters {
+    counters: parking_lot::Mutex<HashMap<String, i64>>,
+}
+
+impl Mocks for MockCounters {
+    fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+        // EVAL <script> <number of keys> <key> [<by> <ttl>]
+        match (
+            &*command.cmd,
+            command.args.get(2).and_then(|key| key.as_string()),
+            command.args.get(3).and_then(|by| by.as_i64()),
+        ) {
+            ("EVAL", Some(key), Some(by)) => {
+                let mut counters = self.counters.lock();
+                let count = counters.entry(key).or_default();
+                *count += by;
+                return Ok(RedisValue::Array(vec![(*count).into(), 60_000.into()]));
+            }
+            ("EVAL", Some(key), None) => {
+                let value = match self.counters.lock().get(&key) {
+                    Some(count) => count.to_string().into(),
+                    None => RedisValue::Null,
+                };
+                return Ok(RedisValue::Array(vec![value, 60_000.into()]));
+            }
+            _ => {}
+        }
+        Err(RedisError::new(
+            RedisErrorKind::Unknown,
+            "unsupported command",
+        ))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_increments_counters_shared_through_redis() {
+    let redis = Arc::new(MockCounters::default());
+    let mut engines = Vec::new();
+    for _ in 0..2 {
+        let store = Store::new(&StoreConf::default())
+            .await
+            .expect("can create store")
+            .with_redis(
+                RedisCacheStorage::from_mocks(redis.clone())
+                    .await
+                    .expect("can mock redis"),
+            );
+        engines.push(Rhai::new_rhai_engine(
+            None,
+            "".to_string(),
+            PathBuf::new(),
+            Arc::new(HttpClient::new(&HttpClientConf::default()).expect("can create http client")),
+            Arc::new(store),
+        ));
+    }
+
+    // Each router instance increments the counter stored in Redis
+    let first: i64 = engines[0]
+        .eval(r#"store::incr("requests", 2)"#)
+        .expect("can increment counter");
+    let second: i64 = engines[1]
+        .eval(r#"store::incr("requests", 3, 60)"#)
+        .expect("can increment counter");
+    assert_eq!((first, second), (2, 5));
+    assert_eq!(redis.counters.lock().get("requests"), Some(&5));
+
+    // The first instance reads the counter from Redis, not its own in memory copy
+    let read: i64 = engines[0]
+        .eval(r#"store::get("requests")"#)
+        .expect("can read counter");
+    assert_eq!(read, 5);
+    let missing: bool = engines[0]
+        .eval(r#"store::get("missing") == ()"#)
+        .expect("can read missing key");
+    assert!(missing);
+}
+
+const OPERATION_SDL: &str = r#"
+type Query {
+    me: User
+    topProducts(first: Int, sort: Sort): [Product]
+}
+enum Sort {
+    PRICE
+    NAME
+}
+type User {
+    id: ID!
+    name: String
+    reviews: [Review]
+}
+type Review {
+    body: String
+}
+type Product {
+    name: String
+}
+"#;
+
+fn eval_operation_script(query: &str, script: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    let engine = new_rhai_test_engine_with(
+        OPERATION_SDL,
+        &HttpClientConf::default(),
+        &StoreConf::default(),
+    );
+    let mut scope = rhai::Scope::new();
+    scope.push("query", query.to_string());
+    engine.eval_with_scope(&mut scope, script)
+}
+
+#[test]
+fn it_can_inspect_operations() {
+    let result = eval_operation_script(
+        r#"
+        query GetMe($withReviews: Boolean!) {
+            me {
+                ...UserFields
+                reviews @include(if: $withReviews) { body }
+            }
+            products: topProducts(first: 2, sort: PRICE) { name }
+        }
+        fragment UserFields on User { id name }
+        "#,
+        r#"
+        let op = operation::parse(query);
+        let fields = op.fields;
+        #{
+            type: op.type,
+            name: op.name,
+            root_fields: op.root_fields,
+            paths: fields.map(|field| field.path),
+            products: fields[5],
+            reviews: fields[3].directives,
+            directives: op.directives,
+        }
+        "#,
+    )
+    .expect("can inspect operation");
+
+    assert_eq!(
+        rhai::serde::from_dynamic::<Value>(&result).unwrap(),
+        serde_json::json!({
+            "type": "query",
+            "name": "GetMe",
+            "root_fields": ["me", "topProducts"],
+            "paths": [
+                "me",
+                "me.id",
+                "me.name",
+                "me.reviews",
+                "me.reviews.body",
+                "products",
+                "products.name",
+            ],
+            "products": {
+                "path": "products",
+                "name": "topProducts",
+                "alias": "products",
+                "arguments": { "first": 2, "sort": "PRICE" },
+                "directives": [],
+            },
+            "reviews": ["include"],
+            "directives": ["include"],
+        })
+    );
+}
+
+#[test]
+fn it_can_rewrite_operations() {
+    let result = eval_operation_script(
+        r#"
+        query GetMe {
+            me {
+                ...UserFields
+                reviews { body }
+            }
+            topProducts(first: 2) { name }
+        }
+        fragment UserFields on User { id name }
+        "#,
+        r#"
+        let op = operation::parse(query, "GetMe");
+        op.remove_field("me.name");
+        op.add_directive("me.reviews", "skip", #{ "if": true });
+        op.set_argument("topProducts", "first", 5);
+        op.set_argument("topProducts", "sort", "NAME");
+        op.to_string()
+        "#,
+    )
+    .expect("can rewrite operation");
+
+    assert_eq!(
+        result.into_string().unwrap(),
+        r#"fragment UserFields on User {
+  id
+}
+
+query GetMe {
+  me {
+    ...UserFields
+    reviews @skip(if: true) {
+      body
+    }
+  }
+  topProducts(first: 5, sort: NAME) {
+    name
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn it_rejects_invalid_operation_rewrites() {
+    let query = "query { me { name } }";
+
+    let missing: bool =
+        eval_operation_script(query, r#"operation::parse(query).remove_field("me.email")"#)
+            .unwrap()
+            .as_bool()
+            .unwrap();
+    assert!(!missing);
+
+    for (script, message) in [
+        (
+            r#"operation::parse(query).remove_field("me")"#,
+            "removing field 'me' would leave the operation empty",
+        ),
+        (
+            r#"operation::parse(query).set_argument("me", "id", 1)"#,
+            "field 'me' has no argument 'id'",
+        ),
+        (
+            r#"operation::parse(query).add_directive("me", "unknown")"#,
+            "unknown directive @unknown",
+        ),
+        (r#"operation::parse("query {")"#, "cannot parse operation"),
+    ] {
+        let error = eval_operation_script(query, script).expect_err("script should fail");
+        assert!(
+            error.to_string().contains(message),
+            "'{error}' should contain '{message}'"
+        );
+    }
+}
+
 async fn base_globals_function(fn_name: &str) -> Result<bool, Box<rhai::EvalAltResult>> {
     let dyn_plugin: Box<dyn DynPlugin> = crate::plugin::plugins()
         .find(|factory| factory.name == "apollo.rhai")
@@ -24,6 +24,9 @@ use super::*;
 use crate::plugin::serde::deserialize_option_header_name;
 use crate::plugins::telemetry::metrics;
 use crate::plugins::telemetry::resource::ConfigResource;
+use crate::plugins::telemetry::server_timing::ServerTiming;
+use crate::plugins::telemetry::tracing::sampling_rules::SamplingRule;
+use crate::plugins::telemetry::tracing::tail_sampling::TailSampling;
 use crate::Configuration;
 
 #[derive(thiserror::Error, Debug)]
@@ -80,6 +83,8 @@ pub(crate) struct Exporters {
     pub(crate) metrics: Metrics,
     /// Tracing configuration
     pub(crate) tracing: Tracing,
+    /// Server-Timing header configuration
+    pub(crate) server_timing: ServerTiming,
 }
 
 /// Instrumentation configuration
@@ -349,6 +354,10 @@ pub(crate) struct TracingCommon {
     pub(crate) sampler: SamplerOption,
     /// Whether to use parent based sampling
     pub(crate) parent_based_sampler: bool,
+    /// Samplers for the requests matching a condition. The first matching rule replaces `sampler`
+    pub(crate) experimental_sampling_rules: Vec<SamplingRule>,
+    /// Tail-based sampling, deciding whether to export a trace once its request completes
+    pub(crate) experimental_tail_sampling: TailSampling,
     /// The maximum events per span before discarding
     pub(crate) max_events_per_span: u32,
     /// The maximum attributes per span before discarding
@@ -402,6 +411,8 @@ impl Default for TracingCommon {
             service_namespace: Default::default(),
             sampler: default_sampler(),
             parent_based_sampler: default_parent_based_sampler(),
+            experimental_sampling_rules: Vec::new(),
+            experimental_tail_sampling: Default::default(),
             max_events_per_span: default_max_events_per_span(),
             max_attributes_per_span: default_max_attributes_per_span(),
             max_links_per_span: default_max_links_per_span(),
@@ -378,7 +378,7 @@ impl DefaultForLevel for HttpCommonAttributes {
             DefaultAttributeRequirementLevel::Recommended => {
                 // Recommended
                 match kind {
-                    TelemetryDataKind::Traces => {
+                    TelemetryDataKind::Traces | TelemetryDataKind::Logs => {
                         if self.http_request_body_size.is_none() {
                             self.http_request_body_size = Some(true);
                         }
@@ -534,7 +534,7 @@ impl DefaultForLevel for HttpServerAttributes {
     ) {
         match requirement_level {
             DefaultAttributeRequirementLevel::Required => match kind {
-                TelemetryDataKind::Traces => {
+                TelemetryDataKind::Traces | TelemetryDataKind::Logs => {
                     if self.url_scheme.is_none() {
                         self.url_scheme = Some(true);
                     }
@@ -559,7 +559,7 @@ impl DefaultForLevel for HttpServerAttributes {
                 }
             },
             DefaultAttributeRequirementLevel::Recommended => match kind {
-                TelemetryDataKind::Traces => {
+                TelemetryDataKind::Traces | TelemetryDataKind::Logs => {
                     if self.client_address.is_none() {
                         self.client_address = Some(true);
                     }
@@ -1,5 +1,6 @@
 // Note that this configuration will be removed when events are implemented.
 
+use http::HeaderMap;
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -41,49 +42,32 @@ pub(crate) enum HeaderLoggingCondition {
 }
 
 impl HeaderLoggingCondition {
-    /// Returns if we should display the request/response headers and body given the `SupergraphRequest`
-    pub(crate) fn should_log(&self, req: &SupergraphRequest) -> (bool, bool) {
+    /// Returns if the request headers match this condition
+    pub(crate) fn matches(&self, headers: &HeaderMap) -> bool {
         match self {
             HeaderLoggingCondition::Matching {
                 name,
                 matching: matched,
-                headers,
-                body,
-            } => {
-                let header_match = req
-                    .supergraph_request
-                    .headers()
-                    .get(name)
-                    .and_then(|h| h.to_str().ok())
-                    .map(|h| matched.is_match(h))
-                    .unwrap_or_default();
-
-                if header_match {
-                    (*headers, *body)
-                } else {
-                    (false, false)
-                }
+                ..
+            } => header_value(headers, name).is_some_and(|h| matched.is_match(h)),
+            HeaderLoggingCondition::Value { name, value, .. } => {
+                header_value(headers, name).is_some_and(|h| value.as_str() == h)
             }
-            HeaderLoggingCondition::Value {
-                name,
-                value,
-                headers,
-                body,
-            } => {
-                let header_match = req
-                    .supergraph_request
-                    .headers()
-                    .get(name)
-                    .and_then(|h| h.to_str().ok())
-                    .map(|h| value.as_str() == h)
-                    .unwrap_or_default();
+        }
+    }
 
-                if header_match {
-                    (*headers, *body)
-                } else {
-                    (false, false)
-                }
-            }
+    /// Returns if we should display the request/response headers and body given the `SupergraphRequest`
+    pub(crate) fn should_log(&self, req: &SupergraphRequest) -> (bool, bool) {
+        if !self.matches(req.supergraph_request.headers()) {
+            return (false, false);
+        }
+        match self {
+            HeaderLoggingCondition::Matching { headers, body, .. }
+            | HeaderLoggingCondition::Value { headers, body, .. } => (*headers, *body),
         }
     }
 }
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|h| h.to_str().ok())
+}
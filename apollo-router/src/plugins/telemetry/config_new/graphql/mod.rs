@@ -18,6 +18,7 @@ use crate::plugins::telemetry::config_new::instruments::CustomHistogram;
 use crate::plugins::telemetry::config_new::instruments::DefaultedStandardInstrument;
 use crate::plugins::telemetry::config_new::instruments::Instrumented;
 use crate::plugins::telemetry::config_new::DefaultForLevel;
+use crate::plugins::telemetry::metrics::field_metrics::FieldMetricsConfig;
 use crate::plugins::telemetry::otlp::TelemetryDataKind;
 use crate::services::supergraph;
 use crate::Context;
@@ -40,6 +41,10 @@ pub(crate) struct GraphQLInstrumentsConfig {
     #[serde(rename = "field.execution")]
     pub(crate) field_execution:
         DefaultedStandardInstrument<Extendable<GraphQLAttributes, GraphQLSelector>>,
+
+    /// Per-field execution counts, error counts and resolution times, computed from the
+    /// field-level traces of subgraphs
+    pub(crate) experimental_field_metrics: FieldMetricsConfig,
 }
 
 impl DefaultForLevel for GraphQLInstrumentsConfig {
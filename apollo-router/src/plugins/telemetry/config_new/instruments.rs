@@ -6,6 +6,7 @@ use std::sync::Arc;
 use opentelemetry::metrics::Unit;
 use opentelemetry_api::metrics::Counter;
 use opentelemetry_api::metrics::Histogram;
+use opentelemetry_api::metrics::Meter;
 use opentelemetry_api::metrics::MeterProvider;
 use opentelemetry_api::metrics::UpDownCounter;
 use opentelemetry_api::KeyValue;
@@ -52,6 +53,9 @@ use crate::plugins::telemetry::config_new::selectors::SubgraphValue;
 use crate::plugins::telemetry::config_new::selectors::SupergraphSelector;
 use crate::plugins::telemetry::config_new::selectors::SupergraphValue;
 use crate::plugins::telemetry::config_new::Selectors;
+use crate::plugins::telemetry::metrics::cardinality::default_cardinality_limit;
+use crate::plugins::telemetry::metrics::cardinality::limit_counter;
+use crate::plugins::telemetry::metrics::cardinality::limit_histogram;
 use crate::plugins::telemetry::otlp::TelemetryDataKind;
 use crate::services::router;
 use crate::services::subgraph;
@@ -223,32 +227,10 @@ impl InstrumentsConfig {
         }
 
         for (instrument_name, instrument) in &self.router.custom {
-            match instrument.ty {
-                InstrumentType::Counter => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::CounterF64(
-                            meter
-                                .f64_counter(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-                InstrumentType::Histogram => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::Histogram(
-                            meter
-                                .f64_histogram(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-            }
+            static_instruments.insert(
+                instrument_name.clone(),
+                instrument.static_instrument(&meter, instrument_name),
+            );
         }
 
         static_instruments
@@ -418,32 +400,10 @@ impl InstrumentsConfig {
 
         let mut static_instruments = HashMap::with_capacity(self.supergraph.custom.len());
         for (instrument_name, instrument) in &self.supergraph.custom {
-            match instrument.ty {
-                InstrumentType::Counter => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::CounterF64(
-                            meter
-                                .f64_counter(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-                InstrumentType::Histogram => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::Histogram(
-                            meter
-                                .f64_histogram(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-            }
+            static_instruments.insert(
+                instrument_name.clone(),
+                instrument.static_instrument(&meter, instrument_name),
+            );
         }
         static_instruments.extend(self.supergraph.attributes.cost.new_static_instruments());
 
@@ -523,32 +483,10 @@ impl InstrumentsConfig {
         }
 
         for (instrument_name, instrument) in &self.subgraph.custom {
-            match instrument.ty {
-                InstrumentType::Counter => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::CounterF64(
-                            meter
-                                .f64_counter(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-                InstrumentType::Histogram => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::Histogram(
-                            meter
-                                .f64_histogram(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-            }
+            static_instruments.insert(
+                instrument_name.clone(),
+                instrument.static_instrument(&meter, instrument_name),
+            );
         }
 
         static_instruments
@@ -713,32 +651,10 @@ impl InstrumentsConfig {
         }
 
         for (instrument_name, instrument) in &self.graphql.custom {
-            match instrument.ty {
-                InstrumentType::Counter => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::CounterF64(
-                            meter
-                                .f64_counter(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-                InstrumentType::Histogram => {
-                    static_instruments.insert(
-                        instrument_name.clone(),
-                        StaticInstrument::Histogram(
-                            meter
-                                .f64_histogram(instrument_name.clone())
-                                .with_description(instrument.description.clone())
-                                .with_unit(Unit::new(instrument.unit.clone()))
-                                .init(),
-                        ),
-                    );
-                }
-            }
+            static_instruments.insert(
+                instrument_name.clone(),
+                instrument.static_instrument(&meter, instrument_name),
+            );
         }
 
         static_instruments
@@ -1172,6 +1088,41 @@ where
     /// The instrument conditions.
     #[serde(default = "Condition::empty::<E>")]
     condition: Condition<E>,
+
+    /// The maximum number of distinct attribute sets recorded by the instrument. Measurements with
+    /// new attribute sets over the limit are recorded with the `otel.metric.overflow` attribute.
+    #[serde(default = "default_cardinality_limit")]
+    cardinality_limit: usize,
+}
+
+impl<A, E, V> Instrument<A, E, V>
+where
+    A: Default + Debug,
+    E: Debug,
+    for<'a> &'a V: Into<InstrumentValue<E>>,
+{
+    fn static_instrument(&self, meter: &Meter, name: &str) -> StaticInstrument {
+        match self.ty {
+            InstrumentType::Counter => StaticInstrument::CounterF64(limit_counter(
+                meter
+                    .f64_counter(name.to_string())
+                    .with_description(self.description.clone())
+                    .with_unit(Unit::new(self.unit.clone()))
+                    .init(),
+                name,
+                self.cardinality_limit,
+            )),
+            InstrumentType::Histogram => StaticInstrument::Histogram(limit_histogram(
+                meter
+                    .f64_histogram(name.to_string())
+                    .with_description(self.description.clone())
+                    .with_unit(Unit::new(self.unit.clone()))
+                    .init(),
+                name,
+                self.cardinality_limit,
+            )),
+        }
+    }
 }
 
 impl<A, E, Request, Response, EventResponse, SelectorValue> Selectors
@@ -20,6 +20,7 @@ use crate::configuration::ConfigurationError;
 use crate::plugins::telemetry::config::AttributeValue;
 use crate::plugins::telemetry::config::TraceIdFormat;
 use crate::plugins::telemetry::config_new::experimental_when_header::HeaderLoggingCondition;
+use crate::plugins::telemetry::logging::body::BodyLogging;
 use crate::plugins::telemetry::resource::ConfigResource;
 use crate::services::SupergraphRequest;
 
@@ -31,6 +32,8 @@ pub(crate) struct Logging {
     pub(crate) common: LoggingCommon,
     /// Settings for logging to stdout.
     pub(crate) stdout: StdOut,
+    /// Settings for exporting logs with OTLP.
+    pub(crate) otlp: crate::plugins::telemetry::otlp::Config,
     #[serde(skip)]
     /// Settings for logging to a file.
     pub(crate) file: File,
@@ -39,6 +42,10 @@ pub(crate) struct Logging {
     /// Note that this will be removed when events are implemented.
     #[serde(rename = "experimental_when_header")]
     pub(crate) when_header: Vec<HeaderLoggingCondition>,
+
+    /// Log the GraphQL request and response bodies of a sample of the requests, with redaction
+    #[serde(rename = "experimental_body_logging")]
+    pub(crate) body_logging: BodyLogging,
 }
 
 impl Logging {
@@ -169,15 +176,6 @@ pub(crate) enum Format {
     // !!!!WARNING!!!!, if you change this enum then be sure to add the changes to the JsonSchema AND the custom deserializer.
 
     // Want to see support for these formats? Please open an issue!
-    // /// https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/CWL_AnalyzeLogData-discoverable-fields.html
-    // Aws,
-    // /// https://github.com/trentm/node-bunyan
-    // Bunyan,
-    // /// https://go2docs.graylog.org/5-0/getting_in_log_data/ingest_gelf.html#:~:text=The%20Graylog%20Extended%20Log%20Format,UDP%2C%20TCP%2C%20or%20HTTP.
-    // Gelf,
-    //
-    // /// https://cloud.google.com/logging/docs/structured-logging
-    // Google,
     // /// https://github.com/open-telemetry/opentelemetry-rust/tree/main/opentelemetry-appender-log
     // OpenTelemetry,
     /// https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/format/struct.Json.html
@@ -185,6 +183,18 @@ pub(crate) enum Format {
 
     /// https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/format/struct.Full.html
     Text(TextFormat),
+
+    /// https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/CWL_AnalyzeLogData-discoverable-fields.html
+    Aws(AwsFormat),
+
+    /// https://github.com/trentm/node-bunyan
+    Bunyan(BunyanFormat),
+
+    /// https://go2docs.graylog.org/5-0/getting_in_log_data/ingest_gelf.html
+    Gelf(GelfFormat),
+
+    /// https://cloud.google.com/logging/docs/structured-logging
+    Google(GoogleFormat),
 }
 
 // This custom implementation JsonSchema allows the user to supply an enum or a struct in the same way that the custom deserializer does.
@@ -198,6 +208,10 @@ impl JsonSchema for Format {
         let types = vec![
             ("json", JsonFormat::json_schema(gen), "Tracing subscriber https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/format/struct.Json.html"),
             ("text", TextFormat::json_schema(gen), "Tracing subscriber https://docs.rs/tracing-subscriber/latest/tracing_subscriber/fmt/format/struct.Full.html"),
+            ("aws", AwsFormat::json_schema(gen), "AWS CloudWatch https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/CWL_AnalyzeLogData-discoverable-fields.html"),
+            ("bunyan", BunyanFormat::json_schema(gen), "Bunyan https://github.com/trentm/node-bunyan"),
+            ("gelf", GelfFormat::json_schema(gen), "Graylog Extended Log Format https://go2docs.graylog.org/5-0/getting_in_log_data/ingest_gelf.html"),
+            ("google", GoogleFormat::json_schema(gen), "Google Cloud structured logging https://cloud.google.com/logging/docs/structured-logging"),
         ];
 
         Schema::Object(SchemaObject {
@@ -276,6 +290,10 @@ impl<'de> Deserialize<'de> for Format {
                 match value {
                     "json" => Ok(Format::Json(JsonFormat::default())),
                     "text" => Ok(Format::Text(TextFormat::default())),
+                    "aws" => Ok(Format::Aws(AwsFormat::default())),
+                    "bunyan" => Ok(Format::Bunyan(BunyanFormat::default())),
+                    "gelf" => Ok(Format::Gelf(GelfFormat::default())),
+                    "google" => Ok(Format::Google(GoogleFormat::default())),
                     _ => Err(E::custom(format!("unknown log format: {}", value))),
                 }
             }
@@ -289,6 +307,10 @@ impl<'de> Deserialize<'de> for Format {
                 match key.as_deref() {
                     Some("json") => Ok(Format::Json(map.next_value::<JsonFormat>()?)),
                     Some("text") => Ok(Format::Text(map.next_value::<TextFormat>()?)),
+                    Some("aws") => Ok(Format::Aws(map.next_value::<AwsFormat>()?)),
+                    Some("bunyan") => Ok(Format::Bunyan(map.next_value::<BunyanFormat>()?)),
+                    Some("gelf") => Ok(Format::Gelf(map.next_value::<GelfFormat>()?)),
+                    Some("google") => Ok(Format::Google(map.next_value::<GoogleFormat>()?)),
                     Some(value) => Err(serde::de::Error::custom(format!(
                         "unknown log format: {}",
                         value
@@ -450,6 +472,84 @@ impl Default for TextFormat {
     }
 }
 
+#[derive(Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", default)]
+pub(crate) struct AwsFormat {
+    /// Include all of the containing span information with the log event. (default: true)
+    pub(crate) display_span_list: bool,
+    /// Include the resource with the log event. (default: true)
+    pub(crate) display_resource: bool,
+}
+
+impl Default for AwsFormat {
+    fn default() -> Self {
+        AwsFormat {
+            display_span_list: true,
+            display_resource: true,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", default)]
+pub(crate) struct BunyanFormat {
+    /// Include all of the containing span information with the log event. (default: true)
+    pub(crate) display_span_list: bool,
+    /// Include the resource with the log event. (default: true)
+    pub(crate) display_resource: bool,
+}
+
+impl Default for BunyanFormat {
+    fn default() -> Self {
+        BunyanFormat {
+            display_span_list: true,
+            display_resource: true,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", default)]
+pub(crate) struct GelfFormat {
+    /// Include the attributes of the containing spans as additional fields. (default: true)
+    pub(crate) display_span_list: bool,
+    /// Include the resource as additional fields. (default: true)
+    pub(crate) display_resource: bool,
+}
+
+impl Default for GelfFormat {
+    fn default() -> Self {
+        GelfFormat {
+            display_span_list: true,
+            display_resource: true,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", default)]
+pub(crate) struct GoogleFormat {
+    /// The Google Cloud project that traces are exported to, used to link log entries to their trace.
+    pub(crate) project_id: Option<String>,
+    /// Include the file, line number and target of the log event.
+    pub(crate) display_source_location: bool,
+    /// Include all of the containing span information with the log event. (default: true)
+    pub(crate) display_span_list: bool,
+    /// Include the resource as labels of the log event. (default: true)
+    pub(crate) display_resource: bool,
+}
+
+impl Default for GoogleFormat {
+    fn default() -> Self {
+        GoogleFormat {
+            project_id: None,
+            display_source_location: false,
+            display_span_list: true,
+            display_resource: true,
+        }
+    }
+}
+
 /// The period to rollover the log file.
 #[allow(dead_code)]
 #[derive(Deserialize, JsonSchema, Clone, Default, Debug)]
@@ -22,7 +22,7 @@ pub(crate) mod cache;
 mod conditional;
 pub(crate) mod cost;
 pub(crate) mod events;
-mod experimental_when_header;
+pub(crate) mod experimental_when_header;
 pub(crate) mod extendable;
 pub(crate) mod graphql;
 pub(crate) mod instruments;
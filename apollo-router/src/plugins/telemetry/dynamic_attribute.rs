@@ -223,6 +223,10 @@ impl EventAttributes {
     pub(crate) fn take(&mut self) -> Vec<KeyValue> {
         std::mem::take(&mut self.attributes)
     }
+
+    pub(crate) fn get(&self) -> &[KeyValue] {
+        &self.attributes
+    }
 }
 
 /// To add dynamic attributes for spans
@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::io::IsTerminal;
 use std::marker::PhantomData;
 
+use opentelemetry::sdk::logs::LoggerProvider;
 use opentelemetry::Key;
 use opentelemetry::KeyValue;
 use tracing::field;
@@ -22,17 +23,37 @@ use super::formatters::EXCLUDED_ATTRIBUTES;
 use super::reload::IsSampled;
 use crate::plugins::telemetry::config;
 use crate::plugins::telemetry::config_new::logging::Format;
+use crate::plugins::telemetry::config_new::logging::RateLimit;
 use crate::plugins::telemetry::config_new::logging::StdOut;
+use crate::plugins::telemetry::formatters::aws::Aws;
+use crate::plugins::telemetry::formatters::bunyan::Bunyan;
 use crate::plugins::telemetry::formatters::filter_metric_events;
+use crate::plugins::telemetry::formatters::gelf::Gelf;
+use crate::plugins::telemetry::formatters::google::Google;
 use crate::plugins::telemetry::formatters::json::Json;
 use crate::plugins::telemetry::formatters::text::Text;
 use crate::plugins::telemetry::formatters::FilteringFormatter;
+use crate::plugins::telemetry::logging::otlp::OtlpLogLayer;
 use crate::plugins::telemetry::reload::LayeredTracer;
 use crate::plugins::telemetry::resource::ConfigResource;
 
 pub(crate) fn create_fmt_layer(
     config: &config::Conf,
+    logger_provider: Option<LoggerProvider>,
 ) -> Box<dyn Layer<LayeredTracer> + Send + Sync> {
+    let stdout_layer = create_stdout_layer(config);
+    match logger_provider {
+        // The stdout layer takes the custom attributes of events, so it runs last
+        Some(logger_provider) => {
+            OtlpLogLayer::new(logger_provider, !config.exporters.logging.stdout.enabled)
+                .and_then(stdout_layer)
+                .boxed()
+        }
+        None => stdout_layer,
+    }
+}
+
+fn create_stdout_layer(config: &config::Conf) -> Box<dyn Layer<LayeredTracer> + Send + Sync> {
     match &config.exporters.logging.stdout {
         StdOut {
             enabled,
@@ -47,29 +68,25 @@ pub(crate) fn create_fmt_layer(
             } else {
                 format
             };
+            let resource = config.exporters.logging.common.to_resource();
             match format {
                 Format::Json(format_config) => {
-                    let format = Json::new(
-                        config.exporters.logging.common.to_resource(),
-                        format_config.clone(),
-                    );
-                    FmtLayer::new(
-                        FilteringFormatter::new(format, filter_metric_events, rate_limit),
-                        std::io::stdout,
-                    )
-                    .boxed()
+                    stdout_fmt_layer(Json::new(resource, format_config.clone()), rate_limit)
                 }
-
                 Format::Text(format_config) => {
-                    let format = Text::new(
-                        config.exporters.logging.common.to_resource(),
-                        format_config.clone(),
-                    );
-                    FmtLayer::new(
-                        FilteringFormatter::new(format, filter_metric_events, rate_limit),
-                        std::io::stdout,
-                    )
-                    .boxed()
+                    stdout_fmt_layer(Text::new(resource, format_config.clone()), rate_limit)
+                }
+                Format::Aws(format_config) => {
+                    stdout_fmt_layer(Aws::new(resource, format_config.clone()), rate_limit)
+                }
+                Format::Bunyan(format_config) => {
+                    stdout_fmt_layer(Bunyan::new(resource, format_config.clone()), rate_limit)
+                }
+                Format::Gelf(format_config) => {
+                    stdout_fmt_layer(Gelf::new(resource, format_config.clone()), rate_limit)
+                }
+                Format::Google(format_config) => {
+                    stdout_fmt_layer(Google::new(resource, format_config.clone()), rate_limit)
                 }
             }
         }
@@ -77,6 +94,21 @@ pub(crate) fn create_fmt_layer(
     }
 }
 
+/// Writes the events formatted by `format` to stdout
+fn stdout_fmt_layer<T>(
+    format: T,
+    rate_limit: &RateLimit,
+) -> Box<dyn Layer<LayeredTracer> + Send + Sync>
+where
+    T: EventFormatter<LayeredTracer> + Send + Sync + 'static,
+{
+    FmtLayer::new(
+        FilteringFormatter::new(format, filter_metric_events, rate_limit),
+        std::io::stdout,
+    )
+    .boxed()
+}
+
 struct NoOpLayer;
 
 impl Layer<LayeredTracer> for NoOpLayer {}
@@ -266,6 +298,7 @@ mod tests {
 
     use http::header::CONTENT_LENGTH;
     use http::HeaderValue;
+    use opentelemetry::sdk::Resource;
     use tracing::error;
     use tracing::info;
     use tracing::info_span;
@@ -278,6 +311,10 @@ mod tests {
     use crate::plugins::telemetry::config_new::events::log_event;
     use crate::plugins::telemetry::config_new::events::EventLevel;
     use crate::plugins::telemetry::config_new::instruments::Instrumented;
+    use crate::plugins::telemetry::config_new::logging::AwsFormat;
+    use crate::plugins::telemetry::config_new::logging::BunyanFormat;
+    use crate::plugins::telemetry::config_new::logging::GelfFormat;
+    use crate::plugins::telemetry::config_new::logging::GoogleFormat;
     use crate::plugins::telemetry::config_new::logging::JsonFormat;
     use crate::plugins::telemetry::config_new::logging::RateLimit;
     use crate::plugins::telemetry::config_new::logging::TextFormat;
@@ -432,6 +469,43 @@ subgraph:
         info!(event_attr = "foo", "Hello from test");
     }
 
+    fn generate_custom_events() {
+        let test_span = info_span!(
+            "test",
+            first = "one",
+            apollo_private.should_not_display = "this should be skipped"
+        );
+        test_span.set_span_dyn_attribute("another".into(), 2.into());
+        test_span.set_span_dyn_attribute("custom_dyn".into(), "test".into());
+        let _enter = test_span.enter();
+        let attributes = vec![
+            KeyValue::new(
+                Key::from_static_str("http.response.body.size"),
+                opentelemetry::Value::String("125".to_string().into()),
+            ),
+            KeyValue::new(
+                Key::from_static_str("http.response.body"),
+                opentelemetry::Value::String(r#"{"foo": "bar"}"#.to_string().into()),
+            ),
+        ];
+        log_event(
+            EventLevel::Info,
+            "my_custom_event",
+            attributes,
+            "my message",
+        );
+
+        error!(http.method = "GET", "Hello from test");
+    }
+
+    fn test_resource() -> Resource {
+        Resource::new([
+            KeyValue::new("service.name", "router"),
+            KeyValue::new("host.name", "router-0"),
+            KeyValue::new("process.pid", 42),
+        ])
+    }
+
     #[tokio::test]
     async fn test_text_logging_attributes() {
         let buff = LogBuffer::default();
@@ -933,4 +1007,192 @@ subgraph:
     }
 
     // TODO add test using on_request/on_reponse/on_error
+
+    #[tokio::test]
+    async fn test_gelf_logging_attributes_nested_spans() {
+        let buff = LogBuffer::default();
+        let format = Gelf::new(
+            Resource::new([KeyValue::new("host.name", "router-0")]),
+            GelfFormat::default(),
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new().with(fmt_layer),
+            generate_nested_spans,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_gelf_logging_with_custom_events() {
+        let buff = LogBuffer::default();
+        let format = Gelf::new(
+            test_resource(),
+            GelfFormat {
+                display_resource: false,
+                ..Default::default()
+            },
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new()
+                .with(otel::layer().force_sampling())
+                .with(fmt_layer),
+            generate_custom_events,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_google_logging_attributes_nested_spans() {
+        let buff = LogBuffer::default();
+        let format = Google::new(
+            Resource::new([KeyValue::new("service.name", "router")]),
+            GoogleFormat::default(),
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new().with(fmt_layer),
+            generate_nested_spans,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_google_logging_with_custom_events() {
+        let buff = LogBuffer::default();
+        let format = Google::new(
+            Default::default(),
+            GoogleFormat {
+                project_id: Some("my-project".to_string()),
+                ..Default::default()
+            },
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new()
+                .with(otel::layer().force_sampling())
+                .with(fmt_layer),
+            generate_custom_events,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_aws_logging_attributes_nested_spans() {
+        let buff = LogBuffer::default();
+        let format = Aws::new(Default::default(), AwsFormat::default());
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new().with(fmt_layer),
+            generate_nested_spans,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_aws_logging_with_custom_events() {
+        let buff = LogBuffer::default();
+        let format = Aws::new(
+            Default::default(),
+            AwsFormat {
+                display_span_list: false,
+                display_resource: false,
+            },
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new()
+                .with(otel::layer().force_sampling())
+                .with(fmt_layer),
+            generate_custom_events,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_bunyan_logging_attributes_nested_spans() {
+        let buff = LogBuffer::default();
+        let format = Bunyan::new(
+            test_resource(),
+            BunyanFormat {
+                display_resource: false,
+                ..Default::default()
+            },
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new().with(fmt_layer),
+            generate_nested_spans,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_bunyan_logging_with_custom_events() {
+        let buff = LogBuffer::default();
+        let format = Bunyan::new(
+            test_resource(),
+            BunyanFormat {
+                display_span_list: false,
+                display_resource: false,
+            },
+        );
+        let fmt_layer = FmtLayer::new(
+            FilteringFormatter::new(format, filter_metric_events, &RateLimit::default()),
+            buff.clone(),
+        )
+        .boxed();
+
+        ::tracing::subscriber::with_default(
+            fmt::Subscriber::new()
+                .with(otel::layer().force_sampling())
+                .with(fmt_layer),
+            generate_custom_events,
+        );
+
+        insta::assert_snapshot!(buff.to_string());
+    }
 }
@@ -0,0 +1,99 @@
+//! JSON logs for AWS CloudWatch, see https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/CWL_AnalyzeLogData-discoverable-fields.html
+use std::collections::HashSet;
+use std::fmt;
+
+use opentelemetry::sdk::Resource;
+use opentelemetry_api::trace::TraceId;
+use serde_json::Map;
+use serde_json::Value;
+use tracing_core::Event;
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::get_trace_and_span_id;
+use super::rfc3339_timestamp;
+use super::span_list;
+use super::to_list;
+use super::EventFormatter;
+use super::JsonFields;
+use super::EXCLUDED_ATTRIBUTES;
+use crate::plugins::telemetry::config::TraceIdFormat;
+use crate::plugins::telemetry::config_new::logging::AwsFormat;
+
+pub(crate) struct Aws {
+    config: AwsFormat,
+    resource: Map<String, Value>,
+    excluded_attributes: HashSet<&'static str>,
+}
+
+impl Aws {
+    pub(crate) fn new(resource: Resource, config: AwsFormat) -> Self {
+        Self {
+            config,
+            resource: to_list(resource).into_iter().collect(),
+            excluded_attributes: EXCLUDED_ATTRIBUTES.into(),
+        }
+    }
+}
+
+/// X-Ray trace ids are made of a version, the first 8 hex digits and the remaining 24
+fn xray_trace_id(trace_id: TraceId) -> String {
+    let trace_id = TraceIdFormat::Hexadecimal.format(trace_id);
+    format!("1-{}-{}", &trace_id[..8], &trace_id[8..])
+}
+
+impl<S> EventFormatter<S> for Aws
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn format_event<W>(
+        &self,
+        ctx: &Context<'_, S>,
+        writer: &mut W,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let meta = event.metadata();
+        let span = ctx.event_span(event);
+        let JsonFields { message, fields } = JsonFields::new(event, span.as_ref());
+
+        let mut record = Map::new();
+        record.insert("timestamp".to_string(), rfc3339_timestamp()?.into());
+        record.insert("level".to_string(), meta.level().as_str().into());
+        record.insert("message".to_string(), message.unwrap_or_default().into());
+        record.insert("target".to_string(), meta.target().into());
+
+        if let Some(span) = &span {
+            if let Some((trace_id, span_id)) = get_trace_and_span_id(span) {
+                record.insert(
+                    "trace_id".to_string(),
+                    TraceIdFormat::Hexadecimal.format(trace_id).into(),
+                );
+                record.insert("xray_trace_id".to_string(), xray_trace_id(trace_id).into());
+                record.insert("span_id".to_string(), span_id.to_string().into());
+            }
+        }
+
+        for (key, value) in fields {
+            record.entry(key).or_insert(value);
+        }
+
+        if self.config.display_span_list {
+            if let Some(span) = &span {
+                record.insert(
+                    "spans".to_string(),
+                    span_list(span, &self.excluded_attributes),
+                );
+            }
+        }
+
+        if self.config.display_resource {
+            record.insert("resource".to_string(), Value::Object(self.resource.clone()));
+        }
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
@@ -0,0 +1,132 @@
+//! Bunyan log records, see https://github.com/trentm/node-bunyan#core-fields
+use std::collections::HashSet;
+use std::fmt;
+
+use opentelemetry::sdk::Resource;
+use serde_json::Map;
+use serde_json::Value;
+use tracing_core::Event;
+use tracing_core::Level;
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::get_trace_and_span_id;
+use super::rfc3339_timestamp;
+use super::span_list;
+use super::to_list;
+use super::EventFormatter;
+use super::JsonFields;
+use super::EXCLUDED_ATTRIBUTES;
+use crate::plugins::telemetry::config::TraceIdFormat;
+use crate::plugins::telemetry::config_new::logging::BunyanFormat;
+
+const BUNYAN_VERSION: u8 = 0;
+
+pub(crate) struct Bunyan {
+    config: BunyanFormat,
+    name: String,
+    hostname: String,
+    pid: Value,
+    resource: Map<String, Value>,
+    excluded_attributes: HashSet<&'static str>,
+}
+
+impl Bunyan {
+    /// The name, hostname and pid are the `service.name`, `host.name` and `process.pid` resource
+    /// attributes if set.
+    pub(crate) fn new(resource: Resource, config: BunyanFormat) -> Self {
+        let resource: Map<String, Value> = to_list(resource).into_iter().collect();
+        let name = resource
+            .get("service.name")
+            .and_then(Value::as_str)
+            .unwrap_or(env!("CARGO_PKG_NAME"))
+            .to_string();
+        let hostname = resource
+            .get("host.name")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| sys_info::hostname().unwrap_or_else(|_| "unknown".to_string()));
+        let pid = resource
+            .get("process.pid")
+            .cloned()
+            .unwrap_or_else(|| std::process::id().into());
+        Self {
+            config,
+            name,
+            hostname,
+            pid,
+            resource,
+            excluded_attributes: EXCLUDED_ATTRIBUTES.into(),
+        }
+    }
+}
+
+fn level(level: &Level) -> u8 {
+    match *level {
+        Level::TRACE => 10,
+        Level::DEBUG => 20,
+        Level::INFO => 30,
+        Level::WARN => 40,
+        Level::ERROR => 50,
+    }
+}
+
+impl<S> EventFormatter<S> for Bunyan
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn format_event<W>(
+        &self,
+        ctx: &Context<'_, S>,
+        writer: &mut W,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let meta = event.metadata();
+        let span = ctx.event_span(event);
+        let JsonFields { message, fields } = JsonFields::new(event, span.as_ref());
+
+        let mut record = Map::new();
+        record.insert("v".to_string(), BUNYAN_VERSION.into());
+        record.insert("level".to_string(), level(meta.level()).into());
+        record.insert("name".to_string(), self.name.clone().into());
+        record.insert("hostname".to_string(), self.hostname.clone().into());
+        record.insert("pid".to_string(), self.pid.clone());
+        record.insert("time".to_string(), rfc3339_timestamp()?.into());
+        record.insert("msg".to_string(), message.unwrap_or_default().into());
+        record.insert("target".to_string(), meta.target().into());
+
+        if let Some(span) = &span {
+            if let Some((trace_id, span_id)) = get_trace_and_span_id(span) {
+                record.insert(
+                    "trace_id".to_string(),
+                    TraceIdFormat::Hexadecimal.format(trace_id).into(),
+                );
+                record.insert("span_id".to_string(), span_id.to_string().into());
+            }
+        }
+
+        // Fields can't override the core fields
+        for (key, value) in fields {
+            record.entry(key).or_insert(value);
+        }
+
+        if self.config.display_span_list {
+            if let Some(span) = &span {
+                record.insert(
+                    "spans".to_string(),
+                    span_list(span, &self.excluded_attributes),
+                );
+            }
+        }
+
+        if self.config.display_resource {
+            record.insert("resource".to_string(), Value::Object(self.resource.clone()));
+        }
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
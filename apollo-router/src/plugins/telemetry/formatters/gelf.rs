@@ -0,0 +1,169 @@
+//! Graylog Extended Log Format, see https://go2docs.graylog.org/5-0/getting_in_log_data/ingest_gelf.html
+use std::collections::HashSet;
+use std::fmt;
+
+use opentelemetry::sdk::Resource;
+use serde_json::Map;
+use serde_json::Value;
+use tracing_core::Event;
+use tracing_core::Level;
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::get_trace_and_span_id;
+use super::span_attributes;
+use super::to_list;
+use super::EventFormatter;
+use super::JsonFields;
+use super::EXCLUDED_ATTRIBUTES;
+use crate::plugins::telemetry::config::TraceIdFormat;
+use crate::plugins::telemetry::config_new::logging::GelfFormat;
+
+const GELF_VERSION: &str = "1.1";
+
+pub(crate) struct Gelf {
+    config: GelfFormat,
+    host: String,
+    resource: Vec<(String, Value)>,
+    excluded_attributes: HashSet<&'static str>,
+}
+
+impl Gelf {
+    /// The host is the `host.name` resource attribute if set, or the hostname of the machine.
+    pub(crate) fn new(resource: Resource, config: GelfFormat) -> Self {
+        let resource = to_list(resource);
+        let host = resource
+            .iter()
+            .find(|(key, _)| key == "host.name")
+            .and_then(|(_, value)| value.as_str().map(str::to_string))
+            .unwrap_or_else(|| sys_info::hostname().unwrap_or_else(|_| "unknown".to_string()));
+        Self {
+            config,
+            host,
+            resource,
+            excluded_attributes: EXCLUDED_ATTRIBUTES.into(),
+        }
+    }
+}
+
+/// Syslog severity of a level
+fn syslog_level(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        Level::DEBUG | Level::TRACE => 7,
+    }
+}
+
+/// Additional fields are prefixed by an underscore, and their name can only contain word
+/// characters, dots and dashes. `_id` is reserved.
+fn additional_field(name: &str) -> String {
+    let name = format!(
+        "_{}",
+        name.replace(
+            |c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '-'),
+            "_"
+        )
+    );
+    if name == "_id" {
+        "__id".to_string()
+    } else {
+        name
+    }
+}
+
+/// Additional field values can only be strings or numbers
+fn additional_value(value: Value) -> Value {
+    match value {
+        Value::String(_) | Value::Number(_) => value,
+        value => Value::String(value.to_string()),
+    }
+}
+
+fn timestamp() -> Value {
+    #[cfg(test)]
+    {
+        "[timestamp]".into()
+    }
+    #[cfg(not(test))]
+    {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        // Milliseconds precision
+        serde_json::Number::from_f64(now.as_millis() as f64 / 1000.0)
+            .map(Value::Number)
+            .unwrap_or_default()
+    }
+}
+
+impl<S> EventFormatter<S> for Gelf
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn format_event<W>(
+        &self,
+        ctx: &Context<'_, S>,
+        writer: &mut W,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let meta = event.metadata();
+        let span = ctx.event_span(event);
+        let JsonFields { message, fields } = JsonFields::new(event, span.as_ref());
+
+        let mut record = Map::new();
+        record.insert("version".to_string(), GELF_VERSION.into());
+        record.insert("host".to_string(), self.host.clone().into());
+        record.insert(
+            "short_message".to_string(),
+            message.unwrap_or_default().into(),
+        );
+        record.insert("timestamp".to_string(), timestamp());
+        record.insert("level".to_string(), syslog_level(meta.level()).into());
+        record.insert("_target".to_string(), meta.target().into());
+
+        if let Some(span) = &span {
+            if let Some((trace_id, span_id)) = get_trace_and_span_id(span) {
+                record.insert(
+                    "_trace_id".to_string(),
+                    TraceIdFormat::Hexadecimal.format(trace_id).into(),
+                );
+                record.insert("_span_id".to_string(), span_id.to_string().into());
+            }
+        }
+
+        for (key, value) in fields {
+            record
+                .entry(additional_field(&key))
+                .or_insert_with(|| additional_value(value));
+        }
+
+        // Attributes of inner spans take precedence over the ones of their parents
+        if self.config.display_span_list {
+            if let Some(span) = &span {
+                for span in span.scope() {
+                    for (key, value) in span_attributes(&span, &self.excluded_attributes) {
+                        record
+                            .entry(additional_field(&key))
+                            .or_insert_with(|| additional_value(value));
+                    }
+                }
+            }
+        }
+
+        if self.config.display_resource {
+            for (key, value) in &self.resource {
+                record
+                    .entry(additional_field(key))
+                    .or_insert_with(|| additional_value(value.clone()));
+            }
+        }
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
@@ -0,0 +1,131 @@
+//! Google Cloud structured logging, see https://cloud.google.com/logging/docs/structured-logging
+use std::collections::HashSet;
+use std::fmt;
+
+use opentelemetry::sdk::Resource;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use tracing_core::Event;
+use tracing_core::Level;
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::get_trace_and_span_id;
+use super::rfc3339_timestamp;
+use super::span_list;
+use super::to_list;
+use super::EventFormatter;
+use super::JsonFields;
+use super::EXCLUDED_ATTRIBUTES;
+use crate::plugins::telemetry::config::TraceIdFormat;
+use crate::plugins::telemetry::config_new::logging::GoogleFormat;
+use crate::plugins::telemetry::reload::IsSampled;
+
+const TRACE_FIELD: &str = "logging.googleapis.com/trace";
+const SPAN_ID_FIELD: &str = "logging.googleapis.com/spanId";
+const TRACE_SAMPLED_FIELD: &str = "logging.googleapis.com/trace_sampled";
+const SOURCE_LOCATION_FIELD: &str = "logging.googleapis.com/sourceLocation";
+const LABELS_FIELD: &str = "logging.googleapis.com/labels";
+
+pub(crate) struct Google {
+    config: GoogleFormat,
+    labels: Map<String, Value>,
+    excluded_attributes: HashSet<&'static str>,
+}
+
+impl Google {
+    pub(crate) fn new(resource: Resource, config: GoogleFormat) -> Self {
+        // Label values must be strings
+        let labels = to_list(resource)
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(value) => (key, Value::String(value)),
+                value => (key, Value::String(value.to_string())),
+            })
+            .collect();
+        Self {
+            config,
+            labels,
+            excluded_attributes: EXCLUDED_ATTRIBUTES.into(),
+        }
+    }
+}
+
+fn severity(level: &Level) -> &'static str {
+    match *level {
+        Level::TRACE | Level::DEBUG => "DEBUG",
+        Level::INFO => "INFO",
+        Level::WARN => "WARNING",
+        Level::ERROR => "ERROR",
+    }
+}
+
+impl<S> EventFormatter<S> for Google
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn format_event<W>(
+        &self,
+        ctx: &Context<'_, S>,
+        writer: &mut W,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        let meta = event.metadata();
+        let span = ctx.event_span(event);
+        let JsonFields { message, fields } = JsonFields::new(event, span.as_ref());
+
+        let mut record = Map::new();
+        record.insert("time".to_string(), rfc3339_timestamp()?.into());
+        record.insert("severity".to_string(), severity(meta.level()).into());
+        record.insert("message".to_string(), message.unwrap_or_default().into());
+
+        if let Some(span) = &span {
+            if let Some((trace_id, span_id)) = get_trace_and_span_id(span) {
+                let trace_id = TraceIdFormat::Hexadecimal.format(trace_id);
+                let trace = match &self.config.project_id {
+                    Some(project_id) => format!("projects/{project_id}/traces/{trace_id}"),
+                    None => trace_id,
+                };
+                record.insert(TRACE_FIELD.to_string(), trace.into());
+                record.insert(SPAN_ID_FIELD.to_string(), span_id.to_string().into());
+                record.insert(TRACE_SAMPLED_FIELD.to_string(), span.is_sampled().into());
+            }
+        }
+
+        if self.config.display_source_location {
+            let mut location = json!({ "function": meta.target() });
+            if let Some(file) = meta.file() {
+                location["file"] = file.into();
+            }
+            // The line number is an int64, which the API encodes as a string
+            if let Some(line) = meta.line() {
+                location["line"] = line.to_string().into();
+            }
+            record.insert(SOURCE_LOCATION_FIELD.to_string(), location);
+        }
+
+        for (key, value) in fields {
+            record.entry(key).or_insert(value);
+        }
+
+        if self.config.display_span_list {
+            if let Some(span) = &span {
+                record.insert(
+                    "spans".to_string(),
+                    span_list(span, &self.excluded_attributes),
+                );
+            }
+        }
+
+        if self.config.display_resource && !self.labels.is_empty() {
+            record.insert(LABELS_FIELD.to_string(), Value::Object(self.labels.clone()));
+        }
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
@@ -4,7 +4,6 @@ use std::io;
 
 use opentelemetry::sdk::Resource;
 use opentelemetry::Array;
-use opentelemetry::OrderMap;
 use opentelemetry::Value;
 use serde::ser::SerializeMap;
 use serde::ser::Serializer as _;
@@ -17,6 +16,7 @@ use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::registry::SpanRef;
 
 use super::get_trace_and_span_id;
+use super::take_event_attributes;
 use super::EventFormatter;
 use super::APOLLO_PRIVATE_PREFIX;
 use super::EXCLUDED_ATTRIBUTES;
@@ -24,7 +24,6 @@ use crate::plugins::telemetry::config::AttributeValue;
 use crate::plugins::telemetry::config::TraceIdFormat;
 use crate::plugins::telemetry::config_new::logging::DisplayTraceIdFormat;
 use crate::plugins::telemetry::config_new::logging::JsonFormat;
-use crate::plugins::telemetry::dynamic_attribute::EventAttributes;
 use crate::plugins::telemetry::dynamic_attribute::LogAttributes;
 use crate::plugins::telemetry::formatters::to_list;
 use crate::plugins::telemetry::otel::OtelData;
@@ -257,26 +256,7 @@ where
                             .unwrap_or(());
                     }
                 };
-                let event_attributes = {
-                    let mut extensions = span.extensions_mut();
-                    let otel_data = extensions.get_mut::<OtelData>();
-                    let attrs = otel_data.and_then(|od| od.event_attributes.take());
-                    match attrs {
-                        Some(attrs) => Some(attrs),
-                        None => {
-                            let event_attributes = extensions.get_mut::<EventAttributes>();
-                            event_attributes.map(|event_attributes| {
-                                OrderMap::from_iter(
-                                    event_attributes
-                                        .take()
-                                        .into_iter()
-                                        .map(|kv| (kv.key, kv.value)),
-                                )
-                            })
-                        }
-                    }
-                };
-                if let Some(event_attributes) = event_attributes {
+                if let Some(event_attributes) = take_event_attributes(span) {
                     for (key, value) in event_attributes {
                         serializer.serialize_entry(key.as_str(), &AttributeValue::from(value))?;
                     }
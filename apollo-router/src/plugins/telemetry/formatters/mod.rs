@@ -1,12 +1,19 @@
 //! Our formatters and visitors used for logging
+pub(crate) mod aws;
+pub(crate) mod bunyan;
+pub(crate) mod gelf;
+pub(crate) mod google;
 pub(crate) mod json;
 pub(crate) mod text;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::time::Instant;
 
 use opentelemetry::sdk::Resource;
+use opentelemetry::Key;
+use opentelemetry::OrderMap;
 use opentelemetry_api::trace::SpanId;
 use opentelemetry_api::trace::TraceContextExt;
 use opentelemetry_api::trace::TraceId;
@@ -15,6 +22,8 @@ use parking_lot::Mutex;
 use serde_json::Number;
 use tracing::Subscriber;
 use tracing_core::callsite::Identifier;
+use tracing_core::field::Visit;
+use tracing_core::Field;
 use tracing_subscriber::fmt::format::Writer;
 use tracing_subscriber::fmt::FormatEvent;
 use tracing_subscriber::fmt::FormatFields;
@@ -23,6 +32,7 @@ use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::registry::SpanRef;
 
 use super::config_new::logging::RateLimit;
+use super::dynamic_attribute::EventAttributes;
 use super::dynamic_attribute::LogAttributes;
 use super::reload::SampledSpan;
 use crate::metrics::layer::METRIC_PREFIX_COUNTER;
@@ -238,51 +248,48 @@ pub(crate) fn filter_metric_events(event: &tracing::Event<'_>) -> bool {
 pub(crate) fn to_list(resource: Resource) -> Vec<(String, serde_json::Value)> {
     resource
         .into_iter()
-        .map(|(k, v)| {
-            (
-                k.into(),
-                match v {
-                    opentelemetry::Value::Bool(value) => serde_json::Value::Bool(value),
-                    opentelemetry::Value::I64(value) => {
-                        serde_json::Value::Number(Number::from(value))
-                    }
-                    opentelemetry::Value::F64(value) => serde_json::Value::Number(
-                        Number::from_f64(value).unwrap_or(Number::from(0)),
-                    ),
-                    opentelemetry::Value::String(value) => serde_json::Value::String(value.into()),
-                    opentelemetry::Value::Array(value) => match value {
-                        opentelemetry::Array::Bool(array) => serde_json::Value::Array(
-                            array.into_iter().map(serde_json::Value::Bool).collect(),
-                        ),
-                        opentelemetry::Array::I64(array) => serde_json::Value::Array(
-                            array
-                                .into_iter()
-                                .map(|value| serde_json::Value::Number(Number::from(value)))
-                                .collect(),
-                        ),
-                        opentelemetry::Array::F64(array) => serde_json::Value::Array(
-                            array
-                                .into_iter()
-                                .map(|value| {
-                                    serde_json::Value::Number(
-                                        Number::from_f64(value).unwrap_or(Number::from(0)),
-                                    )
-                                })
-                                .collect(),
-                        ),
-                        opentelemetry::Array::String(array) => serde_json::Value::Array(
-                            array
-                                .into_iter()
-                                .map(|s| serde_json::Value::String(s.to_string()))
-                                .collect(),
-                        ),
-                    },
-                },
-            )
-        })
+        .map(|(k, v)| (k.into(), to_json_value(v)))
         .collect()
 }
 
+pub(crate) fn to_json_value(value: opentelemetry::Value) -> serde_json::Value {
+    match value {
+        opentelemetry::Value::Bool(value) => serde_json::Value::Bool(value),
+        opentelemetry::Value::I64(value) => serde_json::Value::Number(Number::from(value)),
+        opentelemetry::Value::F64(value) => {
+            serde_json::Value::Number(Number::from_f64(value).unwrap_or(Number::from(0)))
+        }
+        opentelemetry::Value::String(value) => serde_json::Value::String(value.into()),
+        opentelemetry::Value::Array(value) => match value {
+            opentelemetry::Array::Bool(array) => {
+                serde_json::Value::Array(array.into_iter().map(serde_json::Value::Bool).collect())
+            }
+            opentelemetry::Array::I64(array) => serde_json::Value::Array(
+                array
+                    .into_iter()
+                    .map(|value| serde_json::Value::Number(Number::from(value)))
+                    .collect(),
+            ),
+            opentelemetry::Array::F64(array) => serde_json::Value::Array(
+                array
+                    .into_iter()
+                    .map(|value| {
+                        serde_json::Value::Number(
+                            Number::from_f64(value).unwrap_or(Number::from(0)),
+                        )
+                    })
+                    .collect(),
+            ),
+            opentelemetry::Array::String(array) => serde_json::Value::Array(
+                array
+                    .into_iter()
+                    .map(|s| serde_json::Value::String(s.to_string()))
+                    .collect(),
+            ),
+        },
+    }
+}
+
 pub(crate) trait EventFormatter<S> {
     fn format_event<W>(
         &self,
@@ -325,3 +332,158 @@ where
 
     None
 }
+
+/// Takes the custom attributes that an event set on its span, so that they are only displayed once.
+pub(crate) fn take_event_attributes<S>(
+    span: &SpanRef<S>,
+) -> Option<OrderMap<Key, opentelemetry::Value>>
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    let mut extensions = span.extensions_mut();
+    let otel_data = extensions.get_mut::<OtelData>();
+    match otel_data.and_then(|od| od.event_attributes.take()) {
+        Some(attrs) => Some(attrs),
+        None => {
+            let event_attributes = extensions.get_mut::<EventAttributes>();
+            event_attributes.map(|event_attributes| {
+                OrderMap::from_iter(
+                    event_attributes
+                        .take()
+                        .into_iter()
+                        .map(|kv| (kv.key, kv.value)),
+                )
+            })
+        }
+    }
+}
+
+/// Attributes of a span displayed in logs, without the private and excluded ones.
+pub(crate) fn span_attributes<S>(
+    span: &SpanRef<S>,
+    excluded_attributes: &HashSet<&'static str>,
+) -> serde_json::Map<String, serde_json::Value>
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    let ext = span.extensions();
+    let otel_attributes = ext
+        .get::<OtelData>()
+        .and_then(|otel_data| otel_data.builder.attributes.as_ref())
+        .into_iter()
+        .flat_map(|attributes| attributes.iter());
+    let custom_attributes = ext
+        .get::<LogAttributes>()
+        .into_iter()
+        .flat_map(|attributes| attributes.attributes().iter())
+        .map(|kv| (&kv.key, &kv.value));
+    #[allow(unused_mut)]
+    let mut attributes: Vec<_> = otel_attributes
+        .chain(custom_attributes)
+        .filter(|(key, _)| {
+            !key.as_str().starts_with(APOLLO_PRIVATE_PREFIX)
+                && !excluded_attributes.contains(key.as_str())
+        })
+        .map(|(key, value)| (key.to_string(), to_json_value(value.clone())))
+        .collect();
+    #[cfg(test)]
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    attributes.into_iter().collect()
+}
+
+/// The spans containing an event from the root, with their name and attributes.
+pub(crate) fn span_list<S>(
+    span: &SpanRef<S>,
+    excluded_attributes: &HashSet<&'static str>,
+) -> serde_json::Value
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    span.scope()
+        .from_root()
+        .map(|span| {
+            let mut attributes = span_attributes(&span, excluded_attributes);
+            attributes.insert("name".to_string(), span.metadata().name().into());
+            serde_json::Value::Object(attributes)
+        })
+        .collect()
+}
+
+/// The fields of an event as JSON values, preceded by the custom attributes of the event.
+///
+/// The message is kept apart, as structured formats give it a field of their own.
+pub(crate) struct JsonFields {
+    pub(crate) message: Option<String>,
+    pub(crate) fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl JsonFields {
+    pub(crate) fn new<S>(event: &tracing::Event<'_>, span: Option<&SpanRef<S>>) -> Self
+    where
+        S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    {
+        let mut fields = Self {
+            message: None,
+            fields: span
+                .and_then(take_event_attributes)
+                .into_iter()
+                .flatten()
+                .map(|(key, value)| (key.to_string(), to_json_value(value)))
+                .collect(),
+        };
+        event.record(&mut fields);
+        fields
+    }
+
+    fn record(&mut self, field: &Field, value: serde_json::Value) {
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.record(field, value.into());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{value:?}"));
+        } else {
+            self.record(field, format!("{value:?}").into());
+        }
+    }
+}
+
+/// Timestamp of an event in RFC 3339 format.
+pub(crate) fn rfc3339_timestamp() -> Result<String, fmt::Error> {
+    #[cfg(test)]
+    {
+        Ok("[timestamp]".to_string())
+    }
+    #[cfg(not(test))]
+    {
+        time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(|_| fmt::Error)
+    }
+}
@@ -6,7 +6,6 @@ use std::fmt;
 use nu_ansi_term::Color;
 use nu_ansi_term::Style;
 use opentelemetry::sdk::Resource;
-use opentelemetry::OrderMap;
 use serde_json::Value;
 use tracing_core::Event;
 use tracing_core::Field;
@@ -24,13 +23,13 @@ use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::registry::SpanRef;
 
 use super::get_trace_and_span_id;
+use super::take_event_attributes;
 use super::EventFormatter;
 use super::APOLLO_PRIVATE_PREFIX;
 use super::EXCLUDED_ATTRIBUTES;
 use crate::plugins::telemetry::config::TraceIdFormat;
 use crate::plugins::telemetry::config_new::logging::DisplayTraceIdFormat;
 use crate::plugins::telemetry::config_new::logging::TextFormat;
-use crate::plugins::telemetry::dynamic_attribute::EventAttributes;
 use crate::plugins::telemetry::dynamic_attribute::LogAttributes;
 use crate::plugins::telemetry::formatters::to_list;
 use crate::plugins::telemetry::otel::OtelData;
@@ -383,24 +382,7 @@ where
             DefaultVisitor::new(writer.by_ref(), true, self.config.ansi_escape_codes);
 
         if let Some(span) = ctx.event_span(event) {
-            let mut extensions = span.extensions_mut();
-            let otel_data = extensions.get_mut::<OtelData>();
-            let attrs = otel_data.and_then(|od| od.event_attributes.take());
-            let event_attributes = match attrs {
-                Some(attrs) => Some(attrs),
-                None => {
-                    let event_attributes = extensions.get_mut::<EventAttributes>();
-                    event_attributes.map(|event_attributes| {
-                        OrderMap::from_iter(
-                            event_attributes
-                                .take()
-                                .into_iter()
-                                .map(|kv| (kv.key, kv.value)),
-                        )
-                    })
-                }
-            };
-            if let Some(event_attributes) = event_attributes {
+            if let Some(event_attributes) = take_event_attributes(&span) {
                 for (key, value) in event_attributes {
                     default_visitor.log_debug_attrs(key.as_str(), &value);
                 }
@@ -0,0 +1,450 @@
+//! Logging of the GraphQL request and response bodies, for debugging.
+//!
+//! Bodies are logged for a sample of the requests, with the values at the configured redaction
+//! paths replaced and truncated to a maximum size.
+use std::io;
+use std::str::FromStr;
+
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use serde_json::Value;
+
+use crate::configuration::subgraph::SubgraphConfiguration;
+use crate::graphql;
+use crate::plugins::telemetry::config::Sampler;
+use crate::plugins::telemetry::config::SamplerOption;
+use crate::plugins::telemetry::otel::layer::sampling_ratio;
+use crate::services::subgraph;
+use crate::services::supergraph;
+use crate::Context;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Body logging configuration
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct BodyLogging {
+    /// Log the GraphQL request and response bodies of the supergraph
+    pub(crate) enabled: bool,
+    /// The ratio of requests whose bodies are logged, always_on, always_off or a decimal between
+    /// 0.0 and 1.0
+    pub(crate) sampler: SamplerOption,
+    /// The maximum size of a logged body in bytes, longer bodies are truncated
+    pub(crate) max_size: usize,
+    /// Paths of the values replaced with "[REDACTED]" in the logged bodies, like
+    /// `$.variables.password`, `$.data.users[*].email` or `$..token`
+    #[schemars(with = "Vec<String>")]
+    pub(crate) redact: Vec<RedactionPath>,
+    /// Logging of the GraphQL request and response bodies of subgraphs, for the sampled requests
+    pub(crate) subgraph: SubgraphConfiguration<SubgraphBodyLogging>,
+}
+
+impl Default for BodyLogging {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sampler: SamplerOption::Always(Sampler::AlwaysOn),
+            max_size: 8192,
+            redact: Vec::new(),
+            subgraph: Default::default(),
+        }
+    }
+}
+
+/// Subgraph body logging configuration
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct SubgraphBodyLogging {
+    /// Log the GraphQL request and response bodies of the subgraph
+    pub(crate) enabled: bool,
+    /// Redaction paths applied to the subgraph bodies, in addition to the common ones
+    #[schemars(with = "Vec<String>")]
+    pub(crate) redact: Vec<RedactionPath>,
+}
+
+/// Marks the requests whose bodies are logged
+#[derive(Clone, Copy, Debug)]
+struct BodyLoggingSampled;
+
+impl BodyLogging {
+    /// Decides whether the bodies of the request are logged, and logs the request body
+    pub(crate) fn on_supergraph_request(&self, request: &supergraph::Request) {
+        if !self.enabled
+            || !rand::thread_rng().gen_bool(sampling_ratio(&self.sampler).clamp(0.0, 1.0))
+        {
+            return;
+        }
+        request
+            .context
+            .extensions()
+            .with_lock(|mut lock| lock.insert(BodyLoggingSampled));
+        if let Some((body, truncated)) = self.format(request.supergraph_request.body(), &[]) {
+            ::tracing::debug!(
+                http.request.body = %body,
+                http.request.body.truncated = truncated,
+                "Supergraph request body"
+            );
+        }
+    }
+
+    /// Logs a response of the supergraph, there are several of them for deferred responses and
+    /// subscriptions
+    pub(crate) fn on_supergraph_response(&self, context: &Context, response: &graphql::Response) {
+        if !is_sampled(context) {
+            return;
+        }
+        if let Some((body, truncated)) = self.format(response, &[]) {
+            ::tracing::debug!(
+                http.response.body = %body,
+                http.response.body.truncated = truncated,
+                "Supergraph response body"
+            );
+        }
+    }
+
+    pub(crate) fn on_subgraph_request(&self, subgraph_name: &str, request: &subgraph::Request) {
+        let subgraph = self.subgraph.get(subgraph_name);
+        if !subgraph.enabled || !is_sampled(&request.context) {
+            return;
+        }
+        if let Some((body, truncated)) =
+            self.format(request.subgraph_request.body(), &subgraph.redact)
+        {
+            ::tracing::debug!(
+                subgraph.name = subgraph_name,
+                http.request.body = %body,
+                http.request.body.truncated = truncated,
+                "Subgraph request body"
+            );
+        }
+    }
+
+    pub(crate) fn on_subgraph_response(&self, subgraph_name: &str, response: &subgraph::Response) {
+        let subgraph = self.subgraph.get(subgraph_name);
+        if !subgraph.enabled || !is_sampled(&response.context) {
+            return;
+        }
+        if let Some((body, truncated)) = self.format(response.response.body(), &subgraph.redact) {
+            ::tracing::debug!(
+                subgraph.name = subgraph_name,
+                http.response.body = %body,
+                http.response.body.truncated = truncated,
+                "Subgraph response body"
+            );
+        }
+    }
+
+    /// The redacted and truncated body, and whether it was truncated
+    fn format<T: Serialize>(&self, body: &T, redact: &[RedactionPath]) -> Option<(String, bool)> {
+        if self.redact.is_empty() && redact.is_empty() {
+            return to_truncated_string(body, self.max_size);
+        }
+        let mut value = serde_json::to_value(body).ok()?;
+        for path in self.redact.iter().chain(redact) {
+            path.redact(&mut value);
+        }
+        to_truncated_string(&value, self.max_size)
+    }
+}
+
+fn is_sampled(context: &Context) -> bool {
+    context
+        .extensions()
+        .with_lock(|lock| lock.contains_key::<BodyLoggingSampled>())
+}
+
+/// Keeps the first `max_size` bytes written, then fails to stop the serialization
+struct TruncatingWriter {
+    body: Vec<u8>,
+    max_size: usize,
+    truncated: bool,
+}
+
+impl io::Write for TruncatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = self.max_size - self.body.len();
+        if buf.len() > available {
+            self.body.extend_from_slice(&buf[..available]);
+            self.truncated = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "body truncated"));
+        }
+        self.body.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the body up to `max_size` bytes, cut on a char boundary
+fn to_truncated_string<T: Serialize>(body: &T, max_size: usize) -> Option<(String, bool)> {
+    let mut writer = TruncatingWriter {
+        body: Vec::new(),
+        max_size,
+        truncated: false,
+    };
+    if serde_json::to_writer(&mut writer, body).is_err() && !writer.truncated {
+        return None;
+    }
+    let body = match String::from_utf8(writer.body) {
+        Ok(body) => body,
+        // The last char was cut
+        Err(error) => {
+            let valid_up_to = error.utf8_error().valid_up_to();
+            let mut body = error.into_bytes();
+            body.truncate(valid_up_to);
+            String::from_utf8(body).ok()?
+        }
+    };
+    Some((body, writer.truncated))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    /// `.name` or `['name']`
+    Key(String),
+    /// `[0]`
+    Index(usize),
+    /// `.*` or `[*]`
+    Wildcard,
+    /// `..name`, the `name` key at any depth
+    Descendant(String),
+}
+
+/// A JSONPath-like path to the values to redact
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RedactionPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl RedactionPath {
+    fn redact(&self, value: &mut Value) {
+        redact(value, &self.segments)
+    }
+}
+
+fn redact(value: &mut Value, segments: &[Segment]) {
+    let Some((segment, rest)) = segments.split_first() else {
+        *value = Value::String(REDACTED.to_string());
+        return;
+    };
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(object)) => {
+            if let Some(value) = object.get_mut(key) {
+                redact(value, rest);
+            }
+        }
+        (Segment::Index(index), Value::Array(array)) => {
+            if let Some(value) = array.get_mut(*index) {
+                redact(value, rest);
+            }
+        }
+        (Segment::Wildcard, Value::Object(object)) => {
+            for value in object.values_mut() {
+                redact(value, rest);
+            }
+        }
+        (Segment::Wildcard, Value::Array(array)) => {
+            for value in array {
+                redact(value, rest);
+            }
+        }
+        (Segment::Descendant(key), Value::Object(object)) => {
+            for (name, value) in object.iter_mut() {
+                if name == key {
+                    redact(value, rest);
+                }
+                redact(value, segments);
+            }
+        }
+        (Segment::Descendant(_), Value::Array(array)) => {
+            for value in array {
+                redact(value, segments);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl FromStr for RedactionPath {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid redaction path '{source}': {reason}");
+        let mut rest = source
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("it must start with '$'"))?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, after) = split_name(after);
+                if name.is_empty() {
+                    return Err(invalid("expected a field name after '..'"));
+                }
+                segments.push(Segment::Descendant(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                if let Some(after) = after.strip_prefix('*') {
+                    segments.push(Segment::Wildcard);
+                    rest = after;
+                } else {
+                    let (name, after) = split_name(after);
+                    if name.is_empty() {
+                        return Err(invalid("expected a field name after '.'"));
+                    }
+                    segments.push(Segment::Key(name.to_string()));
+                    rest = after;
+                }
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (inside, after) = after
+                    .split_once(']')
+                    .ok_or_else(|| invalid("unclosed '['"))?;
+                let segment = if inside == "*" {
+                    Segment::Wildcard
+                } else if let Some(name) = inside
+                    .strip_prefix('\'')
+                    .and_then(|inside| inside.strip_suffix('\''))
+                {
+                    Segment::Key(name.to_string())
+                } else {
+                    Segment::Index(
+                        inside
+                            .parse()
+                            .map_err(|_| invalid("expected '*', an index or a quoted name"))?,
+                    )
+                };
+                segments.push(segment);
+                rest = after;
+            } else {
+                return Err(invalid("expected '.', '..' or '['"));
+            }
+        }
+        if segments.is_empty() {
+            return Err(invalid("it must select a value"));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+}
+
+fn split_name(path: &str) -> (&str, &str) {
+    let end = path
+        .find(|c: char| c == '.' || c == '[')
+        .unwrap_or(path.len());
+    path.split_at(end)
+}
+
+impl<'de> Deserialize<'de> for RedactionPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        source.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for RedactionPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn redacted(paths: &[&str], mut value: Value) -> Value {
+        for path in paths {
+            path.parse::<RedactionPath>().unwrap().redact(&mut value);
+        }
+        value
+    }
+
+    #[test]
+    fn redaction_paths() {
+        assert_eq!(
+            redacted(
+                &["$.variables.password", "$.variables.cards[*].number"],
+                json!({
+                    "query": "mutation { signUp }",
+                    "variables": {
+                        "login": "ada",
+                        "password": "hunter2",
+                        "cards": [{ "number": "4242", "expiry": "12/30" }, { "number": "1111" }]
+                    }
+                })
+            ),
+            json!({
+                "query": "mutation { signUp }",
+                "variables": {
+                    "login": "ada",
+                    "password": REDACTED,
+                    "cards": [{ "number": REDACTED, "expiry": "12/30" }, { "number": REDACTED }]
+                }
+            })
+        );
+        assert_eq!(
+            redacted(
+                &["$..email", "$.data.users[0]['name']"],
+                json!({
+                    "data": {
+                        "users": [
+                            { "name": "ada", "email": "ada@example.com" },
+                            { "name": "grace", "contact": { "email": "grace@example.com" } }
+                        ]
+                    }
+                })
+            ),
+            json!({
+                "data": {
+                    "users": [
+                        { "name": REDACTED, "email": REDACTED },
+                        { "name": "grace", "contact": { "email": REDACTED } }
+                    ]
+                }
+            })
+        );
+        assert_eq!(
+            redacted(&["$.data.*"], json!({ "data": { "a": 1, "b": [2] } })),
+            json!({ "data": { "a": REDACTED, "b": REDACTED } })
+        );
+    }
+
+    #[test]
+    fn invalid_redaction_paths() {
+        for path in ["", "$", "variables.password", "$.", "$..", "$[x]", "$[0"] {
+            assert!(path.parse::<RedactionPath>().is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn bodies_are_truncated_on_char_boundaries() {
+        assert_eq!(
+            to_truncated_string(&json!({}), 10),
+            Some(("{}".to_string(), false))
+        );
+        assert_eq!(
+            to_truncated_string(&json!("héllo"), 3),
+            Some(("\"h".to_string(), true))
+        );
+        assert_eq!(
+            to_truncated_string(&json!({ "data": [1, 2, 3] }), 12),
+            Some(("{\"data\":[1,2".to_string(), true))
+        );
+    }
+}
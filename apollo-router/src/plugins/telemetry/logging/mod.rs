@@ -1,4 +1,7 @@
 //TODO move telemetry logging functionality to this file
+pub(crate) mod body;
+pub(crate) mod otlp;
+
 #[cfg(test)]
 mod test {
     use tracing_futures::WithSubscriber;
@@ -0,0 +1,305 @@
+//! Export of log records with OTLP.
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use opentelemetry::sdk::logs::BatchLogProcessor;
+use opentelemetry::sdk::logs::LoggerProvider;
+use opentelemetry::Key;
+use opentelemetry::Value;
+use opentelemetry_api::logs::AnyValue;
+use opentelemetry_api::logs::LogRecord;
+use opentelemetry_api::logs::Logger as _;
+use opentelemetry_api::logs::LoggerProvider as _;
+use opentelemetry_api::logs::Severity;
+use opentelemetry_api::trace::SpanContext;
+use opentelemetry_api::trace::TraceFlags;
+use opentelemetry_api::trace::TraceState;
+use opentelemetry_otlp::LogExporterBuilder;
+use tower::BoxError;
+use tracing_core::field::Visit;
+use tracing_core::Event;
+use tracing_core::Field;
+use tracing_core::Level;
+use tracing_core::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::plugins::telemetry::config;
+use crate::plugins::telemetry::dynamic_attribute::EventAttributes;
+use crate::plugins::telemetry::formatters::filter_metric_events;
+use crate::plugins::telemetry::formatters::get_trace_and_span_id;
+use crate::plugins::telemetry::formatters::EXCLUDED_ATTRIBUTES;
+use crate::plugins::telemetry::otel::OtelData;
+use crate::plugins::telemetry::otlp::TelemetryDataKind;
+use crate::plugins::telemetry::reload::IsSampled;
+use crate::plugins::telemetry::resource::ConfigResource;
+use crate::plugins::telemetry::Telemetry;
+use crate::plugins::telemetry::GLOBAL_TRACER_NAME;
+
+/// Creates the provider exporting logs with OTLP, if it is enabled.
+pub(crate) fn create_logger_provider(
+    config: &config::Conf,
+) -> Result<Option<LoggerProvider>, BoxError> {
+    let logging = &config.exporters.logging;
+    if !logging.otlp.enabled {
+        return Ok(None);
+    }
+
+    tracing::info!("Configuring Otlp logging: {}", logging.otlp.batch_processor);
+    let exporter: LogExporterBuilder = logging.otlp.exporter(TelemetryDataKind::Logs)?;
+    let batch_config = &logging.otlp.batch_processor;
+    let processor = BatchLogProcessor::builder(
+        exporter.build_log_exporter()?,
+        opentelemetry::runtime::Tokio,
+    )
+    .with_scheduled_delay(batch_config.scheduled_delay)
+    .with_max_queue_size(batch_config.max_queue_size)
+    .with_max_export_batch_size(batch_config.max_export_batch_size)
+    .with_max_timeout(batch_config.max_export_timeout)
+    .build();
+
+    Ok(Some(
+        LoggerProvider::builder()
+            .with_config(
+                opentelemetry::sdk::logs::Config::default()
+                    .with_resource(logging.common.to_resource()),
+            )
+            .with_log_processor(processor)
+            .build(),
+    ))
+}
+
+/// Sends every log event to an OTLP logger, along with the trace and span it happened in.
+pub(crate) struct OtlpLogLayer {
+    // Loggers only hold a weak reference to their provider
+    provider: Option<LoggerProvider>,
+    logger: opentelemetry::sdk::logs::Logger,
+    excluded_attributes: HashSet<&'static str>,
+    /// When another layer also writes the custom attributes of events, it takes them after us
+    take_event_attributes: bool,
+}
+
+impl OtlpLogLayer {
+    pub(crate) fn new(provider: LoggerProvider, take_event_attributes: bool) -> Self {
+        let logger = provider.versioned_logger(
+            GLOBAL_TRACER_NAME,
+            Some(env!("CARGO_PKG_VERSION").into()),
+            None,
+            None,
+        );
+        Self {
+            provider: Some(provider),
+            logger,
+            excluded_attributes: EXCLUDED_ATTRIBUTES.into(),
+            take_event_attributes,
+        }
+    }
+}
+
+impl Drop for OtlpLogLayer {
+    fn drop(&mut self) {
+        // Shutting down the provider flushes the pending logs, which blocks
+        if let Some(provider) = self.provider.take() {
+            Telemetry::checked_spawn_task(Box::new(move || drop(provider)));
+        }
+    }
+}
+
+impl<S> Layer<S> for OtlpLogLayer
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if !filter_metric_events(event) {
+            return;
+        }
+
+        let mut visitor = LogRecordVisitor {
+            excluded_attributes: &self.excluded_attributes,
+            body: None,
+            attributes: Vec::new(),
+        };
+        event.record(&mut visitor);
+
+        let now = SystemTime::now();
+        let level = event.metadata().level();
+        let mut record = LogRecord::builder()
+            .with_timestamp(now)
+            .with_observed_timestamp(now)
+            .with_severity_text(level.as_str())
+            .with_severity_number(severity(level));
+        if let Some(body) = visitor.body {
+            record = record.with_body(body);
+        }
+
+        if let Some(span) = ctx.event_span(event) {
+            if let Some((trace_id, span_id)) = get_trace_and_span_id(&span) {
+                let trace_flags = if span.is_sampled() {
+                    TraceFlags::SAMPLED
+                } else {
+                    TraceFlags::default()
+                };
+                record = record.with_span_context(&SpanContext::new(
+                    trace_id,
+                    span_id,
+                    trace_flags,
+                    false,
+                    TraceState::default(),
+                ));
+            }
+
+            let mut extensions = span.extensions_mut();
+            let event_attributes: Vec<(Key, Value)> =
+                match extensions.get_mut::<OtelData>().and_then(|otel_data| {
+                    if self.take_event_attributes {
+                        otel_data.event_attributes.take()
+                    } else {
+                        otel_data.event_attributes.clone()
+                    }
+                }) {
+                    Some(attributes) => attributes.into_iter().collect(),
+                    None => extensions
+                        .get_mut::<EventAttributes>()
+                        .map(|attributes| {
+                            if self.take_event_attributes {
+                                attributes.take()
+                            } else {
+                                attributes.get().to_vec()
+                            }
+                        })
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|kv| (kv.key, kv.value))
+                        .collect(),
+                };
+            visitor.attributes.extend(
+                event_attributes
+                    .into_iter()
+                    .map(|(key, value)| (key, AnyValue::from(value))),
+            );
+        }
+
+        if !visitor.attributes.is_empty() {
+            record = record.with_attributes(visitor.attributes);
+        }
+        self.logger.emit(record.build());
+    }
+}
+
+fn severity(level: &Level) -> Severity {
+    match *level {
+        Level::TRACE => Severity::Trace,
+        Level::DEBUG => Severity::Debug,
+        Level::INFO => Severity::Info,
+        Level::WARN => Severity::Warn,
+        Level::ERROR => Severity::Error,
+    }
+}
+
+struct LogRecordVisitor<'a> {
+    excluded_attributes: &'a HashSet<&'static str>,
+    body: Option<AnyValue>,
+    attributes: Vec<(Key, AnyValue)>,
+}
+
+impl LogRecordVisitor<'_> {
+    fn record(&mut self, field: &Field, value: AnyValue) {
+        let name = field.name();
+        if name == "message" {
+            self.body = Some(value);
+        } else if !name.starts_with("log.") && !self.excluded_attributes.contains(name) {
+            self.attributes.push((Key::from_static_str(name), value));
+        }
+    }
+}
+
+impl Visit for LogRecordVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.record(field, value.into()),
+            Err(_) => self.record(field, value.to_string().into()),
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use opentelemetry::sdk::export::logs::LogData;
+    use opentelemetry::sdk::export::logs::LogExporter;
+    use opentelemetry_api::logs::LogResult;
+    use parking_lot::Mutex;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[derive(Clone, Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<LogData>>>);
+
+    #[async_trait::async_trait]
+    impl LogExporter for Recorder {
+        async fn export(&mut self, batch: Vec<LogData>) -> LogResult<()> {
+            self.0.lock().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_are_exported_as_log_records() {
+        let recorder = Recorder::default();
+        let provider = LoggerProvider::builder()
+            .with_simple_exporter(recorder.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry().with(OtlpLogLayer::new(provider, true));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(
+                http.response.status_code = 500,
+                code.lineno = 12,
+                "subgraph failed"
+            );
+        });
+
+        let records = recorder.0.lock();
+        assert_eq!(records.len(), 1);
+        let record = &records[0].record;
+        assert_eq!(record.severity_number, Some(Severity::Warn));
+        assert_eq!(record.severity_text.as_deref(), Some("WARN"));
+        assert_eq!(
+            format!("{:?}", record.body),
+            format!("{:?}", Some(AnyValue::from("subgraph failed")))
+        );
+        assert_eq!(
+            format!("{:?}", record.attributes),
+            format!(
+                "{:?}",
+                Some(vec![(
+                    Key::from_static_str("http.response.status_code"),
+                    AnyValue::Int(500)
+                )])
+            )
+        );
+    }
+}
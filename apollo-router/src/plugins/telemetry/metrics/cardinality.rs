@@ -0,0 +1,183 @@
+//! Cardinality limit of custom instruments.
+//!
+//! Once an instrument has recorded its limit of distinct attribute sets, measurements with new
+//! attribute sets are recorded in a single series with the `otel.metric.overflow=true` attribute,
+//! as described in https://opentelemetry.io/docs/specs/otel/metrics/sdk/#overflow-attribute
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::hash::DefaultHasher;
+use std::hash::Hasher;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use opentelemetry::metrics::Counter;
+use opentelemetry::metrics::Histogram;
+use opentelemetry::metrics::SyncCounter;
+use opentelemetry::metrics::SyncHistogram;
+use opentelemetry::KeyValue;
+use parking_lot::RwLock;
+
+pub(crate) const OVERFLOW_ATTRIBUTE: &str = "otel.metric.overflow";
+
+pub(crate) const fn default_cardinality_limit() -> usize {
+    2000
+}
+
+/// Writes formatted values into a hasher
+struct HashWriter<'a>(&'a mut DefaultHasher);
+
+impl Write for HashWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Identifies an attribute set whatever the order of its attributes, without allocating.
+///
+/// Attribute sets with the same hash share a series, which is unlikely enough to be ignored.
+fn attribute_set_hash(attributes: &[KeyValue]) -> u64 {
+    attributes.iter().fold(0u64, |hash, kv| {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(kv.key.as_str().as_bytes());
+        hasher.write_u8(0xff);
+        let _ = write!(HashWriter(&mut hasher), "{}", kv.value);
+        // Adding the hashes of the attributes makes the order irrelevant
+        hash.wrapping_add(hasher.finish())
+    })
+}
+
+/// The distinct attribute sets recorded by an instrument
+pub(crate) struct CardinalityLimiter {
+    name: String,
+    limit: usize,
+    attribute_sets: RwLock<HashSet<u64>>,
+    warned: AtomicBool,
+}
+
+impl CardinalityLimiter {
+    pub(crate) fn new(name: &str, limit: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            limit,
+            attribute_sets: Default::default(),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// The attributes to record a measurement with, the overflow attribute if the measurement
+    /// would create a new series over the limit
+    pub(crate) fn attributes<'a>(&self, attributes: &'a [KeyValue]) -> Cow<'a, [KeyValue]> {
+        let attribute_set = attribute_set_hash(attributes);
+
+        // Most measurements are recorded in an existing series, only a read lock is needed
+        if self.attribute_sets.read().contains(&attribute_set) {
+            return Cow::Borrowed(attributes);
+        }
+        let overflow = {
+            let mut attribute_sets = self.attribute_sets.write();
+            if attribute_sets.len() < self.limit {
+                attribute_sets.insert(attribute_set);
+                false
+            } else {
+                !attribute_sets.contains(&attribute_set)
+            }
+        };
+        if !overflow {
+            return Cow::Borrowed(attributes);
+        }
+
+        if !self.warned.swap(true, Ordering::Relaxed) {
+            ::tracing::warn!(
+                "the instrument '{}' reached its cardinality limit of {} attribute sets, new attribute sets are recorded with the '{}' attribute",
+                self.name,
+                self.limit,
+                OVERFLOW_ATTRIBUTE
+            );
+        }
+        u64_counter!(
+            "apollo.router.telemetry.metrics.cardinality_overflow",
+            "Number of measurements recorded in the overflow series of an instrument because it reached its cardinality limit",
+            1,
+            [KeyValue::new("metric.name", self.name.clone())]
+        );
+        Cow::Owned(vec![KeyValue::new(OVERFLOW_ATTRIBUTE, true)])
+    }
+}
+
+struct LimitedCounter {
+    delegate: Counter<f64>,
+    limiter: CardinalityLimiter,
+}
+
+impl SyncCounter<f64> for LimitedCounter {
+    fn add(&self, value: f64, attributes: &[KeyValue]) {
+        self.delegate
+            .add(value, &self.limiter.attributes(attributes))
+    }
+}
+
+struct LimitedHistogram {
+    delegate: Histogram<f64>,
+    limiter: CardinalityLimiter,
+}
+
+impl SyncHistogram<f64> for LimitedHistogram {
+    fn record(&self, value: f64, attributes: &[KeyValue]) {
+        self.delegate
+            .record(value, &self.limiter.attributes(attributes))
+    }
+}
+
+pub(crate) fn limit_counter(counter: Counter<f64>, name: &str, limit: usize) -> Counter<f64> {
+    Counter::new(Arc::new(LimitedCounter {
+        delegate: counter,
+        limiter: CardinalityLimiter::new(name, limit),
+    }))
+}
+
+pub(crate) fn limit_histogram(
+    histogram: Histogram<f64>,
+    name: &str,
+    limit: usize,
+) -> Histogram<f64> {
+    Histogram::new(Arc::new(LimitedHistogram {
+        delegate: histogram,
+        limiter: CardinalityLimiter::new(name, limit),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metrics::FutureMetricsExt;
+
+    #[tokio::test]
+    async fn new_attribute_sets_over_the_limit_overflow() {
+        async {
+            let limiter = CardinalityLimiter::new("acme.requests", 2);
+            let a = [KeyValue::new("client", "a"), KeyValue::new("status", 200)];
+            let a_reordered = [KeyValue::new("status", 200), KeyValue::new("client", "a")];
+            let b = [KeyValue::new("client", "b")];
+            let c = [KeyValue::new("client", "c")];
+            let overflow = [KeyValue::new(OVERFLOW_ATTRIBUTE, true)];
+
+            assert_eq!(limiter.attributes(&a).as_ref(), &a);
+            assert_eq!(limiter.attributes(&b).as_ref(), &b);
+            assert_eq!(limiter.attributes(&c).as_ref(), &overflow);
+            assert_eq!(limiter.attributes(&c).as_ref(), &overflow);
+            // Attribute sets already recorded are still recorded in their own series
+            assert_eq!(limiter.attributes(&a_reordered).as_ref(), &a_reordered);
+
+            assert_counter!(
+                "apollo.router.telemetry.metrics.cardinality_overflow",
+                2,
+                "metric.name" = "acme.requests"
+            );
+        }
+        .with_metrics()
+        .await;
+    }
+}
@@ -0,0 +1,466 @@
+//! Exemplars link the observations of a histogram to the trace they were recorded in.
+//!
+//! See https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md#exemplars
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use num_traits::ToPrimitive;
+use opentelemetry::metrics::Histogram;
+use opentelemetry::metrics::SyncHistogram;
+use opentelemetry::metrics::Unit;
+use opentelemetry::KeyValue;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::plugins::telemetry::config::MetricView;
+use crate::tracer::TraceId;
+
+/// Number of exemplars kept for each series, the oldest ones are dropped first
+const MAX_EXEMPLARS_PER_SERIES: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Exemplar {
+    pub(crate) trace_id: String,
+    pub(crate) value: f64,
+    pub(crate) timestamp: SystemTime,
+}
+
+type Labels = Vec<(String, String)>;
+
+/// The latest exemplars of the series of a metric family, by labels
+type Family = Mutex<HashMap<Labels, VecDeque<Exemplar>>>;
+
+/// The latest exemplars of each histogram series, by Prometheus metric family name and labels
+#[derive(Debug, Default)]
+pub(crate) struct Exemplars {
+    families: Mutex<HashMap<String, Arc<Family>>>,
+    /// Instrument name patterns of the metric views, with the unit they set
+    views: Vec<(Regex, Option<String>)>,
+}
+
+impl Exemplars {
+    /// Keeps the exemplars of histograms exported with these views
+    pub(crate) fn new(views: &[MetricView]) -> Self {
+        Self {
+            families: Default::default(),
+            views: views
+                .iter()
+                .filter_map(|view| Some((view_pattern(&view.name)?, view.unit.clone())))
+                .collect(),
+        }
+    }
+
+    /// The families an instrument is exported as: one for each view matching it, or its own
+    /// name and unit if none does.
+    ///
+    /// The family name of a histogram is its sanitized instrument name, followed by its unit if it
+    /// has one, like the Prometheus exporter names it.
+    fn families(&self, name: &str, unit: Option<&Unit>) -> Vec<Arc<Family>> {
+        let unit = unit.map(Unit::as_str).unwrap_or_default();
+        let mut units: Vec<&str> = self
+            .views
+            .iter()
+            .filter(|(pattern, _)| pattern.is_match(name))
+            .map(|(_, view_unit)| view_unit.as_deref().unwrap_or(unit))
+            .collect();
+        if units.is_empty() {
+            units.push(unit);
+        }
+        units.dedup();
+
+        let mut families = self.families.lock();
+        units
+            .into_iter()
+            .map(|unit| families.entry(family_name(name, unit)).or_default().clone())
+            .collect()
+    }
+
+    /// Adds an exemplar to a series of a family
+    #[cfg(test)]
+    pub(crate) fn insert(&self, family: &str, attributes: &[KeyValue], exemplar: Exemplar) {
+        let family = self
+            .families
+            .lock()
+            .entry(family.to_string())
+            .or_default()
+            .clone();
+        push(&family, attributes, exemplar);
+    }
+
+    /// The most recent exemplar of a metric family with `lower < value <= upper`, recorded with
+    /// all the given labels.
+    pub(crate) fn latest(
+        &self,
+        family: &str,
+        labels: &[(&str, &str)],
+        lower: f64,
+        upper: f64,
+    ) -> Option<Exemplar> {
+        let family = self.families.lock().get(family).cloned()?;
+        let series = family.lock();
+        series
+            .iter()
+            .filter(|(series_labels, _)| {
+                labels.iter().all(|(name, value)| {
+                    series_labels.iter().any(|(series_name, series_value)| {
+                        series_name == name && series_value == value
+                    })
+                })
+            })
+            .flat_map(|(_, exemplars)| exemplars.iter())
+            .filter(|exemplar| lower < exemplar.value && exemplar.value <= upper)
+            .max_by_key(|exemplar| exemplar.timestamp)
+            .cloned()
+    }
+}
+
+fn push(family: &Family, attributes: &[KeyValue], exemplar: Exemplar) {
+    let mut labels: Labels = attributes
+        .iter()
+        .map(|kv| (sanitize_label(kv.key.as_str()), kv.value.to_string()))
+        .collect();
+    labels.sort();
+
+    let mut series = family.lock();
+    let exemplars = series.entry(labels).or_default();
+    if exemplars.len() == MAX_EXEMPLARS_PER_SERIES {
+        exemplars.pop_front();
+    }
+    exemplars.push_back(exemplar);
+}
+
+/// Matches instrument names the same way as metric views, which support `*` and `?` wildcards
+fn view_pattern(name: &str) -> Option<Regex> {
+    let pattern = if name.contains(['*', '?']) {
+        name.trim_start_matches('^')
+            .trim_end_matches('$')
+            .replace('?', ".")
+            .replace('*', ".*")
+    } else {
+        regex::escape(name)
+    };
+    Regex::new(&format!("^{pattern}$")).ok()
+}
+
+/// Family names are built the same way the Prometheus exporter does
+fn family_name(name: &str, unit: &str) -> String {
+    let name = sanitize_name(name);
+    match unit_suffix(unit) {
+        Some(suffix) => format!("{name}_{suffix}"),
+        None => name,
+    }
+}
+
+/// Units that can't be used as the numerator of a `x/y` unit
+const NON_APPLICABLE_ON_PER_UNIT: [&str; 8] = ["1", "d", "h", "min", "s", "ms", "us", "ns"];
+
+fn unit_suffix(unit: &str) -> Option<Cow<'static, str>> {
+    if unit.is_empty() {
+        return None;
+    }
+    if let Some(suffix) = prometheus_unit(unit) {
+        return Some(Cow::Borrowed(suffix));
+    }
+    let (first, second) = unit.split_once('/')?;
+    match (
+        NON_APPLICABLE_ON_PER_UNIT.contains(&first),
+        prometheus_unit(first),
+        prometheus_per_unit(second),
+    ) {
+        (true, _, Some(second)) | (false, None, Some(second)) => {
+            Some(Cow::Owned(format!("per_{second}")))
+        }
+        (false, Some(first), Some(second)) => Some(Cow::Owned(format!("{first}_per_{second}"))),
+        _ => None,
+    }
+}
+
+fn prometheus_unit(unit: &str) -> Option<&'static str> {
+    Some(match unit {
+        "d" => "days",
+        "h" => "hours",
+        "min" => "minutes",
+        "s" => "seconds",
+        "ms" => "milliseconds",
+        "us" => "microseconds",
+        "ns" => "nanoseconds",
+        "By" | "B" => "bytes",
+        "KiBy" => "kibibytes",
+        "MiBy" => "mebibytes",
+        "GiBy" => "gibibytes",
+        "TiBy" => "tibibytes",
+        "KBy" | "KB" => "kilobytes",
+        "MBy" | "MB" => "megabytes",
+        "GBy" | "GB" => "gigabytes",
+        "TBy" | "TB" => "terabytes",
+        "m" => "meters",
+        "V" => "volts",
+        "A" => "amperes",
+        "J" => "joules",
+        "W" => "watts",
+        "g" => "grams",
+        "Cel" => "celsius",
+        "Hz" => "hertz",
+        "1" => "ratio",
+        "%" => "percent",
+        "$" => "dollars",
+        _ => return None,
+    })
+}
+
+fn prometheus_per_unit(unit: &str) -> Option<&'static str> {
+    Some(match unit {
+        "s" => "second",
+        "m" => "minute",
+        "h" => "hour",
+        "d" => "day",
+        "w" => "week",
+        "mo" => "month",
+        "y" => "year",
+        _ => return None,
+    })
+}
+
+/// Names are sanitized the same way the Prometheus exporter does
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn sanitize_label(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Histogram keeping an exemplar of the values recorded in a sampled trace
+pub(crate) struct ExemplarHistogram<T> {
+    delegate: Histogram<T>,
+    families: Vec<Arc<Family>>,
+}
+
+impl<T> ExemplarHistogram<T> {
+    pub(crate) fn new(
+        delegate: Histogram<T>,
+        name: &str,
+        unit: Option<&Unit>,
+        exemplars: &Exemplars,
+    ) -> Self {
+        Self {
+            delegate,
+            families: exemplars.families(name, unit),
+        }
+    }
+}
+
+impl<T: Copy + ToPrimitive> SyncHistogram<T> for ExemplarHistogram<T> {
+    fn record(&self, value: T, attributes: &[KeyValue]) {
+        if let (Some(trace_id), Some(exemplar_value)) = (TraceId::current_sampled(), value.to_f64())
+        {
+            let exemplar = Exemplar {
+                trace_id: trace_id.to_string(),
+                value: exemplar_value,
+                timestamp: SystemTime::now(),
+            };
+            for family in &self.families {
+                push(family, attributes, exemplar.clone());
+            }
+        }
+        self.delegate.record(value, attributes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use opentelemetry::metrics::noop::NoopMeterProvider;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+
+    use super::*;
+    use crate::plugins::telemetry::otel;
+
+    fn exemplar(trace_id: &str, value: f64, seconds: u64) -> Exemplar {
+        Exemplar {
+            trace_id: trace_id.to_string(),
+            value,
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        }
+    }
+
+    #[test]
+    fn latest_exemplar_of_bucket() {
+        let exemplars = Exemplars::default();
+        let family = "apollo_router_http_request_duration_seconds";
+        let attributes = [KeyValue::new("http.response.status_code", 200)];
+        exemplars.insert(family, &attributes, exemplar("a", 0.2, 1));
+        exemplars.insert(family, &attributes, exemplar("b", 0.3, 2));
+        exemplars.insert(family, &attributes, exemplar("c", 2.0, 3));
+
+        let labels = [("http_response_status_code", "200")];
+        assert_eq!(
+            exemplars.latest(family, &labels, 0.1, 0.5),
+            Some(exemplar("b", 0.3, 2))
+        );
+        assert_eq!(
+            exemplars.latest(family, &labels, 1.0, f64::INFINITY),
+            Some(exemplar("c", 2.0, 3))
+        );
+        assert_eq!(exemplars.latest(family, &labels, 0.5, 1.0), None);
+        assert_eq!(
+            exemplars.latest(family, &[("http_response_status_code", "500")], 0.1, 0.5),
+            None
+        );
+        assert_eq!(
+            exemplars.latest(
+                "apollo_router_http_request_body_size_bytes",
+                &labels,
+                0.1,
+                0.5
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn families_are_named_like_the_prometheus_exporter() {
+        assert_eq!(
+            family_name("apollo.router.http.request.duration", "s"),
+            "apollo_router_http_request_duration_seconds"
+        );
+        assert_eq!(
+            family_name("http.server.request.body.size", "By"),
+            "http_server_request_body_size_bytes"
+        );
+        assert_eq!(family_name("requests", "1/s"), "requests_per_second");
+        assert_eq!(family_name("distance", "m/s"), "distance_meters_per_second");
+        assert_eq!(
+            family_name("apollo.router.cache.size", ""),
+            "apollo_router_cache_size"
+        );
+        assert_eq!(family_name("2xx", "unknown"), "_2xx");
+    }
+
+    #[test]
+    fn instruments_are_only_matched_to_their_family() {
+        let exemplars = Exemplars::default();
+        let attributes = [KeyValue::new("http.response.status_code", 200)];
+        for family in exemplars.families("apollo.router.http", None) {
+            push(&family, &attributes, exemplar("a", 0.2, 1));
+        }
+
+        let labels = [("http_response_status_code", "200")];
+        assert!(exemplars
+            .latest("apollo_router_http", &labels, 0.1, 0.5)
+            .is_some());
+        assert_eq!(
+            exemplars.latest(
+                "apollo_router_http_request_duration_seconds",
+                &labels,
+                0.1,
+                0.5
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn views_set_the_unit_of_families() {
+        let view = |name: &str, unit: Option<&str>| MetricView {
+            name: name.to_string(),
+            description: None,
+            unit: unit.map(str::to_string),
+            aggregation: None,
+            allowed_attribute_keys: None,
+        };
+        let exemplars = Exemplars::new(&[
+            view("apollo.router.http.request.duration", Some("ms")),
+            view("apollo.router.*", None),
+        ]);
+        exemplars.families("apollo.router.http.request.duration", Some(&Unit::new("s")));
+        exemplars.families("other.duration", Some(&Unit::new("s")));
+
+        let mut families: Vec<String> = exemplars.families.lock().keys().cloned().collect();
+        families.sort();
+        assert_eq!(
+            families,
+            [
+                "apollo_router_http_request_duration_milliseconds",
+                "apollo_router_http_request_duration_seconds",
+                "other_duration_seconds",
+            ]
+        );
+    }
+
+    #[test]
+    fn exemplars_are_only_recorded_in_sampled_traces() {
+        let exemplars = Exemplars::default();
+        let histogram = ExemplarHistogram::new(
+            NoopMeterProvider::default()
+                .meter("")
+                .f64_histogram("test")
+                .init(),
+            "test",
+            None,
+            &exemplars,
+        );
+        let latest = || exemplars.latest("test", &[], 0.0, f64::INFINITY);
+
+        let tracer = opentelemetry::sdk::trace::TracerProvider::default().versioned_tracer(
+            "noop",
+            None::<String>,
+            None::<String>,
+            None,
+        );
+        let unsampled = Registry::default().with(otel::layer().with_tracer(tracer.clone()));
+        tracing::subscriber::with_default(unsampled, || {
+            let _span = tracing::info_span!("test").entered();
+            histogram.record(1.0, &[]);
+        });
+        assert_eq!(latest(), None);
+
+        let sampled = Registry::default().with(otel::layer().force_sampling().with_tracer(tracer));
+        tracing::subscriber::with_default(sampled, || {
+            let _span = tracing::info_span!("test").entered();
+            histogram.record(2.0, &[]);
+        });
+        assert_eq!(latest().map(|exemplar| exemplar.value), Some(2.0));
+    }
+
+    #[test]
+    fn oldest_exemplars_are_dropped() {
+        let exemplars = Exemplars::default();
+        for index in 0..=MAX_EXEMPLARS_PER_SERIES as u64 {
+            exemplars.insert("test", &[], exemplar(&index.to_string(), 1.0, index));
+        }
+        let families = exemplars.families.lock();
+        let series = families["test"].lock();
+        let kept = &series[&Vec::new()];
+        assert_eq!(kept.len(), MAX_EXEMPLARS_PER_SERIES);
+        assert_eq!(kept.front().unwrap().trace_id, "1");
+    }
+}
@@ -0,0 +1,265 @@
+//! Per-field metrics, computed from the field-level traces (FTV1) returned by subgraphs.
+//!
+//! Unlike the field stats sent to Apollo Studio, they are exported with the other metrics, so they
+//! can be used without Studio.
+
+use std::collections::HashSet;
+
+use opentelemetry::KeyValue;
+use parking_lot::Mutex;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::plugins::telemetry::apollo_exporter::proto::reports::trace::node::Id::ResponseName;
+use crate::plugins::telemetry::apollo_exporter::proto::reports::trace::Node;
+use crate::plugins::telemetry::config::SamplerOption;
+use crate::plugins::telemetry::otel::layer::sampling_ratio;
+use crate::plugins::telemetry::tracing::apollo_telemetry::decode_ftv1_trace;
+use crate::services::subgraph;
+use crate::Context;
+
+/// Type and field names of the fields over the `max_fields` limit
+const OTHER: &str = "__other";
+
+/// Per-field metrics configuration
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct FieldMetricsConfig {
+    /// Export per-field execution counts, error counts and resolution times, computed from the
+    /// field-level traces of subgraphs
+    pub(crate) enabled: bool,
+    /// The ratio of requests for which subgraphs are asked for field-level traces, always_on,
+    /// always_off or a decimal between 0.0 and 1.0. Counts are extrapolated from the sampled
+    /// requests
+    pub(crate) sampler: SamplerOption,
+    /// The maximum number of fields with their own series. Further fields are recorded with a
+    /// type and field name of `__other`
+    pub(crate) max_fields: usize,
+}
+
+impl Default for FieldMetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sampler: SamplerOption::TraceIdRatioBased(0.01),
+            max_fields: 1000,
+        }
+    }
+}
+
+/// Marks the requests for which subgraphs are asked for field-level traces
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FieldMetricsSampled;
+
+pub(crate) struct FieldMetrics {
+    config: FieldMetricsConfig,
+    ratio: f64,
+    fields: Mutex<HashSet<(String, String, String)>>,
+}
+
+impl FieldMetrics {
+    pub(crate) fn new(config: FieldMetricsConfig) -> Self {
+        let ratio = if config.enabled {
+            sampling_ratio(&config.sampler).max(0.0)
+        } else {
+            0.0
+        };
+        Self {
+            config,
+            ratio,
+            fields: Default::default(),
+        }
+    }
+
+    /// Decides whether the subgraphs are asked for field-level traces for this request
+    pub(crate) fn on_supergraph_request(&self, context: &Context) {
+        if self.ratio > 0.0 && rand::thread_rng().gen_bool(self.ratio) {
+            context
+                .extensions()
+                .with_lock(|mut lock| lock.insert(FieldMetricsSampled));
+        }
+    }
+
+    pub(crate) fn is_sampled(context: &Context) -> bool {
+        context
+            .extensions()
+            .with_lock(|lock| lock.contains_key::<FieldMetricsSampled>())
+    }
+
+    /// Records the fields of the trace returned by a subgraph
+    pub(crate) fn on_subgraph_response(&self, subgraph_name: &str, response: &subgraph::Response) {
+        if !Self::is_sampled(&response.context) {
+            return;
+        }
+        let trace = response
+            .response
+            .body()
+            .extensions
+            .get("ftv1")
+            .and_then(|ftv1| ftv1.as_str())
+            .and_then(decode_ftv1_trace);
+        if let Some(root) = trace.as_ref().and_then(|trace| trace.root.as_ref()) {
+            self.record(subgraph_name, root);
+        }
+    }
+
+    fn record(&self, subgraph_name: &str, node: &Node) {
+        for child in &node.child {
+            self.record(subgraph_name, child);
+        }
+        let Some(ResponseName(response_name)) = &node.id else {
+            return;
+        };
+        let field_name = if node.original_field_name.is_empty() {
+            response_name
+        } else {
+            &node.original_field_name
+        };
+        if field_name.is_empty()
+            || node.parent_type.is_empty()
+            || node.start_time == 0
+            || node.end_time == 0
+        {
+            return;
+        }
+
+        let (type_name, field_name) = self.series(subgraph_name, &node.parent_type, field_name);
+        let attributes = [
+            KeyValue::new("subgraph.name", subgraph_name.to_string()),
+            KeyValue::new("graphql.type.name", type_name),
+            KeyValue::new("graphql.field.name", field_name),
+        ];
+        // Each sampled execution stands for 1 / ratio executions
+        let weight = 1.0 / self.ratio;
+        f64_counter!(
+            "apollo.router.graphql.field.executions",
+            "Number of executions of a field, extrapolated from the sampled requests",
+            weight,
+            attributes
+        );
+        if !node.error.is_empty() {
+            f64_counter!(
+                "apollo.router.graphql.field.errors",
+                "Number of errors of a field, extrapolated from the sampled requests",
+                weight * node.error.len() as f64,
+                attributes
+            );
+        }
+        f64_histogram!(
+            "apollo.router.graphql.field.duration",
+            "Resolution time of a field in seconds, from the sampled requests",
+            node.end_time.saturating_sub(node.start_time) as f64 / 1_000_000_000.0,
+            attributes
+        );
+    }
+
+    /// The type and field names of the series of a field, or `__other` if there are already too
+    /// many fields
+    fn series(&self, subgraph_name: &str, type_name: &str, field_name: &str) -> (String, String) {
+        let key = (
+            subgraph_name.to_string(),
+            type_name.to_string(),
+            field_name.to_string(),
+        );
+        let mut fields = self.fields.lock();
+        if fields.contains(&key) || fields.len() < self.config.max_fields {
+            let (_, type_name, field_name) = key.clone();
+            fields.insert(key);
+            (type_name, field_name)
+        } else {
+            (OTHER.to_string(), OTHER.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metrics::FutureMetricsExt;
+
+    fn field(parent_type: &str, name: &str, errors: usize) -> Node {
+        Node {
+            id: Some(ResponseName(name.to_string())),
+            parent_type: parent_type.to_string(),
+            r#type: "String".to_string(),
+            start_time: 1_000_000,
+            end_time: 3_000_000,
+            error: vec![Default::default(); errors],
+            ..Default::default()
+        }
+    }
+
+    fn root() -> Node {
+        Node {
+            child: vec![Node {
+                child: vec![field("Product", "name", 0), field("Product", "price", 2)],
+                ..field("Query", "topProducts", 0)
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn field_metrics(max_fields: usize) -> FieldMetrics {
+        FieldMetrics::new(FieldMetricsConfig {
+            enabled: true,
+            sampler: SamplerOption::TraceIdRatioBased(0.5),
+            max_fields,
+        })
+    }
+
+    #[tokio::test]
+    async fn fields_are_recorded() {
+        async {
+            field_metrics(1000).record("products", &root());
+
+            assert_counter!(
+                "apollo.router.graphql.field.executions",
+                2.0,
+                "subgraph.name" = "products",
+                "graphql.type.name" = "Product",
+                "graphql.field.name" = "price"
+            );
+            assert_counter!(
+                "apollo.router.graphql.field.errors",
+                4.0,
+                "subgraph.name" = "products",
+                "graphql.type.name" = "Product",
+                "graphql.field.name" = "price"
+            );
+            assert_histogram_sum!(
+                "apollo.router.graphql.field.duration",
+                0.002,
+                "subgraph.name" = "products",
+                "graphql.type.name" = "Query",
+                "graphql.field.name" = "topProducts"
+            );
+        }
+        .with_metrics()
+        .await;
+    }
+
+    #[tokio::test]
+    async fn fields_over_the_limit_are_grouped() {
+        async {
+            field_metrics(1).record("products", &root());
+
+            assert_counter!(
+                "apollo.router.graphql.field.executions",
+                2.0,
+                "subgraph.name" = "products",
+                "graphql.type.name" = "Product",
+                "graphql.field.name" = "name"
+            );
+            assert_counter!(
+                "apollo.router.graphql.field.executions",
+                4.0,
+                "subgraph.name" = "products",
+                "graphql.type.name" = OTHER,
+                "graphql.field.name" = OTHER
+            );
+        }
+        .with_metrics()
+        .await;
+    }
+}
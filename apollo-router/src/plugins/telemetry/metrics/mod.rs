@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use ::serde::Deserialize;
 use access_json::JSONQuery;
@@ -25,12 +26,16 @@ use crate::plugins::telemetry::apollo_exporter::Sender;
 use crate::plugins::telemetry::config::AttributeValue;
 use crate::plugins::telemetry::config::Conf;
 use crate::plugins::telemetry::config::MetricsCommon;
+use crate::plugins::telemetry::metrics::exemplars::Exemplars;
 use crate::plugins::telemetry::resource::ConfigResource;
 use crate::router_factory::Endpoint;
 use crate::Context;
 use crate::ListenAddr;
 
 pub(crate) mod apollo;
+pub(crate) mod cardinality;
+pub(crate) mod exemplars;
+pub(crate) mod field_metrics;
 pub(crate) mod local_type_stats;
 pub(crate) mod otlp;
 pub(crate) mod prometheus;
@@ -431,6 +436,7 @@ pub(crate) struct MetricsBuilder {
     pub(crate) public_meter_provider_builder: opentelemetry::sdk::metrics::MeterProviderBuilder,
     pub(crate) apollo_meter_provider_builder: opentelemetry::sdk::metrics::MeterProviderBuilder,
     pub(crate) prometheus_meter_provider: Option<opentelemetry::sdk::metrics::MeterProvider>,
+    pub(crate) prometheus_exemplars: Option<Arc<Exemplars>>,
     pub(crate) custom_endpoints: MultiMap<ListenAddr, Endpoint>,
     pub(crate) apollo_metrics_sender: Sender,
     pub(crate) resource: Resource,
@@ -446,6 +452,7 @@ impl MetricsBuilder {
                 .with_resource(resource.clone()),
             apollo_meter_provider_builder: opentelemetry::sdk::metrics::MeterProvider::builder(),
             prometheus_meter_provider: None,
+            prometheus_exemplars: None,
             custom_endpoints: MultiMap::new(),
             apollo_metrics_sender: Sender::default(),
         }
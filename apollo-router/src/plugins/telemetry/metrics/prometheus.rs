@@ -1,6 +1,9 @@
+use std::fmt::Write;
+use std::sync::Arc;
 use std::sync::Mutex;
 use std::task::Context;
 use std::task::Poll;
+use std::time::UNIX_EPOCH;
 
 use futures::future::BoxFuture;
 use http::StatusCode;
@@ -8,6 +11,9 @@ use once_cell::sync::Lazy;
 use opentelemetry::sdk::metrics::MeterProvider;
 use opentelemetry::sdk::metrics::View;
 use opentelemetry::sdk::Resource;
+use prometheus::proto::LabelPair;
+use prometheus::proto::MetricFamily;
+use prometheus::proto::MetricType;
 use prometheus::Encoder;
 use prometheus::Registry;
 use prometheus::TextEncoder;
@@ -19,6 +25,7 @@ use tower_service::Service;
 
 use crate::plugins::telemetry::config::MetricView;
 use crate::plugins::telemetry::config::MetricsCommon;
+use crate::plugins::telemetry::metrics::exemplars::Exemplars;
 use crate::plugins::telemetry::metrics::CustomAggregationSelector;
 use crate::plugins::telemetry::metrics::MetricsBuilder;
 use crate::plugins::telemetry::metrics::MetricsConfigurator;
@@ -37,6 +44,9 @@ pub(crate) struct Config {
     pub(crate) listen: ListenAddr,
     /// The path where prometheus will be exposed
     pub(crate) path: String,
+    /// Attach exemplars with the trace id of sampled requests to histogram buckets. They are only
+    /// returned to scrapers asking for the OpenMetrics format
+    pub(crate) exemplars: bool,
 }
 
 impl Default for Config {
@@ -45,6 +55,7 @@ impl Default for Config {
             enabled: false,
             listen: ListenAddr::SocketAddr("127.0.0.1:9090".parse().expect("valid listenAddr")),
             path: "/metrics".to_string(),
+            exemplars: false,
         }
     }
 }
@@ -52,16 +63,17 @@ impl Default for Config {
 // Prometheus metrics are special. We want them to persist between restarts if possible.
 // This means reusing the existing registry and meter provider if we can.
 // These statics will keep track of new registry for commit when the telemetry plugin is activated.
-static EXISTING_PROMETHEUS: Lazy<Mutex<Option<(PrometheusConfig, Registry)>>> =
-    Lazy::new(Default::default);
-static NEW_PROMETHEUS: Lazy<Mutex<Option<(PrometheusConfig, Registry)>>> =
-    Lazy::new(Default::default);
+type PrometheusState = (PrometheusConfig, Registry, Option<Arc<Exemplars>>);
+
+static EXISTING_PROMETHEUS: Lazy<Mutex<Option<PrometheusState>>> = Lazy::new(Default::default);
+static NEW_PROMETHEUS: Lazy<Mutex<Option<PrometheusState>>> = Lazy::new(Default::default);
 
 #[derive(PartialEq, Clone)]
 struct PrometheusConfig {
     resource: Resource,
     buckets: Vec<f64>,
     views: Vec<MetricView>,
+    exemplars: bool,
 }
 
 pub(crate) fn commit_prometheus() {
@@ -91,6 +103,7 @@ impl MetricsConfigurator for Config {
             resource: builder.resource.clone(),
             buckets: metrics_config.buckets.clone(),
             views: metrics_config.views.clone(),
+            exemplars: self.exemplars,
         };
 
         // Check the last registry to see if the resources are the same, if they are we can use it as is.
@@ -98,7 +111,7 @@ impl MetricsConfigurator for Config {
         // Note that during tests the prom registry cannot be reused as we have a different meter provider for each test.
         // Prom reloading IS tested in an integration test.
         #[cfg(not(test))]
-        if let Some((last_config, last_registry)) =
+        if let Some((last_config, last_registry, last_exemplars)) =
             EXISTING_PROMETHEUS.lock().expect("lock poisoned").clone()
         {
             if prometheus_config == last_config {
@@ -109,6 +122,7 @@ impl MetricsConfigurator for Config {
                         self.path.clone(),
                         PrometheusService {
                             registry: last_registry.clone(),
+                            exemplars: last_exemplars,
                         }
                         .boxed(),
                     ),
@@ -144,22 +158,28 @@ impl MetricsConfigurator for Config {
             meter_provider_builder = meter_provider_builder.with_view(view);
         }
         let meter_provider = meter_provider_builder.build();
+        let exemplars = self
+            .exemplars
+            .then(|| Arc::new(Exemplars::new(&metrics_config.views)));
         builder.custom_endpoints.insert(
             self.listen.clone(),
             Endpoint::from_router_service(
                 self.path.clone(),
                 PrometheusService {
                     registry: registry.clone(),
+                    exemplars: exemplars.clone(),
                 }
                 .boxed(),
             ),
         );
         builder.prometheus_meter_provider = Some(meter_provider.clone());
+        builder.prometheus_exemplars = exemplars.clone();
 
-        NEW_PROMETHEUS
-            .lock()
-            .expect("lock poisoned")
-            .replace((prometheus_config, registry));
+        NEW_PROMETHEUS.lock().expect("lock poisoned").replace((
+            prometheus_config,
+            registry,
+            exemplars,
+        ));
 
         tracing::info!(
             "Prometheus endpoint exposed at {}{}",
@@ -174,6 +194,7 @@ impl MetricsConfigurator for Config {
 #[derive(Clone)]
 pub(crate) struct PrometheusService {
     registry: Registry,
+    exemplars: Option<Arc<Exemplars>>,
 }
 
 impl Service<router::Request> for PrometheusService {
@@ -187,7 +208,18 @@ impl Service<router::Request> for PrometheusService {
 
     fn call(&mut self, req: router::Request) -> Self::Future {
         let metric_families = self.registry.gather();
+        let exemplars = self.exemplars.clone().filter(|_| accepts_openmetrics(&req));
         Box::pin(async move {
+            if let Some(exemplars) = exemplars {
+                return Ok(router::Response {
+                    response: http::Response::builder()
+                        .status(StatusCode::OK)
+                        .header(http::header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)
+                        .body::<Body>(encode_openmetrics(&metric_families, &exemplars).into())
+                        .map_err(BoxError::from)?,
+                    context: req.context,
+                });
+            }
             let encoder = TextEncoder::new();
             let mut result = Vec::new();
             encoder.encode(&metric_families, &mut result)?;
@@ -206,3 +238,248 @@ impl Service<router::Request> for PrometheusService {
         })
     }
 }
+
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+fn accepts_openmetrics(req: &router::Request) -> bool {
+    req.router_request
+        .headers()
+        .get_all(http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("application/openmetrics-text"))
+}
+
+/// Encodes metrics in the OpenMetrics text format, with the latest exemplar of each histogram
+/// bucket.
+///
+/// See https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+fn encode_openmetrics(metric_families: &[MetricFamily], exemplars: &Exemplars) -> String {
+    let mut result = String::new();
+    for family in metric_families {
+        // otel 0.19.0 started adding "_total" onto various statistics, and OpenMetrics only adds
+        // it to the samples of counters.
+        let name = family.get_name().replace("_total_total", "_total");
+        let (name, metric_type) = match family.get_field_type() {
+            MetricType::COUNTER => (
+                name.strip_suffix("_total").unwrap_or(&name).to_string(),
+                "counter",
+            ),
+            MetricType::GAUGE => (name, "gauge"),
+            MetricType::HISTOGRAM => (name, "histogram"),
+            MetricType::SUMMARY => (name, "summary"),
+            MetricType::UNTYPED => (name, "unknown"),
+        };
+        if !family.get_help().is_empty() {
+            let _ = writeln!(result, "# HELP {name} {}", escape(family.get_help()));
+        }
+        let _ = writeln!(result, "# TYPE {name} {metric_type}");
+
+        for metric in family.get_metric() {
+            let labels = metric.get_label();
+            match family.get_field_type() {
+                MetricType::COUNTER => {
+                    let value = metric.get_counter().get_value();
+                    write_sample(&mut result, &name, "_total", labels, None, float(value));
+                }
+                MetricType::GAUGE => {
+                    let value = metric.get_gauge().get_value();
+                    write_sample(&mut result, &name, "", labels, None, float(value));
+                }
+                MetricType::UNTYPED => {
+                    let value = metric.get_untyped().get_value();
+                    write_sample(&mut result, &name, "", labels, None, float(value));
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        let quantile_label = ("quantile", float(quantile.get_quantile()));
+                        write_sample(
+                            &mut result,
+                            &name,
+                            "",
+                            labels,
+                            Some(quantile_label),
+                            float(quantile.get_value()),
+                        );
+                    }
+                    write_sample(
+                        &mut result,
+                        &name,
+                        "_sum",
+                        labels,
+                        None,
+                        float(summary.get_sample_sum()),
+                    );
+                    write_sample(
+                        &mut result,
+                        &name,
+                        "_count",
+                        labels,
+                        None,
+                        summary.get_sample_count().to_string(),
+                    );
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let series_labels: Vec<(&str, &str)> = labels
+                        .iter()
+                        .filter(|label| !label.get_name().starts_with("otel_scope_"))
+                        .map(|label| (label.get_name(), label.get_value()))
+                        .collect();
+                    let mut bounds: Vec<(f64, u64)> = histogram
+                        .get_bucket()
+                        .iter()
+                        .map(|bucket| (bucket.get_upper_bound(), bucket.get_cumulative_count()))
+                        .collect();
+                    if bounds.last().map(|(upper, _)| *upper) != Some(f64::INFINITY) {
+                        bounds.push((f64::INFINITY, histogram.get_sample_count()));
+                    }
+                    let mut lower = f64::NEG_INFINITY;
+                    for (upper, count) in bounds {
+                        let mut value = count.to_string();
+                        if let Some(exemplar) =
+                            exemplars.latest(&name, &series_labels, lower, upper)
+                        {
+                            let timestamp = exemplar
+                                .timestamp
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs_f64();
+                            let _ = write!(
+                                value,
+                                " # {{trace_id=\"{}\"}} {} {:.3}",
+                                exemplar.trace_id,
+                                float(exemplar.value),
+                                timestamp
+                            );
+                        }
+                        write_sample(
+                            &mut result,
+                            &name,
+                            "_bucket",
+                            labels,
+                            Some(("le", float(upper))),
+                            value,
+                        );
+                        lower = upper;
+                    }
+                    write_sample(
+                        &mut result,
+                        &name,
+                        "_sum",
+                        labels,
+                        None,
+                        float(histogram.get_sample_sum()),
+                    );
+                    write_sample(
+                        &mut result,
+                        &name,
+                        "_count",
+                        labels,
+                        None,
+                        histogram.get_sample_count().to_string(),
+                    );
+                }
+            }
+        }
+    }
+    result.push_str("# EOF\n");
+    result
+}
+
+fn write_sample(
+    result: &mut String,
+    name: &str,
+    suffix: &str,
+    labels: &[LabelPair],
+    extra_label: Option<(&str, String)>,
+    value: String,
+) {
+    result.push_str(name);
+    result.push_str(suffix);
+    let labels = labels
+        .iter()
+        .map(|label| (label.get_name(), escape(label.get_value())))
+        .chain(extra_label)
+        .map(|(name, value)| format!("{name}=\"{value}\""))
+        .collect::<Vec<_>>();
+    if !labels.is_empty() {
+        let _ = write!(result, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(result, " {value}");
+}
+
+fn float(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else if value.is_nan() {
+        "NaN".to_string()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use opentelemetry::KeyValue;
+    use prometheus::HistogramOpts;
+
+    use super::*;
+    use crate::plugins::telemetry::metrics::exemplars::Exemplar;
+
+    #[test]
+    fn openmetrics_with_exemplars() {
+        let registry = Registry::new();
+        let histogram = prometheus::Histogram::with_opts(
+            HistogramOpts::new("apollo_router_test_seconds", "Test \"duration\"")
+                .const_label("http_method", "GET")
+                .buckets(vec![0.1, 1.0]),
+        )
+        .unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        histogram.observe(0.5);
+        histogram.observe(2.0);
+        let counter = prometheus::Counter::new("apollo_router_test_total", "Test count").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.inc();
+
+        let exemplars = Exemplars::default();
+        exemplars.insert(
+            "apollo_router_test_seconds",
+            &[KeyValue::new("http.method", "GET")],
+            Exemplar {
+                trace_id: "0af7651916cd43dd8448eb211c80319c".to_string(),
+                value: 0.5,
+                timestamp: UNIX_EPOCH + Duration::from_millis(1_700_000_000_500),
+            },
+        );
+
+        assert_eq!(
+            encode_openmetrics(&registry.gather(), &exemplars),
+            r#"# HELP apollo_router_test_seconds Test \"duration\"
+# TYPE apollo_router_test_seconds histogram
+apollo_router_test_seconds_bucket{http_method="GET",le="0.1"} 0
+apollo_router_test_seconds_bucket{http_method="GET",le="1.0"} 1 # {trace_id="0af7651916cd43dd8448eb211c80319c"} 0.5 1700000000.500
+apollo_router_test_seconds_bucket{http_method="GET",le="+Inf"} 2
+apollo_router_test_seconds_sum{http_method="GET"} 2.5
+apollo_router_test_seconds_count{http_method="GET"} 2
+# HELP apollo_router_test Test count
+# TYPE apollo_router_test counter
+apollo_router_test_total 1.0
+# EOF
+"#
+        );
+    }
+}
@@ -123,6 +123,7 @@ use crate::plugins::telemetry::metrics::apollo::studio::SinglePathErrorStats;
 use crate::plugins::telemetry::metrics::apollo::studio::SingleQueryLatencyStats;
 use crate::plugins::telemetry::metrics::apollo::studio::SingleStats;
 use crate::plugins::telemetry::metrics::apollo::studio::SingleStatsReport;
+use crate::plugins::telemetry::metrics::field_metrics::FieldMetrics;
 use crate::plugins::telemetry::metrics::prometheus::commit_prometheus;
 use crate::plugins::telemetry::metrics::MetricsBuilder;
 use crate::plugins::telemetry::metrics::MetricsConfigurator;
@@ -131,6 +132,9 @@ use crate::plugins::telemetry::reload::metrics_layer;
 use crate::plugins::telemetry::reload::OPENTELEMETRY_TRACER_HANDLE;
 use crate::plugins::telemetry::tracing::apollo_telemetry::decode_ftv1_trace;
 use crate::plugins::telemetry::tracing::apollo_telemetry::APOLLO_PRIVATE_OPERATION_SIGNATURE;
+use crate::plugins::telemetry::tracing::sampling_rules;
+use crate::plugins::telemetry::tracing::sampling_rules::SamplingRule;
+use crate::plugins::telemetry::tracing::tail_sampling;
 use crate::plugins::telemetry::tracing::TracingConfigurator;
 use crate::plugins::telemetry::utils::TracingUtils;
 use crate::query_planner::OperationKind;
@@ -168,6 +172,7 @@ pub(crate) mod otel;
 mod otlp;
 pub(crate) mod reload;
 mod resource;
+pub(crate) mod server_timing;
 mod span_factory;
 pub(crate) mod tracing;
 pub(crate) mod utils;
@@ -202,6 +207,8 @@ pub(crate) struct Telemetry {
     apollo_metrics_sender: apollo_exporter::Sender,
     field_level_instrumentation_ratio: f64,
     sampling_filter_ratio: SamplerOption,
+    sampling_rules: Vec<SamplingRule>,
+    field_metrics: Arc<FieldMetrics>,
     pub(crate) graphql_custom_instruments: RwLock<Arc<HashMap<String, StaticInstrument>>>,
     router_custom_instruments: RwLock<Arc<HashMap<String, StaticInstrument>>>,
     supergraph_custom_instruments: RwLock<Arc<HashMap<String, StaticInstrument>>>,
@@ -216,6 +223,7 @@ struct TelemetryActivation {
     public_meter_provider: Option<FilterMeterProvider>,
     public_prometheus_meter_provider: Option<FilterMeterProvider>,
     private_meter_provider: Option<FilterMeterProvider>,
+    logger_provider: Option<opentelemetry::sdk::logs::LoggerProvider>,
     is_active: bool,
 }
 
@@ -251,9 +259,14 @@ impl Drop for Telemetry {
             activation.public_prometheus_meter_provider.take(),
         ];
         let tracer_provider = activation.tracer_provider.take();
+        let logger_provider = activation.logger_provider.take();
         drop(activation);
         TelemetryActivation::checked_meter_shutdown(metrics_providers);
 
+        if let Some(logger_provider) = logger_provider {
+            Self::checked_spawn_task(Box::new(move || drop(logger_provider)));
+        }
+
         if let Some(tracer_provider) = tracer_provider {
             Self::checked_tracer_shutdown(tracer_provider);
         }
@@ -298,7 +311,10 @@ impl Plugin for Telemetry {
             config.calculate_field_level_instrumentation_ratio()?;
         let metrics_builder = Self::create_metrics_builder(&config)?;
 
-        let (sampling_filter_ratio, tracer_provider) = Self::create_tracer_provider(&config)?;
+        sampling_rules::validate(&config.exporters.tracing.common.experimental_sampling_rules)?;
+        let (sampling_filter_ratio, sampling_rules, tracer_provider) =
+            Self::create_tracer_provider(&config)?;
+        let logger_provider = logging::otlp::create_logger_provider(&config)?;
 
         if config.instrumentation.spans.mode == SpanMode::Deprecated {
             ::tracing::warn!("telemetry.instrumentation.spans.mode is currently set to 'deprecated', either explicitly or via defaulting. Set telemetry.instrumentation.spans.mode explicitly in your router.yaml to 'spec_compliant' for log and span attributes that follow OpenTelemetry semantic conventions. This option will be defaulted to 'spec_compliant' in a future release and eventually removed altogether");
@@ -324,9 +340,13 @@ impl Plugin for Telemetry {
                 private_meter_provider: Some(FilterMeterProvider::private(
                     metrics_builder.apollo_meter_provider_builder.build(),
                 )),
-                public_prometheus_meter_provider: metrics_builder
-                    .prometheus_meter_provider
-                    .map(FilterMeterProvider::public),
+                public_prometheus_meter_provider: metrics_builder.prometheus_meter_provider.map(
+                    |provider| {
+                        FilterMeterProvider::public(provider)
+                            .with_exemplars(metrics_builder.prometheus_exemplars.clone())
+                    },
+                ),
+                logger_provider,
                 is_active: false,
             }),
             graphql_custom_instruments: RwLock::new(graphql_custom_instruments),
@@ -335,6 +355,16 @@ impl Plugin for Telemetry {
             subgraph_custom_instruments: RwLock::new(subgraph_custom_instruments),
             cache_custom_instruments: RwLock::new(cache_custom_instruments),
             sampling_filter_ratio,
+            sampling_rules,
+            field_metrics: Arc::new(FieldMetrics::new(
+                config
+                    .instrumentation
+                    .instruments
+                    .graphql
+                    .attributes
+                    .experimental_field_metrics
+                    .clone(),
+            )),
             config: Arc::new(config),
         })
     }
@@ -421,6 +451,8 @@ impl Plugin for Telemetry {
                         let _ = request.context.insert(CLIENT_VERSION, version.to_owned());
                     }
 
+                    config_request.exporters.server_timing.on_request(request);
+
                     let mut custom_attributes = config_request
                         .instrumentation
                         .spans
@@ -473,7 +505,7 @@ impl Plugin for Telemetry {
                     async move {
                         let span = Span::current();
                         span.set_span_dyn_attributes(custom_attributes);
-                        let response: Result<router::Response, BoxError> = fut.await;
+                        let mut response: Result<router::Response, BoxError> = fut.await;
 
                         span.record(
                             APOLLO_PRIVATE_DURATION_NS,
@@ -481,7 +513,21 @@ impl Plugin for Telemetry {
                         );
 
                         let expose_trace_id = &config.exporters.tracing.response_trace_id;
-                        if let Ok(response) = &response {
+                        if config
+                            .exporters
+                            .tracing
+                            .common
+                            .experimental_tail_sampling
+                            .enabled
+                        {
+                            tail_sampling::record_router_response(
+                                &span,
+                                response.as_ref().ok().map(|r| r.response.status()),
+                                &ctx,
+                            );
+                        }
+                        if let Ok(response) = &mut response {
+                            config.exporters.server_timing.on_response(response);
                             span.set_span_dyn_attributes(
                                 config
                                     .instrumentation
@@ -570,6 +616,7 @@ impl Plugin for Telemetry {
         let config_map_res_first = config.clone();
         let config_map_res = config.clone();
         let field_level_instrumentation_ratio = self.field_level_instrumentation_ratio;
+        let field_metrics = self.field_metrics.clone();
         let static_supergraph_instruments = self.supergraph_custom_instruments.read().clone();
         let static_graphql_instruments = self.graphql_custom_instruments.read().clone();
         ServiceBuilder::new()
@@ -623,10 +670,12 @@ impl Plugin for Telemetry {
                     ::tracing::info!(http.response.headers = ?sorted_headers, "Supergraph response headers");
                 }
                 let display_body = resp.context.contains_key(LOGGING_DISPLAY_BODY);
+                let context = resp.context.clone();
                 resp.map_stream(move |gql_response| {
                     if display_body {
                         ::tracing::info!(http.response.body = ?gql_response, "Supergraph GraphQL response");
                     }
+                    config.exporters.logging.body_logging.on_supergraph_response(&context, &gql_response);
                     gql_response
                 })
             })
@@ -634,6 +683,9 @@ impl Plugin for Telemetry {
                 move |req: &SupergraphRequest| {
                     let custom_attributes = config.instrumentation.spans.supergraph.attributes.on_request(req);
                     Self::populate_context(config.clone(), field_level_instrumentation_ratio, req);
+                    sampling_rules::on_supergraph_request(req);
+                    field_metrics.on_supergraph_request(&req.context);
+                    config.exporters.logging.body_logging.on_supergraph_request(req);
                     let custom_instruments = config
                         .instrumentation
                         .instruments
@@ -735,13 +787,33 @@ impl Plugin for Telemetry {
         let subgraph_metrics_conf_req = self.create_subgraph_metrics_conf(name);
         let subgraph_metrics_conf_resp = subgraph_metrics_conf_req.clone();
         let subgraph_name = ByteString::from(name);
+        let field_metrics = self.field_metrics.clone();
+        let body_logging_request = self.config.clone();
+        let body_logging_response = self.config.clone();
+        let body_logging_name = name.to_owned();
+        let server_timing_description = name.to_owned();
         let name = name.to_owned();
         let static_subgraph_instruments = self.subgraph_custom_instruments.read().clone();
         let static_cache_instruments = self.cache_custom_instruments.read().clone();
         ServiceBuilder::new()
             .instrument(move |req: &SubgraphRequest| span_mode.create_subgraph(name.as_str(), req))
-            .map_request(move |req: SubgraphRequest| request_ftv1(req))
-            .map_response(move |resp| store_ftv1(&subgraph_name, resp))
+            .map_request(move |req: SubgraphRequest| {
+                body_logging_request
+                    .exporters
+                    .logging
+                    .body_logging
+                    .on_subgraph_request(&body_logging_name, &req);
+                request_ftv1(req)
+            })
+            .map_response(move |resp| {
+                field_metrics.on_subgraph_response(subgraph_name.as_str(), &resp);
+                body_logging_response
+                    .exporters
+                    .logging
+                    .body_logging
+                    .on_subgraph_response(subgraph_name.as_str(), &resp);
+                store_ftv1(&subgraph_name, resp)
+            })
             .map_future_with_request_data(
                 move |sub_request: &SubgraphRequest| {
                     Self::store_subgraph_request_attributes(
@@ -793,6 +865,7 @@ impl Plugin for Telemetry {
                       f: BoxFuture<'static, Result<SubgraphResponse, BoxError>>| {
                     let subgraph_attribute = subgraph_attribute.clone();
                     let subgraph_metrics_conf = subgraph_metrics_conf_resp.clone();
+                    let server_timing_description = server_timing_description.clone();
                     let conf = conf.clone();
                     // Using Instant because it is guaranteed to be monotonically increasing.
                     let now = Instant::now();
@@ -800,6 +873,26 @@ impl Plugin for Telemetry {
                         let span = Span::current();
                         span.set_span_dyn_attributes(custom_attributes);
                         let result: Result<SubgraphResponse, BoxError> = f.await;
+                        server_timing::record(
+                            &context,
+                            server_timing::FETCH,
+                            Some(&server_timing_description),
+                            now.elapsed(),
+                        );
+
+                        if conf
+                            .exporters
+                            .tracing
+                            .common
+                            .experimental_tail_sampling
+                            .enabled
+                            && result.as_ref().map_or(true, |resp| {
+                                resp.response.status() >= StatusCode::BAD_REQUEST
+                                    || !resp.response.body().errors.is_empty()
+                            })
+                        {
+                            tail_sampling::record_subgraph_error(&span);
+                        }
 
                         match &result {
                             Ok(resp) => {
@@ -866,6 +959,7 @@ impl Telemetry {
         // Users that are rolling their own routers will need to set up telemetry themselves.
         if let Some(hot_tracer) = OPENTELEMETRY_TRACER_HANDLE.get() {
             otel::layer::configure(&self.sampling_filter_ratio);
+            sampling_rules::configure(self.sampling_rules.clone());
 
             // The reason that this has to happen here is that we are interacting with global state.
             // If we do this logic during plugin init then if a subsequent plugin fails to init then we
@@ -907,7 +1001,10 @@ impl Telemetry {
         *self.subgraph_custom_instruments.write() = subgraph_custom_instruments;
         *self.cache_custom_instruments.write() = cache_custom_instruments;
 
-        reload_fmt(create_fmt_layer(&self.config));
+        reload_fmt(create_fmt_layer(
+            &self.config,
+            activation.logger_provider.take(),
+        ));
         activation.is_active = true;
     }
 
@@ -951,11 +1048,19 @@ impl Telemetry {
 
     fn create_tracer_provider(
         config: &config::Conf,
-    ) -> Result<(SamplerOption, opentelemetry::sdk::trace::TracerProvider), BoxError> {
+    ) -> Result<
+        (
+            SamplerOption,
+            Vec<SamplingRule>,
+            opentelemetry::sdk::trace::TracerProvider,
+        ),
+        BoxError,
+    > {
         let tracing_config = &config.exporters.tracing;
         let spans_config = &config.instrumentation.spans;
         let mut common = tracing_config.common.clone();
         let mut sampler = common.sampler.clone();
+        let mut sampling_rules = common.experimental_sampling_rules.clone();
         // set it to AlwaysOn: it is now done in the SamplingFilter, so whatever is sent to an exporter
         // should be accepted
         common.sampler = SamplerOption::Always(Sampler::AlwaysOn);
@@ -976,10 +1081,11 @@ impl Telemetry {
             && !TracingConfigurator::enabled(&config.apollo)
         {
             sampler = SamplerOption::Always(Sampler::AlwaysOff);
+            sampling_rules.clear();
         }
 
         let tracer_provider = builder.build();
-        Ok((sampler, tracer_provider))
+        Ok((sampler, sampling_rules, tracer_provider))
     }
 
     fn create_metrics_builder(config: &config::Conf) -> Result<MetricsBuilder, BoxError> {
@@ -1976,13 +2082,16 @@ fn handle_error_internal<T: Into<opentelemetry::global::Error>>(
 
 register_plugin!("apollo", "telemetry", Telemetry);
 
-fn request_ftv1(mut req: SubgraphRequest) -> SubgraphRequest {
-    if req
-        .context
+/// Whether the subgraph traces of this request are sent to Apollo Studio
+fn studio_ftv1_enabled(context: &Context) -> bool {
+    context
         .extensions()
         .with_lock(|lock| lock.contains_key::<EnableSubgraphFtv1>())
         && Span::current().context().span().span_context().is_sampled()
-    {
+}
+
+fn request_ftv1(mut req: SubgraphRequest) -> SubgraphRequest {
+    if studio_ftv1_enabled(&req.context) || FieldMetrics::is_sampled(&req.context) {
         req.subgraph_request
             .headers_mut()
             .insert(FTV1_HEADER_NAME.clone(), FTV1_HEADER_VALUE.clone());
@@ -1992,11 +2101,7 @@ fn request_ftv1(mut req: SubgraphRequest) -> SubgraphRequest {
 
 fn store_ftv1(subgraph_name: &ByteString, resp: SubgraphResponse) -> SubgraphResponse {
     // Stash the FTV1 data
-    if resp
-        .context
-        .extensions()
-        .with_lock(|lock| lock.contains_key::<EnableSubgraphFtv1>())
-    {
+    if studio_ftv1_enabled(&resp.context) {
         if let Some(serde_json_bytes::Value::String(ftv1)) =
             resp.response.body().extensions.get("ftv1")
         {
@@ -1,4 +1,5 @@
 use std::any::TypeId;
+use std::cell::RefCell;
 use std::fmt;
 use std::marker;
 use std::sync::atomic::Ordering;
@@ -46,6 +47,8 @@ use crate::plugins::telemetry::formatters::filter_metric_events;
 use crate::plugins::telemetry::reload::IsSampled;
 use crate::plugins::telemetry::reload::SampledSpan;
 use crate::plugins::telemetry::reload::SPAN_SAMPLING_RATE;
+use crate::plugins::telemetry::tracing::sampling_rules::DeferredSampling;
+use crate::plugins::telemetry::tracing::sampling_rules::RootSampling;
 use crate::query_planner::subscription::SUBSCRIPTION_EVENT_SPAN_NAME;
 use crate::router_factory::STARTING_SPAN_NAME;
 
@@ -661,8 +664,17 @@ thread_local! {
     });
 }
 
+thread_local! {
+    /// Sampling of the root span being created, overriding the configured one
+    static ROOT_SAMPLING: RefCell<Option<RootSampling>> = const { RefCell::new(None) };
+}
+
 pub(crate) fn configure(sampler: &SamplerOption) {
-    let ratio = match sampler {
+    SPAN_SAMPLING_RATE.store(f64::to_bits(sampling_ratio(sampler)), Ordering::Relaxed);
+}
+
+pub(crate) fn sampling_ratio(sampler: &SamplerOption) -> f64 {
+    match sampler {
         SamplerOption::TraceIdRatioBased(ratio) => {
             // can't use std::cmp::min because f64 is not Ord
             if *ratio > 1.0 {
@@ -675,15 +687,50 @@ pub(crate) fn configure(sampler: &SamplerOption) {
             Sampler::AlwaysOn => 1f64,
             Sampler::AlwaysOff => 0f64,
         },
-    };
+    }
+}
+
+/// The ratio of the configured sampler
+pub(crate) fn configured_sampling_ratio() -> f64 {
+    f64::from_bits(SPAN_SAMPLING_RATE.load(Ordering::Relaxed))
+}
 
-    SPAN_SAMPLING_RATE.store(f64::to_bits(ratio), Ordering::Relaxed);
+/// Randomly decides whether a trace is sampled
+pub(crate) fn sample(ratio: f64) -> bool {
+    let s: f64 = thread_rng().gen_range(0.0..=1.0);
+    s <= ratio
+}
+
+/// Creates a root span, sampled as given instead of with the configured sampler
+pub(crate) fn with_root_sampling<R>(
+    sampling: Option<RootSampling>,
+    create: impl FnOnce() -> R,
+) -> R {
+    if sampling.is_none() {
+        return create();
+    }
+    let previous = ROOT_SAMPLING.with(|cell| cell.replace(sampling));
+    let result = create();
+    ROOT_SAMPLING.with(|cell| cell.replace(previous));
+    result
+}
+
+/// The deferred sampling decision of the root span being created
+fn root_deferred_sampling() -> Option<DeferredSampling> {
+    ROOT_SAMPLING.with(|cell| match &*cell.borrow() {
+        Some(RootSampling::Deferred(deferred)) => Some(deferred.clone()),
+        _ => None,
+    })
 }
 
 impl<S, T> OpenTelemetryLayer<S, T> {
     fn sample(&self) -> bool {
-        let s: f64 = thread_rng().gen_range(0.0..=1.0);
-        s <= f64::from_bits(SPAN_SAMPLING_RATE.load(Ordering::Relaxed))
+        ROOT_SAMPLING.with(|cell| match &*cell.borrow() {
+            Some(RootSampling::Ratio(ratio)) => sample(*ratio),
+            // The trace is recorded until the decision is made
+            Some(RootSampling::Deferred(_)) => true,
+            None => sample(configured_sampling_ratio()),
+        })
     }
 }
 
@@ -720,7 +767,11 @@ where
             .id()
             .and_then(|id| cx.span(id))
         {
-            return spanref.is_sampled();
+            return spanref.is_sampled()
+                && !spanref
+                    .extensions()
+                    .get::<DeferredSampling>()
+                    .map_or(false, DeferredSampling::is_dropped);
         }
 
         // always sample the router loading trace
@@ -791,6 +842,19 @@ where
             return;
         }
 
+        // Spans of a trace whose sampling decision is deferred share it, unless the decision
+        // was already made by a sampled remote parent
+        let deferred_sampling = match span.parent() {
+            Some(parent) => parent.extensions().get::<DeferredSampling>().cloned(),
+            None if !self.force_sampling && !parent_cx.span().span_context().is_sampled() => {
+                root_deferred_sampling()
+            }
+            None => None,
+        };
+        if let Some(deferred_sampling) = deferred_sampling {
+            extensions.insert(deferred_sampling);
+        }
+
         if self.tracked_inactivity && extensions.get_mut::<Timings>().is_none() {
             extensions.insert(Timings::new());
         }
@@ -1103,9 +1167,19 @@ where
             }
 
             // Assign end time, build and start span, drop span to export
-            builder
-                .with_end_time(SystemTime::now())
-                .start_with_context(&self.tracer, &parent_cx);
+            let builder = builder.with_end_time(SystemTime::now());
+            match extensions.get_mut::<DeferredSampling>() {
+                Some(deferred_sampling) => {
+                    for (builder, parent_cx) in
+                        deferred_sampling.on_end(builder, parent_cx, span.parent().is_none())
+                    {
+                        builder.start_with_context(&self.tracer, &parent_cx);
+                    }
+                }
+                None => {
+                    builder.start_with_context(&self.tracer, &parent_cx);
+                }
+            }
         }
     }
 
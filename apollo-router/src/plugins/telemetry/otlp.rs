@@ -1,4 +1,4 @@
-//! Shared configuration for Otlp tracing and metrics.
+//! Shared configuration for Otlp tracing, metrics and logs.
 use std::collections::HashMap;
 use std::str::FromStr;
 
@@ -31,7 +31,8 @@ lazy_static! {
     static ref DEFAULT_HTTP_ENDPOINT: Uri = Uri::from_static("http://127.0.0.1:4318");
 }
 
-const DEFAULT_HTTP_ENDPOINT_PATH: &str = "/v1/traces";
+const DEFAULT_HTTP_TRACES_PATH: &str = "/v1/traces";
+const DEFAULT_HTTP_LOGS_PATH: &str = "/v1/logs";
 
 #[derive(Debug, Clone, Deserialize, JsonSchema, Default)]
 #[serde(deny_unknown_fields)]
@@ -69,6 +70,7 @@ pub(crate) struct Config {
 pub(crate) enum TelemetryDataKind {
     Traces,
     Metrics,
+    Logs,
 }
 
 impl Config {
@@ -121,32 +123,34 @@ fn add_missing_path(
     kind: TelemetryDataKind,
     mut endpoint_parts: Option<Parts>,
 ) -> Result<Option<Uri>, BoxError> {
-    if let Some(endpoint_parts) = &mut endpoint_parts {
-        if let TelemetryDataKind::Traces = kind {
-            match &mut endpoint_parts.path_and_query {
-                Some(path_and_query) => {
-                    if !path_and_query.path().ends_with(DEFAULT_HTTP_ENDPOINT_PATH) {
-                        match path_and_query.query() {
-                            Some(query) => {
-                                endpoint_parts.path_and_query =
-                                    Some(PathAndQuery::from_str(&format!(
-                                        "{}{DEFAULT_HTTP_ENDPOINT_PATH}?{query}",
-                                        path_and_query.path().trim_end_matches('/')
-                                    ))?);
-                            }
-                            None => {
-                                *path_and_query = PathAndQuery::from_str(&format!(
-                                    "{}{DEFAULT_HTTP_ENDPOINT_PATH}",
+    let default_path = match kind {
+        TelemetryDataKind::Traces => Some(DEFAULT_HTTP_TRACES_PATH),
+        TelemetryDataKind::Logs => Some(DEFAULT_HTTP_LOGS_PATH),
+        TelemetryDataKind::Metrics => None,
+    };
+    if let (Some(endpoint_parts), Some(default_path)) = (&mut endpoint_parts, default_path) {
+        match &mut endpoint_parts.path_and_query {
+            Some(path_and_query) => {
+                if !path_and_query.path().ends_with(default_path) {
+                    match path_and_query.query() {
+                        Some(query) => {
+                            endpoint_parts.path_and_query =
+                                Some(PathAndQuery::from_str(&format!(
+                                    "{}{default_path}?{query}",
                                     path_and_query.path().trim_end_matches('/')
-                                ))?;
-                            }
+                                ))?);
+                        }
+                        None => {
+                            *path_and_query = PathAndQuery::from_str(&format!(
+                                "{}{default_path}",
+                                path_and_query.path().trim_end_matches('/')
+                            ))?;
                         }
                     }
                 }
-                None => {
-                    endpoint_parts.path_and_query =
-                        Some(PathAndQuery::from_static(DEFAULT_HTTP_ENDPOINT_PATH));
-                }
+            }
+            None => {
+                endpoint_parts.path_and_query = Some(PathAndQuery::from_static(default_path));
             }
         }
     }
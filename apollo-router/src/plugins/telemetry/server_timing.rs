@@ -0,0 +1,266 @@
+//! W3C `Server-Timing` header, with the time spent in each stage of a request.
+//!
+//! See https://www.w3.org/TR/server-timing/
+use std::fmt::Write;
+use std::time::Duration;
+
+use http::HeaderName;
+use http::HeaderValue;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::config_new::experimental_when_header::HeaderLoggingCondition;
+use crate::services::router;
+use crate::Context;
+
+pub(crate) static SERVER_TIMING_HEADER_NAME: HeaderName = HeaderName::from_static("server-timing");
+/// Request header asking for the Server-Timing header, when no other condition is configured
+static SERVER_TIMING_REQUEST_HEADER_NAME: HeaderName =
+    HeaderName::from_static("apollo-server-timing");
+
+pub(crate) const PARSE: &str = "parse";
+pub(crate) const VALIDATE: &str = "validate";
+pub(crate) const PLAN: &str = "plan";
+pub(crate) const FETCH: &str = "fetch";
+pub(crate) const SERIALIZE: &str = "serialize";
+
+/// Server-Timing configuration
+#[derive(Clone, Default, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct ServerTiming {
+    /// Return a Server-Timing header with the time spent parsing, validating and planning the
+    /// operation, fetching from each subgraph and serializing the response
+    pub(crate) enabled: bool,
+    /// Only return the header for requests matching one of these header conditions. If empty, it
+    /// is only returned for requests with an `apollo-server-timing: true` header
+    #[serde(rename = "experimental_when_header")]
+    pub(crate) when_header: Vec<HeaderLoggingCondition>,
+}
+
+impl ServerTiming {
+    /// Starts collecting the timings of a request if the header must be returned for it
+    pub(crate) fn on_request(&self, request: &router::Request) {
+        let headers = request.router_request.headers();
+        let requested = if self.when_header.is_empty() {
+            headers
+                .get(&SERVER_TIMING_REQUEST_HEADER_NAME)
+                .is_some_and(|value| value == "true")
+        } else {
+            self.when_header
+                .iter()
+                .any(|condition| condition.matches(headers))
+        };
+        if self.enabled && requested {
+            request
+                .context
+                .extensions()
+                .with_lock(|mut lock| lock.insert(ServerTimings::default()));
+        }
+    }
+
+    /// Adds the collected timings to the response headers
+    pub(crate) fn on_response(&self, response: &mut router::Response) {
+        if !self.enabled {
+            return;
+        }
+        let value = response.context.extensions().with_lock(|lock| {
+            lock.get::<ServerTimings>()
+                .and_then(ServerTimings::header_value)
+        });
+        if let Some(value) = value {
+            response
+                .response
+                .headers_mut()
+                .append(&SERVER_TIMING_HEADER_NAME, value);
+        }
+    }
+}
+
+/// Timings of the stages of a request
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ServerTimings {
+    metrics: Vec<Metric>,
+}
+
+#[derive(Clone, Debug)]
+struct Metric {
+    name: &'static str,
+    description: Option<String>,
+    duration: Duration,
+}
+
+impl ServerTimings {
+    fn header_value(&self) -> Option<HeaderValue> {
+        if self.metrics.is_empty() {
+            return None;
+        }
+        let mut value = String::new();
+        for (index, metric) in self.metrics.iter().enumerate() {
+            if index > 0 {
+                value.push_str(", ");
+            }
+            value.push_str(metric.name);
+            if let Some(description) = &metric.description {
+                let _ = write!(value, ";desc={}", quoted_string(description));
+            }
+            let _ = write!(value, ";dur={:.3}", metric.duration.as_secs_f64() * 1000.0);
+        }
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+/// Descriptions are quoted strings, in which quotes and backslashes are escaped
+fn quoted_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Records the duration of a stage of the request, if its timings are collected
+pub(crate) fn record(
+    context: &Context,
+    name: &'static str,
+    description: Option<&str>,
+    duration: Duration,
+) {
+    context.extensions().with_lock(|mut lock| {
+        if let Some(timings) = lock.get_mut::<ServerTimings>() {
+            timings.metrics.push(Metric {
+                name,
+                description: description.map(str::to_string),
+                duration,
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> ServerTiming {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn request(headers: &[(&'static str, &'static str)]) -> router::Request {
+        let mut builder = router::Request::fake_builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.build().unwrap()
+    }
+
+    fn respond(config: &ServerTiming, request: router::Request) -> router::Response {
+        record(&request.context, PARSE, None, Duration::from_micros(1500));
+        record(
+            &request.context,
+            PLAN,
+            Some("cache hit"),
+            Duration::from_micros(20),
+        );
+        record(
+            &request.context,
+            FETCH,
+            Some("products \"v2\""),
+            Duration::from_millis(12),
+        );
+        let mut response = router::Response::fake_builder()
+            .context(request.context)
+            .build()
+            .unwrap();
+        config.on_response(&mut response);
+        response
+    }
+
+    #[test]
+    fn header_lists_recorded_timings() {
+        let config = config("enabled: true");
+        let request = request(&[("apollo-server-timing", "true")]);
+        config.on_request(&request);
+        let response = respond(&config, request);
+
+        assert_eq!(
+            response.response.headers().get(&SERVER_TIMING_HEADER_NAME),
+            Some(&HeaderValue::from_static(
+                "parse;dur=1.500, plan;desc=\"cache hit\";dur=0.020, fetch;desc=\"products \\\"v2\\\"\";dur=12.000"
+            ))
+        );
+    }
+
+    #[test]
+    fn header_is_requested_by_clients_by_default() {
+        let config = config("enabled: true");
+
+        for (headers, expected) in [
+            (&[][..], false),
+            (&[("apollo-server-timing", "false")][..], false),
+            (&[("apollo-server-timing", "true")][..], true),
+        ] {
+            let request = request(headers);
+            config.on_request(&request);
+            let response = respond(&config, request);
+            assert_eq!(
+                response
+                    .response
+                    .headers()
+                    .contains_key(&SERVER_TIMING_HEADER_NAME),
+                expected,
+                "{headers:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn header_is_gated_by_request_headers() {
+        let config = config(
+            r#"
+            enabled: true
+            experimental_when_header:
+              - name: apollo-server-timing
+                value: "true"
+              - name: x-debug
+                match: ^frontend-
+            "#,
+        );
+
+        for (headers, expected) in [
+            (&[][..], false),
+            (&[("apollo-server-timing", "false")][..], false),
+            (&[("apollo-server-timing", "true")][..], true),
+            (&[("x-debug", "frontend-web")][..], true),
+            (&[("x-debug", "backend")][..], false),
+        ] {
+            let request = request(headers);
+            config.on_request(&request);
+            let response = respond(&config, request);
+            assert_eq!(
+                response
+                    .response
+                    .headers()
+                    .contains_key(&SERVER_TIMING_HEADER_NAME),
+                expected,
+                "{headers:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn header_is_not_returned_when_disabled() {
+        let config = config("experimental_when_header: []");
+        let request = request(&[]);
+        config.on_request(&request);
+        let response = respond(&config, request);
+
+        assert!(!response
+            .response
+            .headers()
+            .contains_key(&SERVER_TIMING_HEADER_NAME));
+    }
+}
@@ -221,7 +221,8 @@ impl TracingConfigurator for Config {
             )
             .with_batch_config(self.batch_processor.clone().into())
             .build()
-            .filtered(),
+            .filtered()
+            .tail_sampled(&trace.experimental_tail_sampling),
         ))
     }
 }
@@ -108,7 +108,8 @@ impl TracingConfigurator for Config {
                     BatchSpanProcessor::builder(exporter, opentelemetry::runtime::Tokio)
                         .with_batch_config(batch_processor.clone().into())
                         .build()
-                        .filtered(),
+                        .filtered()
+                        .tail_sampled(&common.experimental_tail_sampling),
                 ))
             }
             Config::Collector {
@@ -139,7 +140,8 @@ impl TracingConfigurator for Config {
                 Ok(builder.with_span_processor(
                     BatchSpanProcessor::builder(exporter, runtime::Tokio)
                         .with_batch_config(batch_processor.clone().into())
-                        .build(),
+                        .build()
+                        .tail_sampled(&common.experimental_tail_sampling),
                 ))
             }
             _ => Ok(builder),
@@ -15,6 +15,8 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use tower::BoxError;
 
+use self::tail_sampling::TailSampling;
+use self::tail_sampling::TailSamplingSpanProcessor;
 use super::config_new::spans::Spans;
 use super::formatters::APOLLO_PRIVATE_PREFIX;
 use crate::plugins::telemetry::config::TracingCommon;
@@ -27,6 +29,8 @@ pub(crate) mod datadog_exporter;
 pub(crate) mod jaeger;
 pub(crate) mod otlp;
 pub(crate) mod reload;
+pub(crate) mod sampling_rules;
+pub(crate) mod tail_sampling;
 pub(crate) mod zipkin;
 
 pub(crate) trait TracingConfigurator {
@@ -91,6 +95,7 @@ where
     Self: Sized + SpanProcessor,
 {
     fn filtered(self) -> ApolloFilterSpanProcessor<Self>;
+    fn tail_sampled(self, config: &TailSampling) -> TailSamplingSpanProcessor<Self>;
 }
 
 impl<T: SpanProcessor> SpanProcessorExt for T
@@ -100,6 +105,10 @@ where
     fn filtered(self) -> ApolloFilterSpanProcessor<Self> {
         ApolloFilterSpanProcessor { delegate: self }
     }
+
+    fn tail_sampled(self, config: &TailSampling) -> TailSamplingSpanProcessor<Self> {
+        TailSamplingSpanProcessor::new(self, config)
+    }
 }
 
 /// Batch processor configuration
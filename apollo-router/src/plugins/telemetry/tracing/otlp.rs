@@ -20,7 +20,7 @@ impl TracingConfigurator for super::super::otlp::Config {
     fn apply(
         &self,
         builder: Builder,
-        _common: &TracingCommon,
+        common: &TracingCommon,
         _spans_config: &Spans,
     ) -> Result<Builder, BoxError> {
         tracing::info!("Configuring Otlp tracing: {}", self.batch_processor);
@@ -33,7 +33,8 @@ impl TracingConfigurator for super::super::otlp::Config {
             )
             .with_batch_config(self.batch_processor.clone().into())
             .build()
-            .filtered(),
+            .filtered()
+            .tail_sampled(&common.experimental_tail_sampling),
         ))
     }
 }
@@ -0,0 +1,453 @@
+//! Head sampling rules.
+//!
+//! The sampler of a trace is chosen from the first rule whose condition matches the request.
+//! Traces of requests matching no rule use the common sampler.
+//!
+//! Router conditions are evaluated when the root span is created. If a supergraph condition has
+//! to be evaluated first, the decision is deferred: the trace is recorded until the supergraph
+//! request is known, and the spans ending before the decision are held back until it is made.
+//! The decision is made before the first subgraph request, so the propagated trace context
+//! always carries it.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use arc_swap::ArcSwap;
+use once_cell::sync::Lazy;
+use opentelemetry::trace::SpanBuilder;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Registry;
+
+use crate::plugins::telemetry::config::SamplerOption;
+use crate::plugins::telemetry::config_new::conditions::Condition;
+use crate::plugins::telemetry::config_new::selectors::RouterSelector;
+use crate::plugins::telemetry::config_new::selectors::SupergraphSelector;
+use crate::plugins::telemetry::config_new::Stage;
+use crate::plugins::telemetry::otel::layer::configured_sampling_ratio;
+use crate::plugins::telemetry::otel::layer::sample;
+use crate::plugins::telemetry::otel::layer::sampling_ratio;
+use crate::services::router;
+use crate::services::router::Body;
+use crate::services::supergraph;
+use crate::Context;
+
+static SAMPLING_RULES: Lazy<ArcSwap<Vec<SamplingRule>>> = Lazy::new(Default::default);
+
+/// A sampler applied to the requests matching a condition
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SamplingRule {
+    /// The condition on the request
+    pub(crate) condition: SamplingCondition,
+    /// The sampler, always_on, always_off or a decimal between 0.0 and 1.0
+    pub(crate) sampler: SamplerOption,
+}
+
+/// A condition on the router or supergraph request
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(crate) enum SamplingCondition {
+    /// A condition on the router request, evaluated before the request body is read
+    Router(Condition<RouterSelector>),
+    /// A condition on the supergraph request, evaluated once the operation is parsed
+    Supergraph(Condition<SupergraphSelector>),
+}
+
+/// Rules are evaluated before the request is executed, so they can only use request selectors
+pub(crate) fn validate(rules: &[SamplingRule]) -> Result<(), String> {
+    for rule in rules {
+        match &rule.condition {
+            SamplingCondition::Router(condition) => condition.validate(Some(Stage::Request))?,
+            SamplingCondition::Supergraph(condition) => condition.validate(Some(Stage::Request))?,
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the rules used for new traces
+pub(crate) fn configure(rules: Vec<SamplingRule>) {
+    SAMPLING_RULES.store(Arc::new(rules));
+}
+
+/// How the root span of a request is sampled
+pub(crate) enum RootSampling {
+    /// Sampled with this ratio instead of the configured one
+    Ratio(f64),
+    /// Recorded until the supergraph request is known
+    Deferred(DeferredSampling),
+}
+
+/// The sampling of the first rule matching the request, if any
+pub(crate) fn root_sampling<B>(request: &http::Request<B>) -> Option<RootSampling> {
+    evaluate(SAMPLING_RULES.load_full(), request)
+}
+
+fn evaluate<B>(rules: Arc<Vec<SamplingRule>>, request: &http::Request<B>) -> Option<RootSampling> {
+    if rules.is_empty() {
+        return None;
+    }
+
+    // Selectors read the router request, whose body is not available yet
+    let mut builder = http::Request::builder()
+        .method(request.method().clone())
+        .uri(request.uri().clone())
+        .version(request.version());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = request.headers().clone();
+    }
+    let request = router::Request {
+        router_request: builder.body(Body::empty()).ok()?,
+        context: Context::new(),
+    };
+
+    let mut first_supergraph_rule = None;
+    let mut matching_router_rule = None;
+    for (index, rule) in rules.iter().enumerate() {
+        match &rule.condition {
+            SamplingCondition::Router(condition) => {
+                if condition.clone().evaluate_request(&request) == Some(true) {
+                    matching_router_rule = Some(index);
+                    break;
+                }
+            }
+            SamplingCondition::Supergraph(_) => {
+                first_supergraph_rule.get_or_insert(index);
+            }
+        }
+    }
+
+    match first_supergraph_rule {
+        Some(from) => Some(RootSampling::Deferred(DeferredSampling::new(
+            rules,
+            from,
+            matching_router_rule,
+        ))),
+        None => matching_router_rule
+            .map(|index| RootSampling::Ratio(sampling_ratio(&rules[index].sampler))),
+    }
+}
+
+/// Makes the deferred sampling decision of the current trace, if any
+pub(crate) fn on_supergraph_request(request: &supergraph::Request) {
+    if let Some(deferred) = DeferredSampling::current() {
+        deferred.decide(request);
+    }
+}
+
+/// The sampling decision of a trace waiting for its supergraph request, shared by its spans
+#[derive(Clone, Debug)]
+pub(crate) struct DeferredSampling(Arc<Mutex<Deferred>>);
+
+#[derive(Debug)]
+struct Deferred {
+    rules: Arc<Vec<SamplingRule>>,
+    /// The first rule with a supergraph condition
+    from: usize,
+    /// The first router rule matching the request, used if no supergraph rule before it matches
+    matching_router_rule: Option<usize>,
+    decision: Option<bool>,
+    /// Spans which ended before the decision, with their parent context
+    ended: Vec<(SpanBuilder, opentelemetry::Context)>,
+}
+
+impl DeferredSampling {
+    fn new(
+        rules: Arc<Vec<SamplingRule>>,
+        from: usize,
+        matching_router_rule: Option<usize>,
+    ) -> Self {
+        Self(Arc::new(Mutex::new(Deferred {
+            rules,
+            from,
+            matching_router_rule,
+            decision: None,
+            ended: Vec::new(),
+        })))
+    }
+
+    fn current() -> Option<Self> {
+        ::tracing::Span::current()
+            .with_subscriber(|(id, dispatch)| {
+                let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+                let deferred = span.extensions().get::<DeferredSampling>().cloned();
+                deferred
+            })
+            .flatten()
+    }
+
+    /// Evaluates the supergraph rules and decides whether the trace is kept
+    fn decide(&self, request: &supergraph::Request) {
+        let mut deferred = self.0.lock().expect("lock poisoned");
+        if deferred.decision.is_some() {
+            return;
+        }
+        let until = deferred
+            .matching_router_rule
+            .unwrap_or(deferred.rules.len());
+        let matching_rule = deferred.rules[deferred.from..until]
+            .iter()
+            .find(|rule| match &rule.condition {
+                SamplingCondition::Supergraph(condition) => {
+                    condition.clone().evaluate_request(request) == Some(true)
+                }
+                // Router rules before `until` did not match
+                SamplingCondition::Router(_) => false,
+            })
+            .or_else(|| {
+                deferred
+                    .matching_router_rule
+                    .map(|index| &deferred.rules[index])
+            });
+        let ratio = matching_rule.map_or_else(configured_sampling_ratio, |rule| {
+            sampling_ratio(&rule.sampler)
+        });
+        deferred.decision = Some(sample(ratio));
+    }
+
+    /// Whether the trace was decided to be dropped, so new spans are not recorded
+    pub(crate) fn is_dropped(&self) -> bool {
+        self.0.lock().expect("lock poisoned").decision == Some(false)
+    }
+
+    /// Records the end of a span, returning the spans to export.
+    ///
+    /// If the root span ends before the decision was made, the trace never reached the
+    /// supergraph and is sampled like requests matching no supergraph rule.
+    pub(crate) fn on_end(
+        &self,
+        builder: SpanBuilder,
+        parent_cx: opentelemetry::Context,
+        is_root: bool,
+    ) -> Vec<(SpanBuilder, opentelemetry::Context)> {
+        let mut deferred = self.0.lock().expect("lock poisoned");
+        if deferred.decision.is_none() && is_root {
+            let ratio = deferred
+                .matching_router_rule
+                .map_or_else(configured_sampling_ratio, |index| {
+                    sampling_ratio(&deferred.rules[index].sampler)
+                });
+            deferred.decision = Some(sample(ratio));
+        }
+        match deferred.decision {
+            None => {
+                deferred.ended.push((builder, parent_cx));
+                Vec::new()
+            }
+            Some(true) => {
+                let mut spans = std::mem::take(&mut deferred.ended);
+                spans.push((builder, parent_cx));
+                spans
+            }
+            Some(false) => {
+                deferred.ended.clear();
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use opentelemetry::trace::SpanId;
+
+    use super::*;
+    use crate::context::OPERATION_NAME;
+
+    fn rules(yaml: &str) -> Vec<SamplingRule> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn request(headers: &[(&str, &str)]) -> http::Request<()> {
+        let mut builder = http::Request::post("http://localhost/graphql");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    fn ratio(request: &http::Request<()>) -> Option<f64> {
+        match root_sampling(request) {
+            Some(RootSampling::Ratio(ratio)) => Some(ratio),
+            Some(RootSampling::Deferred(_)) => panic!("the decision should not be deferred"),
+            None => None,
+        }
+    }
+
+    fn deferred_sampling(
+        rules: &Arc<Vec<SamplingRule>>,
+        request: &http::Request<()>,
+    ) -> DeferredSampling {
+        match evaluate(rules.clone(), request) {
+            Some(RootSampling::Deferred(deferred)) => deferred,
+            _ => panic!("the decision should be deferred"),
+        }
+    }
+
+    fn supergraph_request(operation_name: &str) -> supergraph::Request {
+        let context = Context::new();
+        let _ = context.insert(OPERATION_NAME, operation_name.to_string());
+        supergraph::Request::fake_builder()
+            .context(context)
+            .build()
+            .unwrap()
+    }
+
+    fn end(deferred: &DeferredSampling, span_id: u64, is_root: bool) -> Vec<SpanId> {
+        deferred
+            .on_end(
+                SpanBuilder::from_name("span").with_span_id(SpanId::from_u64(span_id)),
+                opentelemetry::Context::new(),
+                is_root,
+            )
+            .into_iter()
+            .filter_map(|(builder, _)| builder.span_id)
+            .collect()
+    }
+
+    #[test]
+    fn first_matching_rule_is_used() {
+        configure(rules(
+            r#"
+            - condition:
+                router:
+                  eq:
+                    - request_header: apollographql-client-name
+                    - health-check
+              sampler: 0.001
+            - condition:
+                router:
+                  eq:
+                    - request_header: x-apollo-operation-name
+                    - Checkout
+              sampler: always_on
+            - condition:
+                router:
+                  exists:
+                    request_header: x-apollo-operation-name
+              sampler: always_off
+            "#,
+        ));
+
+        assert_eq!(
+            ratio(&request(&[("apollographql-client-name", "health-check")])),
+            Some(0.001)
+        );
+        assert_eq!(
+            ratio(&request(&[("x-apollo-operation-name", "Checkout")])),
+            Some(1.0)
+        );
+        assert_eq!(
+            ratio(&request(&[("x-apollo-operation-name", "Search")])),
+            Some(0.0)
+        );
+        assert_eq!(ratio(&request(&[])), None);
+
+        configure(Vec::new());
+        assert_eq!(
+            ratio(&request(&[("apollographql-client-name", "health-check")])),
+            None
+        );
+    }
+
+    #[test]
+    fn supergraph_rules_defer_the_decision() {
+        let rules = Arc::new(rules(
+            r#"
+            - condition:
+                router:
+                  eq:
+                    - request_header: apollographql-client-name
+                    - health-check
+              sampler: always_off
+            - condition:
+                supergraph:
+                  eq:
+                    - operation_name: string
+                    - Checkout
+              sampler: always_on
+            - condition:
+                router:
+                  exists:
+                    request_header: apollographql-client-name
+              sampler: always_off
+            "#,
+        ));
+
+        // A router rule before every supergraph rule decides immediately
+        assert!(matches!(
+            evaluate(
+                rules.clone(),
+                &request(&[("apollographql-client-name", "health-check")])
+            ),
+            Some(RootSampling::Ratio(ratio)) if ratio == 0.0
+        ));
+
+        // Spans ending before the decision are held back until it is made
+        let deferred = deferred_sampling(&rules, &request(&[("apollographql-client-name", "web")]));
+        assert!(end(&deferred, 2, false).is_empty());
+        deferred.decide(&supergraph_request("Checkout"));
+        assert!(!deferred.is_dropped());
+        assert_eq!(
+            end(&deferred, 3, false),
+            vec![SpanId::from_u64(2), SpanId::from_u64(3)]
+        );
+        assert_eq!(end(&deferred, 1, true), vec![SpanId::from_u64(1)]);
+
+        // The matching router rule applies when no supergraph rule before it matches
+        let deferred = deferred_sampling(&rules, &request(&[("apollographql-client-name", "web")]));
+        assert!(end(&deferred, 2, false).is_empty());
+        deferred.decide(&supergraph_request("Search"));
+        assert!(deferred.is_dropped());
+        assert!(end(&deferred, 3, false).is_empty());
+        assert!(end(&deferred, 1, true).is_empty());
+
+        // Without a supergraph request, the decision is made when the root span ends
+        let deferred = deferred_sampling(&rules, &request(&[("apollographql-client-name", "web")]));
+        assert!(end(&deferred, 2, false).is_empty());
+        assert!(end(&deferred, 1, true).is_empty());
+    }
+
+    #[test]
+    fn rules_must_use_request_selectors() {
+        assert!(validate(&rules(
+            r#"
+            - condition:
+                router:
+                  eq:
+                    - request_header: apollographql-client-name
+                    - health-check
+              sampler: 0.001
+            - condition:
+                supergraph:
+                  eq:
+                    - operation_name: string
+                    - Checkout
+              sampler: always_on
+            "#,
+        ))
+        .is_ok());
+        assert!(validate(&rules(
+            r#"
+            - condition:
+                router:
+                  eq:
+                    - response_status: code
+                    - 500
+              sampler: always_on
+            "#,
+        ))
+        .is_err());
+        assert!(validate(&rules(
+            r#"
+            - condition:
+                supergraph:
+                  exists:
+                    response_errors: "$.*"
+              sampler: always_on
+            "#,
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,488 @@
+//! Tail-based sampling.
+//!
+//! Spans are buffered per trace until the local root span of the trace ends. The trace is then
+//! kept or dropped as a whole, depending on the outcome of the request. The telemetry plugin
+//! records that outcome as private attributes of the router and subgraph spans, which are
+//! removed before the spans are exported.
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http::StatusCode;
+use lru::LruCache;
+use opentelemetry::sdk::export::trace::SpanData;
+use opentelemetry::sdk::trace::Span;
+use opentelemetry::sdk::trace::SpanProcessor;
+use opentelemetry::trace::SpanId;
+use opentelemetry::trace::TraceId;
+use opentelemetry::trace::TraceResult;
+use opentelemetry::Context;
+use opentelemetry::Key;
+use opentelemetry::KeyValue;
+use opentelemetry::Value;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::context::CONTAINS_GRAPHQL_ERROR;
+use crate::context::OPERATION_NAME;
+use crate::plugins::telemetry::config::Sampler;
+use crate::plugins::telemetry::config::SamplerOption;
+use crate::plugins::telemetry::dynamic_attribute::SpanDynAttribute;
+
+const ROOT: Key = Key::from_static_str("apollo_private.tail_sampling.root");
+const STATUS_CODE: Key = Key::from_static_str("apollo_private.tail_sampling.status_code");
+const GRAPHQL_ERRORS: Key = Key::from_static_str("apollo_private.tail_sampling.graphql_errors");
+const OPERATION_NAME_KEY: Key = Key::from_static_str("apollo_private.tail_sampling.operation_name");
+const SUBGRAPH_ERROR: Key = Key::from_static_str("apollo_private.tail_sampling.subgraph_error");
+
+/// Tail-based sampling configuration
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct TailSampling {
+    /// Buffer the spans of each trace until the request completes, and only export the traces
+    /// matching a rule
+    pub(crate) enabled: bool,
+    /// A trace is kept if any of these rules matches
+    pub(crate) rules: Vec<TailSamplingRule>,
+    /// The sampler for traces that match no rule, always_on, always_off or a decimal between 0.0
+    /// and 1.0
+    pub(crate) fallback_sampler: SamplerOption,
+    /// The maximum number of traces waiting for a decision. When it is reached, the oldest
+    /// trace is dropped
+    pub(crate) max_traces: NonZeroUsize,
+    /// The maximum number of spans buffered for a trace. Further spans of the trace are dropped
+    pub(crate) max_spans_per_trace: usize,
+}
+
+impl Default for TailSampling {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            fallback_sampler: SamplerOption::Always(Sampler::AlwaysOff),
+            max_traces: NonZeroUsize::new(10_000).expect("not zero"),
+            max_spans_per_trace: 1_000,
+        }
+    }
+}
+
+/// A condition for keeping a trace
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(crate) enum TailSamplingRule {
+    /// Keep traces of requests answered with one of these HTTP status codes
+    StatusCode(Vec<u16>),
+    /// Keep traces of requests whose response contains GraphQL errors
+    GraphqlErrors(bool),
+    /// Keep traces of requests taking at least this long
+    Duration(
+        #[serde(deserialize_with = "humantime_serde::deserialize")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
+    /// Keep traces of operations with one of these names
+    OperationName(Vec<String>),
+    /// Keep traces where a subgraph request failed, or returned an HTTP error or GraphQL errors
+    SubgraphErrors(bool),
+}
+
+impl TailSamplingRule {
+    /// Evaluates the rule on the spans of a trace, the root span being last
+    fn matches(&self, spans: &[SpanData]) -> bool {
+        let mut attributes = spans.iter().flat_map(|span| span.attributes.iter());
+        match self {
+            TailSamplingRule::StatusCode(codes) => attributes.any(|(key, value)| match value {
+                Value::I64(code) if *key == STATUS_CODE => {
+                    u16::try_from(*code).map_or(false, |code| codes.contains(&code))
+                }
+                _ => false,
+            }),
+            TailSamplingRule::GraphqlErrors(enabled) => {
+                *enabled
+                    && attributes
+                        .any(|(key, value)| *key == GRAPHQL_ERRORS && *value == Value::Bool(true))
+            }
+            TailSamplingRule::Duration(threshold) => spans.last().map_or(false, |root| {
+                root.end_time
+                    .duration_since(root.start_time)
+                    .map_or(false, |duration| duration >= *threshold)
+            }),
+            TailSamplingRule::OperationName(names) => attributes.any(|(key, value)| match value {
+                Value::String(name) if *key == OPERATION_NAME_KEY => {
+                    names.iter().any(|n| n == name.as_str())
+                }
+                _ => false,
+            }),
+            TailSamplingRule::SubgraphErrors(enabled) => {
+                *enabled
+                    && attributes
+                        .any(|(key, value)| *key == SUBGRAPH_ERROR && *value == Value::Bool(true))
+            }
+        }
+    }
+}
+
+/// Records the outcome of a request on its router span
+pub(crate) fn record_router_response(
+    span: &::tracing::Span,
+    status: Option<StatusCode>,
+    context: &crate::Context,
+) {
+    let graphql_errors = status.is_none()
+        || context.get_json_value(CONTAINS_GRAPHQL_ERROR)
+            == Some(serde_json_bytes::Value::Bool(true));
+    let mut attributes = vec![
+        KeyValue::new(ROOT, true),
+        KeyValue::new(GRAPHQL_ERRORS, graphql_errors),
+    ];
+    if let Some(status) = status {
+        attributes.push(KeyValue::new(STATUS_CODE, status.as_u16() as i64));
+    }
+    if let Ok(Some(operation_name)) = context.get::<_, String>(OPERATION_NAME) {
+        attributes.push(KeyValue::new(OPERATION_NAME_KEY, operation_name));
+    }
+    span.set_span_dyn_attributes(attributes);
+}
+
+/// Records on a subgraph span that the subgraph request failed
+pub(crate) fn record_subgraph_error(span: &::tracing::Span) {
+    span.set_span_dyn_attribute(SUBGRAPH_ERROR, true.into());
+}
+
+#[derive(Debug)]
+struct Buffer {
+    /// Spans of the traces waiting for their root span
+    pending: LruCache<TraceId, Vec<SpanData>>,
+    /// Decisions for recent traces, for spans ending after their root span
+    decided: LruCache<TraceId, bool>,
+}
+
+#[derive(Debug)]
+pub(crate) struct TailSamplingSpanProcessor<T: SpanProcessor> {
+    delegate: T,
+    config: TailSampling,
+    buffer: Option<Mutex<Buffer>>,
+}
+
+impl<T: SpanProcessor> TailSamplingSpanProcessor<T> {
+    pub(crate) fn new(delegate: T, config: &TailSampling) -> Self {
+        let buffer = config.enabled.then(|| {
+            Mutex::new(Buffer {
+                pending: LruCache::new(config.max_traces),
+                decided: LruCache::new(config.max_traces),
+            })
+        });
+        Self {
+            delegate,
+            config: config.clone(),
+            buffer,
+        }
+    }
+
+    fn keep(&self, trace_id: TraceId, spans: &[SpanData]) -> bool {
+        self.config.rules.iter().any(|rule| rule.matches(spans))
+            || match &self.config.fallback_sampler {
+                SamplerOption::Always(Sampler::AlwaysOn) => true,
+                SamplerOption::Always(Sampler::AlwaysOff) => false,
+                // Same computation as the trace id ratio sampler, so that every exporter takes
+                // the same decision
+                SamplerOption::TraceIdRatioBased(ratio) => {
+                    let low = u128::from_be_bytes(trace_id.to_bytes()) as u64 >> 1;
+                    let threshold = (ratio.clamp(0.0, 1.0) * (1u64 << 63) as f64) as u64;
+                    low < threshold
+                }
+            }
+    }
+}
+
+impl<T: SpanProcessor> SpanProcessor for TailSamplingSpanProcessor<T> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.delegate.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        let Some(buffer) = &self.buffer else {
+            return self.delegate.on_end(span);
+        };
+        let trace_id = span.span_context.trace_id();
+        let is_root =
+            span.parent_span_id == SpanId::INVALID || span.attributes.get(&ROOT).is_some();
+
+        let mut buffer = buffer.lock().expect("lock poisoned");
+        if let Some(keep) = buffer.decided.get(&trace_id).copied() {
+            drop(buffer);
+            if keep {
+                self.delegate.on_end(span);
+            }
+            return;
+        }
+
+        if !is_root {
+            let mut evicted = false;
+            if !buffer.pending.contains(&trace_id)
+                && buffer.pending.len() == buffer.pending.cap().get()
+            {
+                evicted = buffer.pending.pop_lru().is_some();
+            }
+            let spans = buffer.pending.get_or_insert_mut(trace_id, Vec::new);
+            let dropped = spans.len() >= self.config.max_spans_per_trace;
+            if !dropped {
+                spans.push(span);
+            }
+            drop(buffer);
+
+            if evicted {
+                u64_counter!(
+                    "apollo.router.telemetry.tail_sampling.evicted_traces",
+                    "Traces dropped by tail sampling because too many traces were waiting for a decision",
+                    1
+                );
+            }
+            if dropped {
+                u64_counter!(
+                    "apollo.router.telemetry.tail_sampling.dropped_spans",
+                    "Spans dropped by tail sampling because their trace had too many spans",
+                    1
+                );
+            }
+            return;
+        }
+
+        let mut spans = buffer.pending.pop(&trace_id).unwrap_or_default();
+        spans.push(span);
+        let keep = self.keep(trace_id, &spans);
+        buffer.decided.put(trace_id, keep);
+        drop(buffer);
+
+        u64_counter!(
+            "apollo.router.telemetry.tail_sampling.traces",
+            "Traces evaluated by tail sampling",
+            1,
+            sampled = keep
+        );
+        if keep {
+            for span in spans {
+                self.delegate.on_end(span);
+            }
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.delegate.force_flush()
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        self.delegate.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::time::SystemTime;
+
+    use opentelemetry::sdk::trace::EvictedHashMap;
+    use opentelemetry::sdk::trace::EvictedQueue;
+    use opentelemetry::sdk::Resource;
+    use opentelemetry::trace::SpanContext;
+    use opentelemetry::trace::SpanKind;
+    use opentelemetry::trace::Status;
+    use opentelemetry::trace::TraceFlags;
+    use opentelemetry::trace::TraceState;
+    use opentelemetry::InstrumentationLibrary;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<SpanData>>>);
+
+    impl Recorder {
+        fn span_ids(&self) -> Vec<u64> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|span| u64::from_be_bytes(span.span_context.span_id().to_bytes()))
+                .collect()
+        }
+    }
+
+    impl SpanProcessor for Recorder {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span);
+        }
+
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&mut self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    fn span(
+        trace_id: u128,
+        span_id: u64,
+        parent_span_id: u64,
+        duration: Duration,
+        attributes: Vec<KeyValue>,
+    ) -> SpanData {
+        let start_time = SystemTime::UNIX_EPOCH;
+        let mut map = EvictedHashMap::new(16, attributes.len());
+        for attribute in attributes {
+            map.insert(attribute);
+        }
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_u128(trace_id),
+                SpanId::from_u64(span_id),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from_u64(parent_span_id),
+            span_kind: SpanKind::Internal,
+            name: "span".into(),
+            start_time,
+            end_time: start_time + duration,
+            attributes: map,
+            events: EvictedQueue::new(0),
+            links: EvictedQueue::new(0),
+            status: Status::Unset,
+            resource: Cow::Owned(Resource::empty()),
+            instrumentation_lib: InstrumentationLibrary::new(
+                "test",
+                None::<&'static str>,
+                None::<&'static str>,
+                None,
+            ),
+        }
+    }
+
+    fn router_span(trace_id: u128, duration: Duration, mut attributes: Vec<KeyValue>) -> SpanData {
+        attributes.push(KeyValue::new(ROOT, true));
+        // The router span may have a remote parent
+        span(trace_id, 1, 42, duration, attributes)
+    }
+
+    fn tail_sampler(config: serde_json::Value) -> (TailSamplingSpanProcessor<Recorder>, Recorder) {
+        let config: TailSampling = serde_json::from_value(config).unwrap();
+        let recorder = Recorder::default();
+        (
+            TailSamplingSpanProcessor::new(recorder.clone(), &config),
+            recorder,
+        )
+    }
+
+    #[test]
+    fn disabled_forwards_spans() {
+        let (processor, recorder) = tail_sampler(serde_json::json!({}));
+        processor.on_end(span(1, 2, 1, Duration::ZERO, vec![]));
+        assert_eq!(recorder.span_ids(), vec![2]);
+    }
+
+    #[test]
+    fn trace_is_kept_when_a_rule_matches() {
+        let (processor, recorder) = tail_sampler(serde_json::json!({
+            "enabled": true,
+            "rules": [{ "status_code": [500] }, { "subgraph_errors": true }]
+        }));
+
+        // Error status code
+        processor.on_end(span(1, 2, 1, Duration::ZERO, vec![]));
+        assert!(recorder.span_ids().is_empty());
+        processor.on_end(router_span(
+            1,
+            Duration::ZERO,
+            vec![KeyValue::new(STATUS_CODE, 500)],
+        ));
+        assert_eq!(recorder.span_ids(), vec![2, 1]);
+
+        // Failed subgraph request
+        processor.on_end(span(
+            2,
+            3,
+            1,
+            Duration::ZERO,
+            vec![KeyValue::new(SUBGRAPH_ERROR, true)],
+        ));
+        processor.on_end(router_span(
+            2,
+            Duration::ZERO,
+            vec![KeyValue::new(STATUS_CODE, 200)],
+        ));
+        assert_eq!(recorder.span_ids(), vec![2, 1, 3, 1]);
+    }
+
+    #[test]
+    fn trace_is_dropped_when_no_rule_matches() {
+        let (processor, recorder) = tail_sampler(serde_json::json!({
+            "enabled": true,
+            "rules": [
+                { "status_code": [500] },
+                { "graphql_errors": true },
+                { "duration": "1s" },
+                { "operation_name": ["Slow"] }
+            ]
+        }));
+
+        processor.on_end(span(1, 2, 1, Duration::ZERO, vec![]));
+        processor.on_end(router_span(
+            1,
+            Duration::from_millis(10),
+            vec![
+                KeyValue::new(STATUS_CODE, 200),
+                KeyValue::new(GRAPHQL_ERRORS, false),
+                KeyValue::new(OPERATION_NAME_KEY, "Fast"),
+            ],
+        ));
+        // Spans ending after the decision follow it
+        processor.on_end(span(1, 3, 1, Duration::ZERO, vec![]));
+        assert!(recorder.span_ids().is_empty());
+
+        processor.on_end(router_span(2, Duration::from_secs(2), vec![]));
+        processor.on_end(span(2, 3, 1, Duration::ZERO, vec![]));
+        assert_eq!(recorder.span_ids(), vec![1, 3]);
+    }
+
+    #[test]
+    fn fallback_sampler_applies_to_unmatched_traces() {
+        let (processor, recorder) = tail_sampler(serde_json::json!({
+            "enabled": true,
+            "fallback_sampler": "always_on"
+        }));
+        processor.on_end(router_span(1, Duration::ZERO, vec![]));
+        assert_eq!(recorder.span_ids(), vec![1]);
+
+        let (processor, recorder) = tail_sampler(serde_json::json!({
+            "enabled": true,
+            "fallback_sampler": 0.5
+        }));
+        processor.on_end(router_span(0, Duration::ZERO, vec![]));
+        processor.on_end(router_span(u64::MAX as u128, Duration::ZERO, vec![]));
+        assert_eq!(recorder.span_ids(), vec![1]);
+    }
+
+    #[test]
+    fn buffer_is_bounded() {
+        let (processor, recorder) = tail_sampler(serde_json::json!({
+            "enabled": true,
+            "fallback_sampler": "always_on",
+            "max_traces": 1,
+            "max_spans_per_trace": 1
+        }));
+
+        processor.on_end(span(1, 2, 1, Duration::ZERO, vec![]));
+        processor.on_end(span(1, 3, 1, Duration::ZERO, vec![]));
+        // Evicts the first trace
+        processor.on_end(span(2, 4, 1, Duration::ZERO, vec![]));
+        processor.on_end(router_span(1, Duration::ZERO, vec![]));
+        processor.on_end(router_span(2, Duration::ZERO, vec![]));
+        assert_eq!(recorder.span_ids(), vec![1, 4, 1]);
+    }
+}
@@ -68,7 +68,8 @@ impl TracingConfigurator for Config {
             BatchSpanProcessor::builder(exporter, opentelemetry::runtime::Tokio)
                 .with_batch_config(self.batch_processor.clone().into())
                 .build()
-                .filtered(),
+                .filtered()
+                .tail_sampled(&trace.experimental_tail_sampling),
         ))
     }
 }
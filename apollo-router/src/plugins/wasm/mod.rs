@@ -0,0 +1,298 @@
+//! WebAssembly plugin runtime
+//!
+//! Runs `.wasm` modules at the router, supergraph, execution and subgraph stages. Modules
+//! exchange the same payloads as an external coprocessor, but run inside the router with
+//! bounded CPU time and memory.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use notify::event::DataChange;
+use notify::event::MetadataKind;
+use notify::event::ModifyKind;
+use notify::Config;
+use notify::EventKind;
+use notify::PollWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tower::BoxError;
+
+use self::runtime::WasmModule;
+use self::runtime::WasmService;
+use crate::plugin::Plugin;
+use crate::plugin::PluginInit;
+use crate::plugins::coprocessor::ExecutionStage;
+use crate::plugins::coprocessor::RouterStage;
+use crate::plugins::coprocessor::SubgraphStages;
+use crate::plugins::coprocessor::SupergraphStage;
+use crate::register_plugin;
+use crate::services::execution;
+use crate::services::external::PipelineStep;
+use crate::services::router;
+use crate::services::subgraph;
+use crate::services::supergraph;
+
+mod runtime;
+#[cfg(test)]
+mod tests;
+
+/// Payloads are handed to the module directly, this URI is never resolved
+const WASM_URI: &str = "wasm://module";
+const DEFAULT_FUEL: u64 = 100_000_000;
+const DEFAULT_MAX_MEMORY: usize = 16 * 1024 * 1024;
+
+/// Configuration for the WebAssembly plugin runtime
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct Conf {
+    /// The modules to load. For requests, modules run in the order they are listed
+    modules: Vec<ModuleConf>,
+}
+
+/// Configuration for a WebAssembly module
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ModuleConf {
+    /// Path to the `.wasm` file
+    path: PathBuf,
+    /// The fuel available to each stage call. Executing an instruction consumes fuel, and
+    /// the call fails once it runs out
+    #[serde(default = "default_fuel")]
+    fuel: u64,
+    /// The maximum size of the module's memory, in bytes
+    #[serde(default = "default_max_memory")]
+    max_memory: usize,
+    /// The router stage request/response configuration
+    #[serde(default)]
+    router: RouterStage,
+    /// The supergraph stage request/response configuration
+    #[serde(default)]
+    supergraph: SupergraphStage,
+    /// The execution stage request/response configuration
+    #[serde(default)]
+    execution: ExecutionStage,
+    /// The subgraph stage request/response configuration
+    #[serde(default)]
+    subgraph: SubgraphStages,
+}
+
+fn default_fuel() -> u64 {
+    DEFAULT_FUEL
+}
+
+fn default_max_memory() -> usize {
+    DEFAULT_MAX_MEMORY
+}
+
+impl ModuleConf {
+    /// The stages this module is called for
+    fn steps(&self) -> Vec<PipelineStep> {
+        [
+            (
+                self.router.request != Default::default(),
+                PipelineStep::RouterRequest,
+            ),
+            (
+                self.router.response != Default::default(),
+                PipelineStep::RouterResponse,
+            ),
+            (
+                self.supergraph.request != Default::default(),
+                PipelineStep::SupergraphRequest,
+            ),
+            (
+                self.supergraph.response != Default::default(),
+                PipelineStep::SupergraphResponse,
+            ),
+            (
+                self.execution.request != Default::default(),
+                PipelineStep::ExecutionRequest,
+            ),
+            (
+                self.execution.response != Default::default(),
+                PipelineStep::ExecutionResponse,
+            ),
+            (
+                self.subgraph.all.request != Default::default(),
+                PipelineStep::SubgraphRequest,
+            ),
+            (
+                self.subgraph.all.response != Default::default(),
+                PipelineStep::SubgraphResponse,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, step)| enabled.then_some(step))
+        .collect()
+    }
+
+    /// Reads and compiles the module file
+    fn load(&self) -> Result<WasmModule, BoxError> {
+        let wasm = std::fs::read(&self.path).map_err(|e| {
+            format!(
+                "could not read WebAssembly module {}: {e}",
+                self.path.display()
+            )
+        })?;
+        WasmModule::new(&self.path, &wasm, self.fuel, self.max_memory, &self.steps())
+    }
+}
+
+/// Plugin running WebAssembly modules
+struct Wasm {
+    modules: Vec<(ModuleConf, WasmService)>,
+    sdl: Arc<String>,
+    park_flag: Arc<AtomicBool>,
+    watcher_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+#[async_trait::async_trait]
+impl Plugin for Wasm {
+    type Config = Conf;
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        let mut modules = Vec::with_capacity(init.config.modules.len());
+        let mut watched = Vec::with_capacity(init.config.modules.len());
+        for conf in init.config.modules {
+            let module = Arc::new(ArcSwap::from_pointee(conf.load()?));
+            watched.push((conf.clone(), module.clone()));
+            modules.push((conf, WasmService::new(module)));
+        }
+
+        let park_flag = Arc::new(AtomicBool::new(false));
+        let watching_flag = park_flag.clone();
+        let watcher_handle = std::thread::spawn(move || watch(watched, watching_flag));
+
+        Ok(Self {
+            modules,
+            sdl: init.supergraph_sdl,
+            park_flag,
+            watcher_handle: Some(watcher_handle),
+        })
+    }
+
+    fn router_service(&self, service: router::BoxService) -> router::BoxService {
+        // Wrapping in reverse order makes the first module the outermost layer
+        self.modules
+            .iter()
+            .rev()
+            .fold(service, |service, (conf, wasm)| {
+                conf.router
+                    .as_service(wasm.clone(), service, WASM_URI.into(), self.sdl.clone())
+            })
+    }
+
+    fn supergraph_service(&self, service: supergraph::BoxService) -> supergraph::BoxService {
+        self.modules
+            .iter()
+            .rev()
+            .fold(service, |service, (conf, wasm)| {
+                conf.supergraph
+                    .as_service(wasm.clone(), service, WASM_URI.into(), self.sdl.clone())
+            })
+    }
+
+    fn execution_service(&self, service: execution::BoxService) -> execution::BoxService {
+        self.modules
+            .iter()
+            .rev()
+            .fold(service, |service, (conf, wasm)| {
+                conf.execution
+                    .as_service(wasm.clone(), service, WASM_URI.into(), self.sdl.clone())
+            })
+    }
+
+    fn subgraph_service(&self, name: &str, service: subgraph::BoxService) -> subgraph::BoxService {
+        self.modules
+            .iter()
+            .rev()
+            .fold(service, |service, (conf, wasm)| {
+                conf.subgraph.all.as_service(
+                    wasm.clone(),
+                    service,
+                    WASM_URI.into(),
+                    name.to_string(),
+                )
+            })
+    }
+}
+
+impl Drop for Wasm {
+    fn drop(&mut self) {
+        if let Some(wh) = self.watcher_handle.take() {
+            self.park_flag.store(true, Ordering::Release);
+            wh.thread().unpark();
+            wh.join().expect("wasm file watcher thread terminating");
+        }
+    }
+}
+
+/// Watches the module files and swaps a module when its file changes, until the plugin is
+/// dropped. A module that can't be loaded keeps its previous version.
+fn watch(modules: Vec<(ModuleConf, Arc<ArcSwap<WasmModule>>)>, watching_flag: Arc<AtomicBool>) {
+    let paths: Vec<PathBuf> = modules.iter().map(|(conf, _)| conf.path.clone()).collect();
+    let config = Config::default()
+        .with_poll_interval(Duration::from_secs(3))
+        .with_compare_contents(true);
+    let mut watcher = PollWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| match res {
+            Ok(event) => {
+                if !matches!(
+                    event.kind,
+                    EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime))
+                        | EventKind::Modify(ModifyKind::Data(DataChange::Any))
+                        | EventKind::Create(_)
+                ) {
+                    return;
+                }
+                for (conf, module) in &modules {
+                    if !event.paths.iter().any(|path| same_file(path, &conf.path)) {
+                        continue;
+                    }
+                    match conf.load() {
+                        Ok(new_module) => {
+                            tracing::info!("updating WebAssembly module {}", conf.path.display());
+                            module.store(Arc::new(new_module))
+                        }
+                        Err(e) => {
+                            tracing::warn!("could not update WebAssembly module: {e}");
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::error!("wasm watching event error: {:?}", e),
+        },
+        config,
+    )
+    .expect("could not create wasm file watcher");
+    for path in &paths {
+        if let Err(e) = watcher.watch(path, RecursiveMode::NonRecursive) {
+            tracing::error!("could not watch {}: {e}", path.display());
+        }
+    }
+    // Park the thread until the plugin is dropped (see Drop impl)
+    // We may actually unpark() before this code executes or exit from park() spuriously.
+    // Use the watching_flag to control a loop which waits from the flag to be updated
+    // from Drop.
+    while !watching_flag.load(Ordering::Acquire) {
+        std::thread::park();
+    }
+}
+
+/// The watcher may report paths in a different form than the configured ones
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (a.canonicalize(), b.canonicalize()),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+register_plugin!("apollo", "wasm", Wasm);
@@ -0,0 +1,203 @@
+//! Host side of the WebAssembly plugin ABI.
+//!
+//! A guest module must export:
+//! - `memory`: its linear memory,
+//! - `alloc(len: i32) -> i32`: reserves `len` bytes and returns their offset,
+//! - one function per stage it handles (`router_request`, `supergraph_response`, ...), with
+//!   the signature `(ptr: i32, len: i32) -> i64`.
+//!
+//! Stage functions receive the same JSON payload as an external coprocessor and return the
+//! offset of their JSON reply in the upper 32 bits of the result, and its length in the lower
+//! 32 bits. Every call runs in a fresh instance, so no state is shared between requests.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::task::Poll;
+
+use arc_swap::ArcSwap;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tower::BoxError;
+use tower::Service;
+use wasmi::Config;
+use wasmi::Engine;
+use wasmi::Instance;
+use wasmi::Linker;
+use wasmi::Module;
+use wasmi::Store;
+use wasmi::StoreLimits;
+use wasmi::StoreLimitsBuilder;
+
+use crate::services::external::PipelineStep;
+use crate::services::router::body::get_body_bytes;
+use crate::services::router::body::RouterBody;
+
+/// Name of the function a module exports to handle a stage.
+pub(super) fn export_name(step: &PipelineStep) -> &'static str {
+    match step {
+        PipelineStep::RouterRequest => "router_request",
+        PipelineStep::RouterResponse => "router_response",
+        PipelineStep::SupergraphRequest => "supergraph_request",
+        PipelineStep::SupergraphResponse => "supergraph_response",
+        PipelineStep::ExecutionRequest => "execution_request",
+        PipelineStep::ExecutionResponse => "execution_response",
+        PipelineStep::SubgraphRequest => "subgraph_request",
+        PipelineStep::SubgraphResponse => "subgraph_response",
+    }
+}
+
+/// A compiled WebAssembly module and the limits applied to each of its calls.
+pub(crate) struct WasmModule {
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+    linker: Linker<StoreLimits>,
+    fuel: u64,
+    max_memory: usize,
+}
+
+impl WasmModule {
+    /// Compiles a module and checks that it exports the functions for the given stages.
+    pub(crate) fn new(
+        path: &Path,
+        wasm: &[u8],
+        fuel: u64,
+        max_memory: usize,
+        steps: &[PipelineStep],
+    ) -> Result<Self, BoxError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|e| {
+            format!(
+                "could not compile WebAssembly module {}: {e}",
+                path.display()
+            )
+        })?;
+
+        let wasm_module = Self {
+            path: path.to_path_buf(),
+            linker: Linker::new(&engine),
+            engine,
+            module,
+            fuel,
+            max_memory,
+        };
+
+        // Instantiating once checks that the module has no unresolved imports, and that its
+        // exports have the expected signatures.
+        let (mut store, instance) = wasm_module.instantiate()?;
+        instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| wasm_module.error("does not export `memory`"))?;
+        instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| wasm_module.error(format!("has an invalid `alloc` export: {e}")))?;
+        for step in steps {
+            let name = export_name(step);
+            instance
+                .get_typed_func::<(i32, i32), i64>(&mut store, name)
+                .map_err(|e| wasm_module.error(format!("has an invalid `{name}` export: {e}")))?;
+        }
+
+        Ok(wasm_module)
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> BoxError {
+        format!("WebAssembly module {} {message}", self.path.display()).into()
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance), BoxError> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.add_fuel(self.fuel).map_err(|e| e.to_string())?;
+
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| self.error(format!("could not be instantiated: {e}")))?;
+        Ok((store, instance))
+    }
+
+    /// Runs the stage function exported as `name` on a JSON payload, and returns its reply.
+    pub(crate) fn call(&self, name: &str, payload: &[u8]) -> Result<Vec<u8>, BoxError> {
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| self.error("does not export `memory`"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+        let stage = instance.get_typed_func::<(i32, i32), i64>(&store, name)?;
+
+        let len = i32::try_from(payload.len())?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|e| self.error(format!("failed in `alloc`: {e}")))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, payload)
+            .map_err(|e| self.error(format!("returned an invalid allocation: {e}")))?;
+
+        let result = stage
+            .call(&mut store, (ptr, len))
+            .map_err(|e| self.error(format!("failed in `{name}`: {e}")))?
+            as u64;
+        let (ptr, len) = ((result >> 32) as usize, (result & 0xffff_ffff) as usize);
+        // The reply is checked against the guest memory before anything is allocated on the host
+        let output = ptr
+            .checked_add(len)
+            .and_then(|end| memory.data(&store).get(ptr..end))
+            .ok_or_else(|| {
+                self.error(format!(
+                    "returned an invalid reply from `{name}`: {len} bytes at {ptr} are out of bounds"
+                ))
+            })?;
+        Ok(output.to_vec())
+    }
+}
+
+#[derive(Deserialize)]
+struct Stage {
+    stage: PipelineStep,
+}
+
+/// Exposes a module as the HTTP service the coprocessor stages send their payloads to.
+///
+/// The module is swapped when its file changes, calls in flight keep the previous version.
+#[derive(Clone)]
+pub(crate) struct WasmService {
+    module: Arc<ArcSwap<WasmModule>>,
+}
+
+impl WasmService {
+    pub(crate) fn new(module: Arc<ArcSwap<WasmModule>>) -> Self {
+        Self { module }
+    }
+}
+
+impl Service<http::Request<RouterBody>> for WasmService {
+    type Response = http::Response<RouterBody>;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<RouterBody>) -> Self::Future {
+        let module = self.module.load_full();
+        Box::pin(async move {
+            let payload = get_body_bytes(request.into_body()).await?;
+            let Stage { stage } = serde_json::from_slice(&payload)?;
+            // Guest code may run for as long as its fuel allows, keep it off the async workers
+            let output =
+                tokio::task::spawn_blocking(move || module.call(export_name(&stage), &payload))
+                    .await??;
+            Ok(http::Response::new(output.into()))
+        })
+    }
+}
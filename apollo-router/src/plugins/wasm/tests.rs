@@ -0,0 +1,243 @@
+use serde_json::json;
+use serde_json::Value;
+use tower::util::BoxService;
+use tower::BoxError;
+use tower::Service;
+use tower::ServiceExt;
+
+use crate::plugin::test::MockSupergraphService;
+use crate::plugin::DynPlugin;
+use crate::services::SupergraphRequest;
+use crate::services::SupergraphResponse;
+use crate::Context;
+
+/// Bump allocator growing the memory as needed, and a supergraph request stage returning
+/// its payload unchanged
+const ECHO: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 0))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (local.get $ptr) (local.get $len)))
+    (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow (i32.add (i32.shr_u (local.get $len) (i32.const 16)) (i32.const 1))))))
+    (local.get $ptr))
+  (func (export "supergraph_request") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len)))))
+"#;
+
+async fn wasm_plugin(wat: &str, mut module_conf: Value) -> Result<Box<dyn DynPlugin>, BoxError> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("plugin.wasm");
+    std::fs::write(&path, wat::parse_str(wat)?)?;
+    module_conf["path"] = path.to_string_lossy().into();
+
+    crate::plugin::plugins()
+        .find(|factory| factory.name == "apollo.wasm")
+        .expect("Plugin not found")
+        .create_instance_without_schema(&json!({ "modules": [module_conf] }))
+        .await
+}
+
+fn supergraph_request_conf() -> Value {
+    json!({ "supergraph": { "request": { "body": true, "context": true } } })
+}
+
+#[tokio::test]
+async fn supergraph_request_passes_through_module() -> Result<(), BoxError> {
+    let mut mock_service = MockSupergraphService::new();
+    mock_service
+        .expect_call()
+        .times(1)
+        .returning(move |req: SupergraphRequest| {
+            assert_eq!(
+                req.supergraph_request.body().query.as_deref(),
+                Some("{ me }")
+            );
+            assert_eq!(req.context.get::<_, i64>("test").unwrap(), Some(5));
+            Ok(SupergraphResponse::fake_builder()
+                .context(req.context)
+                .build()
+                .unwrap())
+        });
+
+    let plugin = wasm_plugin(ECHO, supergraph_request_conf()).await?;
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let context = Context::new();
+    context.insert("test", 5i64).unwrap();
+    let request = SupergraphRequest::fake_builder()
+        .query("{ me }")
+        .context(context)
+        .build()?;
+
+    let response = service.ready().await?.call(request).await?;
+    assert_eq!(response.response.status(), 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn supergraph_request_break() -> Result<(), BoxError> {
+    let reply = r#"{"version":1,"stage":"SupergraphRequest","control":{"break":403},"body":{"errors":[{"message":"forbidden"}]}}"#;
+    let wat = format!(
+        r#"
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 32768) "{}")
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "supergraph_request") (param i32 i32) (result i64)
+    (i64.or (i64.shl (i64.const 32768) (i64.const 32)) (i64.const {}))))
+"#,
+        reply.replace('"', "\\\""),
+        reply.len()
+    );
+
+    let mut mock_service = MockSupergraphService::new();
+    mock_service.expect_call().never();
+
+    let plugin = wasm_plugin(&wat, supergraph_request_conf()).await?;
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let request = SupergraphRequest::fake_builder().query("{ me }").build()?;
+
+    let mut response = service.ready().await?.call(request).await?;
+    assert_eq!(response.response.status(), 403);
+    let body = response.next_response().await.unwrap();
+    assert_eq!(body.errors[0].message, "forbidden");
+    Ok(())
+}
+
+#[tokio::test]
+async fn module_running_out_of_fuel_fails() -> Result<(), BoxError> {
+    let wat = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "supergraph_request") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (unreachable)))
+"#;
+    let mut conf = supergraph_request_conf();
+    conf["fuel"] = 10_000.into();
+
+    let mut mock_service = MockSupergraphService::new();
+    mock_service.expect_call().never();
+
+    let plugin = wasm_plugin(wat, conf).await?;
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let request = SupergraphRequest::fake_builder().query("{ me }").build()?;
+
+    let error = service.ready().await?.call(request).await.err().unwrap();
+    assert!(
+        error.to_string().contains("failed in `supergraph_request`"),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn module_growing_memory_over_limit_fails() -> Result<(), BoxError> {
+    let mut conf = supergraph_request_conf();
+    conf["max_memory"] = 65536.into();
+    // Copying a payload larger than a page makes the echo module grow its memory
+    let payload = "x".repeat(70_000);
+
+    let mut mock_service = MockSupergraphService::new();
+    mock_service.expect_call().never();
+
+    let plugin = wasm_plugin(ECHO, conf).await?;
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let request = SupergraphRequest::fake_builder().query(payload).build()?;
+
+    let error = service.ready().await?.call(request).await.err().unwrap();
+    assert!(error.to_string().contains("failed in `alloc`"), "{error}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn module_replying_out_of_bounds_fails() -> Result<(), BoxError> {
+    // Replies with 4 GiB - 1 bytes, more than the module memory holds
+    let wat = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "supergraph_request") (param i32 i32) (result i64)
+    (i64.const 0xffffffff)))
+"#;
+
+    let mut mock_service = MockSupergraphService::new();
+    mock_service.expect_call().never();
+
+    let plugin = wasm_plugin(wat, supergraph_request_conf()).await?;
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let request = SupergraphRequest::fake_builder().query("{ me }").build()?;
+
+    let error = service.ready().await?.call(request).await.err().unwrap();
+    assert!(error.to_string().contains("out of bounds"), "{error}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn module_is_reloaded_when_its_file_changes() -> Result<(), BoxError> {
+    let failing = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "supergraph_request") (param i32 i32) (result i64)
+    (unreachable)))
+"#;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("plugin.wasm");
+    std::fs::write(&path, wat::parse_str(ECHO)?)?;
+    let mut conf = supergraph_request_conf();
+    conf["path"] = path.to_string_lossy().into();
+    let plugin = crate::plugin::plugins()
+        .find(|factory| factory.name == "apollo.wasm")
+        .expect("Plugin not found")
+        .create_instance_without_schema(&json!({ "modules": [conf] }))
+        .await?;
+
+    let mut mock_service = MockSupergraphService::new();
+    mock_service
+        .expect_call()
+        .returning(|req: SupergraphRequest| {
+            Ok(SupergraphResponse::fake_builder()
+                .context(req.context)
+                .build()
+                .unwrap())
+        });
+    let mut service = plugin.supergraph_service(BoxService::new(mock_service));
+    let request = SupergraphRequest::fake_builder().query("{ me }").build()?;
+    service.ready().await?.call(request).await?;
+
+    std::fs::write(&path, wat::parse_str(failing)?)?;
+    // The file is polled every 3 seconds
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let request = SupergraphRequest::fake_builder().query("{ me }").build()?;
+        if let Err(error) = service.ready().await?.call(request).await {
+            assert!(
+                error.to_string().contains("failed in `supergraph_request`"),
+                "{error}"
+            );
+            return Ok(());
+        }
+    }
+    panic!("the module was not reloaded");
+}
+
+#[tokio::test]
+async fn module_missing_stage_export_is_rejected() {
+    let conf = json!({ "router": { "request": { "headers": true } } });
+
+    let error = wasm_plugin(ECHO, conf).await.err().unwrap();
+    assert!(
+        error
+            .to_string()
+            .contains("invalid `router_request` export"),
+        "{error}"
+    );
+}
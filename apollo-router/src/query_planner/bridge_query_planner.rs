@@ -30,6 +30,7 @@ use super::QueryKey;
 use crate::apollo_studio_interop::generate_usage_reporting;
 use crate::apollo_studio_interop::UsageReportingComparisonResult;
 use crate::configuration::ApolloMetricsGenerationMode;
+use crate::configuration::IntrospectionMode;
 use crate::configuration::QueryPlannerMode;
 use crate::error::PlanErrors;
 use crate::error::QueryPlannerError;
@@ -38,6 +39,9 @@ use crate::error::ServiceBuildError;
 use crate::error::ValidationErrors;
 use crate::graphql;
 use crate::introspection::Introspection;
+use crate::introspection::IntrospectionExecutor;
+use crate::introspection::IntrospectionResponse;
+use crate::introspection::IntrospectionSplit;
 use crate::json_ext::Object;
 use crate::json_ext::Path;
 use crate::metrics::meter_provider;
@@ -178,6 +182,7 @@ impl PlannerMode {
         schema: &Schema,
         configuration: &Configuration,
     ) -> Result<Arc<QueryPlanner>, ServiceBuildError> {
+        let query_planning = &configuration.supergraph.query_planning;
         let config = apollo_federation::query_plan::query_planner::QueryPlannerConfig {
             reuse_query_fragments: configuration
                 .supergraph
@@ -189,7 +194,11 @@ impl PlannerMode {
                 apollo_federation::query_plan::query_planner::QueryPlanIncrementalDeliveryConfig {
                     enable_defer: configuration.supergraph.defer_support,
                 },
-            debug: Default::default(),
+            debug: apollo_federation::query_plan::query_planner::QueryPlannerDebugConfig {
+                max_evaluated_cost: query_planning.experimental_max_evaluated_cost,
+                planning_timeout: query_planning.experimental_planning_timeout,
+                ..Default::default()
+            },
         };
         let result = QueryPlanner::new(schema.federation_supergraph(), config);
 
@@ -290,9 +299,18 @@ impl PlannerMode {
                     .map(|n| Name::new(n).map_err(FederationError::from))
                     .transpose()
                     .and_then(|operation| rust.build_query_plan(&doc.executable, operation))
-                    .map_err(|e| QueryPlannerError::FederationError(e.to_string()));
+                    .map_err(QueryPlannerError::from);
 
                 metric_query_planning_plan_duration(RUST_QP_MODE, start);
+                match &result {
+                    Err(QueryPlannerError::Timeout(_)) => {
+                        metric_query_planning_limit_exceeded("planning_timeout")
+                    }
+                    Err(QueryPlannerError::MaxEvaluatedCostExceeded(_)) => {
+                        metric_query_planning_limit_exceeded("max_evaluated_cost")
+                    }
+                    _ => {}
+                }
 
                 let plan = result?;
 
@@ -315,6 +333,7 @@ impl PlannerMode {
                         query_plan: QueryPlan {
                             node: root_node.map(Arc::new),
                         },
+                        evaluated_plan_count: Some(plan.statistics.evaluated_plan_count.get()),
                     },
                 })
             }
@@ -396,14 +415,15 @@ impl BridgeQueryPlanner {
         let subgraph_schemas = Arc::new(planner.subgraphs().await?);
 
         let introspection = if configuration.supergraph.introspection {
-            Some(Arc::new(
-                Introspection::new(
+            let executor = match configuration.experimental_introspection_mode {
+                IntrospectionMode::New => IntrospectionExecutor::Rust(schema.clone()),
+                IntrospectionMode::Legacy => IntrospectionExecutor::Js(
                     planner
                         .js_for_api_schema_and_introspection_and_operation_signature()
                         .clone(),
-                )
-                .await?,
-            ))
+                ),
+            };
+            Some(Arc::new(Introspection::new(executor).await?))
         } else {
             None
         };
@@ -480,15 +500,22 @@ impl BridgeQueryPlanner {
             subselections,
             defer_stats,
             is_original: true,
+            introspection_data: None,
+            introspection_query: None,
             schema_aware_hash,
         })
     }
 
-    async fn introspection(&self, query: String) -> Result<QueryPlannerContent, QueryPlannerError> {
+    async fn introspection(
+        &self,
+        query: String,
+        doc: &ParsedDocument,
+        operation_name: Option<&str>,
+    ) -> Result<QueryPlannerContent, QueryPlannerError> {
         match self.introspection.as_ref() {
             Some(introspection) => {
                 let response = introspection
-                    .execute(query)
+                    .execute(query, &doc.executable, operation_name)
                     .await
                     .map_err(QueryPlannerError::Introspection)?;
 
@@ -554,6 +581,7 @@ impl BridgeQueryPlanner {
                     QueryPlanResult {
                         query_plan: QueryPlan { node: Some(node) },
                         formatted_query_plan,
+                        evaluated_plan_count,
                     },
                 mut usage_reporting,
             } => {
@@ -671,6 +699,7 @@ impl BridgeQueryPlanner {
                         query: Arc::new(selections),
                         query_metrics,
                         estimated_size: Default::default(),
+                        evaluated_plan_count,
                     }),
                 })
             }
@@ -877,6 +906,49 @@ impl BridgeQueryPlanner {
             selections.unauthorized.paths = unauthorized_paths;
         }
 
+        // The native implementation executes the introspection fields of the query here, and
+        // only plans the other fields
+        let mut filtered_query = key.filtered_query.clone();
+        if let Some(introspection) = self.introspection.as_ref() {
+            if let Some(split) = introspection
+                .split(&doc.executable, key.operation_name.as_deref())
+                .await
+                .map_err(QueryPlannerError::Introspection)?
+            {
+                let IntrospectionSplit { response, document } = split;
+                let document = match (response, document) {
+                    (IntrospectionResponse::Executed(response), Some(document))
+                        if response.errors.is_empty() =>
+                    {
+                        if let Some(Value::Object(data)) = response.data {
+                            selections.introspection_data = Some(data);
+                        }
+                        document
+                    }
+                    (IntrospectionResponse::Executed(response), _) => {
+                        return Ok(QueryPlannerContent::Response {
+                            response: Box::new(response),
+                        })
+                    }
+                    (IntrospectionResponse::Deferred(introspection), Some(document)) => {
+                        selections.introspection_query = Some(introspection);
+                        document
+                    }
+                    (IntrospectionResponse::Deferred(introspection), None) => {
+                        return Ok(QueryPlannerContent::Introspection {
+                            introspection: Box::new(introspection),
+                        })
+                    }
+                };
+                filtered_query = document.to_string();
+                doc = Arc::new(ParsedDocumentInner {
+                    ast: Query::parse_ast(&filtered_query, &self.configuration)?,
+                    executable: Arc::new(document),
+                    hash: doc.hash.clone(),
+                });
+            }
+        }
+
         if selections.contains_introspection() {
             // It can happen if you have a statically skipped query like { get @skip(if: true) { id name }} because it will be statically filtered with {}
             if selections
@@ -911,7 +983,9 @@ impl BridgeQueryPlanner {
                     response: Box::new(graphql::Response::builder().data(data).build()),
                 });
             } else {
-                return self.introspection(key.original_query).await;
+                return self
+                    .introspection(key.original_query, &doc, key.operation_name.as_deref())
+                    .await;
             }
         }
 
@@ -930,7 +1004,7 @@ impl BridgeQueryPlanner {
 
         self.plan(
             key.original_query,
-            key.filtered_query,
+            filtered_query,
             key.operation_name,
             key.metadata,
             selections,
@@ -949,6 +1023,9 @@ impl BridgeQueryPlanner {
 pub struct QueryPlanResult {
     pub(super) formatted_query_plan: Option<Arc<String>>,
     pub(super) query_plan: QueryPlan,
+    /// Not reported by the JavaScript query planner
+    #[serde(default)]
+    pub(super) evaluated_plan_count: Option<usize>,
 }
 
 impl QueryPlanResult {
@@ -1001,6 +1078,15 @@ pub(crate) fn metric_query_planning_plan_duration(planner: &'static str, start:
     );
 }
 
+fn metric_query_planning_limit_exceeded(limit: &'static str) {
+    u64_counter!(
+        "apollo.router.query_planning.limit_exceeded",
+        "Number of operations whose planning exceeded a configured limit",
+        1,
+        "limit" = limit
+    );
+}
+
 pub(crate) fn metric_rust_qp_init(init_error_kind: Option<&'static str>) {
     if let Some(init_error_kind) = init_error_kind {
         u64_counter!(
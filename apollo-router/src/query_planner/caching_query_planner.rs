@@ -3,6 +3,7 @@ use std::hash::Hash;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::task;
+use std::time::Instant;
 
 use apollo_compiler::validation::Valid;
 use futures::future::BoxFuture;
@@ -28,14 +29,18 @@ use crate::cache::estimate_size;
 use crate::cache::storage::InMemoryCache;
 use crate::cache::storage::ValueType;
 use crate::cache::DeduplicatingCache;
+use crate::configuration::IntrospectionMode;
 use crate::error::CacheResolverError;
 use crate::error::QueryPlannerError;
 use crate::plugins::authorization::AuthorizationPlugin;
 use crate::plugins::authorization::CacheKeyMetadata;
 use crate::plugins::progressive_override::LABELS_TO_OVERRIDE_KEY;
+use crate::plugins::telemetry::server_timing;
 use crate::plugins::telemetry::utils::Timer;
 use crate::query_planner::fetch::SubgraphSchemas;
 use crate::query_planner::labeler::add_defer_labels;
+use crate::query_planner::warm_up_file;
+use crate::query_planner::warm_up_file::WarmUpFile;
 use crate::query_planner::BridgeQueryPlannerPool;
 use crate::query_planner::QueryPlanResult;
 use crate::services::layers::persisted_queries::PersistedQueryLayer;
@@ -66,6 +71,24 @@ pub(crate) enum ConfigMode {
     Js(Arc<QueryPlannerConfig>),
 }
 
+/// Where the query plan of a request comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum QueryPlanSource {
+    /// Found in the in-memory cache, or planned for a concurrent identical request
+    InMemoryCache,
+    Redis,
+    Planner,
+}
+
+/// Query planning information of a request, stored in the context extensions
+#[derive(Debug, Clone)]
+pub(crate) struct QueryPlanningDiagnostics {
+    pub(crate) source: QueryPlanSource,
+    /// Time spent getting the query plan, including cache lookups
+    pub(crate) duration: std::time::Duration,
+}
+
 /// A query planner wrapper that caches results.
 ///
 /// The query planner performs LRU caching.
@@ -81,7 +104,9 @@ pub(crate) struct CachingQueryPlanner<T: Clone> {
     enable_authorization_directives: bool,
     config_mode: ConfigMode,
     introspection: bool,
+    introspection_mode: IntrospectionMode,
     legacy_introspection_caching: bool,
+    warm_up_file: Option<Arc<WarmUpFile>>,
 }
 
 fn init_query_plan_from_redis(
@@ -140,6 +165,19 @@ where
                 ConfigMode::BothBestEffort(Arc::new(configuration.js_query_planner_config()))
             }
         };
+        let warm_up_file = configuration
+            .supergraph
+            .query_planning
+            .experimental_warm_up_file
+            .as_ref()
+            .map(|file| {
+                Arc::new(WarmUpFile::new(
+                    file.path.clone(),
+                    cache.in_memory_cache(),
+                    configuration.supergraph.query_planning.warmed_up_queries,
+                    file.interval,
+                ))
+            });
         Ok(Self {
             cache,
             delegate,
@@ -149,10 +187,12 @@ where
             enable_authorization_directives,
             config_mode,
             introspection: configuration.supergraph.introspection,
+            introspection_mode: configuration.experimental_introspection_mode,
             legacy_introspection_caching: configuration
                 .supergraph
                 .query_planning
                 .legacy_introspection_caching,
+            warm_up_file,
         })
     }
 
@@ -203,6 +243,7 @@ where
                                 config_mode: _,
                                 schema_id: _,
                                 introspection: _,
+                                introspection_mode: _,
                             },
                             _,
                         )| WarmUpCachingQueryKey {
@@ -213,12 +254,38 @@ where
                             plan_options: plan_options.clone(),
                             config_mode: self.config_mode.clone(),
                             introspection: self.introspection,
+                            introspection_mode: self.introspection_mode,
                         },
                     )
                     .take(count)
                     .collect::<Vec<_>>()
             }
-            None => Vec::new(),
+            // A router starting cold plans the queries written by the previous process
+            None => match &self.warm_up_file {
+                Some(warm_up_file) => match warm_up_file::read(warm_up_file.path()).await {
+                    Ok(entries) => entries
+                        .into_iter()
+                        .map(|entry| WarmUpCachingQueryKey {
+                            query: entry.query,
+                            operation: entry.operation,
+                            hash: None,
+                            metadata: entry.metadata,
+                            plan_options: entry.plan_options,
+                            config_mode: self.config_mode.clone(),
+                            introspection: self.introspection,
+                            introspection_mode: self.introspection_mode,
+                        })
+                        .collect(),
+                    Err(e) => {
+                        tracing::warn!(
+                            "could not read the query plan cache warm up file {}: {e}",
+                            warm_up_file.path().display()
+                        );
+                        Vec::new()
+                    }
+                },
+                None => Vec::new(),
+            },
         };
 
         cache_keys.shuffle(&mut thread_rng());
@@ -258,6 +325,7 @@ where
                         plan_options: PlanOptions::default(),
                         config_mode: self.config_mode.clone(),
                         introspection: self.introspection,
+                        introspection_mode: self.introspection_mode,
                     });
                 }
             }
@@ -275,11 +343,12 @@ where
             plan_options,
             config_mode: _,
             introspection: _,
+            introspection_mode: _,
         } in all_cache_keys
         {
             let context = Context::new();
             let doc = match query_analysis
-                .parse_document(&query, operation.as_deref())
+                .parse_document(&query, operation.as_deref(), None)
                 .await
             {
                 Ok(doc) => doc,
@@ -295,6 +364,7 @@ where
                 plan_options,
                 config_mode: self.config_mode.clone(),
                 introspection: self.introspection,
+                introspection_mode: self.introspection_mode,
             };
 
             if experimental_reuse_query_plans {
@@ -323,7 +393,7 @@ where
                 .await;
             if entry.is_first() {
                 let doc = match query_analysis
-                    .parse_document(&query, operation.as_deref())
+                    .parse_document(&query, operation.as_deref(), None)
                     .await
                 {
                     Ok(doc) => doc,
@@ -370,7 +440,11 @@ where
                         count += 1;
                         let e = Arc::new(error);
                         tokio::spawn(async move {
-                            entry.insert(Err(e)).await;
+                            if e.is_cacheable() {
+                                entry.insert(Err(e)).await;
+                            } else {
+                                entry.send(Err(e)).await;
+                            }
                         });
                     }
                 }
@@ -492,16 +566,29 @@ where
             plan_options,
             config_mode: self.config_mode.clone(),
             introspection: self.introspection,
+            introspection_mode: self.introspection_mode,
         };
 
         let context = request.context.clone();
+        let start = Instant::now();
+        let mut from_redis = false;
         let entry = self
             .cache
             .get(&caching_key, |v| {
-                init_query_plan_from_redis(&self.subgraph_schemas, v)
+                let result = init_query_plan_from_redis(&self.subgraph_schemas, v);
+                from_redis = result.is_ok();
+                result
             })
             .await;
-        if entry.is_first() {
+        let cache_hit = !entry.is_first();
+        let source = if !cache_hit {
+            QueryPlanSource::Planner
+        } else if from_redis {
+            QueryPlanSource::Redis
+        } else {
+            QueryPlanSource::InMemoryCache
+        };
+        let res = if entry.is_first() {
             let query_planner::CachingRequest {
                 mut query,
                 operation_name,
@@ -535,7 +622,10 @@ where
                         }) => {
                             if let Some(content) = content.clone() {
                                 let can_cache = match &content {
-                                    QueryPlannerContent::Plan { .. } => true,
+                                    // Introspection using variables is executed for each
+                                    // request, only its query is cached
+                                    QueryPlannerContent::Plan { .. }
+                                    | QueryPlannerContent::Introspection { .. } => true,
                                     _ => self.legacy_introspection_caching,
                                 };
 
@@ -562,7 +652,11 @@ where
                             let e = Arc::new(error);
                             let err = e.clone();
                             tokio::spawn(async move {
-                                entry.insert(Err(err)).await;
+                                if err.is_cacheable() {
+                                    entry.insert(Err(err)).await;
+                                } else {
+                                    entry.send(Err(err)).await;
+                                }
                             });
                             Err(CacheResolverError::RetrievalError(e))
                         }
@@ -592,7 +686,7 @@ where
 
                     Ok(QueryPlannerResponse::builder()
                         .content(content)
-                        .context(context)
+                        .context(context.clone())
                         .build())
                 }
                 Err(error) => {
@@ -618,7 +712,19 @@ where
                     Err(CacheResolverError::RetrievalError(error))
                 }
             }
-        }
+        };
+
+        let duration = start.elapsed();
+        server_timing::record(
+            &context,
+            server_timing::PLAN,
+            Some(if cache_hit { "cache hit" } else { "cache miss" }),
+            duration,
+        );
+        context
+            .extensions()
+            .with_lock(|mut lock| lock.insert(QueryPlanningDiagnostics { source, duration }));
+        res
     }
 }
 
@@ -639,6 +745,7 @@ pub(crate) struct CachingQueryKey {
     pub(crate) plan_options: PlanOptions,
     pub(crate) config_mode: ConfigMode,
     pub(crate) introspection: bool,
+    pub(crate) introspection_mode: IntrospectionMode,
 }
 
 // Update this key every time the cache key or the query plan format has to change.
@@ -660,6 +767,13 @@ impl std::fmt::Display for CachingQueryKey {
             .update(serde_json::to_vec(&self.config_mode).expect("serialization should not fail"));
         hasher.update(&*self.schema_id);
         hasher.update([self.introspection as u8]);
+        // Keeps the keys of the plans cached with the legacy introspection mode unchanged
+        if self.introspection_mode != IntrospectionMode::Legacy {
+            hasher.update(
+                serde_json::to_vec(&self.introspection_mode)
+                    .expect("serialization should not fail"),
+            );
+        }
         let metadata = hex::encode(hasher.finalize());
 
         write!(
@@ -679,6 +793,7 @@ impl Hash for CachingQueryKey {
         self.plan_options.hash(state);
         self.config_mode.hash(state);
         self.introspection.hash(state);
+        self.introspection_mode.hash(state);
     }
 }
 
@@ -691,6 +806,7 @@ pub(crate) struct WarmUpCachingQueryKey {
     pub(crate) plan_options: PlanOptions,
     pub(crate) config_mode: ConfigMode,
     pub(crate) introspection: bool,
+    pub(crate) introspection_mode: IntrospectionMode,
 }
 
 impl ValueType for Result<QueryPlannerContent, Arc<QueryPlannerError>> {
@@ -698,6 +814,9 @@ impl ValueType for Result<QueryPlannerContent, Arc<QueryPlannerError>> {
         match self {
             Ok(QueryPlannerContent::Plan { plan }) => Some(plan.estimated_size()),
             Ok(QueryPlannerContent::Response { response }) => Some(estimate_size(response)),
+            Ok(QueryPlannerContent::Introspection { introspection }) => {
+                Some(estimate_size(introspection))
+            }
             Ok(QueryPlannerContent::IntrospectionDisabled) => None,
             Err(e) => Some(estimate_size(e)),
         }
@@ -856,6 +975,7 @@ mod tests {
                     query: Arc::new(Query::empty()),
                     query_metrics: Default::default(),
                     estimated_size: Default::default(),
+                    evaluated_plan_count: None,
                 };
                 let qp_content = QueryPlannerContent::Plan {
                     plan: Arc::new(query_plan),
@@ -0,0 +1,97 @@
+//! Renders the query plans executed by the router as diagrams.
+//!
+//! The nodes are added with the `apollo_federation::query_plan::output` helpers, so that diagrams
+//! look the same whichever planner generated the plan.
+
+use apollo_federation::query_plan::output::Parent;
+use apollo_federation::query_plan::output::PlanDiagram;
+
+use super::PlanNode;
+use crate::json_ext::Path;
+use crate::json_ext::PathElement;
+
+impl PlanNode {
+    /// Converts the plan to a diagram, rooted at a `QueryPlan` node
+    pub(crate) fn diagram(&self) -> PlanDiagram {
+        let mut diagram = PlanDiagram::default();
+        let root = diagram.add_root();
+        self.add_to(&mut diagram, root);
+        diagram
+    }
+
+    fn add_to(&self, diagram: &mut PlanDiagram, parent: Parent) {
+        match self {
+            PlanNode::Sequence { nodes } => {
+                let parents = diagram.add_sequence(parent, nodes.len());
+                for (node, parent) in nodes.iter().zip(parents) {
+                    node.add_to(diagram, parent);
+                }
+            }
+            PlanNode::Parallel { nodes } => {
+                let parents = diagram.add_parallel(parent, nodes.len());
+                for (node, parent) in nodes.iter().zip(parents) {
+                    node.add_to(diagram, parent);
+                }
+            }
+            PlanNode::Fetch(node) => {
+                diagram.add_fetch(parent, &node.service_name, node.id.as_ref());
+            }
+            PlanNode::Flatten(node) => {
+                let parent = diagram.add_flatten(parent, path_elements(&node.path));
+                node.node.add_to(diagram, parent);
+            }
+            PlanNode::Defer { primary, deferred } => {
+                let (index, primary_parent) = diagram.add_defer(parent);
+                if let Some(node) = &primary.node {
+                    node.add_to(diagram, primary_parent);
+                }
+                for deferred in deferred {
+                    let parent = diagram.add_deferred(
+                        index,
+                        deferred.depends.iter().map(|dependency| &dependency.id),
+                        path_elements(&deferred.query_path),
+                        deferred.label.as_deref(),
+                    );
+                    if let Some(node) = &deferred.node {
+                        node.add_to(diagram, parent);
+                    }
+                }
+            }
+            PlanNode::Subscription { primary, rest } => {
+                let (primary_parent, rest_parent) = diagram.add_subscription(parent);
+                diagram.add_fetch(primary_parent, &primary.service_name, None::<&str>);
+                if let Some(rest) = rest {
+                    rest.add_to(diagram, rest_parent);
+                }
+            }
+            PlanNode::Condition {
+                condition,
+                if_clause,
+                else_clause,
+            } => {
+                let (if_parent, else_parent) = diagram.add_condition(
+                    parent,
+                    condition,
+                    if_clause.is_some(),
+                    else_clause.is_some(),
+                );
+                if let Some(if_clause) = if_clause {
+                    if_clause.add_to(diagram, if_parent);
+                }
+                if let Some(else_clause) = else_clause {
+                    else_clause.add_to(diagram, else_parent);
+                }
+            }
+        }
+    }
+}
+
+/// The path elements as the federation planner displays them, the `Path` display has a leading `/`
+fn path_elements(path: &Path) -> impl Iterator<Item = String> + '_ {
+    path.iter().map(|element| match element {
+        PathElement::Key(key, _) => key.clone(),
+        PathElement::Index(index) => index.to_string(),
+        PathElement::Flatten(_) => "@".to_string(),
+        PathElement::Fragment(name) => format!("... on {name}"),
+    })
+}
@@ -14,6 +14,7 @@ pub(crate) mod bridge_query_planner;
 mod bridge_query_planner_pool;
 mod caching_query_planner;
 mod convert;
+mod diagram;
 pub(crate) mod dual_query_planner;
 mod execution;
 pub(crate) mod fetch;
@@ -23,6 +24,7 @@ pub(crate) mod rewrites;
 mod selection;
 mod subgraph_context;
 pub(crate) mod subscription;
+pub(crate) mod warm_up_file;
 
 pub(crate) const FETCH_SPAN_NAME: &str = "fetch";
 pub(crate) const SUBSCRIBE_SPAN_NAME: &str = "subscribe";
@@ -49,6 +49,10 @@ pub struct QueryPlan {
     /// The estimated size in bytes of the query plan
     #[serde(default)]
     pub(crate) estimated_size: Arc<AtomicUsize>,
+
+    /// The number of plans evaluated to generate this plan, only known with the Rust query planner
+    #[serde(default)]
+    pub(crate) evaluated_plan_count: Option<usize>,
 }
 
 /// This default impl is useful for test users
@@ -72,6 +76,7 @@ impl QueryPlan {
             query: Arc::new(Query::empty()),
             query_metrics: Default::default(),
             estimated_size: Default::default(),
+            evaluated_plan_count: None,
         }
     }
 }
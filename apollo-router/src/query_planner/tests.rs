@@ -88,6 +88,7 @@ async fn mock_subgraph_service_withf_panics_should_be_reported_as_service_closed
         }
         .into(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
 
     let mut mock_products_service = plugin::test::MockSubgraphService::new();
@@ -144,6 +145,7 @@ async fn fetch_includes_operation_name() {
         query: Arc::new(Query::empty()),
         query_metrics: Default::default(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
 
     let succeeded: Arc<AtomicBool> = Default::default();
@@ -205,6 +207,7 @@ async fn fetch_makes_post_requests() {
         query: Arc::new(Query::empty()),
         query_metrics: Default::default(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
 
     let succeeded: Arc<AtomicBool> = Default::default();
@@ -334,6 +337,7 @@ async fn defer() {
             query: Arc::new(Query::empty()),
             query_metrics: Default::default(),
             estimated_size: Default::default(),
+            evaluated_plan_count: None,
         };
 
     let mut mock_x_service = plugin::test::MockSubgraphService::new();
@@ -465,6 +469,7 @@ async fn defer_if_condition() {
         formatted_query_plan: None,
         query_metrics: Default::default(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
 
     let mocked_accounts = MockSubgraph::builder()
@@ -648,6 +653,7 @@ async fn dependent_mutations() {
         query: Arc::new(Query::empty()),
         query_metrics: Default::default(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
 
     let mut mock_a_service = plugin::test::MockSubgraphService::new();
@@ -1833,6 +1839,7 @@ fn broken_plan_does_not_panic() {
         query: Arc::new(Query::empty()),
         query_metrics: Default::default(),
         estimated_size: Default::default(),
+        evaluated_plan_count: None,
     };
     let subgraph_schema = apollo_compiler::Schema::parse_and_validate(subgraph_schema, "").unwrap();
     let mut subgraph_schemas = HashMap::new();
@@ -0,0 +1,188 @@
+//! Query plan cache warm up file.
+//!
+//! The most used keys of the query plan cache are written to a local file when the router shuts
+//! down, and periodically if configured. A router starting without a previous cache plans the
+//! queries from that file before it starts serving requests.
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use lru::LruCache;
+use router_bridge::planner::PlanOptions;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tower::BoxError;
+use uuid::Uuid;
+
+use super::CachingQueryKey;
+use super::InMemoryCachePlanner;
+use crate::plugins::authorization::CacheKeyMetadata;
+
+/// A query planned when warming up the cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct WarmUpFileEntry {
+    pub(crate) query: String,
+    pub(crate) operation: Option<String>,
+    pub(crate) metadata: CacheKeyMetadata,
+    pub(crate) plan_options: PlanOptions,
+}
+
+impl From<&CachingQueryKey> for WarmUpFileEntry {
+    fn from(key: &CachingQueryKey) -> Self {
+        Self {
+            query: key.query.clone(),
+            operation: key.operation.clone(),
+            metadata: key.metadata.clone(),
+            plan_options: key.plan_options.clone(),
+        }
+    }
+}
+
+/// Writes the most used keys of the query plan cache to the warm up file
+pub(crate) struct WarmUpFile {
+    path: PathBuf,
+    cache: InMemoryCachePlanner,
+    count: Option<usize>,
+    periodic_write: Option<JoinHandle<()>>,
+}
+
+impl WarmUpFile {
+    pub(crate) fn new(
+        path: PathBuf,
+        cache: InMemoryCachePlanner,
+        count: Option<usize>,
+        interval: Option<Duration>,
+    ) -> Self {
+        let periodic_write = interval.map(|interval| {
+            let path = path.clone();
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(interval);
+                // The first tick completes immediately, when the cache is still empty
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    let entries = {
+                        let cache = cache.lock().await;
+                        most_used(&cache, count)
+                    };
+                    let path = path.clone();
+                    let result = tokio::task::spawn_blocking(move || write(&path, &entries)).await;
+                    if let Ok(Err(e)) = result {
+                        tracing::warn!("could not write the query plan cache warm up file: {e}");
+                    }
+                }
+            })
+        });
+        Self {
+            path,
+            cache,
+            count,
+            periodic_write,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for WarmUpFile {
+    fn drop(&mut self) {
+        if let Some(periodic_write) = self.periodic_write.take() {
+            periodic_write.abort();
+        }
+        // The query planner is dropped on shutdown and when the router reloads. A request
+        // still using the cache only delays the write to the next reload or shutdown
+        let entries = match self.cache.try_lock() {
+            Ok(cache) => most_used(&cache, self.count),
+            Err(_) => return,
+        };
+        let path = self.path.clone();
+        let write = move || {
+            if let Err(e) = write(&path, &entries) {
+                tracing::warn!("could not write the query plan cache warm up file: {e}");
+            }
+        };
+        // Blocking file operations must not run on a runtime worker thread
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(write);
+            }
+            Err(_) => write(),
+        }
+    }
+}
+
+/// Same selection as the warm up on reload, the first keys of the LRU cache are the most recently used
+fn most_used<V>(
+    cache: &LruCache<CachingQueryKey, V>,
+    count: Option<usize>,
+) -> Vec<WarmUpFileEntry> {
+    let count = count.unwrap_or(cache.len() / 3);
+    cache
+        .iter()
+        .take(count)
+        .map(|(key, _)| WarmUpFileEntry::from(key))
+        .collect()
+}
+
+/// Writes to a temporary file renamed once complete, a router starting at the same time never
+/// reads a partial file. The temporary file name is unique, because the planners before and
+/// after a reload can write at the same time
+fn write(path: &Path, entries: &[WarmUpFileEntry]) -> Result<(), BoxError> {
+    let content = serde_json::to_vec(entries)?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", Uuid::new_v4()));
+    if let Err(e) =
+        std::fs::write(&temporary, content).and_then(|_| std::fs::rename(&temporary, path))
+    {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Reads the queries to plan, a missing file is expected on the first start
+pub(crate) async fn read(path: &Path) -> Result<Vec<WarmUpFileEntry>, BoxError> {
+    match tokio::fs::read(path).await {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warm_up.json");
+        assert!(read(&path).await.unwrap().is_empty());
+
+        let entries = vec![
+            WarmUpFileEntry {
+                query: "query A { me { id } }".to_string(),
+                operation: Some("A".to_string()),
+                metadata: CacheKeyMetadata::default(),
+                plan_options: PlanOptions::default(),
+            },
+            WarmUpFileEntry {
+                query: "{ topProducts { upc } }".to_string(),
+                operation: None,
+                metadata: CacheKeyMetadata::default(),
+                plan_options: PlanOptions {
+                    override_conditions: vec!["label".to_string()],
+                },
+            },
+        ];
+        write(&path, &entries).unwrap();
+        assert_eq!(read(&path).await.unwrap(), entries);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(read(&path).await.is_err());
+    }
+}
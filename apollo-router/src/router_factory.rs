@@ -585,6 +585,18 @@ pub(crate) async fn create_plugins(
     let mut errors = Vec::new();
     let mut plugin_instances = Plugins::default();
 
+    // Libraries are loaded when plugins are created, so that validating a configuration doesn't
+    // load them
+    crate::plugin::dynamic::load(&configuration.experimental_plugin_libraries).map_err(
+        |error| ConfigurationError::InvalidConfiguration {
+            message: "could not load plugin library",
+            error,
+        },
+    )?;
+    if !configuration.experimental_plugin_libraries.is_empty() {
+        crate::configuration::validate_user_plugins(configuration)?;
+    }
+
     // Use function-like macros to avoid borrow conflicts of captures
     macro_rules! add_plugin {
         ($name: expr, $factory: expr, $plugin_config: expr) => {{
@@ -655,8 +667,9 @@ pub(crate) async fn create_plugins(
                 let user_span = tracing::info_span!("user_plugin", "name" = &name);
 
                 async {
+                    // User plugins may come from a plugin library
                     if let Some(factory) =
-                        plugin_registry.iter().find(|factory| factory.name == name)
+                        crate::plugin::plugins().find(|factory| factory.name == name)
                     {
                         add_plugin!(name, factory, plugin_config);
                     } else {
@@ -694,11 +707,13 @@ pub(crate) async fn create_plugins(
     add_optional_apollo_plugin!("authentication");
     add_optional_apollo_plugin!("preview_file_uploads");
     add_optional_apollo_plugin!("preview_entity_cache");
+    add_optional_apollo_plugin!("experimental_profiling");
     add_mandatory_apollo_plugin!("progressive_override");
 
     // This relative ordering is documented in `docs/source/customizations/native.mdx`:
     add_optional_apollo_plugin!("rhai");
     add_optional_apollo_plugin!("coprocessor");
+    add_optional_apollo_plugin!("wasm");
     add_optional_apollo_plugin!("demand_control");
     add_user_plugins!();
 
@@ -763,6 +778,7 @@ fn inject_schema_id(schema_id: Option<&str>, configuration: &mut Value) {
 mod test {
     use std::sync::Arc;
 
+    use router_bridge::planner::PlanOptions;
     use schemars::JsonSchema;
     use serde::Deserialize;
     use serde_json::json;
@@ -771,6 +787,8 @@ mod test {
     use crate::configuration::Configuration;
     use crate::plugin::Plugin;
     use crate::plugin::PluginInit;
+    use crate::plugins::authorization::CacheKeyMetadata;
+    use crate::query_planner::warm_up_file::WarmUpFileEntry;
     use crate::register_plugin;
     use crate::router_factory::inject_schema_id;
     use crate::router_factory::RouterSuperServiceFactory;
@@ -873,6 +891,40 @@ mod test {
         assert!(service.is_err())
     }
 
+    #[tokio::test]
+    async fn cold_start_plans_the_warm_up_file_before_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warm_up.json");
+        let query = "{ topProducts { upc } }";
+        let entries = vec![WarmUpFileEntry {
+            query: query.to_string(),
+            operation: None,
+            metadata: CacheKeyMetadata::default(),
+            plan_options: PlanOptions::default(),
+        }];
+        std::fs::write(&path, serde_json::to_vec(&entries).unwrap()).unwrap();
+
+        let config: Configuration = serde_json::from_value(json!({
+            "supergraph": {
+                "query_planning": {
+                    "experimental_warm_up_file": { "path": path }
+                }
+            }
+        }))
+        .unwrap();
+        let schema = Schema::parse(include_str!("testdata/supergraph.graphql"), &config).unwrap();
+        let router = YamlRouterFactory
+            .create(false, Arc::new(config), Arc::new(schema), None, None)
+            .await
+            .unwrap();
+
+        // The router is only ready to serve requests once it is created
+        let cache = router.previous_cache();
+        let cache = cache.lock().await;
+        assert_eq!(cache.len(), 1);
+        assert!(cache.iter().all(|(key, _)| key.query == query));
+    }
+
     async fn create_service(config: Configuration) -> Result<(), BoxError> {
         let schema = include_str!("testdata/supergraph.graphql");
         let schema = Schema::parse(schema, &config)?;
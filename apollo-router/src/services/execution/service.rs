@@ -41,6 +41,7 @@ use crate::json_ext::Path;
 use crate::json_ext::PathElement;
 use crate::json_ext::ValueExt;
 use crate::plugins::authentication::APOLLO_AUTHENTICATION_JWT_CLAIMS;
+use crate::plugins::query_plan_dry_run::DryRun;
 use crate::plugins::subscription::Subscription;
 use crate::plugins::subscription::SubscriptionConfig;
 use crate::plugins::subscription::APOLLO_SUBSCRIPTION_PLUGIN;
@@ -122,6 +123,21 @@ impl ExecutionService {
     async fn call_inner(&mut self, req: ExecutionRequest) -> ExecutionResponse {
         let context = req.context;
         let ctx = context.clone();
+
+        // A dry run keeps the query plan without executing it. This is checked here rather than in
+        // the plugin, so that every plugin processed the execution request
+        let is_dry_run = context.extensions().with_lock(|mut lock| {
+            lock.get_mut::<DryRun>()
+                .map(|dry_run| dry_run.query_plan = Some(req.query_plan.clone()))
+                .is_some()
+        });
+        if is_dry_run {
+            return ExecutionResponse::new_from_response(
+                http::Response::new(once(ready(Response::default())).boxed()),
+                ctx,
+            );
+        }
+
         let variables = req.supergraph_request.body().variables.clone();
         let operation_name = req.supergraph_request.body().operation_name.clone();
 
@@ -173,6 +189,11 @@ impl ExecutionService {
             };
             let stream = filter_stream(first, receiver, stream_mode);
             StreamWrapper(stream, tx_close_signal).boxed()
+        } else if has_initial_data && is_deferred {
+            // If it's a subscription event with deferred fragments, its incremental responses
+            // follow the primary response of the event
+            first.has_next = Some(true);
+            filter_stream(first, receiver, StreamMode::Defer).boxed()
         } else if has_initial_data {
             // If it's a subscription event
             once(ready(first)).boxed()
@@ -237,6 +258,10 @@ impl ExecutionService {
                 response
             })
             .filter_map(move |response: Response| {
+                // Paths nullified in a subscription event do not apply to the next events
+                if is_subscription && response.is_primary() {
+                    nullified_paths.clear();
+                }
                 ready(execution_span.in_scope(|| {
                     Self::process_graphql_response(
                         &query,
@@ -280,7 +305,12 @@ impl ExecutionService {
             .unwrap_or(false)
         {
             if response.has_next == Some(false) {
-                return Some(Response::builder().has_next(false).build());
+                return Some(
+                    Response::builder()
+                        .has_next(false)
+                        .and_subscribed(response.subscribed)
+                        .build(),
+                );
             } else {
                 return None;
             }
@@ -294,6 +324,33 @@ impl ExecutionService {
             return response.into();
         }
 
+        // Introspection fields mixed with other fields were executed when planning, or are
+        // executed with the request variables if they use them, their data goes in the primary
+        // response
+        if let (Some(introspection_data), None, None, Some(Value::Object(data))) = (
+            &query.introspection_data,
+            &response.path,
+            &response.label,
+            &mut response.data,
+        ) {
+            data.extend(
+                introspection_data
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+        }
+        if let (Some(introspection_query), None, None) =
+            (&query.introspection_query, &response.path, &response.label)
+        {
+            let introspection_response = introspection_query.execute(schema, variables);
+            if let (Some(Value::Object(data)), Some(Value::Object(introspection_data))) =
+                (&mut response.data, introspection_response.data)
+            {
+                data.extend(introspection_data);
+            }
+            response.errors.extend(introspection_response.errors);
+        }
+
         let has_next = response.has_next.unwrap_or(true);
         let variables_set = query.defer_variables_set(operation_name, variables);
 
@@ -454,6 +511,8 @@ impl ExecutionService {
         let query = query.clone();
 
         let rewritten_label = rewrite_defer_label(&response);
+        // Incremental responses of a subscription event keep the subscription opened
+        let response_subscribed = response.subscribed;
         let incremental = sub_responses
             .into_iter()
             .filter_map(move |(path, data)| {
@@ -528,6 +587,7 @@ impl ExecutionService {
         Some(
             Response::builder()
                 .has_next(has_next)
+                .and_subscribed(response_subscribed)
                 .incremental(incremental)
                 .build(),
         )
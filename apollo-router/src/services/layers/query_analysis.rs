@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::hash::Hash;
 use std::sync::Arc;
+use std::time::Instant;
 
 use apollo_compiler::ast;
 use apollo_compiler::validation::Valid;
@@ -24,6 +25,7 @@ use crate::plugins::authorization::AuthorizationPlugin;
 use crate::plugins::telemetry::config::ApolloMetricsReferenceMode;
 use crate::plugins::telemetry::config::Conf as TelemetryConfig;
 use crate::plugins::telemetry::consts::QUERY_PARSING_SPAN_NAME;
+use crate::plugins::telemetry::server_timing;
 use crate::query_planner::fetch::QueryHash;
 use crate::query_planner::OperationKind;
 use crate::services::SupergraphRequest;
@@ -73,15 +75,19 @@ impl QueryAnalysisLayer {
         }
     }
 
+    /// Parses and validates an operation. The time spent doing so is recorded in the
+    /// Server-Timing header of the request, if any.
     pub(crate) async fn parse_document(
         &self,
         query: &str,
         operation_name: Option<&str>,
+        context: Option<&Context>,
     ) -> Result<ParsedDocument, SpecError> {
         let query = query.to_string();
         let operation_name = operation_name.map(|o| o.to_string());
         let schema = self.schema.clone();
         let conf = self.configuration.clone();
+        let context = context.cloned();
 
         // Must be created *outside* of the spawn_blocking or the span is not connected to the
         // parent
@@ -89,18 +95,64 @@ impl QueryAnalysisLayer {
 
         task::spawn_blocking(move || {
             span.in_scope(|| {
-                Query::parse_document(
-                    &query,
-                    operation_name.as_deref(),
-                    schema.as_ref(),
-                    conf.as_ref(),
-                )
+                let start = Instant::now();
+                let ast = Query::parse_ast(&query, conf.as_ref())?;
+                if let Some(context) = &context {
+                    server_timing::record(context, server_timing::PARSE, None, start.elapsed());
+                }
+
+                let start = Instant::now();
+                let doc = Query::validate_document(ast, operation_name.as_deref(), schema.as_ref());
+                if let Some(context) = &context {
+                    server_timing::record(context, server_timing::VALIDATE, None, start.elapsed());
+                }
+                doc
             })
         })
         .await
         .expect("parse_document task panicked")
     }
 
+    /// Analyzes a document that a plugin rewrote after this layer analyzed the request.
+    ///
+    /// The operation name and the authorization requirements stored in the context are updated.
+    /// The operation type cannot change, because it was already used, for example to only
+    /// accept mutations over POST requests.
+    pub(crate) fn reanalyze(
+        doc: &ParsedDocument,
+        operation_name: Option<&str>,
+        schema: &Schema,
+        configuration: &Configuration,
+        context: &Context,
+    ) -> Result<(), SpecError> {
+        let (operation_name, operation_kind) = operation_metadata(doc, operation_name);
+        let operation_kind = operation_kind.unwrap_or_default();
+        let previous_kind = context
+            .get::<_, OperationKind>(OPERATION_KIND)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if operation_kind != previous_kind {
+            return Err(SpecError::OperationKindChanged(
+                previous_kind.to_string(),
+                operation_kind.to_string(),
+            ));
+        }
+
+        if AuthorizationPlugin::enable_directives(configuration, schema).unwrap_or(false) {
+            AuthorizationPlugin::update_query_analysis(
+                doc,
+                operation_name.as_deref(),
+                schema,
+                context,
+            );
+        }
+        context
+            .insert(OPERATION_NAME, operation_name)
+            .expect("cannot insert operation name into context; this is a bug");
+        Ok(())
+    }
+
     pub(crate) async fn supergraph_request(
         &self,
         request: SupergraphRequest,
@@ -147,7 +199,10 @@ impl QueryAnalysisLayer {
 
         let res = match entry {
             None => {
-                match self.parse_document(&query, op_name.as_deref()).await {
+                match self
+                    .parse_document(&query, op_name.as_deref(), Some(&request.context))
+                    .await
+                {
                     Err(errors) => {
                         (*self.cache.lock().await).put(
                             QueryAnalysisKey {
@@ -174,10 +229,8 @@ impl QueryAnalysisLayer {
                     Ok(doc) => {
                         let context = Context::new();
 
-                        let operation = doc.executable.operations.get(op_name.as_deref()).ok();
-                        let operation_name = operation.as_ref().and_then(|operation| {
-                            operation.name.as_ref().map(|s| s.as_str().to_owned())
-                        });
+                        let (operation_name, operation_kind) =
+                            operation_metadata(&doc, op_name.as_deref());
 
                         if self.enable_authorization_directives {
                             AuthorizationPlugin::query_analysis(
@@ -191,8 +244,6 @@ impl QueryAnalysisLayer {
                         context
                             .insert(OPERATION_NAME, operation_name)
                             .expect("cannot insert operation name into context; this is a bug");
-                        let operation_kind =
-                            operation.map(|op| OperationKind::from(op.operation_type));
                         // FIXME: I think we should not add an operation kind by default. If it's an invalid graphql operation for example it might be useful to detect there isn't operation_kind
                         context
                             .insert(OPERATION_KIND, operation_kind.unwrap_or_default())
@@ -261,6 +312,18 @@ impl QueryAnalysisLayer {
     }
 }
 
+/// Returns the name and type of the operation selected by `operation_name`.
+fn operation_metadata(
+    doc: &ParsedDocumentInner,
+    operation_name: Option<&str>,
+) -> (Option<String>, Option<OperationKind>) {
+    let operation = doc.executable.operations.get(operation_name).ok();
+    (
+        operation.and_then(|operation| operation.name.as_ref().map(|name| name.to_string())),
+        operation.map(|operation| OperationKind::from(operation.operation_type)),
+    )
+}
+
 pub(crate) type ParsedDocument = Arc<ParsedDocumentInner>;
 
 #[derive(Debug)]
@@ -270,6 +333,16 @@ pub(crate) struct ParsedDocumentInner {
     pub(crate) hash: Arc<QueryHash>,
 }
 
+impl ParsedDocumentInner {
+    /// Returns `true` if this document was parsed from the given query string.
+    pub(crate) fn is_parsed_from(&self, query: &str) -> bool {
+        self.ast
+            .sources
+            .values()
+            .any(|source| source.source_text() == query)
+    }
+}
+
 impl Display for ParsedDocumentInner {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
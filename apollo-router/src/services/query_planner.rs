@@ -10,6 +10,7 @@ use static_assertions::assert_impl_all;
 
 use crate::error::QueryPlannerError;
 use crate::graphql;
+use crate::introspection::VariablesIntrospection;
 use crate::query_planner::QueryPlan;
 use crate::Context;
 
@@ -78,8 +79,15 @@ pub(crate) struct Response {
 /// Query, QueryPlan and Introspection data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum QueryPlannerContent {
-    Plan { plan: Arc<QueryPlan> },
-    Response { response: Box<graphql::Response> },
+    Plan {
+        plan: Arc<QueryPlan>,
+    },
+    Response {
+        response: Box<graphql::Response>,
+    },
+    Introspection {
+        introspection: Box<VariablesIntrospection>,
+    },
     IntrospectionDisabled,
 }
 
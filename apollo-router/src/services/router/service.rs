@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::task::Poll;
+use std::time::Instant;
 
 use axum::body::StreamBody;
 use axum::response::*;
@@ -45,6 +46,7 @@ use crate::graphql;
 use crate::http_ext;
 #[cfg(test)]
 use crate::plugin::test::MockSupergraphService;
+use crate::plugins::telemetry::server_timing;
 use crate::protocols::multipart::Multipart;
 use crate::protocols::multipart::ProtocolMode;
 use crate::query_planner::InMemoryCachePlanner;
@@ -304,7 +306,14 @@ impl RouterService {
                         .headers
                         .insert(CONTENT_TYPE, APPLICATION_JSON_HEADER_VALUE.clone());
                     tracing::trace_span!("serialize_response").in_scope(|| {
+                        let start = Instant::now();
                         let body = serde_json::to_string(&response)?;
+                        server_timing::record(
+                            &context,
+                            server_timing::SERIALIZE,
+                            None,
+                            start.elapsed(),
+                        );
                         Ok(router::Response {
                             response: http::Response::from_parts(
                                 parts,
@@ -314,7 +323,13 @@ impl RouterService {
                         })
                     })
                 } else if accepts_multipart_defer || accepts_multipart_subscription {
-                    if accepts_multipart_defer {
+                    // A subscription using @defer is sent with the subscription protocol
+                    if accepts_multipart_subscription && response.subscribed == Some(true) {
+                        parts.headers.insert(
+                            CONTENT_TYPE,
+                            MULTIPART_SUBSCRIPTION_CONTENT_TYPE_HEADER_VALUE.clone(),
+                        );
+                    } else if accepts_multipart_defer {
                         parts.headers.insert(
                             CONTENT_TYPE,
                             MULTIPART_DEFER_CONTENT_TYPE_HEADER_VALUE.clone(),
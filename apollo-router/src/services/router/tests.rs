@@ -22,7 +22,10 @@ use crate::services::subgraph;
 use crate::services::supergraph;
 use crate::services::SupergraphRequest;
 use crate::services::SupergraphResponse;
+use crate::services::MULTIPART_DEFER_ACCEPT;
 use crate::services::MULTIPART_DEFER_CONTENT_TYPE;
+use crate::services::MULTIPART_SUBSCRIPTION_ACCEPT;
+use crate::services::MULTIPART_SUBSCRIPTION_CONTENT_TYPE;
 use crate::test_harness::make_fake_batch;
 use crate::Context;
 
@@ -453,6 +456,61 @@ async fn it_will_process_a_non_batched_defered_query() {
     assert_eq!(expected_response, data);
 }
 
+#[tokio::test]
+async fn it_sends_deferred_subscription_events_with_the_subscription_protocol() {
+    let mut router_service = from_supergraph_mock_callback(move |req| {
+        let responses = vec![
+            graphql::Response::builder().subscribed(true).build(),
+            graphql::Response::builder()
+                .data(json!({"onNewUser": {"id": "1"}}))
+                .has_next(true)
+                .subscribed(true)
+                .build(),
+            graphql::Response::builder()
+                .incremental(vec![graphql::IncrementalResponse::builder()
+                    .data(json!({"address": "1 Main Street"}))
+                    .path(graphql::JsonPath::from("onNewUser"))
+                    .build()])
+                .has_next(false)
+                .subscribed(true)
+                .build(),
+        ];
+        Ok(SupergraphResponse::new_from_response(
+            http::Response::new(futures::stream::iter(responses).boxed()),
+            req.context,
+        ))
+    })
+    .await;
+
+    let request = supergraph::Request::builder()
+        .query("subscription { onNewUser { id ... @defer { address } } }")
+        .header(
+            http::header::ACCEPT,
+            format!("{MULTIPART_SUBSCRIPTION_ACCEPT}, {MULTIPART_DEFER_ACCEPT}"),
+        )
+        .header(CONTENT_TYPE, APPLICATION_JSON.essence_str())
+        .uri(Uri::from_static("/"))
+        .method(Method::POST)
+        .context(Context::new())
+        .build()
+        .unwrap();
+    let response = router_service
+        .call(request.try_into().unwrap())
+        .await
+        .unwrap()
+        .response;
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        MULTIPART_SUBSCRIPTION_CONTENT_TYPE
+    );
+    let bytes = get_body_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8_lossy(&bytes);
+    assert!(body.contains(r#"{"payload":{"data":{"onNewUser":{"id":"1"}},"hasNext":true}}"#));
+    assert!(body.contains(
+        r#"{"payload":{"hasNext":false,"incremental":[{"data":{"address":"1 Main Street"},"path":["onNewUser"]}]}}"#
+    ));
+}
+
 #[tokio::test]
 async fn it_will_not_process_a_batched_deferred_query() {
     let expected_response = "[\r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"errors\":[{\"message\":\"Deferred responses and subscriptions aren't supported in batches\",\"extensions\":{\"code\":\"BATCHING_DEFER_UNSUPPORTED\"}}]}\r\n--graphql--\r\n, \r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"errors\":[{\"message\":\"Deferred responses and subscriptions aren't supported in batches\",\"extensions\":{\"code\":\"BATCHING_DEFER_UNSUPPORTED\"}}]}\r\n--graphql--\r\n]";
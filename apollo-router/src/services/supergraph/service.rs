@@ -89,6 +89,7 @@ pub(crate) struct SupergraphService {
     query_planner_service: CachingQueryPlanner<BridgeQueryPlannerPool>,
     schema: Arc<Schema>,
     notify: Notify<String, graphql::Response>,
+    configuration: Arc<Configuration>,
 }
 
 #[buildstructor::buildstructor]
@@ -99,12 +100,14 @@ impl SupergraphService {
         execution_service_factory: ExecutionServiceFactory,
         schema: Arc<Schema>,
         notify: Notify<String, graphql::Response>,
+        configuration: Arc<Configuration>,
     ) -> Self {
         SupergraphService {
             query_planner_service,
             execution_service_factory,
             schema,
             notify,
+            configuration,
         }
     }
 }
@@ -135,6 +138,7 @@ impl Service<SupergraphRequest> for SupergraphService {
             schema,
             req,
             self.notify.clone(),
+            self.configuration.clone(),
         )
         .or_else(|error: BoxError| async move {
             let errors = vec![crate::error::Error {
@@ -165,6 +169,7 @@ async fn service_call(
     schema: Arc<Schema>,
     req: SupergraphRequest,
     notify: Notify<String, graphql::Response>,
+    configuration: Arc<Configuration>,
 ) -> Result<SupergraphResponse, BoxError> {
     let context = req.context;
     let body = req.supergraph_request.body();
@@ -179,6 +184,7 @@ async fn service_call(
         body.operation_name.clone(),
         context.clone(),
         schema.clone(),
+        &configuration,
         req.supergraph_request
             .body()
             .query
@@ -212,6 +218,12 @@ async fn service_call(
         Some(QueryPlannerContent::Response { response }) => Ok(
             SupergraphResponse::new_from_graphql_response(*response, context),
         ),
+        Some(QueryPlannerContent::Introspection { introspection }) => {
+            Ok(SupergraphResponse::new_from_graphql_response(
+                introspection.execute(&schema, &variables),
+                context,
+            ))
+        }
         Some(QueryPlannerContent::IntrospectionDisabled) => {
             let mut response = SupergraphResponse::new_from_graphql_response(
                 graphql::Response::builder()
@@ -455,6 +467,7 @@ async fn subscription_task(
                 query: query_plan.query.clone(),
                 query_metrics: query_plan.query_metrics,
                 estimated_size: Default::default(),
+                evaluated_plan_count: query_plan.evaluated_plan_count,
             })
         }),
         _ => {
@@ -510,6 +523,11 @@ async fn subscription_task(
 
     let mut timeout = Box::pin(tokio::time::sleep(expires_in));
 
+    // The deferred responses of the current event. The next event is only dispatched once they
+    // have all been sent, so the client receives the responses of each event in order: a slow
+    // deferred fetch delays the events that follow it, which are buffered in the meantime.
+    let mut deferred_responses: Option<graphql::ResponseStream> = None;
+
     loop {
         tokio::select! {
             // We prefer to specify the order of checks within the select
@@ -530,7 +548,18 @@ async fn subscription_task(
                 let _ = sender.send(response).await;
                 break;
             },
-            message = receiver.next() => {
+            response = next_deferred_response(&mut deferred_responses), if deferred_responses.is_some() => {
+                match response {
+                    Some(response) => {
+                        if let Err(err) = sender.send(response).await {
+                            tracing::error!("cannot send the subscription to the client: {err:?}");
+                            break;
+                        }
+                    }
+                    None => deferred_responses = None,
+                }
+            }
+            message = receiver.next(), if deferred_responses.is_none() => {
                 match message {
                     Some(mut val) => {
                         if display_body {
@@ -544,9 +573,12 @@ async fn subscription_task(
                                 apollo_private.operation_signature = %operation_signature,
                                 apollo_private.duration_ns = field::Empty,)
                             ).await;
-                        if let Err(err) = res {
+                        match res {
+                            Ok(deferred) => deferred_responses = deferred,
+                            Err(err) => {
                                 tracing::error!("cannot send the subscription to the client: {err:?}");
-                            break;
+                                break;
+                            }
                         }
                     }
                     None => break,
@@ -603,6 +635,17 @@ async fn subscription_task(
     }
 }
 
+async fn next_deferred_response(
+    deferred_responses: &mut Option<graphql::ResponseStream>,
+) -> Option<Response> {
+    match deferred_responses {
+        Some(responses) => responses.next().await,
+        None => None,
+    }
+}
+
+/// Executes the query plan of a subscription event and sends its primary response, returning the
+/// stream of the event's deferred responses, if it has any.
 async fn dispatch_event(
     supergraph_req: &SupergraphRequest,
     execution_service_factory: &ExecutionServiceFactory,
@@ -610,7 +653,7 @@ async fn dispatch_event(
     context: Context,
     mut val: graphql::Response,
     sender: mpsc::Sender<Response>,
-) -> Result<(), SendError<Response>> {
+) -> Result<Option<graphql::ResponseStream>, SendError<Response>> {
     let start = Instant::now();
     let span = Span::current();
     let res = match query_plan {
@@ -629,8 +672,8 @@ async fn dispatch_event(
 
             let execution_service = execution_service_factory.create();
             let execution_response = execution_service.oneshot(execution_request).await;
-            let next_response = match execution_response {
-                Ok(mut execution_response) => execution_response.next_response().await,
+            let mut execution_response = match execution_response {
+                Ok(execution_response) => execution_response,
                 Err(err) => {
                     tracing::error!("cannot execute the subscription event: {err:?}");
                     let _ = sender
@@ -645,22 +688,31 @@ async fn dispatch_event(
                                 .build(),
                         )
                         .await;
-                    return Ok(());
+                    return Ok(None);
                 }
             };
 
-            if let Some(mut next_response) = next_response {
+            let subscribed = val.subscribed;
+            if let Some(mut next_response) = execution_response.next_response().await {
                 next_response.created_at = val.created_at;
-                next_response.subscribed = val.subscribed;
+                next_response.subscribed = subscribed;
                 val.errors.append(&mut next_response.errors);
                 next_response.errors = val.errors;
 
-                sender.send(next_response).await
-            } else {
-                Ok(())
+                sender.send(next_response).await?;
             }
+            // Incremental responses of the deferred fragments of this event
+            let deferred_responses = execution_response
+                .response
+                .into_body()
+                .map(move |mut response| {
+                    response.subscribed = subscribed;
+                    response
+                })
+                .boxed();
+            Ok(Some(deferred_responses))
         }
-        None => sender.send(val).await,
+        None => sender.send(val).await.map(|()| None),
     };
     span.record(
         APOLLO_PRIVATE_DURATION_NS,
@@ -675,6 +727,7 @@ async fn plan_query(
     operation_name: Option<String>,
     context: Context,
     schema: Arc<Schema>,
+    configuration: &Configuration,
     query_str: String,
 ) -> Result<QueryPlannerResponse, CacheResolverError> {
     // FIXME: we have about 80 tests creating a supergraph service and crafting a supergraph request for it
@@ -684,16 +737,31 @@ async fn plan_query(
     // tests will pass.
     // During a regular request, `ParsedDocument` is already populated during query analysis.
     // Some tests do populate the document, so we only do it if it's not already there.
-    if !context.extensions().with_lock(|lock| {
-        lock.contains_key::<crate::services::layers::query_analysis::ParsedDocument>()
-    }) {
+    // Plugins can also rewrite the query after it was analyzed: in that case, the new query is
+    // parsed and validated here, at planning time, and analyzed again, so that it is the one
+    // we plan and authorize.
+    let analyzed = context.extensions().with_lock(|lock| {
+        lock.get::<crate::services::layers::query_analysis::ParsedDocument>()
+            .map(|doc| doc.is_parsed_from(&query_str))
+    });
+    if analyzed != Some(true) {
         let doc = crate::spec::Query::parse_document(
             &query_str,
             operation_name.as_deref(),
             &schema,
-            &Configuration::default(),
+            configuration,
         )
         .map_err(crate::error::QueryPlannerError::from)?;
+        if analyzed == Some(false) {
+            crate::services::layers::query_analysis::QueryAnalysisLayer::reanalyze(
+                &doc,
+                operation_name.as_deref(),
+                &schema,
+                configuration,
+                &context,
+            )
+            .map_err(crate::error::QueryPlannerError::from)?;
+        }
         context.extensions().with_lock(|mut lock| {
             lock.insert::<crate::services::layers::query_analysis::ParsedDocument>(doc)
         });
@@ -906,6 +974,7 @@ impl SupergraphCreator {
             })
             .schema(self.schema.clone())
             .notify(self.config.notify.clone())
+            .configuration(self.config.clone())
             .build();
 
         let shaping = self
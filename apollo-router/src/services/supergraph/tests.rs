@@ -1187,6 +1187,117 @@ async fn subscription_without_header() {
     insta::assert_json_snapshot!(res);
 }
 
+async fn subscription_with_deferred_fragments(mode: serde_json::Value) -> Vec<graphql::Response> {
+    let mut notify = Notify::builder().build();
+    let (handle, _) = notify
+        .create_or_subscribe("TEST_TOPIC".to_string(), false)
+        .await
+        .unwrap();
+    let subgraphs = MockedSubgraphs([
+        ("SubgraphA", MockSubgraph::builder().with_json(
+                serde_json::json!{{"query":"subscription { onNewUser { __typename id } }"}},
+                serde_json::json!{{"data": {"onNewUser": { "__typename": "User", "id": "0" }}}}
+            ).with_subscription_stream(handle.clone()).build()),
+        ("SubgraphB", MockSubgraph::builder().with_json(
+            serde_json::json!{{
+                "query":"query($representations: [_Any!]!) { _entities(representations: $representations) { ... on User { address } } }",
+                "variables": {
+                    "representations":[{"__typename": "User", "id":"1"}]
+                }
+            }},
+            serde_json::json!{{
+                "data": {
+                    "_entities": [{ "address": "1 Main Street" }]
+                },
+            }}
+        ).with_json(
+            serde_json::json!{{
+                "query":"query($representations: [_Any!]!) { _entities(representations: $representations) { ... on User { address } } }",
+                "variables": {
+                    "representations":[{"__typename": "User", "id":"2"}]
+                }
+            }},
+            serde_json::json!{{
+                "data": {
+                    "_entities": [{ "address": "2 Main Street" }]
+                },
+            }}
+        ).build())
+    ].into_iter().collect());
+
+    // The legacy query planner rejects @defer in subscriptions
+    let mut configuration: Configuration = serde_json::from_value(serde_json::json!({
+        "experimental_query_planner_mode": "new",
+        "include_subgraph_errors": { "all": true },
+        "subscription": { "enabled": true, "mode": mode }
+    }))
+    .unwrap();
+    configuration.notify = notify.clone();
+    let service = TestHarness::builder()
+        .configuration(Arc::new(configuration))
+        .schema(include_str!(
+            "../../testdata/defer_in_subscription_supergraph.graphql"
+        ))
+        .extra_plugin(subgraphs)
+        .build_supergraph()
+        .await
+        .unwrap();
+
+    let context = Context::new();
+    context.extensions().with_lock(|mut lock| {
+        lock.insert(ClientRequestAccepts {
+            multipart_defer: true,
+            multipart_subscription: true,
+            ..Default::default()
+        })
+    });
+    let request = supergraph::Request::fake_builder()
+        .query("subscription { onNewUser { id ... @defer { address } } }")
+        .context(context)
+        .build()
+        .unwrap();
+    let mut stream = service.oneshot(request).await.unwrap();
+    let mut responses = vec![stream.next_response().await.unwrap()];
+
+    // Both events are published before the deferred response of the first one is sent
+    for id in ["1", "2"] {
+        notify
+            .broadcast(
+                graphql::Response::builder()
+                    .data(
+                        serde_json_bytes::json!({"onNewUser": { "__typename": "User", "id": id }}),
+                    )
+                    .build(),
+            )
+            .await
+            .unwrap();
+    }
+    for _ in 0..4 {
+        let response = stream.next_response().await.unwrap();
+        assert_eq!(response.subscribed, Some(true));
+        responses.push(response);
+    }
+    responses
+}
+
+#[tokio::test]
+async fn subscription_with_deferred_fragments_with_callback() {
+    let responses = subscription_with_deferred_fragments(
+        serde_json::json!({"callback": {"public_url": "http://localhost:4545/callback"}}),
+    )
+    .await;
+    insta::assert_json_snapshot!(responses);
+}
+
+#[tokio::test]
+async fn subscription_with_deferred_fragments_with_passthrough() {
+    let responses = subscription_with_deferred_fragments(
+        serde_json::json!({"passthrough": {"all": {"path": "/ws", "protocol": "graphql_ws"}}}),
+    )
+    .await;
+    insta::assert_json_snapshot!(responses);
+}
+
 #[tokio::test]
 async fn root_typename_with_defer_and_empty_first_response() {
     let subgraphs = MockedSubgraphs([
@@ -3626,3 +3737,171 @@ async fn invalid_input_enum() {
 
     insta::assert_json_snapshot!(response);
 }
+
+#[tokio::test]
+async fn plans_query_rewritten_by_plugins() {
+    let subgraphs = MockedSubgraphs(
+        [
+            (
+                "user",
+                MockSubgraph::builder()
+                    .with_json(
+                        serde_json::json! {{"query":"{currentUser{id}}"}},
+                        serde_json::json! {{"data": {"currentUser": { "id": "0" }}}},
+                    )
+                    .build(),
+            ),
+            ("orga", MockSubgraph::default()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    let service = TestHarness::builder()
+        .configuration_json(serde_json::json!({"include_subgraph_errors": { "all": true } }))
+        .unwrap()
+        .schema(SCHEMA)
+        .extra_plugin(subgraphs)
+        .supergraph_hook(|service| {
+            service
+                .map_request(|mut request: supergraph::Request| {
+                    // The query was already analyzed by the router service
+                    request.supergraph_request.body_mut().query =
+                        Some("query { currentUser { id } }".to_string());
+                    request
+                })
+                .boxed()
+        })
+        .build_router()
+        .await
+        .unwrap();
+
+    let request = supergraph::Request::fake_builder()
+        .query("query { currentUser { id name } }")
+        .build()
+        .unwrap();
+    let response = service
+        .oneshot(request.try_into().unwrap())
+        .await
+        .unwrap()
+        .next_response()
+        .await
+        .unwrap()
+        .unwrap();
+    let response: serde_json::Value = serde_json::from_slice(&response).unwrap();
+
+    assert_eq!(
+        response,
+        serde_json::json!({"data": {"currentUser": {"id": "0"}}})
+    );
+}
+
+#[tokio::test]
+async fn analyzes_query_rewritten_by_plugins() {
+    let subgraphs = MockedSubgraphs(
+        [
+            (
+                "user",
+                MockSubgraph::builder()
+                    .with_json(
+                        serde_json::json! {{"query":"query Rewritten__user__0{currentUser{id}}", "operationName": "Rewritten__user__0"}},
+                        serde_json::json! {{"data": {"currentUser": { "id": "0" }}}},
+                    )
+                    .build(),
+            ),
+            ("orga", MockSubgraph::default()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    let operation_name = Arc::new(std::sync::Mutex::new(None));
+    let recorded_operation_name = operation_name.clone();
+    let service = TestHarness::builder()
+        .configuration_json(serde_json::json!({"include_subgraph_errors": { "all": true } }))
+        .unwrap()
+        .schema(SCHEMA)
+        .extra_plugin(subgraphs)
+        .supergraph_hook(move |service| {
+            let recorded_operation_name = recorded_operation_name.clone();
+            service
+                .map_request(|mut request: supergraph::Request| {
+                    let body = request.supergraph_request.body_mut();
+                    body.query = Some("query Rewritten { currentUser { id } }".to_string());
+                    body.operation_name = Some("Rewritten".to_string());
+                    request
+                })
+                .map_response(move |response: supergraph::Response| {
+                    *recorded_operation_name.lock().unwrap() = response
+                        .context
+                        .get::<_, String>(crate::context::OPERATION_NAME)
+                        .unwrap();
+                    response
+                })
+                .boxed()
+        })
+        .build_router()
+        .await
+        .unwrap();
+
+    let request = supergraph::Request::fake_builder()
+        .query("query Original { currentUser { id name } }")
+        .operation_name("Original")
+        .build()
+        .unwrap();
+    let response = service
+        .oneshot(request.try_into().unwrap())
+        .await
+        .unwrap()
+        .next_response()
+        .await
+        .unwrap()
+        .unwrap();
+    let response: serde_json::Value = serde_json::from_slice(&response).unwrap();
+
+    assert_eq!(
+        response,
+        serde_json::json!({"data": {"currentUser": {"id": "0"}}})
+    );
+    assert_eq!(operation_name.lock().unwrap().as_deref(), Some("Rewritten"));
+}
+
+#[tokio::test]
+async fn rejects_operation_type_changed_by_plugins() {
+    let service = TestHarness::builder()
+        .configuration_json(serde_json::json!({"include_subgraph_errors": { "all": true } }))
+        .unwrap()
+        .schema(SCHEMA)
+        .extra_plugin(MockedSubgraphs(HashMap::new()))
+        .supergraph_hook(|service| {
+            service
+                .map_request(|mut request: supergraph::Request| {
+                    request.supergraph_request.body_mut().query =
+                        Some("subscription { userWasCreated { id } }".to_string());
+                    request
+                })
+                .boxed()
+        })
+        .build_router()
+        .await
+        .unwrap();
+
+    let request = supergraph::Request::fake_builder()
+        .query("query { currentUser { id } }")
+        .build()
+        .unwrap();
+    let response = service
+        .oneshot(request.try_into().unwrap())
+        .await
+        .unwrap()
+        .next_response()
+        .await
+        .unwrap()
+        .unwrap();
+    let response: serde_json::Value = serde_json::from_slice(&response).unwrap();
+
+    assert_eq!(
+        response["errors"][0]["extensions"]["code"],
+        "OPERATION_KIND_CHANGED"
+    );
+}
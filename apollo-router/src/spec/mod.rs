@@ -56,6 +56,8 @@ pub(crate) enum SpecError {
     SubscriptionNotSupported,
     /// query hashing failed: {0}
     QueryHashing(String),
+    /// the operation type cannot be changed from {0} to {1} after the request was analyzed
+    OperationKindChanged(String, String),
 }
 
 pub(crate) const GRAPHQL_VALIDATION_FAILURE_ERROR_KEY: &str = "## GraphQLValidationFailure\n";
@@ -86,6 +88,7 @@ impl ErrorExtension for SpecError {
             SpecError::UnknownOperation(_) => "GRAPHQL_VALIDATION_FAILED",
             SpecError::SubscriptionNotSupported => "SUBSCRIPTION_NOT_SUPPORTED",
             SpecError::QueryHashing(_) => "QUERY_HASHING",
+            SpecError::OperationKindChanged(_, _) => "OPERATION_KIND_CHANGED",
         }
         .to_string()
     }
@@ -26,6 +26,7 @@ use crate::error::FetchError;
 use crate::graphql::Error;
 use crate::graphql::Request;
 use crate::graphql::Response;
+use crate::introspection::VariablesIntrospection;
 use crate::json_ext::Object;
 use crate::json_ext::Path;
 use crate::json_ext::ResponsePathElement;
@@ -70,6 +71,16 @@ pub(crate) struct Query {
     pub(crate) defer_stats: DeferStats,
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     pub(crate) is_original: bool,
+    /// The response data of the introspection fields mixed with other fields, executed when
+    /// planning and merged into the primary response
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[serde(default)]
+    pub(crate) introspection_data: Option<Object>,
+    /// The introspection fields mixed with other fields that use variables, executed with the
+    /// variables of each request and merged into the primary response
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[serde(default)]
+    pub(crate) introspection_query: Option<VariablesIntrospection>,
 
     /// This is a hash that depends on:
     /// - the query itself
@@ -110,6 +121,8 @@ impl Query {
                 conditional_defer_variable_names: IndexSet::default(),
             },
             is_original: true,
+            introspection_data: None,
+            introspection_query: None,
             schema_aware_hash: vec![],
         }
     }
@@ -275,6 +288,15 @@ impl Query {
         schema: &Schema,
         configuration: &Configuration,
     ) -> Result<ParsedDocument, SpecError> {
+        let ast = Self::parse_ast(query, configuration)?;
+        Self::validate_document(ast, operation_name, schema)
+    }
+
+    /// Parses an operation, without validating it against the schema.
+    pub(crate) fn parse_ast(
+        query: &str,
+        configuration: &Configuration,
+    ) -> Result<apollo_compiler::ast::Document, SpecError> {
         let parser = &mut apollo_compiler::parser::Parser::new()
             .recursion_limit(configuration.limits.parser_max_recursion)
             .token_limit(configuration.limits.parser_max_tokens);
@@ -285,6 +307,19 @@ impl Query {
             }
         };
 
+        // Trace log recursion limit data
+        let recursion_limit = parser.recursion_reached();
+        tracing::trace!(?recursion_limit, "recursion limit data");
+
+        Ok(ast)
+    }
+
+    /// Validates a parsed operation against the API schema, and hashes it.
+    pub(crate) fn validate_document(
+        ast: apollo_compiler::ast::Document,
+        operation_name: Option<&str>,
+        schema: &Schema,
+    ) -> Result<ParsedDocument, SpecError> {
         let api_schema = schema.api_schema();
         let executable_document = match ast.to_executable_validate(api_schema) {
             Ok(doc) => doc,
@@ -293,10 +328,6 @@ impl Query {
             }
         };
 
-        // Trace log recursion limit data
-        let recursion_limit = parser.recursion_reached();
-        tracing::trace!(?recursion_limit, "recursion limit data");
-
         let hash = QueryHashVisitor::hash_query(
             schema.supergraph_schema(),
             &schema.raw_sdl,
@@ -333,6 +364,8 @@ impl Query {
             filtered_query: None,
             defer_stats,
             is_original: true,
+            introspection_data: None,
+            introspection_query: None,
             schema_aware_hash,
         })
     }
@@ -5852,6 +5852,8 @@ fn filtered_defer_fragment() {
         subselections,
         defer_stats,
         is_original: true,
+        introspection_data: None,
+        introspection_query: None,
         unauthorized: UnauthorizedPaths::default(),
         schema_aware_hash,
     };
@@ -5879,6 +5881,8 @@ fn filtered_defer_fragment() {
         subselections,
         defer_stats,
         is_original: false,
+        introspection_data: None,
+        introspection_query: None,
         unauthorized: UnauthorizedPaths::default(),
         schema_aware_hash,
     };
@@ -54,6 +54,20 @@ impl TraceId {
         trace_id
     }
 
+    /// Get the current trace id if the trace is sampled
+    pub(crate) fn current_sampled() -> Option<Self> {
+        Span::current()
+            .with_subscriber(move |(id, dispatch)| {
+                let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+                if span.is_sampled() {
+                    span.get_trace_id()
+                } else {
+                    None
+                }
+            })
+            .flatten()
+    }
+
     /// Convert the TraceId to bytes.
     pub fn as_bytes(&self) -> &[u8; 16] {
         &self.0
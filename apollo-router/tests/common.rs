@@ -278,6 +278,7 @@ impl IntegrationTest {
         collect_stdio: Option<tokio::sync::oneshot::Sender<String>>,
         supergraph: Option<PathBuf>,
         mut subgraph_overrides: HashMap<String, String>,
+        router_location: Option<PathBuf>,
     ) -> Self {
         let redis_namespace = Uuid::new_v4().to_string();
         let telemetry = telemetry.unwrap_or_default();
@@ -332,7 +333,7 @@ impl IntegrationTest {
 
         Self {
             router: None,
-            router_location: Self::router_location(),
+            router_location: router_location.unwrap_or_else(Self::router_location),
             test_config_location,
             test_schema_location,
             stdio_tx,
@@ -0,0 +1,50 @@
+use apollo_router::plugin::Plugin;
+use apollo_router::plugin::PluginInit;
+use apollo_router::register_plugin;
+use apollo_router::services::router;
+use http::HeaderValue;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tower::BoxError;
+use tower::ServiceBuilder;
+use tower::ServiceExt;
+
+struct ResponseHeader {
+    value: HeaderValue,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct Conf {
+    /// Value of the `x-plugin-library` response header
+    value: String,
+}
+
+#[async_trait::async_trait]
+impl Plugin for ResponseHeader {
+    type Config = Conf;
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        Ok(Self {
+            value: HeaderValue::from_str(&init.config.value)?,
+        })
+    }
+
+    fn router_service(&self, service: router::BoxService) -> router::BoxService {
+        let value = self.value.clone();
+        ServiceBuilder::new()
+            .map_response(move |mut response: router::Response| {
+                tracing::info!(monotonic_counter.plugin_library.requests = 1u64);
+                response
+                    .response
+                    .headers_mut()
+                    .insert("x-plugin-library", value.clone());
+                response
+            })
+            .service(service)
+            .boxed()
+    }
+}
+
+register_plugin!("example", "plugin_library", ResponseHeader);
+apollo_router::export_plugin_library!();
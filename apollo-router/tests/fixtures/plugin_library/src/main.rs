@@ -0,0 +1,4 @@
+// A router binary without any plugin, which gets them from the library
+fn main() -> anyhow::Result<()> {
+    apollo_router::main()
+}
@@ -0,0 +1,127 @@
+use std::env::consts::DLL_PREFIX;
+use std::env::consts::DLL_SUFFIX;
+use std::env::consts::EXE_SUFFIX;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tokio::process::Command;
+use tower::BoxError;
+
+use crate::integration::IntegrationTest;
+
+/// Builds the router binary and the plugin library of the `plugin_library` fixture, and returns
+/// their paths.
+///
+/// The router binary of the tests can't be used: it must be built with the same dependencies as
+/// the library.
+async fn build_fixture() -> (PathBuf, PathBuf) {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let fixture = manifest_dir.join("tests/fixtures/plugin_library");
+    // Build with the same dependency versions as the router
+    std::fs::copy(
+        manifest_dir.join("../Cargo.lock"),
+        fixture.join("Cargo.lock"),
+    )
+    .expect("could not copy Cargo.lock");
+
+    let target_dir = manifest_dir.join("../target/plugin_library");
+    let features = [
+        ("ci", cfg!(feature = "ci")),
+        ("failfast", cfg!(feature = "failfast")),
+        ("telemetry_next", cfg!(feature = "telemetry_next")),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect::<Vec<_>>()
+    .join(",");
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(fixture.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .arg("--features")
+        .arg(features);
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        build.arg("--release");
+        "release"
+    };
+    let status = build
+        .status()
+        .await
+        .expect("could not build the plugin library");
+    assert!(status.success(), "could not build the plugin library");
+
+    let output_dir = target_dir.join(profile);
+    (
+        output_dir.join(format!("router{EXE_SUFFIX}")),
+        output_dir.join(format!("{DLL_PREFIX}plugin_library{DLL_SUFFIX}")),
+    )
+}
+
+fn config(library: &Path, value: &str) -> String {
+    format!(
+        r#"
+experimental_plugin_libraries:
+  - {}
+plugins:
+  example.plugin_library:
+    value: {value}
+telemetry:
+  exporters:
+    metrics:
+      prometheus:
+        listen: 127.0.0.1:4000
+        enabled: true
+        path: /metrics
+"#,
+        library.display()
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_plugin_library() -> Result<(), BoxError> {
+    let (router_location, library) = build_fixture().await;
+    let mut router = IntegrationTest::builder()
+        .config(config(&library, "loaded"))
+        .router_location(router_location)
+        .build()
+        .await;
+    router.start().await;
+    router.assert_log_contains("loaded plugin library").await;
+    router.assert_started().await;
+
+    let (_trace_id, response) = router.execute_default_query().await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-plugin-library")
+            .expect("the plugin from the library must add its header"),
+        "loaded"
+    );
+    // Metrics from the library go to the router's meter provider
+    router
+        .assert_metrics_contains("plugin_library_requests_total", None)
+        .await;
+
+    router.graceful_shutdown().await;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_plugin_library_with_invalid_plugin_config() -> Result<(), BoxError> {
+    let (router_location, library) = build_fixture().await;
+    let mut router = IntegrationTest::builder()
+        .config(config(&library, "[not a string]"))
+        .router_location(router_location)
+        .build()
+        .await;
+    router.start().await;
+    router.assert_not_started().await;
+    router.assert_shutdown().await;
+    Ok(())
+}